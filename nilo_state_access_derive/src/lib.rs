@@ -141,12 +141,60 @@ pub fn derive_state_access(input: TokenStream) -> TokenStream {
         None
     }
 
+    fn is_map(ty: &Type) -> bool {
+        is_ty(ty, "HashMap") || is_ty(ty, "BTreeMap")
+    }
+    fn is_number(ty: &Type) -> bool {
+        ["i8", "i16", "i32", "i64", "i128", "u8", "u16", "u32", "u64", "u128", "f32", "f64"]
+            .iter()
+            .any(|want| is_ty(ty, want))
+    }
+    // 構造体・列挙型・Option などその他の型はserdeで読み書きする
+    fn is_value(ty: &Type) -> bool {
+        !(is_ty(ty, "String") || is_ty(ty, "bool") || is_number(ty)
+            || vec_inner(ty).is_some() || is_map(ty))
+    }
+
+    // ---- 深いパス（users[0].name, map["key"], profile.age）----
+    // リスト・マップ型の要素や構造体などのプロパティへの操作は state_path に委譲する
+    let nested_arms = |op: proc_macro2::TokenStream| -> Vec<proc_macro2::TokenStream> {
+        fs.iter().filter_map(|f| {
+            let field = &f.ident; let key = &f.key;
+            let update = if vec_inner(&f.ty).is_some() {
+                quote!(::nilo::engine::state_path::update_list_path)
+            } else if is_map(&f.ty) {
+                quote!(::nilo::engine::state_path::update_map_path)
+            } else if is_value(&f.ty) {
+                quote!(::nilo::engine::state_path::update_value_path)
+            } else {
+                return None;
+            };
+            Some(quote! { p if ::nilo::engine::state_path::is_nested_path_of(p, #key) => {
+                #update(&mut self.#field, &p[#key.len()..], ::nilo::engine::state_path::PathOp::#op)?;
+                ::nilo::engine::state::notify_state_watchers(self, #key);
+                Ok(())
+            } })
+        }).collect()
+    };
+    let nested_set_arms = nested_arms(quote!(Set(value)));
+    let nested_toggle_arms = nested_arms(quote!(Toggle));
+    let nested_list_append_arms = nested_arms(quote!(Append(value)));
+    let nested_list_insert_arms = nested_arms(quote!(Insert(index, value)));
+    let nested_list_remove_arms = nested_arms(quote!(Remove(value)));
+    let nested_list_clear_arms = nested_arms(quote!(Clear));
+
     // ---- get_field ----
     let get_field_arms = fs.iter().map(|f| {
         let name = &f.ident; let key = &f.key;
-        if vec_inner(&f.ty).is_some() {
+        if is_map(&f.ty) {
+            // マップ型もJSONオブジェクトとして読み出す
+            quote! { #key => Some(serde_json::to_string(&self.#name).unwrap_or_else(|_| "{}".to_string())) }
+        } else if vec_inner(&f.ty).is_some() {
             // ベクター型の場合はJSONシリアライゼーションを使用
             quote! { #key => Some(serde_json::to_string(&self.#name).unwrap_or_else(|_| "[]".to_string())) }
+        } else if is_value(&f.ty) {
+            // 構造体などはJSONとして読み出す（Displayは要らない）
+            quote! { #key => Some(::nilo::engine::state_path::field_to_string(&self.#name)) }
         } else {
             // その他の型は通常のto_string()を使用
             quote! { #key => Some(self.#name.to_string()) }
@@ -181,10 +229,21 @@ pub fn derive_state_access(input: TokenStream) -> TokenStream {
                 Ok(())
             } }
         } else if vec_inner(&f.ty).is_some() {
-            quote! { #key => { Err(format!("{} is a list; use list_append/remove", #key)) } }
+            // JSON配列でリスト全体を置き換える（読めない要素があれば元のまま）
+            quote! { #key => {
+                self.#field = ::nilo::engine::state_path::parse_list(&value)
+                    .map_err(|e| format!("parse {}: {}", #key, e))?;
+                ::nilo::engine::state::notify_state_watchers(self, #key);
+                Ok(())
+            } }
+        } else if is_map(&f.ty) {
+            quote! { #key => { Err(format!("{} is a map; set an entry like {}[\"key\"]", #key, #key)) } }
         } else {
+            // 構造体などはJSON（または文字列）をserdeで読み直す
             quote! { #key => {
-                Err(format!("unsupported type for set: {}", #key))
+                ::nilo::engine::state_path::update_value_path(&mut self.#field, "", ::nilo::engine::state_path::PathOp::Set(value))?;
+                ::nilo::engine::state::notify_state_watchers(self, #key);
+                Ok(())
             } }
         }
     });
@@ -333,25 +392,28 @@ pub fn derive_state_access(input: TokenStream) -> TokenStream {
     let expanded = quote! {
         impl #trait_path_ts for #struct_ident {
             fn get_field(&self, key: &str) -> Option<String> {
-                match key { #(#get_field_arms,)* _ => None }
+                match key {
+                    #(#get_field_arms,)*
+                    _ => ::nilo::engine::state_path::get_nested_field(self, key),
+                }
             }
             fn set(&mut self, path: &str, value: String) -> Result<(), String> {
-                match path { #(#set_arms,)* _ => Err(format!("unknown field: {}", path)) }
+                match path { #(#set_arms,)* #(#nested_set_arms,)* _ => Err(format!("unknown field: {}", path)) }
             }
             fn toggle(&mut self, path: &str) -> Result<(), String> {
-                match path { #(#toggle_arms,)* _ => Err(format!("unknown field: {}", path)) }
+                match path { #(#toggle_arms,)* #(#nested_toggle_arms,)* _ => Err(format!("unknown field: {}", path)) }
             }
             fn list_append(&mut self, path: &str, value: String) -> Result<(), String> {
-                match path { #(#list_append_arms,)* _ => Err(format!("unknown field: {}", path)) }
+                match path { #(#list_append_arms,)* #(#nested_list_append_arms,)* _ => Err(format!("unknown field: {}", path)) }
             }
             fn list_insert(&mut self, path: &str, index: usize, value: String) -> Result<(), String> {
-                match path { #(#list_insert_arms,)* _ => Err(format!("unknown field: {}", path)) }
+                match path { #(#list_insert_arms,)* #(#nested_list_insert_arms,)* _ => Err(format!("unknown field: {}", path)) }
             }
             fn list_remove(&mut self, path: &str, value: String) -> Result<(), String> {
-                match path { #(#list_remove_arms,)* _ => Err(format!("unknown field: {}", path)) }
+                match path { #(#list_remove_arms,)* #(#nested_list_remove_arms,)* _ => Err(format!("unknown field: {}", path)) }
             }
             fn list_clear(&mut self, path: &str) -> Result<(), String> {
-                match path { #(#list_clear_arms,)* _ => Err(format!("unknown field: {}", path)) }
+                match path { #(#list_clear_arms,)* #(#nested_list_clear_arms,)* _ => Err(format!("unknown field: {}", path)) }
             }
         }
     };
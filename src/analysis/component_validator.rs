@@ -10,12 +10,33 @@ pub fn validate_component_calls(app: &App) -> Vec<String> {
     }
 
     for component in &app.components {
+        validate_component_state(component, &mut warnings);
+        validate_component_emits(component, &mut warnings);
+        validate_enum_matches(component, &mut warnings);
         validate_nodes_recursive(&component.body, app, &mut warnings);
     }
 
     warnings
 }
 
+/// ローカル状態の宣言チェック（パラメータとの名前衝突・重複宣言）
+fn validate_component_state(comp: &Component, warnings: &mut Vec<String>) {
+    for (i, decl) in comp.state.iter().enumerate() {
+        if comp.params.iter().any(|p| p.name == decl.name) {
+            warnings.push(format!(
+                "コンポーネント '{}' の state '{}' はパラメータと同名です（パラメータが優先されます）",
+                comp.name, decl.name
+            ));
+        }
+        if comp.state[..i].iter().any(|d| d.name == decl.name) {
+            warnings.push(format!(
+                "コンポーネント '{}' で state '{}' が重複して宣言されています",
+                comp.name, decl.name
+            ));
+        }
+    }
+}
+
 fn validate_nodes_recursive(nodes: &[WithSpan<ViewNode>], app: &App, warnings: &mut Vec<String>) {
     for node in nodes {
         match &node.node {
@@ -31,7 +52,7 @@ fn validate_nodes_recursive(nodes: &[WithSpan<ViewNode>], app: &App, warnings: &
             ViewNode::VStack(children) | ViewNode::HStack(children) => {
                 validate_nodes_recursive(children, app, warnings);
             }
-            ViewNode::ForEach { body, .. } => {
+            ViewNode::ForEach { body, .. } | ViewNode::Dialog { body, .. } => {
                 validate_nodes_recursive(body, app, warnings);
             }
             ViewNode::If {
@@ -68,7 +89,9 @@ fn validate_component_call(
         if !param.optional && param.default_value.is_none() {
             // 名前付き引数または位置引数で値が渡されているかチェック
             let has_value = args.iter().any(|arg| match arg {
-                ComponentArg::Named(name, _) => name == &param.name,
+                ComponentArg::Named(name, _) | ComponentArg::Handler { name, .. } => {
+                    name == &param.name
+                }
                 ComponentArg::Positional(_) => true, // 位置引数は後で詳細チェック
             });
 
@@ -101,13 +124,13 @@ fn validate_component_call(
 
     // 名前付き引数の名前が存在するかチェック
     for arg in args {
-        if let ComponentArg::Named(name, _) = arg {
-            if !comp.params.iter().any(|p| &p.name == name) {
-                warnings.push(format!(
-                    "{}:{} - コンポーネント '{}' に未定義のパラメータ '{}' が渡されています",
-                    node.line, node.column, comp.name, name
-                ));
-            }
+        if let ComponentArg::Named(name, _) | ComponentArg::Handler { name, .. } = arg
+            && !comp.params.iter().any(|p| &p.name == name)
+        {
+            warnings.push(format!(
+                "{}:{} - コンポーネント '{}' に未定義のパラメータ '{}' が渡されています",
+                node.line, node.column, comp.name, name
+            ));
         }
     }
 
@@ -134,12 +157,300 @@ fn validate_component_call(
                     continue;
                 }
             }
+            ComponentArg::Handler { name, params, .. } => {
+                if let Some(param) = comp.params.iter().find(|p| &p.name == name) {
+                    validate_handler_arg(node, comp, param, params, warnings);
+                }
+                continue;
+            }
         };
 
         validate_param_type(node, &param.name, &param.param_type, expr, warnings);
     }
 }
 
+/// ★ ハンドラブロック引数のチェック（Function型であること・emit の引数個数と一致すること）
+fn validate_handler_arg(
+    node: &WithSpan<ViewNode>,
+    comp: &Component,
+    param: &ComponentParam,
+    handler_params: &[String],
+    warnings: &mut Vec<String>,
+) {
+    if !matches!(
+        param.param_type,
+        ComponentParamType::Function | ComponentParamType::Any
+    ) {
+        warnings.push(format!(
+            "{}:{} - パラメータ '{}' は Function 型ではないためハンドラを渡せません（期待: {:?}）",
+            node.line, node.column, param.name, param.param_type
+        ));
+        return;
+    }
+
+    let mut emits = Vec::new();
+    collect_emits(&comp.body, &mut emits);
+    for when in &comp.whens {
+        collect_emits(&when.actions, &mut emits);
+    }
+
+    for (emit_name, arg_count) in emits {
+        if emit_name == param.name && arg_count != handler_params.len() {
+            warnings.push(format!(
+                "{}:{} - ハンドラ '{}' の引数の数が emit と一致しません（emit: {}, ハンドラ: {}）",
+                node.line,
+                node.column,
+                param.name,
+                arg_count,
+                handler_params.len()
+            ));
+        }
+    }
+}
+
+/// ★ emit 先がFunction型パラメータとして宣言されているかチェック
+fn validate_component_emits(comp: &Component, warnings: &mut Vec<String>) {
+    let mut emits = Vec::new();
+    collect_emits(&comp.body, &mut emits);
+    for when in &comp.whens {
+        collect_emits(&when.actions, &mut emits);
+    }
+
+    for (emit_name, _) in emits {
+        match comp.params.iter().find(|p| p.name == emit_name) {
+            None => warnings.push(format!(
+                "コンポーネント '{}' で未宣言のイベント '{}' が emit されています",
+                comp.name, emit_name
+            )),
+            Some(param)
+                if !matches!(
+                    param.param_type,
+                    ComponentParamType::Function | ComponentParamType::Any
+                ) =>
+            {
+                warnings.push(format!(
+                    "コンポーネント '{}' の emit 先 '{}' は Function 型ではありません（実際: {:?}）",
+                    comp.name, emit_name, param.param_type
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// ★ 列挙型パラメータに対するmatchの網羅性チェック
+/// default も常に一致する case（`case _` / `case v`）も無い場合、case に無い値を警告する
+fn validate_enum_matches(comp: &Component, warnings: &mut Vec<String>) {
+    let mut matches = Vec::new();
+    collect_matches(&comp.body, &mut matches);
+
+    for found in matches {
+        let (Expr::Path(name) | Expr::Ident(name)) = found.scrutinee else {
+            continue;
+        };
+        let Some(values) = comp.params.iter().find_map(|p| match &p.param_type {
+            ComponentParamType::Enum(values) if &p.name == name => Some(values),
+            _ => None,
+        }) else {
+            continue;
+        };
+
+        let mut covered = Vec::new();
+        for case in &found.cases {
+            for pattern in &case.patterns {
+                let MatchPattern::Value(Expr::String(value)) = pattern else {
+                    continue;
+                };
+                if !values.contains(value) {
+                    warnings.push(format!(
+                        "{}:{} - match {} の case \"{}\" は列挙型の値ではありません（期待: {}）",
+                        found.line,
+                        found.column,
+                        name,
+                        value,
+                        quote_values(values)
+                    ));
+                } else if case.guard.is_none() {
+                    covered.push(value);
+                }
+            }
+        }
+
+        if found.has_default || found.cases.iter().any(|c| c.is_catch_all()) {
+            continue;
+        }
+        let missing: Vec<String> = values
+            .iter()
+            .filter(|v| !covered.contains(v))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            warnings.push(format!(
+                "{}:{} - match {} が列挙型の値を網羅していません（未対応: {}）",
+                found.line,
+                found.column,
+                name,
+                quote_values(&missing)
+            ));
+        }
+    }
+}
+
+fn quote_values(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| format!("\"{}\"", v))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// コンポーネント本体で見つかったmatch（ビューのmatchと式のmatchの両方）
+struct FoundMatch<'a> {
+    line: usize,
+    column: usize,
+    scrutinee: &'a Expr,
+    cases: Vec<&'a MatchCase>,
+    has_default: bool,
+}
+
+/// ノードとその式に含まれるmatchを再帰的に収集
+fn collect_matches<'a>(nodes: &'a [WithSpan<ViewNode>], out: &mut Vec<FoundMatch<'a>>) {
+    for node in nodes {
+        let exprs: Vec<&Expr> = match &node.node {
+            ViewNode::Text { args, .. } => args.iter().collect(),
+            ViewNode::ComponentCall { args, .. } => args
+                .iter()
+                .filter_map(|arg| match arg {
+                    ComponentArg::Positional(expr) | ComponentArg::Named(_, expr) => Some(expr),
+                    ComponentArg::Handler { .. } => None,
+                })
+                .collect(),
+            ViewNode::LetDecl { value, .. } | ViewNode::Set { value, .. } => vec![value],
+            _ => Vec::new(),
+        };
+        for expr in exprs {
+            collect_expr_matches(expr, node, out);
+        }
+
+        match &node.node {
+            ViewNode::Match {
+                expr,
+                arms,
+                default,
+            } => {
+                out.push(FoundMatch {
+                    line: node.line,
+                    column: node.column,
+                    scrutinee: expr,
+                    cases: arms.iter().map(|(case, _)| case).collect(),
+                    has_default: default.is_some(),
+                });
+                for (_, arm_body) in arms {
+                    collect_matches(arm_body, out);
+                }
+                if let Some(default_body) = default {
+                    collect_matches(default_body, out);
+                }
+            }
+            ViewNode::VStack(children) | ViewNode::HStack(children) => {
+                collect_matches(children, out)
+            }
+            ViewNode::ForEach { body, .. }
+            | ViewNode::Dialog { body, .. }
+            | ViewNode::DynamicSection { body, .. } => collect_matches(body, out),
+            ViewNode::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                collect_matches(then_body, out);
+                if let Some(else_nodes) = else_body {
+                    collect_matches(else_nodes, out);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_expr_matches<'a>(
+    expr: &'a Expr,
+    node: &WithSpan<ViewNode>,
+    out: &mut Vec<FoundMatch<'a>>,
+) {
+    match expr {
+        Expr::Match {
+            expr: scrutinee,
+            arms,
+            default,
+        } => {
+            out.push(FoundMatch {
+                line: node.line,
+                column: node.column,
+                scrutinee,
+                cases: arms.iter().map(|arm| &arm.case).collect(),
+                has_default: default.is_some(),
+            });
+            for arm in arms {
+                collect_expr_matches(&arm.value, node, out);
+            }
+            if let Some(default) = default {
+                collect_expr_matches(default, node, out);
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            collect_expr_matches(left, node, out);
+            collect_expr_matches(right, node, out);
+        }
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => {
+            collect_expr_matches(condition, node, out);
+            collect_expr_matches(then_expr, node, out);
+            collect_expr_matches(else_expr, node, out);
+        }
+        Expr::FunctionCall { args, .. } | Expr::Array(args) => {
+            for arg in args {
+                collect_expr_matches(arg, node, out);
+            }
+        }
+        Expr::Object(entries) => {
+            for (_, value) in entries {
+                collect_expr_matches(value, node, out);
+            }
+        }
+        Expr::CalcExpr(inner) => collect_expr_matches(inner, node, out),
+        _ => {}
+    }
+}
+
+/// emit ノードを (イベント名, 引数の数) として再帰的に収集
+fn collect_emits(nodes: &[WithSpan<ViewNode>], out: &mut Vec<(String, usize)>) {
+    for node in nodes {
+        match &node.node {
+            ViewNode::Emit { name, args } => out.push((name.clone(), args.len())),
+            ViewNode::VStack(children) | ViewNode::HStack(children) => collect_emits(children, out),
+            ViewNode::ForEach { body, .. } | ViewNode::Dialog { body, .. } => {
+                collect_emits(body, out)
+            }
+            ViewNode::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                collect_emits(then_body, out);
+                if let Some(else_nodes) = else_body {
+                    collect_emits(else_nodes, out);
+                }
+            }
+            ViewNode::When { actions, .. } => collect_emits(actions, out),
+            _ => {}
+        }
+    }
+}
+
 fn validate_param_type(
     node: &WithSpan<ViewNode>,
     param_name: &str,
@@ -13,7 +13,7 @@ pub fn run_lints(app: &App) -> Vec<super::error::Diagnostic> {
     let timeline_names: std::collections::HashSet<_> =
         app.timelines.iter().map(|t| t.name.as_str()).collect();
     for transition in &app.flow.transitions {
-        for target in &transition.to {
+        for target in transition.to.iter().chain(transition.fallback.as_ref()) {
             if !timeline_names.contains(target.timeline.as_str()) {
                 diags.push(super::error::Diagnostic::error(format!(
                     "Timeline '{}' referenced in flow but not defined",
@@ -23,9 +23,21 @@ pub fn run_lints(app: &App) -> Vec<super::error::Diagnostic> {
         }
     }
 
+    if let Some(not_found) = &app.flow.not_found
+        && !timeline_names.contains(not_found.as_str())
+    {
+        diags.push(super::error::Diagnostic::error(format!(
+            "Timeline '{}' referenced as not_found but not defined",
+            not_found
+        )));
+    }
+
     // Flow解析：timeline内のnavigate_toとflowの整合性チェック
     check_flow_consistency(app, &mut diags);
 
+    // サブフロー：call_flow の呼び出し先と finish の位置
+    check_sub_flows(app, &mut diags);
+
     // コンポーネント使用チェック
     let defined_components: std::collections::HashSet<_> =
         app.components.iter().map(|c| c.name.as_str()).collect();
@@ -49,6 +61,32 @@ pub fn run_lints(app: &App) -> Vec<super::error::Diagnostic> {
         );
     }
 
+    // 組み込み関数・ファイルに定義した関数（fn）の引数チェック
+    let functions = &app.functions;
+    for timeline in &app.timelines {
+        check_function_calls(&timeline.body, functions, &mut diags);
+        for when in &timeline.whens {
+            check_function_calls(&when.actions, functions, &mut diags);
+        }
+        for timer in &timeline.timers {
+            check_function_calls(&timer.actions, functions, &mut diags);
+        }
+        for sub in &timeline.subscriptions {
+            check_function_calls(&sub.actions, functions, &mut diags);
+        }
+    }
+    for component in &app.components {
+        check_function_calls(&component.body, functions, &mut diags);
+        for when in &component.whens {
+            check_function_calls(&when.actions, functions, &mut diags);
+        }
+    }
+    for def in functions {
+        for expr in def.lets.iter().map(|(_, e)| e).chain([&def.body]) {
+            check_calls_in_expr(expr, functions, (def.line, def.column), &mut diags);
+        }
+    }
+
     // 未使用コンポーネントチェック
     for c in &app.components {
         if !called_components.contains(&c.name) {
@@ -73,6 +111,20 @@ pub fn run_lints(app: &App) -> Vec<super::error::Diagnostic> {
         }
     }
 
+    // 重複関数チェック
+    let mut function_name_counts = std::collections::HashMap::new();
+    for f in &app.functions {
+        *function_name_counts.entry(&f.name).or_insert(0) += 1;
+    }
+    for (name, count) in &function_name_counts {
+        if *count > 1 {
+            diags.push(super::error::Diagnostic::error(format!(
+                "Function '{}' is defined more than once ({} times)",
+                name, count
+            )));
+        }
+    }
+
     // 重複コンポーネントチェック
     let mut component_name_counts = std::collections::HashMap::new();
     for c in &app.components {
@@ -112,6 +164,14 @@ pub fn run_lints(app: &App) -> Vec<super::error::Diagnostic> {
                     )));
                 }
             }
+            if let EventExpr::ValueChanged(target) = &when.event
+                && !button_ids.contains_key(target)
+            {
+                diags.push(super::error::Diagnostic::warning(format!(
+                    "`when user.change({})` in timeline '{}' refers to a form control id that is not defined",
+                    target, timeline.name
+                )));
+            }
         }
     }
 
@@ -138,6 +198,14 @@ pub fn run_lints(app: &App) -> Vec<super::error::Diagnostic> {
                     )));
                 }
             }
+            if let EventExpr::ValueChanged(target) = &when.event
+                && !button_ids.contains_key(target)
+            {
+                diags.push(super::error::Diagnostic::warning(format!(
+                    "`when user.change({})` in component '{}' refers to a form control id that is not defined",
+                    target, component.name
+                )));
+            }
         }
     }
 
@@ -151,6 +219,8 @@ fn check_flow_consistency(app: &App, diags: &mut Vec<super::error::Diagnostic>)
     // flow定義から遷移マップを作成
     let mut flow_transitions: std::collections::HashMap<String, std::collections::HashSet<String>> =
         std::collections::HashMap::new();
+    let mut flow_fallbacks: std::collections::HashMap<String, std::collections::HashSet<String>> =
+        std::collections::HashMap::new();
 
     for transition in &app.flow.transitions {
         for from in &transition.from {
@@ -164,6 +234,13 @@ fn check_flow_consistency(app: &App, diags: &mut Vec<super::error::Diagnostic>)
                 .entry(from.clone())
                 .or_insert_with(std::collections::HashSet::new)
                 .extend(targets);
+            // ★ 条件付き遷移の else 側へは直接 navigate_to してもよい（実装されていなくても警告しない）
+            if let Some(fallback) = &transition.fallback {
+                flow_fallbacks
+                    .entry(from.clone())
+                    .or_default()
+                    .insert(fallback.timeline.clone());
+            }
         }
     }
 
@@ -173,6 +250,12 @@ fn check_flow_consistency(app: &App, diags: &mut Vec<super::error::Diagnostic>)
             std::collections::HashSet::new();
         collect_navigations(&timeline.body, &mut used_navigations);
         collect_navigations_from_whens(&timeline.whens, &mut used_navigations);
+        for timer in &timeline.timers {
+            collect_navigations(&timer.actions, &mut used_navigations);
+        }
+        for sub in &timeline.subscriptions {
+            collect_navigations(&sub.actions, &mut used_navigations);
+        }
 
         // timeline内のnavigate_toがflowに定義されているかチェック
         for target in &used_navigations {
@@ -187,7 +270,10 @@ fn check_flow_consistency(app: &App, diags: &mut Vec<super::error::Diagnostic>)
 
             // flowでこのtimelineからの遷移が定義されているかチェック
             if let Some(allowed_targets) = flow_transitions.get(&timeline.name) {
-                if !allowed_targets.contains(target) {
+                let is_fallback = flow_fallbacks
+                    .get(&timeline.name)
+                    .is_some_and(|fallbacks| fallbacks.contains(target));
+                if !allowed_targets.contains(target) && !is_fallback {
                     diags.push(super::error::Diagnostic::error(
                         format!(
                             "Timeline '{}' navigates to '{}' but this transition is not defined in flow",
@@ -206,10 +292,33 @@ fn check_flow_consistency(app: &App, diags: &mut Vec<super::error::Diagnostic>)
             }
         }
 
+        // ★ call_flow(Signup) はサブフローの開始タイムラインへの遷移として数える
+        let mut calls = Vec::new();
+        let mut finishes = false;
+        collect_sub_flow_actions(&timeline.body, &mut calls, &mut finishes);
+        for when in &timeline.whens {
+            collect_sub_flow_actions(&when.actions, &mut calls, &mut finishes);
+        }
+        for timer in &timeline.timers {
+            collect_sub_flow_actions(&timer.actions, &mut calls, &mut finishes);
+        }
+        for sub in &timeline.subscriptions {
+            collect_sub_flow_actions(&sub.actions, &mut calls, &mut finishes);
+        }
+        let called_starts: std::collections::HashSet<&str> = app
+            .flow
+            .sub_flows
+            .iter()
+            .filter(|sub| calls.contains(&sub.name))
+            .map(|sub| sub.start.as_str())
+            .collect();
+
         // flowに定義された遷移にnavigate_toが実装されているかチェック
         if let Some(flow_targets) = flow_transitions.get(&timeline.name) {
             for flow_target in flow_targets {
-                if !used_navigations.contains(flow_target) {
+                if !used_navigations.contains(flow_target)
+                    && !called_starts.contains(flow_target.as_str())
+                {
                     diags.push(super::error::Diagnostic::warning(
                         format!(
                             "Flow defines transition from '{}' to '{}' but no navigate_to('{}') found in timeline",
@@ -245,9 +354,21 @@ fn collect_button_ids(
 ) {
     for node in nodes {
         match &node.node {
-            ViewNode::Button { id, .. } => {
+            // ★ フォーム部品もクリック可能なIDとして扱う（user.click / user.change の対象）
+            ViewNode::Button { id, .. }
+            | ViewNode::Checkbox { id, .. }
+            | ViewNode::Switch { id, .. }
+            | ViewNode::RadioGroup { id, .. }
+            | ViewNode::Select { id, .. }
+            | ViewNode::Slider { id, .. }
+            | ViewNode::Stepper { id, .. } => {
                 *map.entry(id.clone()).or_insert(0) += 1;
             }
+            // ★ ダイアログのIDは閉じたときの user.change の対象
+            ViewNode::Dialog { id, body, .. } => {
+                *map.entry(id.clone()).or_insert(0) += 1;
+                collect_button_ids(body, map);
+            }
             ViewNode::VStack(children) | ViewNode::HStack(children) => {
                 collect_button_ids(children, map);
             }
@@ -338,7 +459,9 @@ Use `Text(\"{}\", variable)` instead to display variable values.",
                 // Rust関数呼び出しは定義済みコンポーネントのチェック対象外
                 // Rust側で定義された関数なので、lintでのチェックは不要
             }
-            ViewNode::VStack(children) | ViewNode::HStack(children) => {
+            ViewNode::VStack(children)
+            | ViewNode::HStack(children)
+            | ViewNode::Dialog { body: children, .. } => {
                 visit_nodes(children, out, defined, diags);
             }
             ViewNode::DynamicSection { body, .. } => {
@@ -377,16 +500,190 @@ Use `Text(\"{}\", variable)` instead to display variable values.",
     }
 }
 
+/// 式に含まれる組み込み関数・ファイルに定義した関数（fn）の呼び出しを検証
+fn check_calls_in_expr(
+    expr: &Expr,
+    functions: &[FunctionDef],
+    (line, column): (usize, usize),
+    diags: &mut Vec<super::error::Diagnostic>,
+) {
+    let mut visit = |e: &Expr| check_calls_in_expr(e, functions, (line, column), diags);
+    match expr {
+        Expr::FunctionCall { name, args } => {
+            let result = match functions.iter().find(|f| &f.name == name) {
+                Some(def) => crate::parser::types::check_function_call(def, args),
+                None => crate::parser::types::check_builtin_call(name, args),
+            };
+            for arg in args {
+                visit(arg);
+            }
+            if let Err(message) = result {
+                let mut d = super::error::Diagnostic::warning(message);
+                d.location = Some(format!("line {}, col {}", line, column));
+                diags.push(d);
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            visit(left);
+            visit(right);
+        }
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => {
+            visit(condition);
+            visit(then_expr);
+            visit(else_expr);
+        }
+        Expr::CalcExpr(inner) => visit(inner),
+        Expr::Array(items) => {
+            for item in items {
+                visit(item);
+            }
+        }
+        Expr::Object(entries) => {
+            for (_, value) in entries {
+                visit(value);
+            }
+        }
+        Expr::Match {
+            expr,
+            arms,
+            default,
+        } => {
+            visit(expr);
+            for arm in arms {
+                for case_expr in arm.case.exprs() {
+                    visit(case_expr);
+                }
+                visit(&arm.value);
+            }
+            if let Some(default) = default {
+                visit(default);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// ノード内の式に含まれる関数呼び出しを `check_calls_in_expr` で検証
+fn check_function_calls(
+    nodes: &[WithSpan<ViewNode>],
+    functions: &[FunctionDef],
+    diags: &mut Vec<super::error::Diagnostic>,
+) {
+    for node in nodes {
+        let exprs: Vec<&Expr> = match &node.node {
+            ViewNode::Text { args, .. }
+            | ViewNode::RustCall { args, .. }
+            | ViewNode::Emit { args, .. } => args.iter().collect(),
+            ViewNode::Button { onclick, .. } => onclick.iter().collect(),
+            ViewNode::TextInput {
+                value, on_change, ..
+            } => value.iter().chain(on_change).collect(),
+            ViewNode::RadioGroup { options, .. } | ViewNode::Select { options, .. } => {
+                vec![options]
+            }
+            ViewNode::Slider { min, max, step, .. } | ViewNode::Stepper { min, max, step, .. } => {
+                min.iter().chain(max).chain(step).collect()
+            }
+            ViewNode::ComponentCall { args, .. } => args
+                .iter()
+                .filter_map(|arg| match arg {
+                    ComponentArg::Positional(expr) | ComponentArg::Named(_, expr) => Some(expr),
+                    ComponentArg::Handler { .. } => None,
+                })
+                .collect(),
+            ViewNode::Match { expr, arms, .. } => std::iter::once(expr)
+                .chain(arms.iter().flat_map(|(case, _)| case.exprs()))
+                .collect(),
+            ViewNode::ForEach { iterable, .. } => vec![iterable],
+            ViewNode::If { condition, .. } => vec![condition],
+            ViewNode::NavigateTo { params, .. } | ViewNode::CallFlow { params, .. } => {
+                params.iter().map(|(_, e)| e).collect()
+            }
+            ViewNode::Finish { value } => value.iter().collect(),
+            ViewNode::Http { url, params, .. } => std::iter::once(url)
+                .chain(params.iter().map(|(_, e)| e))
+                .collect(),
+            ViewNode::Toast { message, duration } => {
+                std::iter::once(message).chain(duration).collect()
+            }
+            ViewNode::Set { value, .. }
+            | ViewNode::ListAppend { value, .. }
+            | ViewNode::ListInsert { value, .. }
+            | ViewNode::ListRemove { value, .. }
+            | ViewNode::LetDecl { value, .. } => vec![value],
+            _ => Vec::new(),
+        };
+        for expr in exprs {
+            check_calls_in_expr(expr, functions, (node.line, node.column), diags);
+        }
+
+        match &node.node {
+            ViewNode::VStack(children)
+            | ViewNode::HStack(children)
+            | ViewNode::Dialog { body: children, .. }
+            | ViewNode::DynamicSection { body: children, .. }
+            | ViewNode::ForEach { body: children, .. }
+            | ViewNode::When {
+                actions: children, ..
+            } => check_function_calls(children, functions, diags),
+            ViewNode::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                check_function_calls(then_body, functions, diags);
+                if let Some(else_body) = else_body {
+                    check_function_calls(else_body, functions, diags);
+                }
+            }
+            ViewNode::Match { arms, default, .. } => {
+                for (_, body) in arms {
+                    check_function_calls(body, functions, diags);
+                }
+                if let Some(default) = default {
+                    check_function_calls(default, functions, diags);
+                }
+            }
+            ViewNode::ComponentCall { args, slots, .. } => {
+                for arg in args {
+                    if let ComponentArg::Handler { actions, .. } = arg {
+                        check_function_calls(actions, functions, diags);
+                    }
+                }
+                for body in slots.values() {
+                    check_function_calls(body, functions, diags);
+                }
+            }
+            ViewNode::Http {
+                on_success,
+                on_error,
+                ..
+            } => {
+                for block in [on_success, on_error].into_iter().flatten() {
+                    check_function_calls(&block.actions, functions, diags);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 fn collect_navigations(
     nodes: &[WithSpan<ViewNode>],
     navigations: &mut std::collections::HashSet<String>,
 ) {
     for node in nodes {
         match &node.node {
-            ViewNode::NavigateTo { target } => {
+            ViewNode::NavigateTo { target, .. } => {
                 navigations.insert(target.clone());
             }
-            ViewNode::VStack(children) | ViewNode::HStack(children) => {
+            ViewNode::VStack(children)
+            | ViewNode::HStack(children)
+            | ViewNode::Dialog { body: children, .. } => {
                 collect_navigations(children, navigations);
             }
             ViewNode::DynamicSection { body, .. } => {
@@ -406,6 +703,15 @@ fn collect_navigations(
             ViewNode::ForEach { body, .. } => {
                 collect_navigations(body, navigations);
             }
+            ViewNode::Http {
+                on_success,
+                on_error,
+                ..
+            } => {
+                for block in [on_success, on_error].into_iter().flatten() {
+                    collect_navigations(&block.actions, navigations);
+                }
+            }
             ViewNode::If {
                 then_body,
                 else_body,
@@ -421,6 +727,91 @@ fn collect_navigations(
     }
 }
 
+fn check_sub_flows(app: &App, diags: &mut Vec<super::error::Diagnostic>) {
+    let sub_flow_names: std::collections::HashSet<_> = app
+        .flow
+        .sub_flows
+        .iter()
+        .map(|sub| sub.name.as_str())
+        .collect();
+    let sub_flow_timelines: std::collections::HashSet<_> = app
+        .flow
+        .sub_flows
+        .iter()
+        .flat_map(|sub| sub.timelines.iter().map(String::as_str))
+        .collect();
+
+    for timeline in &app.timelines {
+        let mut calls = Vec::new();
+        let mut finishes = false;
+        collect_sub_flow_actions(&timeline.body, &mut calls, &mut finishes);
+        for when in &timeline.whens {
+            collect_sub_flow_actions(&when.actions, &mut calls, &mut finishes);
+        }
+        for timer in &timeline.timers {
+            collect_sub_flow_actions(&timer.actions, &mut calls, &mut finishes);
+        }
+        for sub in &timeline.subscriptions {
+            collect_sub_flow_actions(&sub.actions, &mut calls, &mut finishes);
+        }
+
+        for name in calls {
+            if !sub_flow_names.contains(name.as_str()) {
+                diags.push(super::error::Diagnostic::error(format!(
+                    "Sub-flow '{}' is referenced in call_flow but not defined",
+                    name
+                )));
+            }
+        }
+        if finishes && !sub_flow_timelines.contains(timeline.name.as_str()) {
+            diags.push(super::error::Diagnostic::warning(format!(
+                "Timeline '{}' calls finish() but is not part of any sub-flow",
+                timeline.name
+            )));
+        }
+    }
+}
+
+fn collect_sub_flow_actions(
+    nodes: &[WithSpan<ViewNode>],
+    calls: &mut Vec<String>,
+    finishes: &mut bool,
+) {
+    for node in nodes {
+        match &node.node {
+            ViewNode::CallFlow { flow, .. } => calls.push(flow.clone()),
+            ViewNode::Finish { .. } => *finishes = true,
+            ViewNode::VStack(children)
+            | ViewNode::HStack(children)
+            | ViewNode::Dialog { body: children, .. }
+            | ViewNode::DynamicSection { body: children, .. }
+            | ViewNode::ForEach { body: children, .. }
+            | ViewNode::When {
+                actions: children, ..
+            } => collect_sub_flow_actions(children, calls, finishes),
+            ViewNode::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                collect_sub_flow_actions(then_body, calls, finishes);
+                if let Some(else_nodes) = else_body {
+                    collect_sub_flow_actions(else_nodes, calls, finishes);
+                }
+            }
+            ViewNode::Match { arms, default, .. } => {
+                for (_, nodes) in arms {
+                    collect_sub_flow_actions(nodes, calls, finishes);
+                }
+                if let Some(default_nodes) = default {
+                    collect_sub_flow_actions(default_nodes, calls, finishes);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 fn collect_navigations_from_whens(
     whens: &[When],
     navigations: &mut std::collections::HashSet<String>,
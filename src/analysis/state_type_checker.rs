@@ -1,3 +1,4 @@
+use crate::engine::state_path::split_root;
 use crate::parser::ast::{App, BinaryOperator, Expr, NiloType, ViewNode, WithSpan};
 use std::collections::HashMap;
 
@@ -19,6 +20,8 @@ pub enum RustFieldType {
     VecU32,
     VecString,
     VecBool,
+    /// 上記以外の型（構造体・`Vec<User>`・`HashMap` など）。型名をそのまま保持する
+    Other(String),
 }
 
 impl RustFieldType {
@@ -35,9 +38,38 @@ impl RustFieldType {
             }
             RustFieldType::VecString => NiloType::Array(Box::new(NiloType::String)),
             RustFieldType::VecBool => NiloType::Array(Box::new(NiloType::Bool)),
+            RustFieldType::Other(ty) if ty.starts_with("Vec<") => {
+                NiloType::Array(Box::new(NiloType::Unknown))
+            }
+            RustFieldType::Other(_) => NiloType::Unknown,
+        }
+    }
+
+    /// リスト操作（append / insert / remove / clear）ができる型か
+    pub fn is_list(&self) -> bool {
+        match self {
+            RustFieldType::VecI32
+            | RustFieldType::VecU32
+            | RustFieldType::VecString
+            | RustFieldType::VecBool => true,
+            RustFieldType::Other(ty) => ty.starts_with("Vec<"),
+            _ => false,
         }
     }
 
+    /// プロパティや要素を持たない値型か（`state.name.first` のようなパスは解決できない）
+    pub fn is_scalar(&self) -> bool {
+        matches!(
+            self,
+            RustFieldType::String
+                | RustFieldType::Bool
+                | RustFieldType::U32
+                | RustFieldType::I32
+                | RustFieldType::F32
+                | RustFieldType::F64
+        )
+    }
+
     /// 型名の表示
     pub fn display(&self) -> String {
         match self {
@@ -51,6 +83,7 @@ impl RustFieldType {
             RustFieldType::VecU32 => "Vec<u32>".to_string(),
             RustFieldType::VecString => "Vec<String>".to_string(),
             RustFieldType::VecBool => "Vec<bool>".to_string(),
+            RustFieldType::Other(ty) => ty.clone(),
         }
     }
 }
@@ -121,7 +154,9 @@ impl RustStateSchema {
                     "Vec<u32>" => Some(RustFieldType::VecU32),
                     "Vec<String>" => Some(RustFieldType::VecString),
                     "Vec<bool>" => Some(RustFieldType::VecBool),
-                    _ => None,
+                    "" => None,
+                    // ★ その他の型もフィールドの存在チェックのために記録する
+                    other => Some(RustFieldType::Other(other.to_string())),
                 };
 
                 if let Some(ft) = field_type {
@@ -241,6 +276,13 @@ fn infer_expr_type(expr: &Expr, local_ctx: &LocalVarContext) -> NiloType {
                 | BinaryOperator::Le
                 | BinaryOperator::Gt
                 | BinaryOperator::Ge => NiloType::Bool,
+                BinaryOperator::Coalesce => {
+                    if left_ty == right_ty {
+                        left_ty
+                    } else {
+                        NiloType::Unknown
+                    }
+                }
             }
         }
         Expr::Array(items) => {
@@ -255,6 +297,36 @@ fn infer_expr_type(expr: &Expr, local_ctx: &LocalVarContext) -> NiloType {
     }
 }
 
+/// `users[0].name` のような深いパスなら先頭のフィールドの存在をチェックしてtrueを返す
+fn check_nested_state_path(
+    field_name: &str,
+    line: usize,
+    column: usize,
+    schema: &RustStateSchema,
+    warnings: &mut Vec<String>,
+) -> bool {
+    let (root, rest) = split_root(field_name);
+    if rest.is_empty() {
+        return false;
+    }
+    match schema.fields.get(root) {
+        None => warnings.push(format!(
+            "{}:{} - 未定義のフィールド: state.{} は Rust の State 構造体に存在しません",
+            line, column, root
+        )),
+        Some(rust_type) if rust_type.is_scalar() && rest != ".len()" => warnings.push(format!(
+            "{}:{} - パスを解決できません: state.{} は {} 型なので state.{} は null になります",
+            line,
+            column,
+            root,
+            rust_type.display(),
+            field_name
+        )),
+        Some(_) => {}
+    }
+    true
+}
+
 /// 式内の変数アクセスをチェックする関数
 fn check_expr(
     expr: &Expr,
@@ -276,13 +348,14 @@ fn check_expr(
         }
         Expr::Path(path) => {
             // state.xxx の形式をチェック
-            if let Some(field_name) = path.strip_prefix("state.") {
-                if schema.fields.get(field_name).is_none() {
-                    warnings.push(format!(
-                        "{}:{} - 未定義のフィールド: state.{} は Rust の State 構造体に存在しません",
-                        line, column, field_name
-                    ));
-                }
+            if let Some(field_name) = path.strip_prefix("state.")
+                && !check_nested_state_path(field_name, line, column, schema, warnings)
+                && !schema.fields.contains_key(field_name)
+            {
+                warnings.push(format!(
+                    "{}:{} - 未定義のフィールド: state.{} は Rust の State 構造体に存在しません",
+                    line, column, field_name
+                ));
             }
         }
         Expr::BinaryOp { left, right, .. } => {
@@ -311,7 +384,9 @@ fn check_expr(
         } => {
             check_expr(match_expr, line, column, schema, warnings, local_ctx);
             for arm in arms {
-                check_expr(&arm.pattern, line, column, schema, warnings, local_ctx);
+                for case_expr in arm.case.exprs() {
+                    check_expr(case_expr, line, column, schema, warnings, local_ctx);
+                }
                 check_expr(&arm.value, line, column, schema, warnings, local_ctx);
             }
             if let Some(default_expr) = default {
@@ -321,7 +396,16 @@ fn check_expr(
         Expr::CalcExpr(inner) => {
             check_expr(inner, line, column, schema, warnings, local_ctx);
         }
-        _ => {} // String, Number, Bool, Dimension はチェック不要
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => {
+            check_expr(condition, line, column, schema, warnings, local_ctx);
+            check_expr(then_expr, line, column, schema, warnings, local_ctx);
+            check_expr(else_expr, line, column, schema, warnings, local_ctx);
+        }
+        _ => {} // String, Number, Bool, Null, Dimension はチェック不要
     }
 }
 
@@ -370,7 +454,9 @@ fn check_node(
             } else {
                 // state.xxx の形式をチェック
                 if let Some(field_name) = path.strip_prefix("state.") {
-                    if let Some(rust_type) = schema.fields.get(field_name) {
+                    if check_nested_state_path(field_name, line, column, schema, warnings) {
+                        // 要素の型はschemaにないため、先頭のフィールドの存在だけを確認する
+                    } else if let Some(rust_type) = schema.fields.get(field_name) {
                         let expected_nilo_type = rust_type.to_nilo_type();
 
                         // 推論された型をチェック
@@ -395,7 +481,9 @@ fn check_node(
         }
         ViewNode::Toggle { path } => {
             if let Some(field_name) = path.strip_prefix("state.") {
-                if let Some(rust_type) = schema.fields.get(field_name) {
+                if check_nested_state_path(field_name, line, column, schema, warnings) {
+                    // 要素の型はschemaにないため、先頭のフィールドの存在だけを確認する
+                } else if let Some(rust_type) = schema.fields.get(field_name) {
                     if *rust_type != RustFieldType::Bool {
                         warnings.push(format!(
                             "{}:{} - 型エラー: state.{} は {} 型なので toggle できません（bool 型のみ可能）",
@@ -412,14 +500,10 @@ fn check_node(
         }
         ViewNode::ListAppend { path, value } => {
             if let Some(field_name) = path.strip_prefix("state.") {
-                if let Some(rust_type) = schema.fields.get(field_name) {
-                    if !matches!(
-                        rust_type,
-                        RustFieldType::VecI32
-                            | RustFieldType::VecU32
-                            | RustFieldType::VecString
-                            | RustFieldType::VecBool
-                    ) {
+                if check_nested_state_path(field_name, line, column, schema, warnings) {
+                    // 要素の型はschemaにないため、先頭のフィールドの存在だけを確認する
+                } else if let Some(rust_type) = schema.fields.get(field_name) {
+                    if !rust_type.is_list() {
                         warnings.push(format!(
                             "{}:{} - 型エラー: state.{} は {} 型なのでリスト操作できません（Vec<T> 型のみ可能）",
                             line, column, field_name, rust_type.display()
@@ -440,14 +524,10 @@ fn check_node(
             value,
         } => {
             if let Some(field_name) = path.strip_prefix("state.") {
-                if let Some(rust_type) = schema.fields.get(field_name) {
-                    if !matches!(
-                        rust_type,
-                        RustFieldType::VecI32
-                            | RustFieldType::VecU32
-                            | RustFieldType::VecString
-                            | RustFieldType::VecBool
-                    ) {
+                if check_nested_state_path(field_name, line, column, schema, warnings) {
+                    // 要素の型はschemaにないため、先頭のフィールドの存在だけを確認する
+                } else if let Some(rust_type) = schema.fields.get(field_name) {
+                    if !rust_type.is_list() {
                         warnings.push(format!(
                             "{}:{} - 型エラー: state.{} は {} 型なのでリスト操作できません（Vec<T> 型のみ可能）",
                             line, column, field_name, rust_type.display()
@@ -464,14 +544,10 @@ fn check_node(
         }
         ViewNode::ListRemove { path, value } => {
             if let Some(field_name) = path.strip_prefix("state.") {
-                if let Some(rust_type) = schema.fields.get(field_name) {
-                    if !matches!(
-                        rust_type,
-                        RustFieldType::VecI32
-                            | RustFieldType::VecU32
-                            | RustFieldType::VecString
-                            | RustFieldType::VecBool
-                    ) {
+                if check_nested_state_path(field_name, line, column, schema, warnings) {
+                    // 要素の型はschemaにないため、先頭のフィールドの存在だけを確認する
+                } else if let Some(rust_type) = schema.fields.get(field_name) {
+                    if !rust_type.is_list() {
                         warnings.push(format!(
                             "{}:{} - 型エラー: state.{} は {} 型なのでリスト操作できません（Vec<T> 型のみ可能）",
                             line, column, field_name, rust_type.display()
@@ -488,14 +564,10 @@ fn check_node(
         }
         ViewNode::ListClear { path } => {
             if let Some(field_name) = path.strip_prefix("state.") {
-                if let Some(rust_type) = schema.fields.get(field_name) {
-                    if !matches!(
-                        rust_type,
-                        RustFieldType::VecI32
-                            | RustFieldType::VecU32
-                            | RustFieldType::VecString
-                            | RustFieldType::VecBool
-                    ) {
+                if check_nested_state_path(field_name, line, column, schema, warnings) {
+                    // 要素の型はschemaにないため、先頭のフィールドの存在だけを確認する
+                } else if let Some(rust_type) = schema.fields.get(field_name) {
+                    if !rust_type.is_list() {
                         warnings.push(format!(
                             "{}:{} - 型エラー: state.{} は {} 型なのでリスト操作できません（Vec<T> 型のみ可能）",
                             line, column, field_name, rust_type.display()
@@ -520,15 +592,47 @@ fn check_node(
             }
         }
         ViewNode::TextInput {
-            value, on_change, ..
+            value,
+            bind,
+            on_change,
+            ..
         } => {
             if let Some(expr) = value {
                 check_expr(expr, line, column, schema, warnings, local_ctx);
             }
+            if let Some(expr) = bind {
+                check_expr(expr, line, column, schema, warnings, local_ctx);
+            }
             if let Some(expr) = on_change {
                 check_expr(expr, line, column, schema, warnings, local_ctx);
             }
         }
+        ViewNode::Checkbox { bind, .. } | ViewNode::Switch { bind, .. } => {
+            check_expr(bind, line, column, schema, warnings, local_ctx);
+        }
+        ViewNode::RadioGroup { bind, options, .. } | ViewNode::Select { bind, options, .. } => {
+            check_expr(bind, line, column, schema, warnings, local_ctx);
+            check_expr(options, line, column, schema, warnings, local_ctx);
+        }
+        ViewNode::Slider {
+            bind,
+            min,
+            max,
+            step,
+            ..
+        }
+        | ViewNode::Stepper {
+            bind,
+            min,
+            max,
+            step,
+            ..
+        } => {
+            check_expr(bind, line, column, schema, warnings, local_ctx);
+            for expr in [min, max, step].into_iter().flatten() {
+                check_expr(expr, line, column, schema, warnings, local_ctx);
+            }
+        }
         ViewNode::ComponentCall { args, .. } => {
             for arg in args {
                 match arg {
@@ -538,6 +642,9 @@ fn check_node(
                     crate::parser::ast::ComponentArg::Named(_, expr) => {
                         check_expr(expr, line, column, schema, warnings, local_ctx);
                     }
+                    crate::parser::ast::ComponentArg::Handler { actions, .. } => {
+                        check_nodes(actions, schema, warnings, local_ctx);
+                    }
                 }
             }
         }
@@ -547,7 +654,10 @@ fn check_node(
             default,
         } => {
             check_expr(expr, line, column, schema, warnings, local_ctx);
-            for (_, body) in arms {
+            for (case, body) in arms {
+                for case_expr in case.exprs() {
+                    check_expr(case_expr, line, column, schema, warnings, local_ctx);
+                }
                 check_nodes(body, schema, warnings, local_ctx);
             }
             if let Some(default_body) = default {
@@ -576,6 +686,30 @@ fn check_node(
                 check_expr(arg, line, column, schema, warnings, local_ctx);
             }
         }
+        ViewNode::Toast { message, duration } => {
+            check_expr(message, line, column, schema, warnings, local_ctx);
+            if let Some(expr) = duration {
+                check_expr(expr, line, column, schema, warnings, local_ctx);
+            }
+        }
+        ViewNode::NavigateTo { params, .. } | ViewNode::CallFlow { params, .. } => {
+            for (_, expr) in params {
+                check_expr(expr, line, column, schema, warnings, local_ctx);
+            }
+        }
+        ViewNode::Finish { value: Some(expr) } => {
+            check_expr(expr, line, column, schema, warnings, local_ctx);
+        }
+        ViewNode::Http { url, params, .. } => {
+            check_expr(url, line, column, schema, warnings, local_ctx);
+            for (_, expr) in params {
+                check_expr(expr, line, column, schema, warnings, local_ctx);
+            }
+        }
+        ViewNode::Dialog { open, body, .. } => {
+            check_expr(open, line, column, schema, warnings, local_ctx);
+            check_nodes(body, schema, warnings, local_ctx);
+        }
         // 再帰的にチェック
         ViewNode::VStack(children) | ViewNode::HStack(children) => {
             check_nodes(children, schema, warnings, local_ctx);
@@ -134,7 +134,8 @@ impl DomRenderer {
                     *depth,
                 );
             }
-            Stencil::Group(children) => {
+            // ★ オーバーレイ層は最後に追加されるため、同じz-indexでも下層より前面に表示される
+            Stencil::Group(children) | Stencil::Overlay { children } => {
                 for child in children {
                     self.render_stencil(child);
                 }
@@ -448,8 +449,8 @@ impl DomRenderer {
             } => {
                 self.render_image(*position, *width, *height, path, *scroll, *depth);
             }
-            DrawCommand::ScrollContainer { .. } => {
-                // ScrollContainerはrender_stencilで直接処理されるため、ここには到達しない
+            DrawCommand::ScrollContainer { .. } | DrawCommand::Overlay { .. } => {
+                // ScrollContainer/Overlayはrender_stencilで直接処理されるため、ここには到達しない
                 // 念のため空の実装を用意
             }
         }
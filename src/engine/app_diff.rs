@@ -0,0 +1,302 @@
+//! ホットリロード時の新旧Appの差分
+//!
+//! 再読み込みしたAppを丸ごと差し替えるのではなく、変わった部分だけを
+//! 実行中の状態に反映するために使う（`AppState::apply_hot_reload`）。
+
+use crate::parser::ast::{App, ComponentArg, Timeline, ViewNode, WithSpan};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct AppDiff {
+    /// 追加・変更されたタイムライン
+    pub changed_timelines: Vec<String>,
+    /// 削除されたタイムライン
+    pub removed_timelines: Vec<String>,
+    pub components_changed: bool,
+    pub functions_changed: bool,
+    pub flow_changed: bool,
+    /// 位置が変わったコンポーネント呼び出し（"Counter@12:5" -> "Counter@14:5"）
+    pub moved_instances: Vec<(String, String)>,
+}
+
+impl AppDiff {
+    pub fn between(old: &App, new: &App) -> Self {
+        let old_timelines: HashMap<&str, &Timeline> = old
+            .timelines
+            .iter()
+            .map(|t| (t.name.as_str(), t))
+            .collect();
+        let changed_timelines = new
+            .timelines
+            .iter()
+            .filter(|t| old_timelines.get(t.name.as_str()) != Some(t))
+            .map(|t| t.name.clone())
+            .collect();
+        let removed_timelines = old
+            .timelines
+            .iter()
+            .filter(|t| !new.timelines.iter().any(|n| n.name == t.name))
+            .map(|t| t.name.clone())
+            .collect();
+
+        Self {
+            changed_timelines,
+            removed_timelines,
+            components_changed: old.components != new.components,
+            functions_changed: old.functions != new.functions,
+            flow_changed: old.flow != new.flow,
+            moved_instances: moved_instances(old, new),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed_timelines.is_empty()
+            && self.removed_timelines.is_empty()
+            && !self.components_changed
+            && !self.functions_changed
+            && !self.flow_changed
+    }
+
+    /// このタイムラインの表示に影響する変更があるか
+    pub fn affects_timeline(&self, name: &str) -> bool {
+        self.components_changed
+            || self.functions_changed
+            || self.changed_timelines.iter().any(|t| t == name)
+    }
+}
+
+/// インスタンスキー・要素ID中のコンポーネント呼び出しの位置を新しい位置に置き換える
+/// （"{i}>Counter@12:5" / "plus@Counter@12:5" の "Counter@12:5" の部分）
+pub fn remap_instance_key(key: &str, moves: &[(String, String)]) -> String {
+    let mut segments: Vec<String> = key.split('>').map(str::to_string).collect();
+    for segment in &mut segments {
+        for (old, new) in moves {
+            if segment == old {
+                *segment = new.clone();
+                break;
+            }
+            if let Some(id) = segment.strip_suffix(old.as_str())
+                && id.ends_with('@')
+            {
+                *segment = format!("{}{}", id, new);
+                break;
+            }
+        }
+    }
+    segments.join(">")
+}
+
+/// Appが参照している画像・フォントのパス（ホットリロードの監視対象）
+pub fn asset_paths(app: &App) -> Vec<String> {
+    let mut paths = Vec::new();
+    let fonts = app
+        .timelines
+        .iter()
+        .map(|t| &t.font)
+        .chain(app.components.iter().map(|c| &c.font));
+    paths.extend(fonts.flatten().cloned());
+
+    let bodies = app
+        .timelines
+        .iter()
+        .map(|t| (&t.body, &t.whens))
+        .chain(app.components.iter().map(|c| (&c.body, &c.whens)));
+    for (body, whens) in bodies {
+        let mut collect = |node: &WithSpan<ViewNode>| {
+            if let ViewNode::Image { path } = &node.node {
+                paths.push(path.clone());
+            }
+            if let Some(font) = node.style.as_ref().and_then(|s| s.font.as_ref()) {
+                paths.push(font.clone());
+            }
+        };
+        visit_nodes(body, &mut collect);
+        for when in whens {
+            visit_nodes(&when.actions, &mut collect);
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// 同じタイムライン・コンポーネント内で、同じ名前のコンポーネント呼び出しを出現順に対応付ける
+/// （行の追加・削除で位置がずれてもローカル状態を引き継ぐため）
+fn moved_instances(old: &App, new: &App) -> Vec<(String, String)> {
+    let mut moves = Vec::new();
+    let mut new_calls_by_owner = component_calls(new);
+    for (owner, old_calls) in component_calls(old) {
+        let Some(new_calls) = new_calls_by_owner.remove(&owner) else {
+            continue;
+        };
+        for (name, old_positions) in old_calls {
+            let Some(new_positions) = new_calls.get(&name) else {
+                continue;
+            };
+            for (old_pos, new_pos) in old_positions.iter().zip(new_positions) {
+                if old_pos != new_pos {
+                    moves.push((
+                        format!("{}@{}:{}", name, old_pos.0, old_pos.1),
+                        format!("{}@{}:{}", name, new_pos.0, new_pos.1),
+                    ));
+                }
+            }
+        }
+    }
+    moves
+}
+
+type CallPositions = HashMap<String, Vec<(usize, usize)>>;
+
+/// タイムライン・コンポーネントごとのコンポーネント呼び出しの位置（名前 -> 出現順の位置）
+fn component_calls(app: &App) -> HashMap<String, CallPositions> {
+    let owners = app
+        .timelines
+        .iter()
+        .map(|t| (format!("timeline {}", t.name), &t.body, &t.whens))
+        .chain(
+            app.components
+                .iter()
+                .map(|c| (format!("component {}", c.name), &c.body, &c.whens)),
+        );
+    owners
+        .map(|(owner, body, whens)| {
+            let mut calls = CallPositions::new();
+            let mut collect = |node: &WithSpan<ViewNode>| {
+                if let ViewNode::ComponentCall { name, .. } = &node.node {
+                    calls
+                        .entry(name.clone())
+                        .or_default()
+                        .push((node.line, node.column));
+                }
+            };
+            visit_nodes(body, &mut collect);
+            for when in whens {
+                visit_nodes(&when.actions, &mut collect);
+            }
+            (owner, calls)
+        })
+        .collect()
+}
+
+/// ノードを子孫まで出現順にたどる
+fn visit_nodes(nodes: &[WithSpan<ViewNode>], f: &mut dyn FnMut(&WithSpan<ViewNode>)) {
+    for node in nodes {
+        f(node);
+        match &node.node {
+            ViewNode::VStack(children)
+            | ViewNode::HStack(children)
+            | ViewNode::Dialog { body: children, .. }
+            | ViewNode::DynamicSection { body: children, .. }
+            | ViewNode::ForEach { body: children, .. }
+            | ViewNode::When {
+                actions: children, ..
+            } => visit_nodes(children, f),
+            ViewNode::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                visit_nodes(then_body, f);
+                if let Some(else_body) = else_body {
+                    visit_nodes(else_body, f);
+                }
+            }
+            ViewNode::Match { arms, default, .. } => {
+                for (_, body) in arms {
+                    visit_nodes(body, f);
+                }
+                if let Some(default) = default {
+                    visit_nodes(default, f);
+                }
+            }
+            ViewNode::ComponentCall { args, slots, .. } => {
+                for arg in args {
+                    if let ComponentArg::Handler { actions, .. } = arg {
+                        visit_nodes(actions, f);
+                    }
+                }
+                let mut slot_names: Vec<_> = slots.keys().collect();
+                slot_names.sort();
+                for name in slot_names {
+                    visit_nodes(&slots[name], f);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse::parse_nilo;
+
+    fn old_app() -> App {
+        parse_nilo(
+            r#"
+            flow { start: Home }
+            timeline Home {
+                Counter()
+                Image("assets/logo.png")
+            }
+            timeline Other { Text("a") }
+            component Counter() { Text("c") }
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn new_app() -> App {
+        parse_nilo(
+            r#"
+            flow { start: Home }
+            timeline Home {
+                Text("new line")
+                Counter()
+                Image("assets/logo.png")
+            }
+            component Counter() { Text("c") }
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_changed_and_removed_timelines() {
+        let diff = AppDiff::between(&old_app(), &new_app());
+        assert_eq!(diff.changed_timelines, vec!["Home"]);
+        assert_eq!(diff.removed_timelines, vec!["Other"]);
+        assert!(!diff.components_changed);
+        assert!(!diff.flow_changed);
+        assert!(diff.affects_timeline("Home"));
+        assert!(!diff.affects_timeline("Other"));
+    }
+
+    #[test]
+    fn test_identical_apps_have_no_diff() {
+        assert!(AppDiff::between(&new_app(), &new_app()).is_empty());
+    }
+
+    #[test]
+    fn test_moved_instances_are_remapped() {
+        let diff = AppDiff::between(&old_app(), &new_app());
+        assert_eq!(diff.moved_instances.len(), 1);
+        let (from, to) = &diff.moved_instances[0];
+        assert_eq!(
+            remap_instance_key(&format!("2>{}", from), &diff.moved_instances),
+            format!("2>{}", to)
+        );
+        assert_eq!(
+            remap_instance_key(&format!("plus@{}", from), &diff.moved_instances),
+            format!("plus@{}", to)
+        );
+        assert_eq!(remap_instance_key("name", &diff.moved_instances), "name");
+    }
+
+    #[test]
+    fn test_asset_paths() {
+        assert_eq!(asset_paths(&new_app()), vec!["assets/logo.png"]);
+    }
+}
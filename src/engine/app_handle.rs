@@ -0,0 +1,183 @@
+//! バックグラウンドのスレッドから状態を更新するためのハンドル
+//!
+//! 更新はキューに積まれ、UIスレッドの `apply_async_results` でまとめて適用される。
+//! 積むとハンドルが持つ `EventLoopProxy<AsyncEvent>` に `AsyncResultReady` を送り、再描画を要求する。
+
+use crate::engine::async_call::{
+    AsyncEvent, StateUpdate, event_loop_proxy, notify_async_result_ready, push_state_updates,
+};
+use crate::engine::state::{StateAccess, update_with_watchers};
+use serde::Serialize;
+use std::any::Any;
+use std::marker::PhantomData;
+use winit::event_loop::EventLoopProxy;
+
+/// 状態 `S` への更新を送るハンドル（複製してどのスレッドにも渡せる）
+pub struct AppHandle<S> {
+    /// 作成時に登録されていたEventLoopProxy（なければ送るたびに登録済みのものを探す）
+    proxy: Option<EventLoopProxy<AsyncEvent>>,
+    _state: PhantomData<fn(&mut S)>,
+}
+
+impl<S> Clone for AppHandle<S> {
+    fn clone(&self) -> Self {
+        Self {
+            proxy: self.proxy.clone(),
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S> Default for AppHandle<S> {
+    fn default() -> Self {
+        Self {
+            proxy: event_loop_proxy(),
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for AppHandle<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppHandle")
+            .field("has_proxy", &self.proxy.is_some())
+            .finish()
+    }
+}
+
+impl<S> AppHandle<S>
+where
+    S: StateAccess + 'static,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// UIスレッドで状態を直接書き換える
+    ///
+    /// 書き換えた後、値が変わったフィールドの `register_state_watcher` のウォッチャーを呼ぶ
+    pub fn update<F>(&self, update: F)
+    where
+        F: FnOnce(&mut S) + Send + 'static,
+    {
+        self.send(vec![StateUpdate::Apply(Box::new(
+            move |state: &mut dyn Any| match state.downcast_mut::<S>() {
+                Some(state) => update_with_watchers(state, update),
+                None => log::error!("State type mismatch in AppHandle::update"),
+            },
+        ))]);
+    }
+
+    /// serdeでシリアライズできる値を設定（`Vec` はリストの中身を置き換え、深いパスにも書ける）
+    pub fn set<T>(&self, path: &str, value: T) -> Result<(), String>
+    where
+        T: Serialize,
+    {
+        let value = serde_json::to_value(value).map_err(|e| format!("{}: {}", path, e))?;
+        self.send(vec![StateUpdate::Value(path.to_string(), value)]);
+        Ok(())
+    }
+
+    /// 複数の更新をまとめて送る（同じフレームで適用される）
+    pub fn send(&self, updates: Vec<StateUpdate>) {
+        if push_state_updates(updates) {
+            self.request_redraw();
+        }
+    }
+
+    /// 状態を変えずに再描画だけを要求する
+    pub fn request_redraw(&self) {
+        match &self.proxy {
+            Some(proxy) => {
+                if let Err(e) = proxy.send_event(AsyncEvent::AsyncResultReady) {
+                    log::error!("Failed to send async event: {:?}", e);
+                }
+            }
+            None => notify_async_result_ready(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::async_call::{apply_async_results, apply_value_update};
+    use crate::engine::state::{AppState, register_state_watcher};
+
+    #[derive(Default, Serialize, serde::Deserialize)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Default, nilo_state_access_derive::StateAccess)]
+    struct Counter {
+        count: i32,
+        tags: Vec<String>,
+        scores: Vec<u32>,
+        user: User,
+    }
+
+    #[test]
+    fn test_app_handle_updates() {
+        let mut state = AppState::new(Counter::default(), "Main".into());
+        let handle = AppHandle::<Counter>::new();
+
+        let worker = handle.clone();
+        std::thread::spawn(move || {
+            worker.set("state.count", 41).unwrap();
+            worker.set("tags", vec!["a", "b"]).unwrap();
+            worker.update(|s| s.count += 1);
+        })
+        .join()
+        .unwrap();
+
+        assert!(apply_async_results(&mut state));
+        assert_eq!(state.custom_state.count, 42);
+        assert_eq!(state.custom_state.tags, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_set_struct_values_and_deep_paths() {
+        let mut counter = Counter::default();
+        let user = User {
+            name: "Ann".into(),
+            age: 30,
+        };
+        apply_value_update(&mut counter, "user", serde_json::to_value(user).unwrap());
+        assert_eq!(counter.user.name, "Ann");
+        assert_eq!(counter.user.age, 30);
+
+        apply_value_update(&mut counter, "user.name", serde_json::json!("Alice"));
+        assert_eq!(counter.user.name, "Alice");
+        assert_eq!(counter.user.age, 30);
+    }
+
+    #[test]
+    fn test_list_update_is_all_or_nothing() {
+        let mut counter = Counter {
+            scores: vec![1],
+            ..Default::default()
+        };
+
+        // 読めない要素があれば元の中身のまま
+        apply_value_update(&mut counter, "scores", serde_json::json!([2, "x", 3]));
+        assert_eq!(counter.scores, vec![1]);
+
+        apply_value_update(&mut counter, "state.scores", serde_json::json!([2, 3]));
+        assert_eq!(counter.scores, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_update_runs_watchers_of_changed_fields() {
+        register_state_watcher::<Counter, _>("tags", |s| s.count = s.tags.len() as i32 * 10);
+        let mut counter = Counter::default();
+
+        update_with_watchers(&mut counter, |s| s.tags.push("a".into()));
+        assert_eq!(counter.count, 10);
+
+        // tags が変わらなければ呼ばない
+        update_with_watchers(&mut counter, |s| s.count += 1);
+        assert_eq!(counter.count, 11);
+    }
+}
@@ -1,4 +1,6 @@
+use crate::engine::resource::take_resource_results;
 use crate::engine::state::{AppState, CustomStateContext, StateAccess};
+use crate::engine::state_path;
 use crate::parser::ast::Expr;
 use log;
 use std::any::Any;
@@ -15,6 +17,10 @@ pub enum AsyncEvent {
     AsyncResultReady,
     /// 定期実行タイマーのティック
     IntervalTick(String),
+    /// HTTPリクエスト（http_get / http_post）の完了
+    HttpResponse(u64),
+    /// 購読（WebSocket / SSE）のメッセージ受信
+    SubscriptionMessage(u64),
 }
 
 // 非同期タスクの結果を保存するキュー
@@ -26,6 +32,22 @@ lazy_static::lazy_static! {
     static ref EVENT_LOOP_PROXY: Arc<Mutex<Option<Box<dyn Any + Send + Sync>>>> = Arc::new(Mutex::new(None));
 }
 
+// ★ HTTPリクエストや購読の接続を動かす共有のtokioランタイム（最初に使うときに作る）
+#[cfg(not(target_arch = "wasm32"))]
+lazy_static::lazy_static! {
+    static ref BACKGROUND_RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("nilo-background")
+        .build()
+        .expect("failed to build tokio runtime");
+}
+
+/// バックグラウンドのタスクを動かす共有のtokioランタイム
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn background_runtime() -> &'static tokio::runtime::Runtime {
+    &BACKGROUND_RUNTIME
+}
+
 /// EventLoopProxyを設定（アプリ起動時に一度だけ呼ばれる）
 pub fn set_event_loop_proxy<T: 'static + Send + Sync>(proxy: T) {
     if let Ok(mut p) = EVENT_LOOP_PROXY.lock() {
@@ -34,8 +56,18 @@ pub fn set_event_loop_proxy<T: 'static + Send + Sync>(proxy: T) {
     }
 }
 
+/// 登録されているEventLoopProxyの複製（`AppHandle` が保持する）
+pub(crate) fn event_loop_proxy() -> Option<winit::event_loop::EventLoopProxy<AsyncEvent>> {
+    EVENT_LOOP_PROXY.lock().ok().and_then(|proxy| {
+        proxy
+            .as_ref()?
+            .downcast_ref::<winit::event_loop::EventLoopProxy<AsyncEvent>>()
+            .cloned()
+    })
+}
+
 /// EventLoopProxyを取得して非同期イベントを送信
-fn notify_async_result_ready() {
+pub(crate) fn notify_async_result_ready() {
     if let Ok(proxy_guard) = EVENT_LOOP_PROXY.lock() {
         if let Some(proxy_box) = proxy_guard.as_ref() {
             // winit::event_loop::EventLoopProxy<AsyncEvent>にダウンキャスト
@@ -53,26 +85,82 @@ fn notify_async_result_ready() {
     }
 }
 
+/// 状態を直接書き換えるクロージャ（引数は `AppState::custom_state`）
+pub type StateUpdateFn = Box<dyn FnOnce(&mut dyn Any) + Send>;
+
+/// 非同期処理から状態への更新（UIスレッドで `apply_async_results` が適用する）
+pub enum StateUpdate {
+    /// 文字列の値を `StateAccess::set` で設定（`state.` は省略可、`__list_set__xxx` はリストの一括設定）
+    Set(String, String),
+    /// serdeの値を設定（配列はリストの中身を置き換え、それ以外は `user.name` のような深いパスにも書ける）
+    Value(String, serde_json::Value),
+    /// 状態を直接書き換えるクロージャ（`AppHandle::update` で作る）
+    Apply(StateUpdateFn),
+}
+
+impl std::fmt::Debug for StateUpdate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateUpdate::Set(key, value) => f.debug_tuple("Set").field(key).field(value).finish(),
+            StateUpdate::Value(key, value) => {
+                f.debug_tuple("Value").field(key).field(value).finish()
+            }
+            StateUpdate::Apply(_) => f.write_str("Apply(..)"),
+        }
+    }
+}
+
 /// 非同期タスクの結果
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct AsyncResult {
-    pub state_updates: HashMap<String, String>,
+    pub updates: Vec<StateUpdate>,
 }
 
-/// 非同期結果をキューに追加
+impl AsyncResult {
+    /// 文字列で表せる更新をキーと値で返す（`Apply` のクロージャは含まない）
+    pub fn state_updates(&self) -> HashMap<String, String> {
+        self.updates
+            .iter()
+            .filter_map(|update| match update {
+                StateUpdate::Set(key, value) => Some((key.clone(), value.clone())),
+                StateUpdate::Value(key, value) => {
+                    Some((key.clone(), state_path::value_to_string(value)))
+                }
+                StateUpdate::Apply(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// 非同期結果をキューに追加（文字列のキーと値）
 pub fn queue_async_result(updates: HashMap<String, String>) {
+    queue_state_updates(
+        updates
+            .into_iter()
+            .map(|(key, value)| StateUpdate::Set(key, value))
+            .collect(),
+    );
+}
+
+/// 型付きの更新をキューに追加し、メインスレッドに再描画を要求する
+pub fn queue_state_updates(updates: Vec<StateUpdate>) {
+    if push_state_updates(updates) {
+        // ★ メインスレッドに通知を送信
+        notify_async_result_ready();
+    }
+}
+
+/// 更新をキューに追加するだけ（通知は呼び出し側が送る）
+pub(crate) fn push_state_updates(updates: Vec<StateUpdate>) -> bool {
     if let Ok(mut queue) = ASYNC_RESULT_QUEUE.lock() {
         let update_count = updates.len();
-        queue.push(AsyncResult {
-            state_updates: updates,
-        });
+        queue.push(AsyncResult { updates });
         // ★ フラグを立てる
         ASYNC_RESULT_PENDING.store(true, Ordering::SeqCst);
         log::info!("🔔 Queued async result with {} updates, flag set", update_count);
-        
-        // ★ メインスレッドに通知を送信
-        notify_async_result_ready();
+        return true;
     }
+    false
 }
 
 /// 非同期結果が待機中かチェック
@@ -85,19 +173,22 @@ pub fn apply_async_results<S>(state: &mut AppState<S>) -> bool
 where
     S: StateAccess + 'static,
 {
+    // ★ リソース（resource user = ...）の結果は世代が一致するものだけ反映
+    let resources_applied = state.resources.apply_results(take_resource_results());
+
     let results = {
         if let Ok(mut queue) = ASYNC_RESULT_QUEUE.lock() {
             if queue.is_empty() {
-                return false;
+                return resources_applied;
             }
             std::mem::take(&mut *queue)
         } else {
-            return false;
+            return resources_applied;
         }
     };
 
     if results.is_empty() {
-        return false;
+        return resources_applied;
     }
 
     // ★ フラグをクリア
@@ -106,44 +197,11 @@ where
     log::debug!("Applying {} async results", results.len());
     
     for result in results {
-        for (key, value) in result.state_updates {
-            log::debug!("  Setting {} = {}", key, value);
-            // state.xxx形式の場合はstate.をstripしてからset
-            let field_name = if key.starts_with("state.") {
-                key.strip_prefix("state.").unwrap()
-            } else {
-                &key
-            };
-            
-            // ★ 特別な構文をサポート: __list_set__field_name でリストを一括設定
-            if field_name.starts_with("__list_set__") {
-                let actual_field = field_name.strip_prefix("__list_set__").unwrap();
-                
-                // JSON配列として解析
-                match serde_json::from_str::<Vec<String>>(&value) {
-                    Ok(items) => {
-                        // リストをクリアしてから追加
-                        if let Err(e) = state.custom_state.list_clear(actual_field) {
-                            log::error!("Failed to clear list {}: {}", actual_field, e);
-                            continue;
-                        }
-                        
-                        for item in items {
-                            if let Err(e) = state.custom_state.list_append(actual_field, item.clone()) {
-                                log::error!("Failed to append to {}: {}", actual_field, e);
-                            }
-                        }
-                        log::info!("✅ Successfully updated list: {}", actual_field);
-                    }
-                    Err(e) => {
-                        log::error!("Failed to parse list for {}: {}", actual_field, e);
-                    }
-                }
-            } else {
-                // 通常のフィールド設定
-                if let Err(e) = state.custom_state.set(field_name, value) {
-                    log::error!("Failed to set {}: {}", field_name, e);
-                }
+        for update in result.updates {
+            match update {
+                StateUpdate::Set(key, value) => apply_string_update(&mut state.custom_state, &key, value),
+                StateUpdate::Value(key, value) => apply_value_update(&mut state.custom_state, &key, value),
+                StateUpdate::Apply(update) => update(&mut state.custom_state as &mut dyn Any),
             }
         }
     }
@@ -151,9 +209,40 @@ where
     true
 }
 
+/// 文字列の更新を適用
+fn apply_string_update<S: StateAccess>(state: &mut S, key: &str, value: String) {
+    log::debug!("  Setting {} = {}", key, value);
+    // state.xxx形式の場合はstate.をstripしてからset
+    let field_name = key.strip_prefix("state.").unwrap_or(key);
+
+    // ★ 特別な構文をサポート: __list_set__field_name でリストを一括設定（JSON配列を1回の set で渡す）
+    let field_name = field_name
+        .strip_prefix("__list_set__")
+        .unwrap_or(field_name);
+    if let Err(e) = state.set(field_name, value) {
+        log::error!("Failed to set {}: {}", field_name, e);
+    }
+}
+
+/// serdeの値の更新を適用（配列もリスト全体を1回の `set` で置き換える）
+pub(crate) fn apply_value_update<S: StateAccess>(
+    state: &mut S,
+    key: &str,
+    value: serde_json::Value,
+) {
+    log::debug!("  Setting {} = {}", key, value);
+    let field_name = key.strip_prefix("state.").unwrap_or(key);
+    if let Err(e) = state.set(field_name, state_path::value_to_string(&value)) {
+        log::error!("Failed to set {}: {}", field_name, e);
+    }
+}
+
 // 非同期関数の結果を処理するコールバック型
 type AsyncCallback = Box<dyn FnOnce(&mut dyn Any, Result<String, String>) + Send + 'static>;
 
+// 非同期関数が返すFuture
+pub(crate) type AsyncFuture = Pin<Box<dyn Future<Output = Result<String, String>> + Send>>;
+
 // 非同期関数型: Future<Output = Result<String, String>> を返す関数
 type AsyncFn = dyn Fn(&[Expr]) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send>> + Send + Sync;
 
@@ -466,6 +555,27 @@ pub fn get_async_result(field: &str) -> Option<String> {
     })
 }
 
+/// 登録された非同期関数を呼び出してFutureを返す（実行はしない）
+///
+/// State付き → 引数のみの順に探し、見つからなければNone
+pub fn async_call_future<S>(
+    name: &str,
+    state: &mut AppState<S>,
+    args: &[Expr],
+) -> Option<AsyncFuture>
+where
+    S: StateAccess + 'static,
+{
+    if let Some(func) = ASYNC_STATE_REGISTRY.lock().unwrap().get(name) {
+        return Some(func(state as &mut dyn Any, args));
+    }
+    ASYNC_CALL_REGISTRY
+        .lock()
+        .unwrap()
+        .get(name)
+        .map(|func| func(args))
+}
+
 /// 登録されている非同期関数が存在するかチェック
 pub fn has_async_call(name: &str) -> bool {
     let state_registry = ASYNC_STATE_REGISTRY.lock().unwrap();
@@ -672,3 +782,86 @@ pub fn is_async_interval_running(name: &str) -> bool {
     let active = ACTIVE_INTERVALS.lock().unwrap();
     active.contains_key(name)
 }
+
+// ★ EventLoopProxyがない環境（WASMのDOMランタイム）で、次のフレームまで溜めておくティック
+lazy_static::lazy_static! {
+    static ref PENDING_INTERVAL_TICKS: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+}
+
+/// EventLoopProxyが登録されていればイベントを送る（登録されていなければfalse）
+pub(crate) fn send_async_event(event: AsyncEvent) -> bool {
+    let Ok(proxy_guard) = EVENT_LOOP_PROXY.lock() else {
+        return false;
+    };
+    let Some(proxy) = proxy_guard
+        .as_ref()
+        .and_then(|p| p.downcast_ref::<winit::event_loop::EventLoopProxy<AsyncEvent>>())
+    else {
+        return false;
+    };
+    if let Err(e) = proxy.send_event(event) {
+        log::error!("Failed to send async event: {:?}", e);
+    }
+    true
+}
+
+/// タイマーのティックをメインスレッドへ通知する
+///
+/// EventLoopProxyが登録されていれば `AsyncEvent::IntervalTick` を送り、
+/// なければ `take_interval_ticks` で取り出すまでキューに溜める
+pub fn notify_interval_tick(name: &str) {
+    if send_async_event(AsyncEvent::IntervalTick(name.to_string())) {
+        return;
+    }
+    if let Ok(mut ticks) = PENDING_INTERVAL_TICKS.lock() {
+        ticks.push(name.to_string());
+    }
+}
+
+/// キューに溜まっているティックを取り出す（古い順）
+pub fn take_interval_ticks() -> Vec<String> {
+    PENDING_INTERVAL_TICKS
+        .lock()
+        .map(|mut ticks| std::mem::take(&mut *ticks))
+        .unwrap_or_default()
+}
+
+/// `interval_ms` ごとに `name` のティックを通知する（`repeat` がfalseなら1回だけ）
+///
+/// `running` をfalseにすると次のティックの前に止まる
+pub fn spawn_interval_ticker(
+    name: String,
+    interval_ms: u64,
+    repeat: bool,
+    running: Arc<AtomicBool>,
+) {
+    let interval_ms = interval_ms.max(1);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(std::time::Duration::from_millis(interval_ms));
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+            notify_interval_tick(&name);
+            if !repeat {
+                break;
+            }
+        }
+    });
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(async move {
+        loop {
+            gloo_timers::future::TimeoutFuture::new(interval_ms as u32).await;
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+            notify_interval_tick(&name);
+            if !repeat {
+                break;
+            }
+        }
+    });
+}
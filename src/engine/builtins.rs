@@ -0,0 +1,311 @@
+//! 式の中で使える組み込み関数（`upper(state.name)`, `sum(state.items, "price")` など）
+//!
+//! 引数も戻り値も評価済みの文字列で受け渡す。リストはJSON配列の文字列として扱い、
+//! リストを返す関数（`split`, `filter_by`, `sort_by`, `range`）もJSON配列の文字列を返すので、
+//! そのまま `foreach` の反復対象や別の組み込み関数の引数にできる。
+//! 引数の型と個数のチェックは `parser::types::check_builtin_call`（Lint）で行う。
+
+use crate::engine::state_path::{self, value_to_string};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// `range` が作る整数列の最大の長さ（`0..state.count` の値が壊れていても固まらないように）
+const MAX_RANGE_LEN: i64 = 100_000;
+
+/// `round` / `format_number` の小数点以下の桁数の上限（f64で意味のある桁数まで）
+const MAX_DECIMALS: usize = 15;
+
+/// `pad` の幅の上限
+const MAX_PAD_WIDTH: usize = 1_000;
+
+/// 組み込み関数を実行する（組み込み関数でなければNone）
+pub fn call_builtin(name: &str, args: &[String]) -> Option<String> {
+    let arg = |i: usize| args.get(i).map_or("", String::as_str);
+    let result = match name {
+        // ---- 文字列 ----
+        "upper" => arg(0).to_uppercase(),
+        "lower" => arg(0).to_lowercase(),
+        "trim" => arg(0).trim().to_string(),
+        "contains" => match serde_json::from_str::<Value>(arg(0)) {
+            Ok(Value::Array(items)) => items.iter().any(|v| value_to_string(v) == arg(1)),
+            _ => arg(0).contains(arg(1)),
+        }
+        .to_string(),
+        "substring" => {
+            let chars: Vec<char> = arg(0).chars().collect();
+            let start = index_arg(arg(1)).min(chars.len());
+            let end = args
+                .get(2)
+                .map_or(chars.len(), |end| index_arg(end).min(chars.len()));
+            chars[start..end.max(start)].iter().collect()
+        }
+        "split" => {
+            let parts: Vec<&str> = if arg(0).is_empty() {
+                Vec::new()
+            } else {
+                arg(0).split(arg(1)).collect()
+            };
+            serde_json::to_string(&parts).unwrap_or_else(|_| "[]".to_string())
+        }
+        "join" => {
+            let separator = args.get(1).map_or(",", String::as_str);
+            list(arg(0))
+                .iter()
+                .map(value_to_string)
+                .collect::<Vec<_>>()
+                .join(separator)
+        }
+        "replace" => arg(0).replace(arg(1), arg(2)),
+
+        // ---- 数値 ----
+        "min" | "max" => {
+            let values = numbers(args);
+            let picked = if name == "min" {
+                values.into_iter().reduce(f64::min)
+            } else {
+                values.into_iter().reduce(f64::max)
+            };
+            picked.map_or_else(String::new, |v| v.to_string())
+        }
+        "round" => {
+            let value = number(arg(0));
+            let scale = 10f64.powi(capped_arg(name, arg(1), MAX_DECIMALS) as i32);
+            let scaled = (value * scale).round();
+            // 大きな値は桁を増やすとあふれるので、そのまま返す
+            if scaled.is_finite() {
+                (scaled / scale).to_string()
+            } else {
+                value.to_string()
+            }
+        }
+        "abs" => number(arg(0)).abs().to_string(),
+        "clamp" => {
+            let (lo, hi) = (number(arg(1)), number(arg(2)));
+            number(arg(0)).max(lo).min(hi.max(lo)).to_string()
+        }
+
+        // ---- リスト ----
+        "len" => match serde_json::from_str::<Value>(arg(0)) {
+            Ok(Value::Array(items)) => items.len(),
+            Ok(Value::Object(map)) => map.len(),
+            _ => arg(0).chars().count(),
+        }
+        .to_string(),
+        "first" => list(arg(0))
+            .first()
+            .map_or_else(String::new, value_to_string),
+        "last" => list(arg(0))
+            .last()
+            .map_or_else(String::new, value_to_string),
+        "filter_by" => {
+            let items: Vec<Value> = list(arg(0))
+                .into_iter()
+                .filter(|item| field(item, arg(1)).is_some_and(|v| value_to_string(v) == arg(2)))
+                .collect();
+            serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string())
+        }
+        "sort_by" => {
+            let mut items = list(arg(0));
+            items.sort_by(|a, b| compare_values(field(a, arg(1)), field(b, arg(1))));
+            serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string())
+        }
+        "sum" => list(arg(0))
+            .iter()
+            .filter_map(|item| field(item, arg(1)))
+            .map(|v| number(&value_to_string(v)))
+            .sum::<f64>()
+            .to_string(),
+        // `foreach n in a..b` の範囲（終端を含まない整数列、長さは MAX_RANGE_LEN まで）
+        "range" => {
+            let bound = |i: usize| number(arg(i)) as i64;
+            let (start, end) = (bound(0), bound(1));
+            let end = if end.saturating_sub(start) > MAX_RANGE_LEN {
+                log::warn!(
+                    "range({}, {}) は長すぎるため先頭の {} 件だけを使います",
+                    start,
+                    end,
+                    MAX_RANGE_LEN
+                );
+                start + MAX_RANGE_LEN
+            } else {
+                end
+            };
+            let values: Vec<String> = (start..end).map(|n| n.to_string()).collect();
+            format!("[{}]", values.join(","))
+        }
+
+        // ---- 書式 ----
+        "format_number" => format_number(number(arg(0)), capped_arg(name, arg(1), MAX_DECIMALS)),
+        "pad" => {
+            let width = capped_arg(name, arg(1), MAX_PAD_WIDTH);
+            let fill = arg(2).chars().next().unwrap_or(' ');
+            let len = arg(0).chars().count();
+            let mut padded: String = std::iter::repeat_n(fill, width.saturating_sub(len)).collect();
+            padded.push_str(arg(0));
+            padded
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// 数値として読む（読めなければ0）
+fn number(value: &str) -> f64 {
+    value.trim().parse::<f64>().unwrap_or(0.0)
+}
+
+/// 位置・桁数などの0以上の整数として読む
+fn index_arg(value: &str) -> usize {
+    number(value).max(0.0) as usize
+}
+
+/// 桁数・幅の引数を読み、上限を超えていたら上限にする（大きすぎる値で固まったり桁あふれしたりしないように）
+fn capped_arg(name: &str, value: &str, max: usize) -> usize {
+    let n = index_arg(value);
+    if n > max {
+        log::warn!("{}: {} は大きすぎるため {} として扱います", name, n, max);
+        return max;
+    }
+    n
+}
+
+/// JSON配列として読む（配列でなければ空）
+fn list(value: &str) -> Vec<Value> {
+    match serde_json::from_str::<Value>(value) {
+        Ok(Value::Array(items)) => items,
+        _ => Vec::new(),
+    }
+}
+
+/// `min(a, b, c)` の引数、または `min(list)` のリストの要素を数値として集める
+fn numbers(args: &[String]) -> Vec<f64> {
+    match args {
+        [single] if single.trim_start().starts_with('[') => list(single)
+            .iter()
+            .map(|v| number(&value_to_string(v)))
+            .collect(),
+        _ => args.iter().map(|v| number(v)).collect(),
+    }
+}
+
+/// 要素のフィールド（`"address.city"` のようなパスも可）。空なら要素そのもの
+fn field<'v>(item: &'v Value, path: &str) -> Option<&'v Value> {
+    if path.is_empty() {
+        Some(item)
+    } else {
+        state_path::lookup(item, &state_path::parse_path(path))
+    }
+}
+
+/// 並べ替えの比較（どちらも数値なら数値として、それ以外は文字列として比較）
+fn compare_values(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    let a = a.map(value_to_string).unwrap_or_default();
+    let b = b.map(value_to_string).unwrap_or_default();
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        _ => a.cmp(&b),
+    }
+}
+
+/// 3桁区切りと小数点以下の桁数を指定して数値を書式化（1234.5, 2 → "1,234.50"）
+fn format_number(value: f64, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (int_part, frac_part) = match formatted.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (formatted.as_str(), None),
+    };
+    let mut grouped = String::new();
+    for (i, c) in int_part.chars().enumerate() {
+        if i > 0 && (int_part.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    if let Some(frac_part) = frac_part {
+        grouped.push('.');
+        grouped.push_str(frac_part);
+    }
+    if value < 0.0 && grouped.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        grouped.insert(0, '-');
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        call_builtin(name, &args).unwrap()
+    }
+
+    const ITEMS: &str =
+        r#"[{"name":"b","price":300,"done":true},{"name":"a","price":1200,"done":false}]"#;
+
+    #[test]
+    fn test_string_functions() {
+        assert_eq!(call("upper", &["nilo"]), "NILO");
+        assert_eq!(call("lower", &["NiLo"]), "nilo");
+        assert_eq!(call("trim", &["  a  "]), "a");
+        assert_eq!(call("substring", &["こんにちは", "1", "3"]), "んに");
+        assert_eq!(call("substring", &["abc", "2"]), "c");
+        assert_eq!(call("contains", &["nilo", "il"]), "true");
+        assert_eq!(call("contains", &[r#"["a","b"]"#, "c"]), "false");
+        assert_eq!(call("split", &["a,b", ","]), r#"["a","b"]"#);
+        assert_eq!(call("split", &["", ","]), "[]");
+        assert_eq!(call("join", &[r#"["a","b"]"#, " / "]), "a / b");
+    }
+
+    #[test]
+    fn test_number_functions() {
+        assert_eq!(call("max", &["3", "7.5", "-1"]), "7.5");
+        assert_eq!(call("min", &["[4,2,9]"]), "2");
+        assert_eq!(call("round", &["3.14159", "2"]), "3.14");
+        assert_eq!(call("clamp", &["12", "0", "10"]), "10");
+    }
+
+    #[test]
+    fn test_list_functions() {
+        assert_eq!(call("len", &[ITEMS]), "2");
+        assert_eq!(call("sum", &[ITEMS, "price"]), "1500");
+        assert_eq!(
+            call("filter_by", &[ITEMS, "done", "true"]),
+            r#"[{"done":true,"name":"b","price":300}]"#
+        );
+        assert_eq!(
+            call("first", &[&call("sort_by", &[ITEMS, "name"])]),
+            r#"{"done":false,"name":"a","price":1200}"#
+        );
+    }
+
+    #[test]
+    fn test_format_functions() {
+        assert_eq!(call("format_number", &["1234567.891", "2"]), "1,234,567.89");
+        assert_eq!(call("pad", &["7", "3", "0"]), "007");
+        assert_eq!(call("pad", &["1234", "3", "0"]), "1234");
+    }
+
+    #[test]
+    fn test_unknown_function() {
+        assert!(call_builtin("unknown", &[]).is_none());
+    }
+
+    #[test]
+    fn test_range_is_capped() {
+        assert_eq!(call("range", &["2", "5"]), "[2,3,4]");
+        assert_eq!(call("range", &["5", "2"]), "[]");
+        let huge = call("range", &["0", "1e12"]);
+        assert_eq!(list(&huge).len(), MAX_RANGE_LEN as usize);
+    }
+
+    #[test]
+    fn test_digits_and_width_are_capped() {
+        let formatted = call("format_number", &["1.5", "1e9"]);
+        assert_eq!(formatted, format!("1.5{}", "0".repeat(MAX_DECIMALS - 1)));
+        assert_eq!(call("pad", &["7", "1e12", "0"]).len(), MAX_PAD_WIDTH);
+        // 桁数を増やしても桁あふれしない
+        assert_eq!(call("round", &["3.14159", "400"]), "3.14159");
+        assert_eq!(call("round", &["1e300", "15"]), 1e300f64.to_string());
+    }
+}
@@ -1,14 +1,37 @@
 // src/engine/core/component.rs
 // コンポーネント展開・パラメータ置換関連
 
-use crate::engine::state::{AppState, StateAccess};
-use crate::parser::ast::{App, ComponentArg, Expr, Style, ViewNode, WithSpan};
+use crate::engine::state::{AppState, StateAccess, component_state_path, foreach_key_placeholder};
+use crate::parser::ast::{
+    App, Component, ComponentArg, ComponentParamType, EventExpr, Expr, Style, ViewNode, WithSpan,
+};
+use std::collections::{HashMap, HashSet};
 
 /// 軽量化されたコンポーネント展開
 pub fn expand_component_calls_lightweight<S>(
     nodes: &[WithSpan<ViewNode>],
     app: &App,
-    _state: &mut AppState<S>,
+    state: &mut AppState<S>,
+) -> Vec<WithSpan<ViewNode>>
+where
+    S: StateAccess + 'static,
+{
+    // ★ インスタンスのwhenは展開のたびに再構築（ローカル状態の値は保持される）
+    state.component_whens.clear();
+    expand_nodes_in_scope(nodes, app, state, "", &HashMap::new(), 0)
+}
+
+/// コンポーネントのネスト展開の上限（再帰的なコンポーネント定義対策）
+const MAX_COMPONENT_DEPTH: usize = 32;
+
+/// スコープ（親インスタンス・foreach）を追跡しながらコンポーネントを展開
+fn expand_nodes_in_scope<S>(
+    nodes: &[WithSpan<ViewNode>],
+    app: &App,
+    state: &mut AppState<S>,
+    scope: &str,
+    handlers: &HashMap<String, EmitHandler>,
+    depth: usize,
 ) -> Vec<WithSpan<ViewNode>>
 where
     S: StateAccess + 'static,
@@ -23,12 +46,22 @@ where
                 slots: _,
             } => {
                 if let Some(comp) = app.components.iter().find(|c| c.name == *name) {
+                    if depth >= MAX_COMPONENT_DEPTH {
+                        log::warn!(
+                            "Component '{}' exceeds max nesting depth ({}), skipped",
+                            name,
+                            MAX_COMPONENT_DEPTH
+                        );
+                        continue;
+                    }
+
                     // コンポーネントのボディをクローンして引数を適用
                     let mut expanded_body = comp.body.clone();
 
                     // ★ Phase 2: デフォルト値を考慮したパラメータ置換
                     // 名前付き引数と位置引数を両方サポート
                     let mut positional_index = 0;
+                    let (mut bound_names, mut bound_values) = (Vec::new(), Vec::new());
 
                     for param in comp.params.iter() {
                         let arg_value =
@@ -52,9 +85,40 @@ where
                             };
 
                         substitute_parameter_in_nodes(&mut expanded_body, &param.name, &arg_value);
+                        bound_names.push(param.name.clone());
+                        bound_values.push(arg_value);
                         positional_index += 1;
                     }
 
+                    // ★ インスタンスキー: 親スコープ + 呼び出し位置（foreach内なら要素のキーを含む）
+                    let instance = instance_key(scope, name, node.line, node.column);
+                    let handlers = resolve_handlers(comp, args, handlers);
+                    if !comp.state.is_empty() || !comp.whens.is_empty() {
+                        bind_component_instance(
+                            comp,
+                            &instance,
+                            &ArgBinding {
+                                params: &bound_names,
+                                args: &bound_values,
+                            },
+                            &handlers,
+                            &mut expanded_body,
+                            state,
+                        );
+                    }
+                    // ★ ボディ内（ボタンの when ブロック以外）の emit も解決
+                    resolve_emits(&mut expanded_body, &handlers);
+
+                    // ネストしたコンポーネント呼び出しも展開
+                    let mut expanded_body = expand_nodes_in_scope(
+                        &expanded_body,
+                        app,
+                        state,
+                        &instance,
+                        &handlers,
+                        depth + 1,
+                    );
+
                     // デフォルトスタイルを適用（ComponentCallのスタイルがない場合のみ）
                     if let Some(default_style) = &comp.default_style {
                         apply_default_style_to_nodes(&mut expanded_body, default_style);
@@ -80,7 +144,8 @@ where
                 }
             }
             ViewNode::VStack(children) => {
-                let expanded_children = expand_component_calls_lightweight(children, app, _state);
+                let expanded_children =
+                    expand_nodes_in_scope(children, app, state, scope, handlers, depth);
                 result.push(WithSpan {
                     node: ViewNode::VStack(expanded_children),
                     line: node.line,
@@ -89,7 +154,8 @@ where
                 });
             }
             ViewNode::HStack(children) => {
-                let expanded_children = expand_component_calls_lightweight(children, app, _state);
+                let expanded_children =
+                    expand_nodes_in_scope(children, app, state, scope, handlers, depth);
                 result.push(WithSpan {
                     node: ViewNode::HStack(expanded_children),
                     line: node.line,
@@ -97,6 +163,65 @@ where
                     style: node.style.clone(),
                 });
             }
+            ViewNode::Dialog { id, open, body } => {
+                let body = expand_nodes_in_scope(body, app, state, scope, handlers, depth);
+                result.push(WithSpan {
+                    node: ViewNode::Dialog {
+                        id: id.clone(),
+                        open: open.clone(),
+                        body,
+                    },
+                    line: node.line,
+                    column: node.column,
+                    style: node.style.clone(),
+                });
+            }
+            ViewNode::ForEach {
+                var,
+                index_var,
+                iterable,
+                key,
+                body,
+            } => {
+                // ★ foreach内のインスタンスはアイテムのキー（key指定が無ければインデックス）で区別する
+                // プレースホルダーはレイアウト時に各要素のkeyの値で置き換えられる
+                let item_scope = join_scope(scope, &foreach_key_placeholder(var));
+                let expanded_body =
+                    expand_nodes_in_scope(body, app, state, &item_scope, handlers, depth);
+                result.push(WithSpan {
+                    node: ViewNode::ForEach {
+                        var: var.clone(),
+                        index_var: index_var.clone(),
+                        iterable: iterable.clone(),
+                        key: key.clone(),
+                        body: expanded_body,
+                    },
+                    line: node.line,
+                    column: node.column,
+                    style: node.style.clone(),
+                });
+            }
+            ViewNode::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                let then_body =
+                    expand_nodes_in_scope(then_body, app, state, scope, handlers, depth);
+                let else_body = else_body
+                    .as_ref()
+                    .map(|nodes| expand_nodes_in_scope(nodes, app, state, scope, handlers, depth));
+                result.push(WithSpan {
+                    node: ViewNode::If {
+                        condition: condition.clone(),
+                        then_body,
+                        else_body,
+                    },
+                    line: node.line,
+                    column: node.column,
+                    style: node.style.clone(),
+                });
+            }
             _ => {
                 result.push(node.clone());
             }
@@ -106,6 +231,521 @@ where
     result
 }
 
+/// インスタンスキーを生成（例: "Counter@12:5", "{item#key}>Counter@12:5"）
+fn instance_key(scope: &str, name: &str, line: usize, column: usize) -> String {
+    join_scope(scope, &format!("{}@{}:{}", name, line, column))
+}
+
+fn join_scope(scope: &str, segment: &str) -> String {
+    if scope.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{}>{}", scope, segment)
+    }
+}
+
+/// コンポーネントインスタンスにローカル状態とwhenブロックを結び付ける
+/// - state宣言を初期化（既存の値は保持）
+/// - ボディ内のstate参照を $local:<instance>/<name> に書き換え
+/// - whenブロック内のパラメータ参照を引数で置換
+/// - ボタン・テキスト入力のIDをインスタンスごとに一意化し、whenブロックを登録
+/// - whenブロック内の emit を呼び出し元のハンドラーに置き換え
+fn bind_component_instance<S>(
+    comp: &Component,
+    instance: &str,
+    params: &ArgBinding,
+    handlers: &HashMap<String, EmitHandler>,
+    body: &mut [WithSpan<ViewNode>],
+    state: &mut AppState<S>,
+) where
+    S: StateAccess + 'static,
+{
+    for decl in &comp.state {
+        let initial = state.eval_expr_from_ast(&decl.initial);
+        state.init_component_state(instance, &decl.name, initial);
+    }
+
+    let binding = InstanceBinding {
+        instance,
+        state_names: comp.state.iter().map(|d| d.name.as_str()).collect(),
+        element_ids: collect_element_ids(body),
+    };
+    binding.rewrite_nodes(body);
+
+    for when in &comp.whens {
+        let mut when = when.clone();
+        if let EventExpr::ButtonPressed(target) | EventExpr::ValueChanged(target) = &mut when.event
+            && binding.element_ids.contains(target.as_str())
+        {
+            *target = binding.element_id(target);
+        }
+        params.rewrite_nodes(&mut when.actions);
+        binding.rewrite_nodes(&mut when.actions);
+        // ★ emit は書き換え後に解決（ハンドラー側は親のスコープで書き換え済み）
+        resolve_emits(&mut when.actions, handlers);
+        state.component_whens.push(when);
+    }
+}
+
+fn collect_element_ids(nodes: &[WithSpan<ViewNode>]) -> HashSet<String> {
+    let mut ids = HashSet::new();
+    for node in nodes {
+        match &node.node {
+            ViewNode::Button { id, .. }
+            | ViewNode::TextInput { id, .. }
+            | ViewNode::Checkbox { id, .. }
+            | ViewNode::Switch { id, .. }
+            | ViewNode::RadioGroup { id, .. }
+            | ViewNode::Select { id, .. }
+            | ViewNode::Slider { id, .. }
+            | ViewNode::Stepper { id, .. } => {
+                ids.insert(id.clone());
+            }
+            ViewNode::Dialog { id, body, .. } => {
+                ids.insert(id.clone());
+                ids.extend(collect_element_ids(body));
+            }
+            ViewNode::VStack(children) | ViewNode::HStack(children) => {
+                ids.extend(collect_element_ids(children));
+            }
+            ViewNode::ForEach { body, .. } | ViewNode::DynamicSection { body, .. } => {
+                ids.extend(collect_element_ids(body));
+            }
+            ViewNode::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                ids.extend(collect_element_ids(then_body));
+                if let Some(else_nodes) = else_body {
+                    ids.extend(collect_element_ids(else_nodes));
+                }
+            }
+            _ => {}
+        }
+    }
+    ids
+}
+
+/// 親から渡されたコールバック（emit の呼び出し先）
+#[derive(Debug, Clone)]
+enum EmitHandler {
+    /// rust_call / onclick 関数名: ItemRow(on_select: select_item)
+    RustCall(String),
+    /// アクションブロック: ItemRow(on_select: |id| { ... })
+    Actions {
+        params: Vec<String>,
+        actions: Vec<WithSpan<ViewNode>>,
+    },
+}
+
+/// Function型パラメータに渡されたハンドラーを解決
+/// 親コンポーネントのハンドラー名がそのまま渡された場合は転送する
+fn resolve_handlers(
+    comp: &Component,
+    args: &[ComponentArg],
+    parent_handlers: &HashMap<String, EmitHandler>,
+) -> HashMap<String, EmitHandler> {
+    let mut handlers = HashMap::new();
+
+    for (positional_index, param) in comp.params.iter().enumerate() {
+        if !matches!(param.param_type, ComponentParamType::Function) {
+            continue;
+        }
+
+        let block = args.iter().find_map(|arg| match arg {
+            ComponentArg::Handler {
+                name,
+                params,
+                actions,
+            } if *name == param.name => Some((params, actions)),
+            _ => None,
+        });
+
+        let handler = if let Some((params, actions)) = block {
+            // ブロック内の emit は親のハンドラーで解決しておく
+            let mut actions = actions.clone();
+            resolve_emits(&mut actions, parent_handlers);
+            Some(EmitHandler::Actions {
+                params: params.clone(),
+                actions,
+            })
+        } else {
+            find_argument_value(args, &param.name, positional_index)
+                .or_else(|| param.default_value.clone())
+                .and_then(|expr| match expr {
+                    Expr::Ident(name) | Expr::Path(name) | Expr::String(name) => Some(
+                        parent_handlers
+                            .get(&name)
+                            .cloned()
+                            .unwrap_or(EmitHandler::RustCall(name)),
+                    ),
+                    _ => None,
+                })
+        };
+
+        if let Some(handler) = handler {
+            handlers.insert(param.name.clone(), handler);
+        }
+    }
+
+    handlers
+}
+
+/// emit ノードを呼び出し元のハンドラーに置き換える
+/// - rust_call: 関数名を差し替えた Emit ノード（引数は実行時に評価）
+/// - アクションブロック: ブロックのパラメータを emit の引数で置換したアクション列
+/// - ハンドラー未指定: 何もしない（オプショナルなコールバック）
+fn resolve_emits(nodes: &mut Vec<WithSpan<ViewNode>>, handlers: &HashMap<String, EmitHandler>) {
+    let mut resolved = Vec::with_capacity(nodes.len());
+    for mut node in nodes.drain(..) {
+        let ViewNode::Emit { name, args } = &node.node else {
+            for children in child_node_lists(&mut node.node) {
+                resolve_emits(children, handlers);
+            }
+            resolved.push(node);
+            continue;
+        };
+        match handlers.get(name) {
+            Some(EmitHandler::RustCall(function)) => resolved.push(WithSpan {
+                node: ViewNode::Emit {
+                    name: function.clone(),
+                    args: args.clone(),
+                },
+                ..node
+            }),
+            Some(EmitHandler::Actions { params, actions }) => {
+                let mut actions = actions.clone();
+                ArgBinding { params, args }.rewrite_nodes(&mut actions);
+                resolved.extend(actions);
+            }
+            None => log::debug!("emit {}: no handler bound by caller", name),
+        }
+    }
+    *nodes = resolved;
+}
+
+/// 子ノードのリスト（if/foreach/match/when のブロックなど）
+/// コンポーネント呼び出しのハンドラー引数は呼び出し先の展開時に解決するため含めない
+fn child_node_lists(node: &mut ViewNode) -> Vec<&mut Vec<WithSpan<ViewNode>>> {
+    match node {
+        ViewNode::VStack(children) | ViewNode::HStack(children) => vec![children],
+        ViewNode::Dialog { body, .. }
+        | ViewNode::DynamicSection { body, .. }
+        | ViewNode::ForEach { body, .. } => vec![body],
+        ViewNode::When { actions, .. } => vec![actions],
+        ViewNode::If {
+            then_body,
+            else_body,
+            ..
+        } => std::iter::once(then_body).chain(else_body).collect(),
+        ViewNode::Match { arms, default, .. } => arms
+            .iter_mut()
+            .map(|(_, body)| body)
+            .chain(default)
+            .collect(),
+        ViewNode::Http {
+            on_success,
+            on_error,
+            ..
+        } => [on_success, on_error]
+            .into_iter()
+            .flatten()
+            .map(|block| &mut block.actions)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 展開時の書き換え規則（式・代入先パス・要素ID）
+trait NodeRewriter {
+    /// Ident/Path を書き換える場合は新しい式を返す
+    fn rewrite_leaf(&self, path: &str) -> Option<Expr>;
+
+    /// set などの代入先パス
+    fn rewrite_target(&self, _path: &mut String) {}
+
+    fn rewrite_element_id(&self, _id: &mut String) {}
+
+    fn rewrite_expr(&self, expr: &mut Expr) {
+        match expr {
+            Expr::Ident(name) | Expr::Path(name) => {
+                if let Some(new_expr) = self.rewrite_leaf(name) {
+                    *expr = new_expr;
+                }
+            }
+            Expr::Array(items) => items.iter_mut().for_each(|e| self.rewrite_expr(e)),
+            Expr::Object(fields) => fields.iter_mut().for_each(|(_, e)| self.rewrite_expr(e)),
+            Expr::CalcExpr(inner) => self.rewrite_expr(inner),
+            Expr::Match {
+                expr,
+                arms,
+                default,
+            } => {
+                self.rewrite_expr(expr);
+                for arm in arms {
+                    for case_expr in arm.case.exprs_mut() {
+                        self.rewrite_expr(case_expr);
+                    }
+                    self.rewrite_expr(&mut arm.value);
+                }
+                if let Some(default) = default {
+                    self.rewrite_expr(default);
+                }
+            }
+            Expr::FunctionCall { args, .. } => args.iter_mut().for_each(|e| self.rewrite_expr(e)),
+            Expr::BinaryOp { left, right, .. } => {
+                self.rewrite_expr(left);
+                self.rewrite_expr(right);
+            }
+            Expr::Conditional {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                self.rewrite_expr(condition);
+                self.rewrite_expr(then_expr);
+                self.rewrite_expr(else_expr);
+            }
+            _ => {}
+        }
+    }
+
+    fn rewrite_nodes(&self, nodes: &mut [WithSpan<ViewNode>]) {
+        for node in nodes {
+            self.rewrite_node(&mut node.node);
+        }
+    }
+
+    fn rewrite_node(&self, node: &mut ViewNode) {
+        match node {
+            ViewNode::Text { args, .. }
+            | ViewNode::RustCall { args, .. }
+            | ViewNode::Emit { args, .. } => {
+                args.iter_mut().for_each(|e| self.rewrite_expr(e));
+            }
+            ViewNode::Button { id, onclick, .. } => {
+                self.rewrite_element_id(id);
+                if let Some(expr) = onclick {
+                    self.rewrite_expr(expr);
+                }
+            }
+            ViewNode::TextInput {
+                id,
+                value,
+                bind,
+                on_change,
+                ..
+            } => {
+                self.rewrite_element_id(id);
+                for expr in [value, bind, on_change].into_iter().flatten() {
+                    self.rewrite_expr(expr);
+                }
+            }
+            ViewNode::Checkbox { id, bind, .. } | ViewNode::Switch { id, bind, .. } => {
+                self.rewrite_element_id(id);
+                self.rewrite_expr(bind);
+            }
+            ViewNode::RadioGroup { id, bind, options }
+            | ViewNode::Select {
+                id, bind, options, ..
+            } => {
+                self.rewrite_element_id(id);
+                self.rewrite_expr(bind);
+                self.rewrite_expr(options);
+            }
+            ViewNode::Slider {
+                id,
+                bind,
+                min,
+                max,
+                step,
+            }
+            | ViewNode::Stepper {
+                id,
+                bind,
+                min,
+                max,
+                step,
+            } => {
+                self.rewrite_element_id(id);
+                self.rewrite_expr(bind);
+                for expr in [min, max, step].into_iter().flatten() {
+                    self.rewrite_expr(expr);
+                }
+            }
+            ViewNode::Dialog { id, open, body } => {
+                self.rewrite_element_id(id);
+                self.rewrite_expr(open);
+                self.rewrite_nodes(body);
+            }
+            ViewNode::Toast { message, duration } => {
+                self.rewrite_expr(message);
+                if let Some(expr) = duration {
+                    self.rewrite_expr(expr);
+                }
+            }
+            ViewNode::NavigateTo { params, .. } => {
+                for (_, expr) in params {
+                    self.rewrite_expr(expr);
+                }
+            }
+            ViewNode::CallFlow { into, params, .. } => {
+                if let Some(path) = into {
+                    self.rewrite_target(path);
+                }
+                for (_, expr) in params {
+                    self.rewrite_expr(expr);
+                }
+            }
+            ViewNode::Finish { value: Some(expr) } => self.rewrite_expr(expr),
+            ViewNode::Http {
+                url,
+                into,
+                params,
+                on_success,
+                on_error,
+                ..
+            } => {
+                self.rewrite_expr(url);
+                if let Some(path) = into {
+                    self.rewrite_target(path);
+                }
+                for (_, expr) in params {
+                    self.rewrite_expr(expr);
+                }
+                for block in [on_success, on_error].into_iter().flatten() {
+                    self.rewrite_nodes(&mut block.actions);
+                }
+            }
+            ViewNode::VStack(children) | ViewNode::HStack(children) => {
+                self.rewrite_nodes(children);
+            }
+            ViewNode::ComponentCall { args, .. } => {
+                for arg in args {
+                    match arg {
+                        ComponentArg::Positional(expr) | ComponentArg::Named(_, expr) => {
+                            self.rewrite_expr(expr)
+                        }
+                        ComponentArg::Handler { actions, .. } => self.rewrite_nodes(actions),
+                    }
+                }
+            }
+            ViewNode::DynamicSection { body, .. } => self.rewrite_nodes(body),
+            ViewNode::Match {
+                expr,
+                arms,
+                default,
+            } => {
+                self.rewrite_expr(expr);
+                for (case, body) in arms {
+                    for case_expr in case.exprs_mut() {
+                        self.rewrite_expr(case_expr);
+                    }
+                    self.rewrite_nodes(body);
+                }
+                if let Some(default) = default {
+                    self.rewrite_nodes(default);
+                }
+            }
+            ViewNode::ForEach { iterable, body, .. } => {
+                self.rewrite_expr(iterable);
+                self.rewrite_nodes(body);
+            }
+            ViewNode::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.rewrite_expr(condition);
+                self.rewrite_nodes(then_body);
+                if let Some(else_nodes) = else_body {
+                    self.rewrite_nodes(else_nodes);
+                }
+            }
+            ViewNode::Set { path, value, .. }
+            | ViewNode::ListAppend { path, value }
+            | ViewNode::ListInsert { path, value, .. }
+            | ViewNode::ListRemove { path, value } => {
+                self.rewrite_target(path);
+                self.rewrite_expr(value);
+            }
+            ViewNode::Toggle { path } | ViewNode::ListClear { path } => self.rewrite_target(path),
+            ViewNode::LetDecl { value, .. } => self.rewrite_expr(value),
+            ViewNode::When { actions, .. } => self.rewrite_nodes(actions),
+            _ => {}
+        }
+    }
+}
+
+/// 1インスタンス分の書き換え規則（ローカル状態・要素ID）
+struct InstanceBinding<'a> {
+    instance: &'a str,
+    state_names: Vec<&'a str>,
+    element_ids: HashSet<String>,
+}
+
+impl InstanceBinding<'_> {
+    fn element_id(&self, id: &str) -> String {
+        format!("{}@{}", id, self.instance)
+    }
+
+    /// state名で始まるパスをローカル状態のパスに変換
+    fn state_path(&self, path: &str) -> Option<String> {
+        let head = path.split('.').next().unwrap_or(path);
+        self.state_names
+            .contains(&head)
+            .then(|| component_state_path(self.instance, path))
+    }
+}
+
+impl NodeRewriter for InstanceBinding<'_> {
+    fn rewrite_leaf(&self, path: &str) -> Option<Expr> {
+        self.state_path(path).map(Expr::Path)
+    }
+
+    fn rewrite_target(&self, path: &mut String) {
+        if let Some(local) = self.state_path(path.trim()) {
+            *path = local;
+        }
+    }
+
+    fn rewrite_element_id(&self, id: &mut String) {
+        if self.element_ids.contains(id.as_str()) {
+            *id = self.element_id(id);
+        }
+    }
+}
+
+/// アクションブロックのパラメータを emit の引数で置換する規則
+struct ArgBinding<'a> {
+    params: &'a [String],
+    args: &'a [Expr],
+}
+
+impl NodeRewriter for ArgBinding<'_> {
+    fn rewrite_leaf(&self, path: &str) -> Option<Expr> {
+        let (head, rest) = match path.split_once('.') {
+            Some((head, rest)) => (head, Some(rest)),
+            None => (path, None),
+        };
+        let index = self.params.iter().position(|p| p == head)?;
+        let arg = self
+            .args
+            .get(index)
+            .cloned()
+            .unwrap_or(Expr::String(String::new()));
+        match (rest, arg) {
+            (None, arg) => Some(arg),
+            // id.name のようなプロパティアクセスはパスとして連結
+            (Some(rest), Expr::Path(base) | Expr::Ident(base)) => {
+                Some(Expr::Path(format!("{}.{}", base, rest)))
+            }
+            (Some(_), _) => None,
+        }
+    }
+}
+
 /// ノード内のパラメータを置換する
 pub fn substitute_parameter_in_nodes(
     nodes: &mut [WithSpan<ViewNode>],
@@ -156,6 +796,10 @@ fn substitute_parameter_in_node(node: &mut ViewNode, param_name: &str, arg: &Exp
         ViewNode::VStack(children) | ViewNode::HStack(children) => {
             substitute_parameter_in_nodes(children, param_name, arg);
         }
+        ViewNode::Dialog { open, body, .. } => {
+            substitute_expr_parameter(open, param_name, arg);
+            substitute_parameter_in_nodes(body, param_name, arg);
+        }
         ViewNode::If {
             condition,
             then_body,
@@ -329,3 +973,145 @@ fn find_argument_value(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::event::step_whens;
+    use crate::parser::parse::parse_nilo;
+    use crate::ui::event::UIEvent;
+    use crate::ui::{LayoutParams, layout_vstack};
+
+    #[derive(Default, nilo_state_access_derive::StateAccess)]
+    struct ListState {
+        items: serde_json::Value,
+    }
+
+    /// 展開・レイアウトして (テキスト, ボタンID) の一覧を返す
+    fn render(app: &App, state: &mut AppState<ListState>) -> (Vec<String>, Vec<String>) {
+        let body = expand_component_calls_lightweight(&app.timelines[0].body, app, state);
+        let eval = |e: &Expr| state.eval_expr_from_ast(e);
+        let layouted = layout_vstack(&body, LayoutParams::default(), app, &eval, &|_| (0, 0));
+        let mut texts = Vec::new();
+        let mut buttons = Vec::new();
+        for node in layouted {
+            match &node.node.node {
+                ViewNode::Text { format, .. } => texts.push(format.clone()),
+                ViewNode::Button { id, .. } => buttons.push(id.clone()),
+                _ => {}
+            }
+        }
+        (texts, buttons)
+    }
+
+    #[test]
+    fn test_keyed_foreach_component_state() {
+        let app = parse_nilo(
+            r#"
+            component Counter(label: String) {
+                state count: Number = 0
+                Text("{}: {}", label, count)
+                Button(id: "inc", label: "+")
+
+                when user.click(inc) {
+                    set count = count + 1
+                }
+            }
+
+            flow { start: Main }
+
+            timeline Main {
+                foreach item in state.items key item.id {
+                    Counter(item.name)
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let mut state = AppState::new(
+            ListState {
+                items: serde_json::json!([{"id":"a","name":"Alpha"},{"id":"b","name":"Beta"}]),
+            },
+            "Main".into(),
+        );
+
+        let (texts, buttons) = render(&app, &mut state);
+        assert_eq!(texts, vec!["Alpha: 0", "Beta: 0"]);
+        assert!(buttons[1].starts_with("inc@b>Counter@"), "{:?}", buttons);
+
+        // Beta の行のカウンターを増やす
+        let click = UIEvent::ButtonPressed {
+            id: buttons[1].clone(),
+        };
+        step_whens(&app, &mut state, &[click]);
+        assert_eq!(render(&app, &mut state).0, vec!["Alpha: 0", "Beta: 1"]);
+
+        // 並べ替え・削除してもローカル状態は要素に付いていく
+        let items = state.custom_state.items.as_array_mut().unwrap();
+        items.reverse();
+        assert_eq!(render(&app, &mut state).0, vec!["Beta: 1", "Alpha: 0"]);
+        state.custom_state.items.as_array_mut().unwrap().remove(1);
+        assert_eq!(render(&app, &mut state).0, vec!["Beta: 1"]);
+    }
+
+    #[test]
+    fn test_emit_resolves_nested_blocks() {
+        use crate::engine::rust_call::register_rust_call;
+        use std::sync::Mutex;
+
+        static RECEIVED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        register_rust_call("test_emit_record_code", |args: &[Expr]| {
+            let value = match &args[0] {
+                Expr::String(s) => format!("string:{}", s),
+                other => format!("{:?}", other),
+            };
+            RECEIVED.lock().unwrap().push(value);
+        });
+
+        let app = parse_nilo(
+            r#"
+            component CodeRow(code: String, on_select: Function) {
+                Button(id: "row", label: "Pick")
+
+                Button(id: "reload", label: "Reload")
+
+                when user.click(row) {
+                    emit on_select(code)
+                }
+                when user.click(reload) {
+                    http_get("/codes", on_success: |res| { emit on_select(res) })
+                }
+            }
+
+            flow { start: Main }
+
+            timeline Main {
+                CodeRow("007", on_select: test_emit_record_code)
+            }
+            "#,
+        )
+        .unwrap();
+        let mut state = AppState::new(ListState::default(), "Main".into());
+
+        let (_, buttons) = render(&app, &mut state);
+        let click = UIEvent::ButtonPressed {
+            id: buttons[0].clone(),
+        };
+        step_whens(&app, &mut state, &[click]);
+        // "007" は数値に丸められずに届く
+        assert_eq!(*RECEIVED.lock().unwrap(), vec!["string:007"]);
+
+        // ネストしたブロック内の emit も呼び出し元のハンドラーに解決される
+        let ViewNode::Http {
+            on_success: Some(block),
+            ..
+        } = &state.component_whens[1].actions[0].node
+        else {
+            panic!("http_get が見つかりません");
+        };
+        assert!(matches!(
+            &block.actions[0].node,
+            ViewNode::Emit { name, .. } if name == "test_emit_record_code"
+        ));
+    }
+}
@@ -4,12 +4,52 @@
 use super::component::expand_component_calls_lightweight;
 use super::dynamic_section::collect_dynamic_sections;
 use super::layout::*;
-use crate::engine::state::{AppState, StateAccess};
+use super::render::{render_reload_error, render_toasts, render_tooltip};
+use crate::engine::resource::start_resource_requests;
+use crate::engine::state::{
+    AppState, DIALOG_BACKDROP_ID, OVERLAY_BACKDROP_ID, StateAccess, TOOLTIP_DELAY_MS, TooltipHover,
+};
 use crate::parser::ast::App;
 use crate::stencil::stencil::Stencil;
+use crate::ui::layout::virtual_render_range;
 
 pub struct Engine;
 
+/// ★ オーバーレイ背景のクリック領域: 表示中のウィンドウから層の本体（`bounds`）を除いた部分
+///
+/// 本体の上下左右の最大4つの矩形になる。座標はクリック判定と同じスクロール込みの座標
+fn backdrop_hit_rects(
+    id: &str,
+    scroll_offset: [f32; 2],
+    window_size: [f32; 2],
+    bounds: Option<([f32; 2], [f32; 2])>,
+) -> Vec<(String, [f32; 2], [f32; 2])> {
+    let [left, top] = [-scroll_offset[0], -scroll_offset[1]];
+    let [right, bottom] = [left + window_size[0], top + window_size[1]];
+    let Some((position, size)) = bounds else {
+        return vec![(id.to_string(), [left, top], window_size)];
+    };
+    let [x0, y0] = [
+        position[0].clamp(left, right),
+        position[1].clamp(top, bottom),
+    ];
+    let [x1, y1] = [
+        (position[0] + size[0]).clamp(x0, right),
+        (position[1] + size[1]).clamp(y0, bottom),
+    ];
+
+    [
+        ([left, top], [right - left, y0 - top]),
+        ([left, y1], [right - left, bottom - y1]),
+        ([left, y0], [x0 - left, y1 - y0]),
+        ([x1, y0], [right - x1, y1 - y0]),
+    ]
+    .into_iter()
+    .filter(|(_, size)| size[0] > 0.0 && size[1] > 0.0)
+    .map(|(position, size)| (id.to_string(), position, size))
+    .collect()
+}
+
 impl Engine {
     /// 静的部分のレイアウト（キャッシュ対応）
     pub fn layout_static_part<S>(
@@ -37,7 +77,8 @@ impl Engine {
         } else {
             "default".to_string()
         };
-        let params = make_layout_params(window_size, default_font);
+        let mut params = make_layout_params(window_size, default_font);
+        params.viewport = Some(state.layout_viewport(window_size));
         layout_nodes_lightweight(
             app,
             state,
@@ -82,7 +123,8 @@ impl Engine {
         } else {
             "default".to_string()
         };
-        let params = make_layout_params(window_size, default_font.clone());
+        let mut params = make_layout_params(window_size, default_font.clone());
+        params.viewport = Some(state.layout_viewport(window_size));
         let eval_fn = |e: &Expr| state.eval_expr_from_ast(e);
         let get_img_size = |path: &str| state.get_image_size(path);
         let layouted_all = layout_vstack(
@@ -133,6 +175,28 @@ impl Engine {
             return (Vec::new(), Vec::new(), Vec::new());
         };
 
+        // ★ 表示中のタイムラインのタイマーを開始（遷移・ホットリロードで止めたものもここで開始し直す）
+        state.timeline_timers.sync(tl);
+
+        // ★ リソースの引数を評価し、値が変わったものだけ取得し直す（前の取得は取り消す）
+        let resource_args = tl
+            .resources
+            .iter()
+            .map(|r| r.args.iter().map(|a| state.eval_expr_from_ast(a)).collect())
+            .collect();
+        let requests = state.resources.sync(&tl.name, &tl.resources, resource_args);
+        start_resource_requests(state, requests);
+
+        // ★ 購読のURLを評価し、未接続・URLが変わったものに接続する
+        let subscription_urls = tl
+            .subscriptions
+            .iter()
+            .map(|s| state.eval_expr_from_ast(&s.url))
+            .collect();
+        state
+            .subscriptions
+            .sync(&tl.name, &tl.subscriptions, subscription_urls);
+
         // ★ ロジック処理: コンポーネント展開とローカル変数初期化（タイムライン変更時のみ）
         if state.expanded_body.is_none() {
             let expanded_nodes = expand_component_calls_lightweight(&tl.body, app, state);
@@ -166,12 +230,32 @@ impl Engine {
             state.cached_window_size = Some(window_size);
         }
 
+        // ★ 仮想化したforeach: 表示範囲が前回レイアウトした行の範囲を外れたら静的部分を作り直す
+        // ScrollContainer内のリストはコンテナの表示範囲で行を選ぶため、コンテナがスクロールしたら作り直す
+        let visible = state.visible_range(window_size);
+        if state.virtual_render_range.is_some_and(|[top, bottom]| {
+            visible[0] < top
+                || visible[1] > bottom
+                || state.virtual_container_offsets != state.scroll_container_offsets
+        }) {
+            state.static_stencils = None;
+            state.static_buttons.clear();
+            state.static_text_inputs.clear();
+        }
+
         let expanded = state.expanded_body.as_ref().unwrap().clone();
 
         // 静的部分はキャッシュを使用
         let (mut stencils, mut buttons, mut text_inputs) = if let Some(cached) = &state.static_stencils {
+            // 動的パートが前のフレームで登録したDialog・ツールチップは作り直す
+            state.dialog_layers.truncate(state.static_dialog_count);
+            state.tooltip_targets.truncate(state.static_tooltip_count);
             (cached.clone(), state.static_buttons.clone(), state.static_text_inputs.clone())
         } else {
+            // オーバーレイ層は所有する部品のレイアウト時に作り直す
+            state.overlay_layer = None;
+            state.dialog_layers.clear();
+            state.tooltip_targets.clear();
             let (s, b, t) = Self::layout_static_part(
                 app,
                 state,
@@ -184,6 +268,11 @@ impl Engine {
             state.static_stencils = Some(s.clone());
             state.static_buttons = b.clone();
             state.static_text_inputs = t.clone();
+            state.static_dialog_count = state.dialog_layers.len();
+            state.static_tooltip_count = state.tooltip_targets.len();
+            state.virtual_render_range =
+                contains_virtual_list(&expanded).then(|| virtual_render_range(visible));
+            state.virtual_container_offsets = state.scroll_container_offsets.clone();
             (s, b, t)
         };
         let (ds, db, dt) = Self::layout_dynamic_part(
@@ -199,6 +288,9 @@ impl Engine {
         stencils.extend(ds);
         buttons.extend(db);
         text_inputs.extend(dt);
+        // ★ 今回表示しなかったforeachの要素のレイアウト結果・コンポーネントの状態は破棄する
+        let rendered = state.foreach_layout_cache.end_frame();
+        state.retain_rendered_component_states(&rendered);
 
         // ★ タイムラインの背景色を追加（最背面に配置）
         if let Some(bg_color) = &tl.background {
@@ -229,6 +321,115 @@ impl Engine {
             );
         }
 
+        // ★ Dialog: 開いた順に重ね、最前面のDialogだけがクリック・入力を受け付ける
+        if let Some(top) = state.dialog_layers.last().cloned() {
+            for layer in &state.dialog_layers {
+                stencils.push(Stencil::Overlay {
+                    children: layer.stencils.clone(),
+                });
+            }
+            // 背景は最後に置く（先頭の一致のみを使うランタイムでもDialog内が優先される）
+            buttons = top.hit_rects;
+            buttons.extend(backdrop_hit_rects(
+                DIALOG_BACKDROP_ID,
+                state.scroll_offset,
+                window_size,
+                top.bounds,
+            ));
+            text_inputs = top.text_inputs;
+
+            // フォーカストラップ: Dialogの外にあるフォーカスは外す
+            if state
+                .focused_text_input
+                .as_ref()
+                .is_some_and(|id| !text_inputs.iter().any(|(t, _, _)| t == id))
+            {
+                state.blur_text_input();
+            }
+            if state
+                .focused_form_control
+                .as_ref()
+                .is_some_and(|id| !buttons.iter().any(|(b, _, _)| b == id))
+            {
+                state.focused_form_control = None;
+            }
+        }
+
+        // ★ オーバーレイ層: 最前面に描画し、クリック判定は下層より優先する
+        // 開いている間は下層のボタン・テキスト入力を無効化し、外側のクリックは背景で受け止める
+        if state.open_overlay.is_some() {
+            match state.overlay_layer.clone() {
+                Some(layer) => {
+                    stencils.push(Stencil::Overlay {
+                        children: layer.stencils,
+                    });
+                    // 背景は最後に置く（先頭の一致のみを使うランタイムでも選択肢が優先される）
+                    buttons = layer.hit_rects;
+                    buttons.extend(backdrop_hit_rects(
+                        OVERLAY_BACKDROP_ID,
+                        state.scroll_offset,
+                        window_size,
+                        layer.bounds,
+                    ));
+                    text_inputs.clear();
+                }
+                // 所有する部品が表示されなくなった場合は閉じる
+                None => state.close_overlay(),
+            }
+        }
+
+        let default_font = tl.font.clone().unwrap_or_else(|| "default".to_string());
+        let now = crate::engine::timer::now_ms();
+
+        // ★ ツールチップ: 同じノードに一定時間ホバーしたら表示（最前面の層のノードのみ対象）
+        let hovered = if state.open_overlay.is_some() {
+            None
+        } else {
+            state
+                .dialog_layers
+                .last()
+                .map_or(&state.tooltip_targets, |top| &top.tooltip_targets)
+                .iter()
+                .find(|target| target.contains(mouse_pos))
+                .cloned()
+        };
+        state.tooltip_hover = match (hovered, state.tooltip_hover.take()) {
+            (Some(target), Some(hover))
+                if hover.target.position == target.position && hover.target.size == target.size =>
+            {
+                Some(TooltipHover { target, ..hover })
+            }
+            (Some(target), _) => Some(TooltipHover { target, since: now }),
+            (None, _) => None,
+        };
+        if let Some(hover) = &state.tooltip_hover
+            && now - hover.since >= TOOLTIP_DELAY_MS
+        {
+            stencils.push(Stencil::Overlay {
+                children: render_tooltip(
+                    &hover.target,
+                    window_size,
+                    state.scroll_offset[1],
+                    &default_font,
+                ),
+            });
+        }
+
+        // ★ トースト: 表示期限を過ぎたものを取り除き、残りを最前面に表示（クリックは受け付けない）
+        state.toasts.retain(|toast| toast.expires_at > now);
+        if !state.toasts.is_empty() {
+            stencils.push(Stencil::Overlay {
+                children: render_toasts(&state.toasts, window_size, &default_font),
+            });
+        }
+
+        // ★ ホットリロードの読み込みエラー（前回のAppのまま動かしていることを画面上で知らせる）
+        if let Some(message) = &state.reload_error {
+            stencils.push(Stencil::Overlay {
+                children: render_reload_error(message, window_size, &default_font),
+            });
+        }
+
         (stencils, buttons, text_inputs)
     }
 
@@ -267,3 +468,589 @@ impl Engine {
         super::event::apply_action(app, state, action)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Engine, backdrop_hit_rects};
+    use crate::engine::state::{AppState, DIALOG_BACKDROP_ID, StateAccess, TOOLTIP_DELAY_MS};
+    use crate::parser::ast::{App, Expr, ViewNode, WithSpan};
+    use crate::parser::parse::parse_nilo;
+    use crate::ui::event::UIEvent;
+    use crate::ui::{LayoutParams, layout_vstack};
+
+    #[derive(Default, nilo_state_access_derive::StateAccess)]
+    struct Fields {
+        name: String,
+        age: u32,
+        items: serde_json::Value,
+        user: serde_json::Value,
+        seen: String,
+        log: String,
+        agree: bool,
+        dark: bool,
+        plan: String,
+        size: String,
+        qty: i32,
+        volume: i32,
+        open: bool,
+    }
+
+    const WINDOW: [f32; 2] = [800.0, 600.0];
+
+    fn app(body: &str) -> App {
+        parse_nilo(&format!(
+            "flow {{ start: Main }}\ntimeline Main {{\n{}\n}}",
+            body
+        ))
+        .unwrap()
+    }
+
+    fn state(fields: &[(&str, &str)]) -> AppState<Fields> {
+        let mut custom = Fields::default();
+        for (path, value) in fields {
+            custom.set(path, value.to_string()).unwrap();
+        }
+        AppState::new(custom, "Main".into())
+    }
+
+    fn field(state: &AppState<Fields>, name: &str) -> String {
+        state.custom_state.get_field(name).unwrap_or_default()
+    }
+
+    /// 1フレーム分: レイアウトし直してからイベントを処理し、クリック領域を返す
+    fn frame(
+        app: &App,
+        state: &mut AppState<Fields>,
+        events: &[UIEvent],
+    ) -> Vec<(String, [f32; 2], [f32; 2])> {
+        state.static_stencils = None;
+        let (_, buttons, _) =
+            Engine::layout_and_stencil(app, state, [0.0, 0.0], false, false, WINDOW);
+        Engine::step_whens(app, state, events);
+        buttons
+    }
+
+    #[test]
+    fn test_text_input_bind_writes_state() {
+        let app = app("TextInput(name, bind: state.name)");
+        let mut state = state(&[("name", "")]);
+        frame(&app, &mut state, &[]);
+        state.set_text_input_value("name".into(), "Ann".into());
+        assert_eq!(field(&state, "name"), "Ann");
+    }
+
+    #[test]
+    fn test_number_bind_rejects_non_numeric_input() {
+        let app = app(r#"TextInput(age, bind: state.age, type: "number")"#);
+        let mut state = state(&[("age", "3")]);
+        frame(&app, &mut state, &[]);
+
+        state.set_text_input_value("age".into(), "abc".into());
+        assert_eq!(field(&state, "age"), "3");
+        assert!(state.get_text_input_error("age").is_some());
+        // 入力中のテキストはそのまま残す
+        assert_eq!(state.get_text_input_value("age"), "abc");
+
+        state.set_text_input_value("age".into(), " 42 ".into());
+        assert_eq!(field(&state, "age"), "42");
+        assert_eq!(state.get_text_input_error("age"), None);
+    }
+
+    #[test]
+    fn test_external_change_updates_bound_input() {
+        let app = app("TextInput(name, bind: state.name)");
+        let mut state = state(&[("name", "Ann")]);
+        frame(&app, &mut state, &[]);
+        assert_eq!(state.get_text_input_value("name"), "Ann");
+
+        state.custom_state.name = "Bob".into();
+        frame(&app, &mut state, &[]);
+        assert_eq!(state.get_text_input_value("name"), "Bob");
+    }
+
+    /// レイアウトしたforeachの要素の (ノード, テキスト, y座標)
+    fn foreach_rows(
+        app: &App,
+        state: &AppState<Fields>,
+    ) -> Vec<(*const WithSpan<ViewNode>, String, f32)> {
+        let body = state.expanded_body.clone().unwrap();
+        let mut params = LayoutParams::with_window_size(WINDOW);
+        params.viewport = Some(state.layout_viewport(WINDOW));
+        let eval = |e: &Expr| state.eval_expr_from_ast(e);
+        layout_vstack(&body, params, app, &eval, &|_| (0, 0))
+            .iter()
+            .filter_map(|n| match &n.node.node {
+                ViewNode::Text { format, .. } => Some((
+                    n.node as *const WithSpan<ViewNode>,
+                    format.clone(),
+                    n.position[1],
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keyed_foreach_reuses_layout_across_frames() {
+        let app = app(r#"foreach item in state.items key item.id { Text("{}", item.name) }"#);
+        let mut state = state(&[("items", r#"[{"id":1,"name":"a"},{"id":2,"name":"b"}]"#)]);
+        frame(&app, &mut state, &[]);
+        let before = foreach_rows(&app, &state);
+        assert_eq!(before[0].1, "a");
+
+        // 並べ替えても同じkeyの要素はレイアウト結果を移動して再利用する
+        state.custom_state.items = serde_json::json!([{"id":2,"name":"b"},{"id":1,"name":"a"}]);
+        frame(&app, &mut state, &[]);
+        let after = foreach_rows(&app, &state);
+        assert_eq!(after[1].1, "a");
+        assert!(std::ptr::eq(before[0].0, after[1].0));
+        assert!(std::ptr::eq(before[1].0, after[0].0));
+        assert_eq!(after[0].2, before[0].2);
+        assert!(after[1].2 > after[0].2);
+
+        // 値が変わった要素はレイアウトし直す
+        state.custom_state.items = serde_json::json!([{"id":2,"name":"b"},{"id":1,"name":"c"}]);
+        frame(&app, &mut state, &[]);
+        let changed = foreach_rows(&app, &state);
+        assert_eq!(changed[1].1, "c");
+        assert!(!std::ptr::eq(after[1].0, changed[1].0));
+
+        // 表示されなくなった要素の結果は、表示されないフレームを終えたら破棄する
+        // （1フレーム目は直前の foreach_rows で使った結果が残る）
+        state.custom_state.items = serde_json::json!([{"id":2,"name":"b"}]);
+        frame(&app, &mut state, &[]);
+        frame(&app, &mut state, &[]);
+        assert_eq!(state.foreach_layout_cache.len(), 1);
+    }
+
+    #[test]
+    fn test_foreach_component_state_dropped_when_item_not_rendered() {
+        let app = parse_nilo(
+            r#"
+            component Counter(label: String) {
+                state count: Number = 0
+                Text("{}: {}", label, count)
+                Button(id: "inc", label: "+")
+                when user.click(inc) { set count = count + 1 }
+            }
+            flow { start: Main }
+            timeline Main {
+                foreach item in state.items key item.id { Counter(item.name) }
+            }
+            "#,
+        )
+        .unwrap();
+        let both = serde_json::json!([{"id":"a","name":"Alpha"},{"id":"b","name":"Beta"}]);
+        let mut state = state(&[]);
+        state.custom_state.items = both.clone();
+        let buttons = frame(&app, &mut state, &[]);
+        let inc = buttons
+            .iter()
+            .find(|(id, _, _)| id.starts_with("inc@b>"))
+            .unwrap();
+        frame(&app, &mut state, &[click(&inc.0)]);
+        let instances = |state: &AppState<Fields>| {
+            state
+                .component_states
+                .keys()
+                .filter(|key| key.starts_with("b>"))
+                .count()
+        };
+        assert_eq!(instances(&state), 1);
+
+        // 表示されなかったフレームの終わりに、その要素のインスタンスの状態を破棄する
+        state.custom_state.items = serde_json::json!([{"id":"a","name":"Alpha"}]);
+        frame(&app, &mut state, &[]);
+        assert_eq!(instances(&state), 0);
+
+        // 再び表示したときは初期値から始まる
+        state.custom_state.items = both;
+        frame(&app, &mut state, &[]);
+        let texts: Vec<_> = foreach_rows(&app, &state)
+            .into_iter()
+            .map(|(_, text, _)| text)
+            .collect();
+        assert_eq!(texts, vec!["Alpha: 0", "Beta: 0"]);
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Profile {
+        age: u32,
+    }
+
+    #[derive(nilo_state_access_derive::StateAccess)]
+    struct Member {
+        profile: Profile,
+    }
+
+    #[test]
+    fn test_nested_bind_writes_derived_struct_field() {
+        let app = app(r#"TextInput(age, bind: state.profile.age, type: "number")"#);
+        let member = Member {
+            profile: Profile { age: 30 },
+        };
+        let mut state = AppState::new(member, "Main".into());
+        state.static_stencils = None;
+        Engine::layout_and_stencil(&app, &mut state, [0.0, 0.0], false, false, WINDOW);
+        assert_eq!(state.get_text_input_value("age"), "30");
+
+        state.set_text_input_value("age".into(), "31".into());
+        assert_eq!(state.custom_state.profile.age, 31);
+        state.set_text_input_value("age".into(), "abc".into());
+        assert_eq!(state.custom_state.profile.age, 31);
+        assert!(state.get_text_input_error("age").is_some());
+    }
+
+    #[test]
+    fn test_http_completions_after_navigation_still_apply_into() {
+        use crate::engine::http::{self, HttpCompletion, HttpHandlers, HttpResponse};
+        use crate::parser::ast::ViewNode;
+
+        let app = parse_nilo(
+            r#"
+            flow {
+                start: Main
+                Main -> Next
+            }
+            timeline Main {
+                when user.click(go) {
+                    http_get("/api/go", on_success: |res| { navigate_to(Next) })
+                }
+            }
+            timeline Next {}
+            "#,
+        )
+        .unwrap();
+        let ViewNode::Http { on_success, .. } = &app.timelines[0].whens[0].actions[0].node else {
+            unreachable!();
+        };
+        let completion = |into: Option<&str>, on_success, body: &str| HttpCompletion {
+            handlers: HttpHandlers {
+                timeline: "Main".into(),
+                into: into.map(str::to_string),
+                on_success,
+                on_error: None,
+            },
+            result: Ok(HttpResponse {
+                status: 200,
+                body: body.into(),
+            }),
+        };
+        // 他のテストのリクエストと重ならないid
+        let (first, second) = (u64::MAX - 1, u64::MAX - 2);
+        http::store_completion(first, completion(None, on_success.clone(), "{}"));
+        let user = r#"{"name":"Ann"}"#;
+        http::store_completion(second, completion(Some("state.user"), None, user));
+
+        let mut state = state(&[]);
+        let events = [
+            UIEvent::HttpResponse { id: first },
+            UIEvent::HttpResponse { id: second },
+        ];
+        let next = Engine::step_whens(&app, &mut state, &events);
+        assert_eq!(next.as_deref(), Some("Next"));
+        assert_eq!(field(&state, "user"), user);
+        assert!(http::take_completion(second).is_none());
+    }
+
+    #[test]
+    fn test_subscription_messages_are_drained_when_first_navigates() {
+        use crate::engine::subscription::{
+            self, Backoff, SubscriptionMessage, TimelineSubscriptions,
+        };
+        use crate::parser::ast::SubscriptionKind;
+        use std::sync::Arc;
+        use std::sync::atomic::AtomicBool;
+
+        fn no_connection(
+            _: SubscriptionKind,
+            _: &str,
+            _: Arc<AtomicBool>,
+            _: Backoff,
+            _: Arc<dyn Fn(String) + Send + Sync>,
+        ) {
+        }
+
+        let app = parse_nilo(
+            r#"
+            flow {
+                start: Main
+                Main -> Next
+            }
+            timeline Main {
+                subscribe ws("ws://nav") as msg { navigate_to(Next) }
+                subscribe ws("ws://chat") as msg { set state.seen = msg }
+            }
+            timeline Next {}
+            "#,
+        )
+        .unwrap();
+        let mut state = state(&[]);
+        let main = &app.timelines[0];
+        state.subscriptions = TimelineSubscriptions::with_connector(no_connection);
+        let urls = vec!["ws://nav".to_string(), "ws://chat".to_string()];
+        state.subscriptions.sync("Main", &main.subscriptions, urls);
+
+        // 他のテストのメッセージと重ならないid
+        let ids = [u64::MAX - 1, u64::MAX - 2];
+        for (id, (sub, text)) in ids.iter().zip([("Main#0@1", "go"), ("Main#1@1", "hi")]) {
+            let message = SubscriptionMessage {
+                subscription: sub.into(),
+                text: text.into(),
+            };
+            subscription::store_message(*id, message);
+        }
+        let events = ids.map(|id| UIEvent::SubscriptionMessage { id });
+        let next = Engine::step_whens(&app, &mut state, &events);
+        assert_eq!(next.as_deref(), Some("Next"));
+        // 遷移前のタイムラインの購読は実行しないが、メッセージは取り残さない
+        assert_eq!(field(&state, "seen"), "");
+        for id in ids {
+            assert!(subscription::take_message(id).is_none());
+        }
+    }
+
+    fn click(id: &str) -> UIEvent {
+        UIEvent::ButtonPressed { id: id.into() }
+    }
+
+    #[test]
+    fn test_checkbox_toggles_and_fires_change() {
+        let app = app(r#"
+            Checkbox(agree, bind: state.agree, label: "同意")
+            Switch(dark, bind: state.dark)
+            when user.change(agree) { set state.log = "agree" }
+            "#);
+        let mut state = state(&[("agree", "false"), ("dark", "true")]);
+        frame(&app, &mut state, &[click("agree")]);
+        assert_eq!(field(&state, "agree"), "true");
+        assert_eq!(field(&state, "log"), "agree");
+        assert_eq!(state.focused_form_control.as_deref(), Some("agree"));
+
+        frame(&app, &mut state, &[click("dark")]);
+        assert_eq!(field(&state, "dark"), "false");
+    }
+
+    #[test]
+    fn test_radio_group_assigns_option_value() {
+        let app = app(r#"RadioGroup(plan, bind: state.plan, options: ["free", "pro"])"#);
+        let mut state = state(&[("plan", "free")]);
+        frame(&app, &mut state, &[click("plan#1")]);
+        assert_eq!(field(&state, "plan"), "pro");
+        frame(&app, &mut state, &[click("plan#0")]);
+        assert_eq!(field(&state, "plan"), "free");
+    }
+
+    fn key(id: &str, key: &str) -> UIEvent {
+        UIEvent::KeyPressed {
+            field_id: id.into(),
+            key: key.into(),
+            modifiers: Default::default(),
+        }
+    }
+
+    const SELECT: &str = r#"
+        Select(size, bind: state.size, options: ["S", "M", "L"])
+        when user.change(size) { set state.log = "size" }
+        "#;
+
+    #[test]
+    fn test_select_picks_option_by_click() {
+        let app = app(SELECT);
+        let mut state = state(&[("size", "S")]);
+        frame(&app, &mut state, &[click("size")]);
+        assert!(state.open_overlay.is_some());
+
+        // 開いている間は選択肢と背景だけがクリックを受け付ける
+        let buttons = frame(&app, &mut state, &[click("size#2")]);
+        assert!(buttons.iter().all(|(id, _, _)| id != "size"));
+        assert_eq!(field(&state, "size"), "L");
+        assert_eq!(field(&state, "log"), "size");
+        assert!(state.open_overlay.is_none());
+    }
+
+    #[test]
+    fn test_select_keyboard_navigation() {
+        let app = app(SELECT);
+        let mut state = state(&[("size", "S")]);
+        frame(&app, &mut state, &[key("size", "Enter")]);
+        // 開いたときは現在の値が選択されている
+        let highlighted = state.open_overlay.as_ref().and_then(|o| o.highlighted);
+        assert_eq!(highlighted, Some(0));
+        frame(&app, &mut state, &[key("size", "ArrowDown")]);
+        frame(&app, &mut state, &[key("size", "Enter")]);
+        assert_eq!(field(&state, "size"), "M");
+        assert!(state.open_overlay.is_none());
+        assert_eq!(state.focused_form_control.as_deref(), Some("size"));
+    }
+
+    #[test]
+    fn test_select_escape_closes_without_change() {
+        let app = app(SELECT);
+        let mut state = state(&[("size", "S")]);
+        frame(&app, &mut state, &[click("size")]);
+        frame(&app, &mut state, &[key("size", "ArrowDown")]);
+        frame(&app, &mut state, &[key("size", "Escape")]);
+        assert!(state.open_overlay.is_none());
+        assert_eq!(field(&state, "size"), "S");
+        assert_eq!(field(&state, "log"), "");
+    }
+
+    #[test]
+    fn test_stepper_stays_within_range() {
+        let app = app("Stepper(qty, bind: state.qty, min: 0, max: 2, step: 1)");
+        let mut state = state(&[("qty", "1")]);
+        frame(&app, &mut state, &[click("qty#inc")]);
+        assert_eq!(field(&state, "qty"), "2");
+        frame(&app, &mut state, &[click("qty#inc")]);
+        assert_eq!(field(&state, "qty"), "2");
+        for _ in 0..3 {
+            frame(&app, &mut state, &[click("qty#dec")]);
+        }
+        assert_eq!(field(&state, "qty"), "0");
+    }
+
+    #[test]
+    fn test_slider_drag_updates_bound_value() {
+        let app = app(r#"
+            Slider(volume, bind: state.volume, min: 0, max: 100, step: 10)
+            when user.change(volume) { set state.log = "volume" }
+            "#);
+        let mut state = state(&[("volume", "50")]);
+        frame(&app, &mut state, &[]);
+        let track = state.drag_targets["volume"].clone();
+        // トラック上の位置（左端0.0〜右端1.0）へのドラッグ
+        let drag = |ratio: f32| UIEvent::PointerDragged {
+            id: "volume".into(),
+            position: [track.track_x + track.track_width * ratio, 0.0],
+        };
+
+        frame(&app, &mut state, &[drag(-0.5)]);
+        assert_eq!(field(&state, "volume"), "0");
+        assert_eq!(field(&state, "log"), "volume");
+        // stepに丸める
+        frame(&app, &mut state, &[drag(0.33)]);
+        assert_eq!(field(&state, "volume"), "30");
+        frame(&app, &mut state, &[drag(2.0)]);
+        assert_eq!(field(&state, "volume"), "100");
+    }
+
+    const DIALOG: &str = r#"
+        Button(id: behind, label: "背面")
+        Dialog(confirm, open: state.open) { Button(id: ok, label: "OK") }
+        when user.click(ok) { set state.log = "ok" }
+        when user.change(confirm) { set state.log = "closed" }
+        "#;
+
+    #[test]
+    fn test_dialog_blocks_background_buttons() {
+        let app = app(DIALOG);
+        let mut state = state(&[("open", "true")]);
+        let buttons = frame(&app, &mut state, &[click("ok")]);
+        assert!(buttons.iter().any(|(id, _, _)| id == "ok"));
+        assert!(buttons.iter().all(|(id, _, _)| id != "behind"));
+        assert_eq!(field(&state, "log"), "ok");
+
+        state.custom_state.open = false;
+        let buttons = frame(&app, &mut state, &[]);
+        assert!(buttons.iter().any(|(id, _, _)| id == "behind"));
+        assert!(!state.has_open_dialog());
+    }
+
+    #[test]
+    fn test_dialog_closes_on_escape_and_backdrop() {
+        let app = app(DIALOG);
+        let mut state = state(&[("open", "true")]);
+        // フォーカスがなければ、キー操作は最前面のDialogが受け取る
+        frame(&app, &mut state, &[]);
+        assert_eq!(state.keyboard_target().as_deref(), Some("confirm"));
+        frame(&app, &mut state, &[key("confirm", "Escape")]);
+        assert_eq!(field(&state, "open"), "false");
+        assert_eq!(field(&state, "log"), "closed");
+        frame(&app, &mut state, &[]);
+        assert_eq!(state.keyboard_target(), None);
+
+        state.custom_state.open = true;
+        frame(&app, &mut state, &[click(DIALOG_BACKDROP_ID)]);
+        assert_eq!(field(&state, "open"), "false");
+    }
+
+    #[test]
+    fn test_toast_expires() {
+        let app = app(r#"
+            Button(id: save, label: "保存")
+            when user.click(save) { toast("保存しました", 1000) }
+            "#);
+        let mut state = state(&[]);
+        frame(&app, &mut state, &[click("save")]);
+        assert_eq!(state.toasts.len(), 1);
+        assert_eq!(state.toasts[0].message, "保存しました");
+        // タイムラインを移動しても残る
+        state.jump_to_timeline("Main");
+        frame(&app, &mut state, &[]);
+        assert_eq!(state.toasts.len(), 1);
+
+        state.toasts[0].expires_at = crate::engine::timer::now_ms() - 1.0;
+        frame(&app, &mut state, &[]);
+        assert!(state.toasts.is_empty());
+    }
+
+    #[test]
+    fn test_tooltip_appears_after_delay() {
+        let app = app(r#"Button(id: help, label: "?", tooltip: "ヘルプ")"#);
+        let mut state = state(&[]);
+        frame(&app, &mut state, &[]);
+        let target = state.tooltip_targets[0].clone();
+        assert_eq!(target.text, "ヘルプ");
+        let center = [
+            target.position[0] + target.size[0] / 2.0,
+            target.position[1] + target.size[1] / 2.0,
+        ];
+        // ボタンの上にポインタを置いて描画し、ステンシルの数を返す
+        let render_hovered = |state: &mut AppState<Fields>| {
+            state.static_stencils = None;
+            Engine::layout_and_stencil(&app, state, center, false, false, WINDOW)
+                .0
+                .len()
+        };
+
+        let before = render_hovered(&mut state);
+        assert!(state.tooltip_hover.is_some());
+        if let Some(hover) = &mut state.tooltip_hover {
+            hover.since -= TOOLTIP_DELAY_MS;
+        }
+        assert_eq!(render_hovered(&mut state), before + 1);
+    }
+
+    fn hit<'a>(rects: &'a [(String, [f32; 2], [f32; 2])], point: [f32; 2]) -> Option<&'a str> {
+        rects
+            .iter()
+            .find(|(_, pos, size)| {
+                point[0] >= pos[0]
+                    && point[0] <= pos[0] + size[0]
+                    && point[1] >= pos[1]
+                    && point[1] <= pos[1] + size[1]
+            })
+            .map(|(id, _, _)| id.as_str())
+    }
+
+    #[test]
+    fn test_backdrop_leaves_panel_clickable() {
+        // 100px 下にスクロールした 400x300 のウィンドウの中央にあるパネル
+        let panel = ([100.0, 200.0], [200.0, 100.0]);
+        let rects = backdrop_hit_rects("backdrop", [0.0, -100.0], [400.0, 300.0], Some(panel));
+        assert_eq!(rects.len(), 4);
+        assert_eq!(hit(&rects, [150.0, 250.0]), None);
+        assert_eq!(hit(&rects, [10.0, 110.0]), Some("backdrop"));
+        assert_eq!(hit(&rects, [350.0, 250.0]), Some("backdrop"));
+        assert_eq!(hit(&rects, [150.0, 390.0]), Some("backdrop"));
+        // ウィンドウの外は覆わない
+        assert_eq!(hit(&rects, [150.0, 50.0]), None);
+
+        let rects = backdrop_hit_rects("backdrop", [0.0, 0.0], [400.0, 300.0], None);
+        assert_eq!(
+            rects,
+            vec![("backdrop".to_string(), [0.0, 0.0], [400.0, 300.0])]
+        );
+    }
+}
@@ -41,6 +41,7 @@ pub fn collect_dynamic_sections<S>(
                     root_font_size: 16.0,
                     font_size: 16.0,
                     default_font: default_font.to_string(),
+                    viewport: Some(state.layout_viewport(window_size)),
                 };
                 let child_layouted = layout_vstack(children, params, app, &eval_fn, &get_img_size);
                 collect_dynamic_sections(
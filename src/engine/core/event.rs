@@ -1,8 +1,13 @@
 // src/engine/engine/event.rs
 // イベント処理関連
 
-use crate::engine::state::{AppState, StateAccess};
-use crate::parser::ast::{App, Component, EventExpr, Expr, ViewNode, WithSpan};
+use crate::engine::async_call::apply_value_update;
+use crate::engine::http::{self, HttpCompletion, HttpHandlers, HttpRequest};
+use crate::engine::state::{
+    AppState, COMPONENT_STATE_PREFIX, DIALOG_BACKDROP_ID, StateAccess, match_instance_pattern,
+};
+use crate::engine::subscription;
+use crate::parser::ast::{App, BinaryOperator, Component, EventExpr, Expr, ViewNode, WithSpan};
 use crate::ui::event::UIEvent;
 use std::collections::{HashMap, HashSet};
 
@@ -35,6 +40,120 @@ where
     }
 }
 
+/// オーバーレイの開閉とキーボード操作を処理
+/// 開いている間のクリックは選択肢・背景のどちらでもオーバーレイを閉じる
+/// （選択肢のbind先更新はフォーム部品のアクションとして処理済み）
+fn update_overlay<S>(
+    app: &App,
+    state: &mut AppState<S>,
+    events: &[UIEvent],
+    clicked: &[&str],
+    changed: &mut Vec<String>,
+) where
+    S: StateAccess + 'static,
+{
+    let keys = events.iter().filter_map(|ev| match ev {
+        UIEvent::KeyPressed { field_id, key, .. } => Some((field_id.as_str(), key.as_str())),
+        _ => None,
+    });
+
+    let Some(open) = state.open_overlay.clone() else {
+        // 閉じている場合: トリガーのクリック、またはフォーカス中のトリガーでのキー操作で開く
+        let opened =
+            clicked.iter().copied().chain(keys.filter_map(|(id, key)| {
+                matches!(key, "Enter" | "Space" | "ArrowDown").then_some(id)
+            }));
+        for id in opened {
+            if let Some(&(_, selected)) = state.overlay_triggers.get(id) {
+                state.show_overlay(id, selected);
+                state.focused_form_control = Some(id.to_string());
+                break;
+            }
+        }
+        return;
+    };
+
+    let count = state
+        .overlay_triggers
+        .get(&open.owner)
+        .map_or(0, |&(count, _)| count);
+    let mut highlighted = open.highlighted;
+    let mut close = !clicked.is_empty();
+
+    for (_, key) in keys {
+        match key {
+            "Escape" => close = true,
+            "ArrowDown" | "ArrowUp" if count > 0 => {
+                highlighted = Some(match (highlighted, key) {
+                    (None, "ArrowDown") => 0,
+                    (None, _) => count - 1,
+                    (Some(i), "ArrowDown") => (i + 1).min(count - 1),
+                    (Some(i), _) => i.saturating_sub(1),
+                });
+            }
+            "Enter" | "Space" => {
+                let action = highlighted.and_then(|index| {
+                    state
+                        .form_control_actions
+                        .get(&format!("{}#{}", open.owner, index))
+                        .cloned()
+                });
+                if let Some((control_id, action)) = action {
+                    apply_action(app, state, &action);
+                    changed.push(control_id);
+                }
+                close = true;
+            }
+            _ => {}
+        }
+    }
+
+    if close {
+        state.close_overlay();
+        if !changed.is_empty() {
+            state.focused_form_control = Some(open.owner);
+        }
+    } else if highlighted != open.highlighted {
+        state.show_overlay(&open.owner, highlighted);
+    }
+}
+
+/// ★ Slider のドラッグ（ポインタキャプチャ中の位置）をbind先の値に反映
+fn update_drag<S>(app: &App, state: &mut AppState<S>, events: &[UIEvent], changed: &mut Vec<String>)
+where
+    S: StateAccess + 'static,
+{
+    for ev in events {
+        let UIEvent::PointerDragged { id, position } = ev else {
+            continue;
+        };
+        let Some(target) = state.drag_targets.get(id).cloned() else {
+            continue;
+        };
+        state.focused_form_control = Some(id.clone());
+
+        let value = target.value_at(position[0]);
+        let current = state.eval_expr_from_ast(&Expr::Path(target.path.clone()));
+        if current.trim().parse::<f32>().ok() == Some(value) {
+            continue;
+        }
+        let action = WithSpan {
+            node: ViewNode::Set {
+                path: target.path.clone(),
+                value: Expr::Number(value),
+                inferred_type: None,
+            },
+            line: target.line,
+            column: target.column,
+            style: None,
+        };
+        apply_action(app, state, &action);
+        if !changed.contains(&target.control_id) {
+            changed.push(target.control_id);
+        }
+    }
+}
+
 /// 簡略化されたボタン同期
 pub fn sync_button_handlers<S>(
     nodes: &[WithSpan<ViewNode>],
@@ -84,10 +203,89 @@ pub fn step_whens<S>(app: &App, state: &mut AppState<S>, events: &[UIEvent]) ->
 where
     S: StateAccess + 'static,
 {
+    // ★ 最初に起きた遷移先。遷移した後も非同期の完了はキューから最後まで取り出し、
+    // 遷移前のタイムラインの残りのハンドラは実行しない
+    let mut next_timeline: Option<String> = None;
+
+    // ★ マウスの戻る/進むボタン
+    for ev in events {
+        let moved = match ev {
+            UIEvent::NavigateBack => state.navigate_back(),
+            UIEvent::NavigateForward => state.navigate_forward(),
+            _ => false,
+        };
+        if moved {
+            next_timeline = Some(state.current_timeline.clone());
+            break;
+        }
+    }
+
     let Some(tl) = state.current_timeline(app) else {
-        return None;
+        return next_timeline;
     };
 
+    // ★ タイマー（every / after）のティック。止めたタイマーの古いティックは無視される
+    for ev in events {
+        if next_timeline.is_some() {
+            break;
+        }
+        let UIEvent::TimerTick { id } = ev else {
+            continue;
+        };
+        let Some(timer) = state
+            .timeline_timers
+            .fire(id)
+            .and_then(|i| tl.timers.get(i))
+        else {
+            continue;
+        };
+        next_timeline = apply_actions(app, state, &timer.actions);
+    }
+
+    // ★ 購読（subscribe ws / sse）のメッセージ。切った購読の古いメッセージは無視される
+    // 途中で遷移しても残りを取り残さないよう、先にすべて取り出す
+    let messages: Vec<_> = events
+        .iter()
+        .filter_map(|ev| match ev {
+            UIEvent::SubscriptionMessage { id } => subscription::take_message(*id),
+            _ => None,
+        })
+        .collect();
+    for message in messages {
+        if next_timeline.is_some() {
+            break;
+        }
+        let Some(sub) = state
+            .subscriptions
+            .fire(&message.subscription)
+            .and_then(|i| tl.subscriptions.get(i))
+        else {
+            continue;
+        };
+        // 受け取った本文は foreach 変数と同じく、msg.text のようなプロパティアクセスもできる
+        state.component_context.enter_foreach();
+        state
+            .component_context
+            .set_foreach_var(sub.var.clone(), message.text);
+        next_timeline = apply_actions(app, state, &sub.actions);
+        state.component_context.exit_foreach();
+    }
+
+    // ★ HTTPリクエスト（http_get / http_post）の完了
+    for ev in events {
+        let UIEvent::HttpResponse { id } = ev else {
+            continue;
+        };
+        // 遷移した後の完了も `into` は反映する（ブロックは遷移前のタイムラインでしか実行しない）
+        if let Some(completion) = http::take_completion(*id) {
+            let new_tl = apply_http_completion(app, state, completion);
+            next_timeline = next_timeline.or(new_tl);
+        }
+    }
+    if next_timeline.is_some() {
+        return next_timeline;
+    }
+
     // ButtonPressedイベントのみを処理対象とする（ButtonReleasedは除外）
     let clicked: Vec<&str> = events
         .iter()
@@ -105,6 +303,40 @@ where
         handle_button_onclick(app, state, &clicked);
     }
 
+    // ★ フォーム部品（Checkbox/Switch/RadioGroup）: bind先を更新してから user.change を発火
+    let mut changed: Vec<String> = Vec::new();
+    for &id in &clicked {
+        if let Some((control_id, action)) = state.form_control_actions.get(id).cloned() {
+            apply_action(app, state, &action);
+            state.focused_form_control = Some(id.to_string());
+            changed.push(control_id);
+        }
+    }
+    if !clicked.is_empty() && changed.is_empty() {
+        state.focused_form_control = None;
+    }
+
+    // ★ Slider のドラッグ
+    update_drag(app, state, events, &mut changed);
+
+    // ★ オーバーレイ（Selectのドロップダウン）の開閉とキーボード操作
+    let overlay_was_open = state.open_overlay.is_some();
+    update_overlay(app, state, events, &clicked, &mut changed);
+
+    // ★ Escapeで最前面のDialogを閉じる（Selectが開いていた場合はそちらを閉じるだけ）
+    let escape_pressed = events
+        .iter()
+        .any(|ev| matches!(ev, UIEvent::KeyPressed { key, .. } if key == "Escape"));
+    if escape_pressed
+        && !overlay_was_open
+        && state.has_open_dialog()
+        && let Some((dialog_id, action)) =
+            state.form_control_actions.get(DIALOG_BACKDROP_ID).cloned()
+    {
+        apply_action(app, state, &action);
+        changed.push(dialog_id);
+    }
+
     for (_i, when) in tl.whens.iter().enumerate() {
         if let EventExpr::ButtonPressed(target) = &when.event {
             log::info!(
@@ -114,12 +346,43 @@ where
             );
             if clicked.iter().any(|&s| s == target) {
                 log::info!("Processing when block for button: {}", target);
-                for (_j, action) in when.actions.iter().enumerate() {
-                    log::info!("Executing action: {:?}", action.node);
-                    if let Some(new_tl) = apply_action(app, state, action) {
-                        return Some(new_tl);
-                    }
-                }
+                next_timeline = apply_actions(app, state, &when.actions);
+            }
+        }
+        if let EventExpr::ValueChanged(target) = &when.event
+            && changed.iter().any(|s| s == target)
+        {
+            log::info!("Processing when block for form control: {}", target);
+            next_timeline = apply_actions(app, state, &when.actions);
+        }
+        if next_timeline.is_some() {
+            return next_timeline;
+        }
+    }
+
+    // ★ コンポーネントインスタンスのwhenブロック
+    // ボタンIDはインスタンスごとに一意化されており、foreach内では要素のキーを含む
+    let component_whens = state.component_whens.clone();
+    for when in &component_whens {
+        let (target, fired_ids): (&String, Vec<&str>) = match &when.event {
+            EventExpr::ButtonPressed(target) => (target, clicked.clone()),
+            EventExpr::ValueChanged(target) => {
+                (target, changed.iter().map(String::as_str).collect())
+            }
+            _ => continue,
+        };
+        for fired_id in fired_ids {
+            let Some(bindings) = match_instance_pattern(target, fired_id) else {
+                continue;
+            };
+            state.component_context.enter_foreach();
+            for (var, value) in bindings {
+                state.component_context.set_foreach_var(var, value);
+            }
+            next_timeline = apply_actions(app, state, &when.actions);
+            state.component_context.exit_foreach();
+            if next_timeline.is_some() {
+                return next_timeline;
             }
         }
     }
@@ -127,6 +390,20 @@ where
     None
 }
 
+/// アクションを順に実行し、遷移したらそこで止めて遷移先を返す
+fn apply_actions<S>(
+    app: &App,
+    state: &mut AppState<S>,
+    actions: &[WithSpan<ViewNode>],
+) -> Option<String>
+where
+    S: StateAccess + 'static,
+{
+    actions
+        .iter()
+        .find_map(|action| apply_action(app, state, action))
+}
+
 pub fn apply_action<S>(
     _app: &App,
     state: &mut AppState<S>,
@@ -136,22 +413,125 @@ where
     S: StateAccess + 'static,
 {
     match &action.node {
-        ViewNode::NavigateTo { target } => {
-            // ルーティング対応のナビゲーション（パラメータなし）
+        ViewNode::NavigateTo { target, params } => {
+            // ルーティング対応のナビゲーション（パラメータは遷移前のスコープで評価）
             log::info!("Navigating to: {}", target);
-            state.navigate_with_params(target, std::collections::HashMap::new());
-            return Some(target.clone());
+            let params = params
+                .iter()
+                .map(|(name, expr)| (name.clone(), state.eval_expr_from_ast(expr)))
+                .collect();
+            // フローの条件やルートガードで遷移先が変わる・遷移しないことがある
+            if state.navigate_with_params(target, params) {
+                return Some(state.current_timeline.clone());
+            }
+        }
+        ViewNode::CallFlow { flow, into, params } => {
+            // 名前付き引数は呼び出し元のスコープで評価し、サブフロー内の flow.xxx になる
+            let vars = params
+                .iter()
+                .map(|(name, expr)| (name.clone(), state.eval_expr_from_ast(expr)))
+                .collect();
+            if state.call_flow(flow, into.clone(), vars) {
+                return Some(state.current_timeline.clone());
+            }
+        }
+        ViewNode::Finish { value } => {
+            // 値はサブフローのスコープ（flow.xxx が有効なうち）で評価する
+            let value = value.as_ref().map(|v| (v, state.eval_expr_from_ast(v)));
+            if let Some(frame) = state.finish_flow() {
+                if let (Some(path), Some((source, value))) = (frame.into, value) {
+                    let set = WithSpan {
+                        node: ViewNode::Set {
+                            path,
+                            value: value_to_literal_expr(source, value),
+                            inferred_type: None,
+                        },
+                        line: action.line,
+                        column: action.column,
+                        style: None,
+                    };
+                    apply_action(_app, state, &set);
+                }
+                return Some(state.current_timeline.clone());
+            }
+        }
+        ViewNode::NavigateBack if state.navigate_back() => {
+            return Some(state.current_timeline.clone());
+        }
+        ViewNode::NavigateForward if state.navigate_forward() => {
+            return Some(state.current_timeline.clone());
         }
         ViewNode::RustCall { name, args } => {
             state.handle_rust_call_viewnode(name, args);
         }
+        ViewNode::Emit { name, args } => {
+            // ★ 展開時に親のrust_call名へ解決済み
+            // 引数はコンポーネント側のスコープ（foreach変数・ローカル状態）で評価してから渡す
+            let values: Vec<Expr> = args
+                .iter()
+                .map(|arg| value_to_literal_expr(arg, state.eval_expr_from_ast(arg)))
+                .collect();
+            state.handle_rust_call_viewnode(name, &values);
+        }
+        ViewNode::Toast { message, duration } => {
+            let message = state.eval_expr_from_ast(message);
+            let duration_ms = duration
+                .as_ref()
+                .and_then(|d| state.eval_expr_from_ast(d).trim().parse::<f64>().ok());
+            state.show_toast(message, duration_ms);
+        }
+        ViewNode::Http {
+            method,
+            url,
+            into,
+            params,
+            on_success,
+            on_error,
+        } => {
+            let url = state.eval_expr_from_ast(url);
+            let mut request = HttpRequest::new(*method, &url);
+            for (name, expr) in params {
+                match (name.as_str(), expr) {
+                    ("body", expr) => request.body = Some(expr_to_json(state, expr).to_string()),
+                    ("headers", Expr::Object(fields)) => {
+                        for (key, value) in fields {
+                            let key = key.trim_matches('"');
+                            request.set_header(key, &state.eval_expr_from_ast(value));
+                        }
+                    }
+                    ("timeout", expr) => {
+                        match state.eval_expr_from_ast(expr).trim().parse::<f64>() {
+                            Ok(ms) => request.timeout_ms = ms as u64,
+                            Err(_) => log::warn!("http: timeout はミリ秒の数値で指定してください"),
+                        }
+                    }
+                    (other, _) => log::warn!("http: 不明な引数 '{}'", other),
+                }
+            }
+            http::spawn_request(
+                request,
+                HttpHandlers {
+                    timeline: state.current_timeline.clone(),
+                    into: into.clone(),
+                    on_success: on_success.clone(),
+                    on_error: on_error.clone(),
+                },
+            );
+        }
         ViewNode::Set { path, value, .. } => {
-            // ★ 優先順位: 1. ローカル変数 → 2. state変数 → 3. その他の変数
+            // ★ 優先順位: 0. コンポーネントローカル状態 → 1. ローカル変数 → 2. state変数 → 3. その他の変数
             let key = path.trim().to_string();
             let v = state.eval_expr_from_ast(value);
 
-            // 1. ローカル変数をチェック
-            if state.component_context.get_local_var(&key).is_some() {
+            // 0. コンポーネントローカル状態（展開時に書き換え済みのパス）
+            if key.starts_with(COMPONENT_STATE_PREFIX) {
+                if let Err(e) = state.set_component_state(&key, v) {
+                    log::error!("Failed to set component state: {}", e);
+                }
+                state.needs_redraw = true;
+                state.static_stencils = None;
+            } else if state.component_context.get_local_var(&key).is_some() {
+                // 1. ローカル変数をチェック
                 // const変数への再代入チェック
                 if state.component_context.is_const_var(&key) {
                     panic!("Cannot reassign to const variable '{}'", key);
@@ -161,9 +541,17 @@ where
                 // ★ ローカル変数変更時も再描画が必要
                 state.needs_redraw = true;
                 state.static_stencils = None;
+            } else if let Some(flow_key) = key.strip_prefix("flow.") {
+                // サブフロー内の変数（finish で破棄される）
+                if !state.sub_flows.set_var(flow_key, v) {
+                    log::warn!("サブフローの外で {} に代入しようとしました", key);
+                }
+                state.needs_redraw = true;
+                state.static_stencils = None;
             } else if path.starts_with("state.") {
                 // 2. state変数
-                let state_key = path.strip_prefix("state.").unwrap().trim().to_string();
+                let state_key =
+                    state.resolve_state_path(path.strip_prefix("state.").unwrap().trim());
 
                 // state.xxxアクセス時はエラーでクラッシュ
                 if let Err(e) = state.custom_state.set(&state_key, v.clone()) {
@@ -184,8 +572,16 @@ where
             }
         }
         ViewNode::Toggle { path } => {
-            if path.starts_with("state.") {
-                let key = path.strip_prefix("state.").unwrap().to_string();
+            if path.starts_with(COMPONENT_STATE_PREFIX) {
+                let cur = state.get_component_state(path).unwrap_or_default();
+                let b = matches!(cur.as_str(), "true" | "1" | "True" | "TRUE");
+                if let Err(e) = state.set_component_state(path, (!b).to_string()) {
+                    log::error!("Failed to toggle component state: {}", e);
+                }
+                state.needs_redraw = true;
+                state.static_stencils = None;
+            } else if path.starts_with("state.") {
+                let key = state.resolve_state_path(path.strip_prefix("state.").unwrap());
 
                 // state.xxxアクセス時はエラーでクラッシュ
                 if let Err(e) = state.custom_state.toggle(&key) {
@@ -213,7 +609,7 @@ where
         }
         ViewNode::ListAppend { path, value } => {
             if path.starts_with("state.") {
-                let key = path.strip_prefix("state.").unwrap().to_string();
+                let key = state.resolve_state_path(path.strip_prefix("state.").unwrap());
                 let v = state.eval_expr_from_ast(value);
 
                 // state.xxxアクセス時はエラーでクラッシュ
@@ -245,7 +641,7 @@ where
         }
         ViewNode::ListInsert { path, index, value } => {
             if path.starts_with("state.") {
-                let key = path.strip_prefix("state.").unwrap().to_string();
+                let key = state.resolve_state_path(path.strip_prefix("state.").unwrap());
                 let v = state.eval_expr_from_ast(value);
 
                 // state.xxxアクセス時はエラーでクラッシュ
@@ -279,7 +675,7 @@ where
         }
         ViewNode::ListRemove { path, value } => {
             if path.starts_with("state.") {
-                let key = path.strip_prefix("state.").unwrap().to_string();
+                let key = state.resolve_state_path(path.strip_prefix("state.").unwrap());
                 let v = state.eval_expr_from_ast(value);
 
                 // state.xxxアクセス時はエラーでクラッシュ
@@ -314,7 +710,7 @@ where
         }
         ViewNode::ListClear { path } => {
             if path.starts_with("state.") {
-                let key = path.strip_prefix("state.").unwrap().to_string();
+                let key = state.resolve_state_path(path.strip_prefix("state.").unwrap());
 
                 // state.xxxアクセス時はエラーでクラッシュ
                 if let Err(e) = state.custom_state.list_clear(&key) {
@@ -343,3 +739,125 @@ where
     }
     None
 }
+
+/// HTTPリクエストの完了を反映する
+///
+/// 成功したらJSONの応答を into のパスへ代入し、on_success |res, status| を実行する。
+/// 失敗したら on_error |err| を実行する（リクエストを送ったタイムラインを離れていたら実行しない）
+fn apply_http_completion<S>(
+    app: &App,
+    state: &mut AppState<S>,
+    completion: HttpCompletion,
+) -> Option<String>
+where
+    S: StateAccess + 'static,
+{
+    let HttpCompletion { handlers, result } = completion;
+    let (block, args) = match result {
+        Ok(response) => {
+            if let Some(path) = &handlers.into {
+                let value = serde_json::from_str(&response.body)
+                    .unwrap_or_else(|_| serde_json::Value::String(response.body.clone()));
+                apply_value_update(&mut state.custom_state, path, value);
+                state.needs_redraw = true;
+                state.static_stencils = None;
+            }
+            (
+                handlers.on_success,
+                vec![response.body, response.status.to_string()],
+            )
+        }
+        Err(e) => {
+            log::warn!("HTTP request failed: {}", e);
+            (handlers.on_error, vec![e])
+        }
+    };
+
+    let block = block?;
+    if handlers.timeline != state.current_timeline {
+        log::debug!(
+            "HTTP response arrived after leaving '{}'",
+            handlers.timeline
+        );
+        return None;
+    }
+
+    // ブロックの引数は foreach 変数と同じく、res.name のようなプロパティアクセスもできる
+    state.component_context.enter_foreach();
+    for (param, value) in block.params.iter().zip(args) {
+        state
+            .component_context
+            .set_foreach_var(param.clone(), value);
+    }
+    let next_timeline = apply_actions(app, state, &block.actions);
+    state.component_context.exit_foreach();
+    next_timeline
+}
+
+/// 式をJSONの値にする（http_post の body）
+///
+/// オブジェクト・配列は要素ごとに評価し、それ以外は評価結果がJSONとして読めればその値、読めなければ文字列
+fn expr_to_json<S>(state: &AppState<S>, expr: &Expr) -> serde_json::Value
+where
+    S: StateAccess + 'static,
+{
+    match expr {
+        Expr::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.trim_matches('"').to_string(),
+                    expr_to_json(state, value),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        Expr::Array(items) => items.iter().map(|item| expr_to_json(state, item)).collect(),
+        Expr::String(s) => serde_json::Value::String(s.clone()),
+        _ => {
+            let value = state.eval_expr_from_ast(expr);
+            serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value))
+        }
+    }
+}
+
+/// 評価済みの値をrust_callに渡せるリテラル式に戻す
+///
+/// 元の式が数値・真偽値のときだけ型付きのリテラルにする。
+/// それ以外は文字列のまま渡す（"007" や桁の大きいIDを数値に丸めないため）
+pub(crate) fn value_to_literal_expr(source: &Expr, value: String) -> Expr {
+    let typed = match literal_kind(source) {
+        // f32 で表せない値（大きな整数など）は文字列のまま渡す
+        LiteralKind::Number => value
+            .parse::<f32>()
+            .ok()
+            .filter(|n| n.to_string() == value)
+            .map(Expr::Number),
+        LiteralKind::Bool => value.parse::<bool>().ok().map(Expr::Bool),
+        LiteralKind::String => None,
+    };
+    typed.unwrap_or(Expr::String(value))
+}
+
+enum LiteralKind {
+    Number,
+    Bool,
+    String,
+}
+
+fn literal_kind(expr: &Expr) -> LiteralKind {
+    match expr {
+        Expr::Number(_) => LiteralKind::Number,
+        Expr::Bool(_) => LiteralKind::Bool,
+        Expr::BinaryOp { op, .. } => match op {
+            BinaryOperator::Add
+            | BinaryOperator::Sub
+            | BinaryOperator::Mul
+            | BinaryOperator::Div => LiteralKind::Number,
+            BinaryOperator::Coalesce => LiteralKind::String,
+            _ => LiteralKind::Bool,
+        },
+        Expr::CalcExpr(inner) => literal_kind(inner),
+        _ => LiteralKind::String,
+    }
+}
@@ -76,6 +76,7 @@ pub fn render_foreach_optimized<S>(
             root_font_size: 16.0,
             font_size: 16.0,
             default_font: "default".to_string(),
+            viewport: Some(state.layout_viewport(window_size)),
         };
         let layouted = layout_vstack(body, item_params, app, &eval_fn, &get_img_size);
 
@@ -4,10 +4,18 @@
 use super::flow::*;
 use super::render::*;
 use super::utils::*;
-use crate::engine::state::{AppState, StateAccess};
-use crate::parser::ast::{App, Expr, ViewNode, WithSpan};
+use crate::engine::state::{
+    AppState, COMPONENT_STATE_PREFIX, DIALOG_BACKDROP_ID, DragTarget, OverlayLayer, StateAccess,
+    TooltipTarget,
+};
+use crate::engine::state_path;
+use crate::parser::ast::{App, BinaryOperator, Expr, ViewNode, WithSpan};
 use crate::stencil::stencil::Stencil;
-use crate::ui::{LayoutParams, layout_vstack};
+use crate::ui::layout::{
+    DIALOG_PADDING, dialog_panel_rect, numeric_range, parse_option_list, radio_option_rects,
+    slider_track, stepper_button_rects, virtual_item_height,
+};
+use crate::ui::{LayoutParams, LayoutedNode, layout_vstack};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
@@ -34,7 +42,8 @@ pub fn extract_state_fields(nodes: &[WithSpan<ViewNode>]) -> Vec<String> {
         match expr {
             Expr::Path(path) if path.starts_with("state.") => {
                 if let Some(field) = path.strip_prefix("state.") {
-                    fields.insert(field.to_string());
+                    // 深いパス（users[0].name）は先頭のフィールドに依存する
+                    fields.insert(state_path::split_root(field).0.to_string());
                 }
             }
             Expr::BinaryOp { left, right, .. } => {
@@ -54,6 +63,15 @@ pub fn extract_state_fields(nodes: &[WithSpan<ViewNode>]) -> Vec<String> {
                     extract_from_expr(arg, fields);
                 }
             }
+            Expr::Conditional {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                extract_from_expr(condition, fields);
+                extract_from_expr(then_expr, fields);
+                extract_from_expr(else_expr, fields);
+            }
             _ => {}
         }
     }
@@ -115,6 +133,32 @@ pub fn extract_state_fields(nodes: &[WithSpan<ViewNode>]) -> Vec<String> {
     fields.into_iter().collect()
 }
 
+/// 仮想化したforeachを含むかどうか（スクロールに合わせて静的レイアウトをやり直す必要がある）
+pub fn contains_virtual_list(nodes: &[WithSpan<ViewNode>]) -> bool {
+    nodes.iter().any(|node| match &node.node {
+        ViewNode::ForEach { body, .. } => {
+            virtual_item_height(node.style.as_ref()).is_some() || contains_virtual_list(body)
+        }
+        ViewNode::VStack(children) | ViewNode::HStack(children) => contains_virtual_list(children),
+        ViewNode::DynamicSection { body, .. } | ViewNode::Dialog { body, .. } => {
+            contains_virtual_list(body)
+        }
+        ViewNode::If {
+            then_body,
+            else_body,
+            ..
+        } => {
+            contains_virtual_list(then_body)
+                || else_body.as_deref().is_some_and(contains_virtual_list)
+        }
+        ViewNode::Match { arms, default, .. } => {
+            arms.iter().any(|(_, body)| contains_virtual_list(body))
+                || default.as_deref().is_some_and(contains_virtual_list)
+        }
+        _ => false,
+    })
+}
+
 /// タイムライン初期化時にローカル変数を一度だけ宣言
 /// レイアウト再計算時には呼ばれない
 pub fn initialize_local_variables<S>(nodes: &[WithSpan<ViewNode>], state: &mut AppState<S>)
@@ -145,7 +189,7 @@ where
             ViewNode::VStack(children) | ViewNode::HStack(children) => {
                 initialize_local_variables(children, state);
             }
-            ViewNode::ForEach { body, .. } => {
+            ViewNode::ForEach { body, .. } | ViewNode::Dialog { body, .. } => {
                 initialize_local_variables(body, state);
             }
             ViewNode::If {
@@ -184,6 +228,7 @@ pub fn make_layout_params(window_size: [f32; 2], default_font: String) -> Layout
         root_font_size: 16.0,
         font_size: 16.0,
         default_font,
+        viewport: None,
     }
 }
 
@@ -216,11 +261,15 @@ where
                 }
                 ViewNode::ForEach {
                     var,
+                    index_var,
                     iterable,
+                    key,
                     body,
                 } => ViewNode::ForEach {
                     var: var.clone(),
+                    index_var: index_var.clone(),
                     iterable: iterable.clone(),
+                    key: key.clone(),
                     body: resolve_responsive_nodes(body, state),
                 },
                 ViewNode::If {
@@ -252,6 +301,11 @@ where
                     name: name.clone(),
                     body: resolve_responsive_nodes(body, state),
                 },
+                ViewNode::Dialog { id, open, body } => ViewNode::Dialog {
+                    id: id.clone(),
+                    open: open.clone(),
+                    body: resolve_responsive_nodes(body, state),
+                },
                 other => other.clone(),
             };
 
@@ -289,8 +343,46 @@ where
     let layouted = layout_vstack(nodes, params.clone(), app, &eval_fn, &get_img_size);
 
     for lnode in &layouted {
+        // ★ ツールチップはホバー判定のため表示領域を登録（表示は毎フレームの合成時）
+        if let Some(tooltip) = lnode.node.style.as_ref().and_then(|s| s.tooltip.as_ref()) {
+            let text = state.eval_expr_from_ast(tooltip);
+            state.tooltip_targets.push(TooltipTarget {
+                text,
+                position: lnode.position,
+                size: lnode.size,
+            });
+        }
+
         match &lnode.node.node {
             ViewNode::DynamicSection { .. } => continue,
+            ViewNode::Dialog { id, open, body } => {
+                let is_open = matches!(
+                    state.eval_expr_from_ast(open).as_str(),
+                    "true" | "1" | "True" | "TRUE"
+                );
+                if !is_open {
+                    continue;
+                }
+                // ★ 背景のクリック・Escapeでopenのbind先をfalseにする（user.change(id) も発火）
+                // 入れ子のDialogは本体のレイアウト中に上書きするので、最前面のものが残る
+                if let Expr::Path(path) = open {
+                    let action = form_control_action(lnode, path, Expr::Bool(false));
+                    state
+                        .form_control_actions
+                        .insert(DIALOG_BACKDROP_ID.to_string(), (id.clone(), action));
+                }
+                push_dialog_layer(
+                    app,
+                    state,
+                    lnode,
+                    body,
+                    &params,
+                    mouse_pos,
+                    mouse_down,
+                    prev_mouse_down,
+                    nest_level,
+                );
+            }
             ViewNode::Button { id, onclick, .. } => {
                 buttons.push((id.clone(), lnode.position, lnode.size));
 
@@ -309,7 +401,13 @@ where
                     &params.default_font,
                 );
             }
-            ViewNode::TextInput { id, value, .. } => {
+            ViewNode::TextInput {
+                id,
+                value,
+                bind,
+                input_type,
+                ..
+            } => {
                 let st = lnode.node.style.as_ref();
                 let (_w,_h,_relw,_relh) = if let Some(s) = st {
                     (
@@ -319,14 +417,30 @@ where
                         s.relative_height.map(|d| (d.value, format!("{:?}", d.unit)))
                     )
                 } else { (None, None, None, None) };
-                if let Some(Expr::Path(p)) = value {
-                    if let Some(field) = p.strip_prefix("state.") {
-                        state.set_text_input_binding(id, field);
-                        if let Some(v) = state.custom_state.get_field(field) {
-                            if state.text_input_values.get(id).is_none() {
-                                state.set_text_input_value(id.clone(), v);
-                            }
-                        }
+                if let Some(Expr::Path(p)) = bind {
+                    // ★ 双方向バインド: state.xxx（ネストしたパス可）、コンポーネントローカル状態、サブフローの変数（flow.xxx）
+                    let field = p.strip_prefix("state.").unwrap_or(p);
+                    if field.starts_with(COMPONENT_STATE_PREFIX)
+                        || p.starts_with("state.")
+                        || p.starts_with("flow.")
+                    {
+                        state.set_text_input_binding_typed(id, field, *input_type);
+                        state.sync_text_input_from_binding(id);
+                    } else {
+                        log::warn!(
+                            "TextInput '{}' のbind先はstateのパスである必要があります: {}",
+                            id,
+                            p
+                        );
+                    }
+                } else if let Some(Expr::Path(p)) = value
+                    && let Some(field) = p.strip_prefix("state.")
+                {
+                    state.set_text_input_binding(id, field);
+                    if let Some(v) = state.custom_state.get_field(field)
+                        && !state.text_input_values.contains_key(id)
+                    {
+                        state.set_text_input_value(id.clone(), v);
                     }
                 }
                 text_inputs.push((id.clone(), lnode.position, lnode.size));
@@ -339,6 +453,168 @@ where
                     &params.default_font,
                 );
             }
+            ViewNode::Checkbox { id, bind, .. } | ViewNode::Switch { id, bind, .. } => {
+                // ★ クリックでbind先を反転（ローカル変数・コンポーネントローカル状態も対象にするためSetで表現）
+                buttons.push((id.clone(), lnode.position, lnode.size));
+                if let Expr::Path(path) = bind {
+                    let action = form_control_action(
+                        lnode,
+                        path,
+                        Expr::BinaryOp {
+                            left: Box::new(bind.clone()),
+                            op: BinaryOperator::Ne,
+                            right: Box::new(Expr::Bool(true)),
+                        },
+                    );
+                    state
+                        .form_control_actions
+                        .insert(id.clone(), (id.clone(), action));
+                }
+                render_toggle_control_lightweight(
+                    lnode,
+                    state,
+                    &mut stencils,
+                    &mut depth_counter,
+                    mouse_pos,
+                    &params.default_font,
+                );
+            }
+            ViewNode::RadioGroup { id, bind, options } => {
+                // ★ 選択肢ごとにクリック領域を登録（`id#index`）
+                let values = parse_option_list(&state.eval_expr_from_ast(options));
+                let rects = radio_option_rects(lnode.position, lnode.size, values.len());
+                for (index, (value, (pos, size))) in values.into_iter().zip(rects).enumerate() {
+                    let option_id = format!("{}#{}", id, index);
+                    buttons.push((option_id.clone(), pos, size));
+                    if let Expr::Path(path) = bind {
+                        let action = form_control_action(lnode, path, Expr::String(value));
+                        state
+                            .form_control_actions
+                            .insert(option_id, (id.clone(), action));
+                    }
+                }
+                render_radio_group_lightweight(
+                    lnode,
+                    state,
+                    &mut stencils,
+                    &mut depth_counter,
+                    mouse_pos,
+                    &params.default_font,
+                );
+            }
+            ViewNode::Select {
+                id, bind, options, ..
+            } => {
+                // ★ 本体のクリックでドロップダウンを開く。選択肢（`id#index`）のクリック領域はオーバーレイ層に置く
+                buttons.push((id.clone(), lnode.position, lnode.size));
+                let values = parse_option_list(&state.eval_expr_from_ast(options));
+                let current = state.eval_expr_from_ast(bind);
+                let selected = values.iter().position(|v| *v == current);
+                state
+                    .overlay_triggers
+                    .insert(id.clone(), (values.len(), selected));
+                if let Expr::Path(path) = bind {
+                    for (index, value) in values.into_iter().enumerate() {
+                        let action = form_control_action(lnode, path, Expr::String(value));
+                        state
+                            .form_control_actions
+                            .insert(format!("{}#{}", id, index), (id.clone(), action));
+                    }
+                }
+                render_select_lightweight(
+                    lnode,
+                    state,
+                    &mut stencils,
+                    &mut depth_counter,
+                    mouse_pos,
+                    &params.default_font,
+                );
+                if state
+                    .open_overlay
+                    .as_ref()
+                    .is_some_and(|overlay| overlay.owner == *id)
+                {
+                    state.overlay_layer = Some(render_select_overlay(
+                        lnode,
+                        state,
+                        mouse_pos,
+                        &params.default_font,
+                    ));
+                }
+            }
+            ViewNode::Slider { id, bind, .. } => {
+                // ★ 押下・ドラッグ中のポインタ位置から値を決める（イベント処理側で計算）
+                buttons.push((id.clone(), lnode.position, lnode.size));
+                if let Expr::Path(path) = bind {
+                    let (min, max, step) =
+                        numeric_range(&lnode.node.node, |e| state.eval_expr_from_ast(e));
+                    let font_size = lnode
+                        .node
+                        .style
+                        .as_ref()
+                        .and_then(|s| s.font_size)
+                        .unwrap_or(16.0);
+                    let (track_x, track_width) =
+                        slider_track(lnode.position, lnode.size, font_size);
+                    state.drag_targets.insert(
+                        id.clone(),
+                        DragTarget {
+                            control_id: id.clone(),
+                            path: path.clone(),
+                            track_x,
+                            track_width,
+                            min,
+                            max,
+                            step,
+                            line: lnode.node.line,
+                            column: lnode.node.column,
+                        },
+                    );
+                }
+                render_slider_lightweight(
+                    lnode,
+                    state,
+                    &mut stencils,
+                    &mut depth_counter,
+                    mouse_pos,
+                    &params.default_font,
+                );
+            }
+            ViewNode::Stepper { id, bind, .. } => {
+                // ★ −/+ ボタン（`id#dec` / `id#inc`）に範囲内へ丸めた代入を登録
+                let (min, max, step) =
+                    numeric_range(&lnode.node.node, |e| state.eval_expr_from_ast(e));
+                // 0.1刻みなどで誤差が溜まらないようf64で計算してから戻す
+                let step = step.unwrap_or(1.0) as f64;
+                let current = state
+                    .eval_expr_from_ast(bind)
+                    .trim()
+                    .parse::<f64>()
+                    .unwrap_or(0.0);
+                let [dec_rect, inc_rect] = stepper_button_rects(lnode.position, lnode.size);
+                for (suffix, rect, value) in [
+                    ("dec", dec_rect, current - step),
+                    ("inc", inc_rect, current + step),
+                ] {
+                    let button_id = format!("{}#{}", id, suffix);
+                    buttons.push((button_id.clone(), rect.0, rect.1));
+                    if let Expr::Path(path) = bind {
+                        let value = (value as f32).max(min).min(max);
+                        let action = form_control_action(lnode, path, Expr::Number(value));
+                        state
+                            .form_control_actions
+                            .insert(button_id, (id.clone(), action));
+                    }
+                }
+                render_stepper_lightweight(
+                    lnode,
+                    state,
+                    &mut stencils,
+                    &mut depth_counter,
+                    mouse_pos,
+                    &params.default_font,
+                );
+            }
             ViewNode::Text { .. } => {
                 render_text_lightweight(
                     lnode,
@@ -371,6 +647,7 @@ where
                 var,
                 iterable,
                 body,
+                ..
             } => {
                 // レンダリング段階でForeach変数を適切に設定してレンダリング
                 let window_size = params.window_size;
@@ -421,3 +698,87 @@ where
     }
     (stencils, buttons, text_inputs)
 }
+
+/// ★ 開いているDialogをオーバーレイ層としてレイアウトし、state.dialog_layers に積む
+/// 本体は画面中央のパネル内に配置し、本体内のクリック領域・ツールチップは層に移す
+#[allow(clippy::too_many_arguments)]
+fn push_dialog_layer<S>(
+    app: &App,
+    state: &mut AppState<S>,
+    lnode: &LayoutedNode<'_>,
+    body: &[WithSpan<ViewNode>],
+    params: &LayoutParams,
+    mouse_pos: [f32; 2],
+    mouse_down: bool,
+    prev_mouse_down: bool,
+    nest_level: u32,
+) where
+    S: StateAccess + 'static,
+{
+    let width = lnode.node.style.as_ref().and_then(|s| s.width);
+    let mut body_params = params.clone();
+    body_params.start = [0.0, 0.0];
+    if let Some(width) = width {
+        body_params.parent_size[0] = width - DIALOG_PADDING * 2.0;
+    }
+
+    // 本体の高さを先に測ってパネルの位置を決める
+    let content_height = {
+        let eval_fn = |e: &Expr| state.eval_expr_from_ast(e);
+        let get_img_size = |path: &str| state.get_image_size(path);
+        layout_vstack(body, body_params.clone(), app, &eval_fn, &get_img_size)
+            .iter()
+            .map(|n| n.position[1] + n.size[1])
+            .fold(0.0, f32::max)
+    };
+    let (position, size) = dialog_panel_rect(
+        params.window_size,
+        state.scroll_offset[1],
+        width,
+        content_height,
+    );
+    body_params.start = [position[0] + DIALOG_PADDING, position[1] + DIALOG_PADDING];
+    body_params.parent_size[0] = size[0] - DIALOG_PADDING * 2.0;
+
+    // 本体内のツールチップ・入れ子のDialogは本体のレイアウト中に登録されるので切り出す
+    let tooltip_mark = state.tooltip_targets.len();
+    let dialog_mark = state.dialog_layers.len();
+    let (body_stencils, hit_rects, text_inputs) = layout_nodes_lightweight(
+        app,
+        state,
+        body,
+        body_params,
+        mouse_pos,
+        mouse_down,
+        prev_mouse_down,
+        nest_level + 1,
+    );
+    let tooltip_targets = state.tooltip_targets.split_off(tooltip_mark);
+    let nested = state.dialog_layers.split_off(dialog_mark);
+
+    let mut stencils = render_dialog_frame(lnode, position, size, params.window_size);
+    stencils.extend(body_stencils);
+    state.dialog_layers.push(OverlayLayer {
+        stencils,
+        hit_rects,
+        text_inputs,
+        tooltip_targets,
+        bounds: Some((position, size)),
+    });
+    // 入れ子のDialogはこのDialogより手前に置く
+    state.dialog_layers.extend(nested);
+}
+
+/// フォーム部品のクリック時に実行する代入アクション
+fn form_control_action(lnode: &LayoutedNode<'_>, path: &str, value: Expr) -> WithSpan<ViewNode> {
+    WithSpan {
+        node: ViewNode::Set {
+            path: path.to_string(),
+            value,
+            inferred_type: None,
+        },
+        line: lnode.node.line,
+        column: lnode.node.column,
+        style: None,
+    }
+}
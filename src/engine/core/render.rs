@@ -2,10 +2,14 @@
 // レンダリング関連（各要素の描画）
 
 use super::utils::*;
-use crate::engine::state::{AppState, StateAccess};
+use crate::engine::state::{AppState, OverlayLayer, StateAccess, Toast, TooltipTarget};
 use crate::parser::ast::{Expr, ViewNode};
 use crate::stencil::stencil::Stencil;
 use crate::ui::LayoutedNode;
+use crate::ui::layout::{
+    FORM_CONTROL_GAP, form_indicator_size, numeric_range, parse_option_list, radio_option_rects,
+    select_popup_rects, slider_track, stepper_button_rects,
+};
 use std::collections::HashMap;
 
 thread_local! {
@@ -95,7 +99,12 @@ pub fn render_text_input_lightweight<S>(
 
         let is_hover = is_point_in_rect(mouse_pos, lnode.position, lnode.size);
 
-        let effective_border_color = if is_focused {
+        // ★ bind先への変換エラー（数値でない入力など）は赤枠で表示
+        let has_error = state.get_text_input_error(id).is_some();
+
+        let effective_border_color = if has_error {
+            [0.9, 0.3, 0.3, 1.0]
+        } else if is_focused {
             [0.3, 0.6, 1.0, 1.0]
         } else if is_hover {
             [0.6, 0.6, 0.6, 1.0]
@@ -448,6 +457,878 @@ pub fn render_button_lightweight(
     }
 }
 
+/// フォーム部品の共通スタイル（ホバー時はhoverスタイルをマージ）
+struct FormControlStyle {
+    accent: [f32; 4],
+    border: [f32; 4],
+    label_color: [f32; 4],
+    font_size: f32,
+    font: String,
+}
+
+impl FormControlStyle {
+    fn resolve(lnode: &LayoutedNode<'_>, is_hover: bool, default_font: &str) -> Self {
+        let mut style = lnode.node.style.clone().unwrap_or_default();
+        if is_hover && let Some(hover_style) = &style.hover {
+            style = style.merged(hover_style);
+        }
+
+        let border = style
+            .border_color
+            .as_ref()
+            .map(convert_to_rgba)
+            .unwrap_or(if is_hover {
+                [0.45, 0.45, 0.5, 1.0]
+            } else {
+                [0.6, 0.6, 0.65, 1.0]
+            });
+
+        Self {
+            // チェック時の色は background で指定（未指定時はButtonと同じ青）
+            accent: style
+                .background
+                .as_ref()
+                .map(convert_to_rgba)
+                .unwrap_or([0.13, 0.59, 0.95, 1.0]),
+            border,
+            label_color: style
+                .color
+                .as_ref()
+                .map(convert_to_rgba)
+                .unwrap_or([0.1, 0.1, 0.1, 1.0]),
+            font_size: style.font_size.unwrap_or(16.0),
+            font: style.font.unwrap_or_else(|| default_font.to_string()),
+        }
+    }
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value, "true" | "1" | "True" | "TRUE")
+}
+
+/// 太さを持つ線分を2つの三角形で描画（チェックマーク・Stepper の記号用）
+fn push_line_segment(
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    from: [f32; 2],
+    to: [f32; 2],
+    thickness: f32,
+    color: [f32; 4],
+) {
+    let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+    let len = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+    let (nx, ny) = (-dy / len * thickness * 0.5, dx / len * thickness * 0.5);
+    let a = [from[0] + nx, from[1] + ny];
+    let b = [from[0] - nx, from[1] - ny];
+    let c = [to[0] - nx, to[1] - ny];
+    let d = [to[0] + nx, to[1] + ny];
+
+    *depth_counter += 0.001;
+    let depth = (1.0 - *depth_counter).max(0.0);
+    for (p1, p2, p3) in [(a, b, c), (a, c, d)] {
+        stencils.push(Stencil::Triangle {
+            p1,
+            p2,
+            p3,
+            color,
+            scroll: true,
+            depth,
+        });
+    }
+}
+
+/// インジケータ右側のラベルを縦中央揃えで描画
+fn push_form_label(
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    label: &str,
+    x: f32,
+    row_position: [f32; 2],
+    row_size: [f32; 2],
+    style: &FormControlStyle,
+) {
+    use crate::ui::text_measurement::measure_text_size;
+    let (_, text_h) = measure_text_size(label, style.font_size, "default", None);
+
+    *depth_counter += 0.001;
+    stencils.push(Stencil::Text {
+        content: label.to_string(),
+        position: [x, row_position[1] + (row_size[1] - text_h) * 0.5],
+        size: style.font_size,
+        color: style.label_color,
+        font: style.font.clone(),
+        max_width: None,
+        scroll: true,
+        depth: (1.0 - *depth_counter).max(0.0),
+    });
+}
+
+/// フォーカス中のフォーム部品の外側に表示するリング
+fn push_focus_ring(
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    position: [f32; 2],
+    size: [f32; 2],
+    radius: f32,
+) {
+    *depth_counter += 0.001;
+    stencils.push(Stencil::RoundedRect {
+        position: [position[0] - 3.0, position[1] - 3.0],
+        width: size[0] + 6.0,
+        height: size[1] + 6.0,
+        radius: radius + 3.0,
+        color: [0.3, 0.6, 1.0, 0.35],
+        scroll: true,
+        depth: (1.0 - *depth_counter).max(0.0),
+    });
+}
+
+/// ★ Checkbox / Switch の描画（インジケータ + ラベル）
+pub fn render_toggle_control_lightweight<S>(
+    lnode: &LayoutedNode<'_>,
+    state: &AppState<S>,
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    mouse_pos: [f32; 2],
+    default_font: &str,
+) where
+    S: StateAccess + 'static,
+{
+    let (id, bind, label, is_switch) = match &lnode.node.node {
+        ViewNode::Checkbox { id, bind, label } => (id, bind, label, false),
+        ViewNode::Switch { id, bind, label } => (id, bind, label, true),
+        _ => return,
+    };
+
+    let is_hover = is_point_in_rect(mouse_pos, lnode.position, lnode.size);
+    let style = FormControlStyle::resolve(lnode, is_hover, default_font);
+    let checked = is_truthy(&state.eval_expr_from_ast(bind));
+
+    let indicator = form_indicator_size(style.font_size);
+    let indicator_width = if is_switch {
+        indicator * 1.8
+    } else {
+        indicator
+    };
+    let ix = lnode.position[0];
+    let iy = lnode.position[1] + (lnode.size[1] - indicator) * 0.5;
+    let radius = if is_switch { indicator * 0.5 } else { 4.0 };
+
+    if state.focused_form_control.as_deref() == Some(id.as_str()) {
+        push_focus_ring(
+            stencils,
+            depth_counter,
+            [ix, iy],
+            [indicator_width, indicator],
+            radius,
+        );
+    }
+
+    if is_switch {
+        // トラック + つまみ（ONで右側へ）
+        let track_color = if checked {
+            style.accent
+        } else if is_hover {
+            [0.65, 0.65, 0.7, 1.0]
+        } else {
+            [0.75, 0.75, 0.78, 1.0]
+        };
+        *depth_counter += 0.001;
+        stencils.push(Stencil::RoundedRect {
+            position: [ix, iy],
+            width: indicator_width,
+            height: indicator,
+            radius,
+            color: track_color,
+            scroll: true,
+            depth: (1.0 - *depth_counter).max(0.0),
+        });
+
+        let knob_x = if checked {
+            ix + indicator_width - indicator * 0.5
+        } else {
+            ix + indicator * 0.5
+        };
+        *depth_counter += 0.001;
+        stencils.push(Stencil::Circle {
+            center: [knob_x, iy + indicator * 0.5],
+            radius: indicator * 0.5 - 2.5,
+            color: [1.0, 1.0, 1.0, 1.0],
+            scroll: true,
+            depth: (1.0 - *depth_counter).max(0.0),
+        });
+    } else {
+        // 枠（チェック時は塗りつぶし）
+        *depth_counter += 0.001;
+        stencils.push(Stencil::RoundedRect {
+            position: [ix, iy],
+            width: indicator,
+            height: indicator,
+            radius,
+            color: if checked { style.accent } else { style.border },
+            scroll: true,
+            depth: (1.0 - *depth_counter).max(0.0),
+        });
+
+        if checked {
+            let point = |fx: f32, fy: f32| [ix + indicator * fx, iy + indicator * fy];
+            let thickness = indicator * 0.12;
+            let white = [1.0, 1.0, 1.0, 1.0];
+            let strokes = [
+                (point(0.22, 0.52), point(0.42, 0.72)),
+                (point(0.42, 0.72), point(0.78, 0.3)),
+            ];
+            for (from, to) in strokes {
+                push_line_segment(stencils, depth_counter, from, to, thickness, white);
+            }
+        } else {
+            *depth_counter += 0.001;
+            stencils.push(Stencil::RoundedRect {
+                position: [ix + 1.5, iy + 1.5],
+                width: indicator - 3.0,
+                height: indicator - 3.0,
+                radius: radius - 1.0,
+                color: [1.0, 1.0, 1.0, 1.0],
+                scroll: true,
+                depth: (1.0 - *depth_counter).max(0.0),
+            });
+        }
+    }
+
+    if let Some(label) = label {
+        push_form_label(
+            stencils,
+            depth_counter,
+            label,
+            ix + indicator_width + FORM_CONTROL_GAP,
+            lnode.position,
+            lnode.size,
+            &style,
+        );
+    }
+}
+
+/// ★ RadioGroup の描画（選択肢を縦に並べる）
+pub fn render_radio_group_lightweight<S>(
+    lnode: &LayoutedNode<'_>,
+    state: &AppState<S>,
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    mouse_pos: [f32; 2],
+    default_font: &str,
+) where
+    S: StateAccess + 'static,
+{
+    let ViewNode::RadioGroup { id, bind, options } = &lnode.node.node else {
+        return;
+    };
+
+    let selected = state.eval_expr_from_ast(bind);
+    let values = parse_option_list(&state.eval_expr_from_ast(options));
+    let rects = radio_option_rects(lnode.position, lnode.size, values.len());
+
+    for (index, (value, (row_pos, row_size))) in values.iter().zip(rects).enumerate() {
+        let is_hover = is_point_in_rect(mouse_pos, row_pos, row_size);
+        let style = FormControlStyle::resolve(lnode, is_hover, default_font);
+        let is_selected = *value == selected;
+
+        let indicator = form_indicator_size(style.font_size);
+        let radius = indicator * 0.5;
+        let center = [row_pos[0] + radius, row_pos[1] + row_size[1] * 0.5];
+
+        let option_id = format!("{}#{}", id, index);
+        if state.focused_form_control.as_deref() == Some(option_id.as_str()) {
+            push_focus_ring(
+                stencils,
+                depth_counter,
+                [center[0] - radius, center[1] - radius],
+                [indicator, indicator],
+                radius,
+            );
+        }
+
+        // 外枠 → 内側の白 → 選択中の点
+        let ring_color = if is_selected {
+            style.accent
+        } else {
+            style.border
+        };
+        let mut circles = vec![(radius, ring_color), (radius - 1.5, [1.0, 1.0, 1.0, 1.0])];
+        if is_selected {
+            circles.push((radius * 0.5, style.accent));
+        }
+        for (r, color) in circles {
+            *depth_counter += 0.001;
+            stencils.push(Stencil::Circle {
+                center,
+                radius: r,
+                color,
+                scroll: true,
+                depth: (1.0 - *depth_counter).max(0.0),
+            });
+        }
+
+        push_form_label(
+            stencils,
+            depth_counter,
+            value,
+            row_pos[0] + indicator + FORM_CONTROL_GAP,
+            row_pos,
+            row_size,
+            &style,
+        );
+    }
+}
+
+/// Selectの開閉を示す矢印（閉じているときは下向き、開いているときは上向き）
+fn push_select_arrow(
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    center: [f32; 2],
+    half_width: f32,
+    is_open: bool,
+    color: [f32; 4],
+) {
+    let half_height = half_width * 0.5;
+    let (base_y, tip_y) = if is_open {
+        (center[1] + half_height, center[1] - half_height)
+    } else {
+        (center[1] - half_height, center[1] + half_height)
+    };
+
+    *depth_counter += 0.001;
+    stencils.push(Stencil::Triangle {
+        p1: [center[0] - half_width, base_y],
+        p2: [center[0] + half_width, base_y],
+        p3: [center[0], tip_y],
+        color,
+        scroll: true,
+        depth: (1.0 - *depth_counter).max(0.0),
+    });
+}
+
+/// ★ Select 本体の描画（選択中の値 + 開閉の矢印）
+pub fn render_select_lightweight<S>(
+    lnode: &LayoutedNode<'_>,
+    state: &AppState<S>,
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    mouse_pos: [f32; 2],
+    default_font: &str,
+) where
+    S: StateAccess + 'static,
+{
+    let ViewNode::Select {
+        id,
+        bind,
+        options,
+        placeholder,
+    } = &lnode.node.node
+    else {
+        return;
+    };
+
+    let is_hover = is_point_in_rect(mouse_pos, lnode.position, lnode.size);
+    let mut style = FormControlStyle::resolve(lnode, is_hover, default_font);
+    let is_open = state
+        .open_overlay
+        .as_ref()
+        .is_some_and(|overlay| overlay.owner == *id);
+    let [x, y] = lnode.position;
+    let [w, h] = lnode.size;
+
+    if is_open || state.focused_form_control.as_deref() == Some(id.as_str()) {
+        push_focus_ring(stencils, depth_counter, lnode.position, lnode.size, 4.0);
+    }
+
+    // 枠 → 内側の白
+    let frame_color = if is_open { style.accent } else { style.border };
+    for (inset, color) in [(0.0, frame_color), (1.5, [1.0, 1.0, 1.0, 1.0])] {
+        *depth_counter += 0.001;
+        stencils.push(Stencil::RoundedRect {
+            position: [x + inset, y + inset],
+            width: w - inset * 2.0,
+            height: h - inset * 2.0,
+            radius: 4.0 - inset * 0.5,
+            color,
+            scroll: true,
+            depth: (1.0 - *depth_counter).max(0.0),
+        });
+    }
+
+    let indicator = form_indicator_size(style.font_size);
+    push_select_arrow(
+        stencils,
+        depth_counter,
+        [x + w - FORM_CONTROL_GAP - indicator * 0.5, y + h * 0.5],
+        indicator * 0.3,
+        is_open,
+        style.border,
+    );
+
+    // 選択中の値（選択肢に含まれない場合はplaceholderを薄く表示）
+    let current = state.eval_expr_from_ast(bind);
+    let values = parse_option_list(&state.eval_expr_from_ast(options));
+    let text = if values.contains(&current) {
+        current
+    } else {
+        style.label_color = [0.55, 0.55, 0.6, 1.0];
+        placeholder.clone().unwrap_or_default()
+    };
+    if !text.is_empty() {
+        push_form_label(
+            stencils,
+            depth_counter,
+            &text,
+            x + FORM_CONTROL_GAP,
+            lnode.position,
+            lnode.size,
+            &style,
+        );
+    }
+}
+
+/// ★ Select のドロップダウン（オーバーレイ層）の描画内容とクリック領域
+/// オーバーレイ層は下層とは別に描画されるため、深度は層の中だけで数える
+pub fn render_select_overlay<S>(
+    lnode: &LayoutedNode<'_>,
+    state: &AppState<S>,
+    mouse_pos: [f32; 2],
+    default_font: &str,
+) -> OverlayLayer
+where
+    S: StateAccess + 'static,
+{
+    let mut layer = OverlayLayer::default();
+    let ViewNode::Select {
+        id, bind, options, ..
+    } = &lnode.node.node
+    else {
+        return layer;
+    };
+
+    let current = state.eval_expr_from_ast(bind);
+    let values = parse_option_list(&state.eval_expr_from_ast(options));
+    let rects = select_popup_rects(lnode.position, lnode.size, values.len());
+    let highlighted = state
+        .open_overlay
+        .as_ref()
+        .and_then(|overlay| overlay.highlighted);
+    let (Some(first), Some(last)) = (rects.first(), rects.last()) else {
+        return layer;
+    };
+
+    let stencils = &mut layer.stencils;
+    let mut depth_counter: f32 = 0.0;
+    let border = FormControlStyle::resolve(lnode, false, default_font).border;
+
+    // 影 → 枠 → 背景
+    let top = first.0[1];
+    let height = last.0[1] + last.1[1] - top;
+    layer.bounds = Some(([first.0[0], top], [lnode.size[0], height]));
+    let panels = [
+        ([2.0, 3.0], 0.0, [0.0, 0.0, 0.0, 0.15]),
+        ([0.0, 0.0], 0.0, border),
+        ([0.0, 0.0], 1.0, [1.0, 1.0, 1.0, 1.0]),
+    ];
+    for (offset, inset, color) in panels {
+        depth_counter += 0.001;
+        stencils.push(Stencil::RoundedRect {
+            position: [first.0[0] + offset[0] + inset, top + offset[1] + inset],
+            width: lnode.size[0] - inset * 2.0,
+            height: height - inset * 2.0,
+            radius: 4.0,
+            color,
+            scroll: true,
+            depth: (1.0 - depth_counter).max(0.0),
+        });
+    }
+
+    for (index, (value, (row_pos, row_size))) in values.iter().zip(rects).enumerate() {
+        // キーボードで選択中・ホバー中の行を強調
+        if highlighted == Some(index) || is_point_in_rect(mouse_pos, row_pos, row_size) {
+            depth_counter += 0.001;
+            stencils.push(Stencil::Rect {
+                position: [row_pos[0] + 1.0, row_pos[1]],
+                width: row_size[0] - 2.0,
+                height: row_size[1],
+                color: [0.9, 0.94, 1.0, 1.0],
+                scroll: true,
+                depth: (1.0 - depth_counter).max(0.0),
+            });
+        }
+
+        let mut style = FormControlStyle::resolve(lnode, false, default_font);
+        if *value == current {
+            style.label_color = style.accent;
+        }
+        push_form_label(
+            stencils,
+            &mut depth_counter,
+            value,
+            row_pos[0] + FORM_CONTROL_GAP,
+            row_pos,
+            row_size,
+            &style,
+        );
+
+        layer
+            .hit_rects
+            .push((format!("{}#{}", id, index), row_pos, row_size));
+    }
+
+    layer
+}
+
+/// ★ Slider の描画（トラック + 現在値までの塗り + つまみ）
+pub fn render_slider_lightweight<S>(
+    lnode: &LayoutedNode<'_>,
+    state: &AppState<S>,
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    mouse_pos: [f32; 2],
+    default_font: &str,
+) where
+    S: StateAccess + 'static,
+{
+    let ViewNode::Slider { id, bind, .. } = &lnode.node.node else {
+        return;
+    };
+
+    // ドラッグ中はポインタが外れてもホバー表示を維持
+    let is_hover = state.pointer_capture.as_deref() == Some(id.as_str())
+        || is_point_in_rect(mouse_pos, lnode.position, lnode.size);
+    let style = FormControlStyle::resolve(lnode, is_hover, default_font);
+    let (min, max, _) = numeric_range(&lnode.node.node, |e| state.eval_expr_from_ast(e));
+    let value = state
+        .eval_expr_from_ast(bind)
+        .trim()
+        .parse::<f32>()
+        .unwrap_or(min);
+    let ratio = if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    let (track_x, track_width) = slider_track(lnode.position, lnode.size, style.font_size);
+    let radius = form_indicator_size(style.font_size) * 0.5;
+    let center_y = lnode.position[1] + lnode.size[1] * 0.5;
+    let thumb_x = track_x + track_width * ratio;
+    let track_height = 4.0;
+
+    // トラック全体 → 現在値までの塗り
+    let segments = [
+        (track_width, [0.8, 0.8, 0.83, 1.0]),
+        (thumb_x - track_x, style.accent),
+    ];
+    for (width, color) in segments {
+        *depth_counter += 0.001;
+        stencils.push(Stencil::RoundedRect {
+            position: [track_x, center_y - track_height * 0.5],
+            width,
+            height: track_height,
+            radius: track_height * 0.5,
+            color,
+            scroll: true,
+            depth: (1.0 - *depth_counter).max(0.0),
+        });
+    }
+
+    if state.focused_form_control.as_deref() == Some(id.as_str()) {
+        push_focus_ring(
+            stencils,
+            depth_counter,
+            [thumb_x - radius, center_y - radius],
+            [radius * 2.0, radius * 2.0],
+            radius,
+        );
+    }
+
+    // つまみ（枠 → 内側の白）
+    let ring_color = if is_hover { style.accent } else { style.border };
+    for (r, color) in [(radius, ring_color), (radius - 1.5, [1.0, 1.0, 1.0, 1.0])] {
+        *depth_counter += 0.001;
+        stencils.push(Stencil::Circle {
+            center: [thumb_x, center_y],
+            radius: r,
+            color,
+            scroll: true,
+            depth: (1.0 - *depth_counter).max(0.0),
+        });
+    }
+}
+
+/// ★ Stepper の描画（− ボタン + 現在値 + ＋ ボタン）
+pub fn render_stepper_lightweight<S>(
+    lnode: &LayoutedNode<'_>,
+    state: &AppState<S>,
+    stencils: &mut Vec<Stencil>,
+    depth_counter: &mut f32,
+    mouse_pos: [f32; 2],
+    default_font: &str,
+) where
+    S: StateAccess + 'static,
+{
+    let ViewNode::Stepper { id, bind, .. } = &lnode.node.node else {
+        return;
+    };
+
+    let style = FormControlStyle::resolve(lnode, false, default_font);
+    let (min, max, _) = numeric_range(&lnode.node.node, |e| state.eval_expr_from_ast(e));
+    let value_text = state.eval_expr_from_ast(bind);
+    let value = value_text.trim().parse::<f32>().ok();
+    let [x, y] = lnode.position;
+    let [w, h] = lnode.size;
+
+    // フォーカスは −/+ ボタン（`id#dec` / `id#inc`）の操作で付く
+    let button_prefix = format!("{}#", id);
+    if state
+        .focused_form_control
+        .as_deref()
+        .is_some_and(|focused| focused.starts_with(&button_prefix))
+    {
+        push_focus_ring(stencils, depth_counter, lnode.position, lnode.size, 4.0);
+    }
+
+    // 枠 → 内側の白
+    for (inset, color) in [(0.0, style.border), (1.5, [1.0, 1.0, 1.0, 1.0])] {
+        *depth_counter += 0.001;
+        stencils.push(Stencil::RoundedRect {
+            position: [x + inset, y + inset],
+            width: w - inset * 2.0,
+            height: h - inset * 2.0,
+            radius: 4.0 - inset * 0.5,
+            color,
+            scroll: true,
+            depth: (1.0 - *depth_counter).max(0.0),
+        });
+    }
+
+    let [dec_rect, inc_rect] = stepper_button_rects(lnode.position, lnode.size);
+    for ((pos, size), is_inc) in [(dec_rect, false), (inc_rect, true)] {
+        // 範囲の端では押せないことを薄い色で示す
+        let enabled = value.is_none_or(|v| if is_inc { v < max } else { v > min });
+        if enabled && is_point_in_rect(mouse_pos, pos, size) {
+            *depth_counter += 0.001;
+            stencils.push(Stencil::Rect {
+                position: [pos[0] + 1.5, pos[1] + 1.5],
+                width: size[0] - 3.0,
+                height: size[1] - 3.0,
+                color: [0.92, 0.94, 0.97, 1.0],
+                scroll: true,
+                depth: (1.0 - *depth_counter).max(0.0),
+            });
+        }
+
+        let color = if enabled {
+            style.label_color
+        } else {
+            [0.7, 0.7, 0.72, 1.0]
+        };
+        let center = [pos[0] + size[0] * 0.5, pos[1] + size[1] * 0.5];
+        let arm = size[1].min(size[0]) * 0.18;
+        let thickness = 2.0;
+        push_line_segment(
+            stencils,
+            depth_counter,
+            [center[0] - arm, center[1]],
+            [center[0] + arm, center[1]],
+            thickness,
+            color,
+        );
+        if is_inc {
+            push_line_segment(
+                stencils,
+                depth_counter,
+                [center[0], center[1] - arm],
+                [center[0], center[1] + arm],
+                thickness,
+                color,
+            );
+        }
+    }
+
+    // 現在値（中央揃え）
+    let text_width = text_width_cached(&value_text, style.font_size, &style.font);
+    push_form_label(
+        stencils,
+        depth_counter,
+        &value_text,
+        x + (w - text_width) * 0.5,
+        lnode.position,
+        lnode.size,
+        &style,
+    );
+}
+
+/// ★ Dialog の背景（表示中の画面全体を暗くする）とパネル
+pub fn render_dialog_frame(
+    lnode: &LayoutedNode<'_>,
+    position: [f32; 2],
+    size: [f32; 2],
+    window_size: [f32; 2],
+) -> Vec<Stencil> {
+    let background = lnode
+        .node
+        .style
+        .as_ref()
+        .and_then(|s| s.background.as_ref())
+        .map(convert_to_rgba)
+        .unwrap_or([1.0, 1.0, 1.0, 1.0]);
+
+    let mut stencils = Vec::new();
+    let mut depth_counter: f32 = 0.001;
+    stencils.push(Stencil::Rect {
+        position: [0.0, 0.0],
+        width: window_size[0],
+        height: window_size[1],
+        color: [0.0, 0.0, 0.0, 0.4],
+        scroll: false,
+        depth: 1.0 - depth_counter,
+    });
+
+    // 影 → パネル
+    for (offset, color) in [([0.0, 4.0], [0.0, 0.0, 0.0, 0.2]), ([0.0, 0.0], background)] {
+        depth_counter += 0.001;
+        stencils.push(Stencil::RoundedRect {
+            position: [position[0] + offset[0], position[1] + offset[1]],
+            width: size[0],
+            height: size[1],
+            radius: 8.0,
+            color,
+            scroll: true,
+            depth: 1.0 - depth_counter,
+        });
+    }
+    stencils
+}
+
+/// ツールチップの文字サイズ
+const TOOLTIP_FONT_SIZE: f32 = 13.0;
+
+/// ★ ツールチップ（対象の直下、画面下端に収まらなければ直上に表示）
+pub fn render_tooltip(
+    target: &TooltipTarget,
+    window_size: [f32; 2],
+    scroll_y: f32,
+    default_font: &str,
+) -> Vec<Stencil> {
+    use crate::ui::text_measurement::measure_text_size;
+    let (text_w, text_h) = measure_text_size(&target.text, TOOLTIP_FONT_SIZE, default_font, None);
+    let size = [text_w + 16.0, text_h + 10.0];
+
+    let x = (target.position[0] + (target.size[0] - size[0]) * 0.5)
+        .min(window_size[0] - size[0] - 4.0)
+        .max(4.0);
+    let below = target.position[1] + target.size[1] + 6.0;
+    let y = if below + size[1] > window_size[1] - scroll_y {
+        target.position[1] - size[1] - 6.0
+    } else {
+        below
+    };
+
+    vec![
+        Stencil::RoundedRect {
+            position: [x, y],
+            width: size[0],
+            height: size[1],
+            radius: 4.0,
+            color: [0.15, 0.15, 0.17, 0.92],
+            scroll: true,
+            depth: 0.999,
+        },
+        Stencil::Text {
+            content: target.text.clone(),
+            position: [x + 8.0, y + 5.0],
+            size: TOOLTIP_FONT_SIZE,
+            color: [1.0, 1.0, 1.0, 1.0],
+            font: default_font.to_string(),
+            max_width: None,
+            scroll: true,
+            depth: 0.998,
+        },
+    ]
+}
+
+/// ★ トースト通知（画面下部中央に、新しいものほど下に積む）
+pub fn render_toasts(toasts: &[Toast], window_size: [f32; 2], default_font: &str) -> Vec<Stencil> {
+    use crate::ui::text_measurement::measure_text_size;
+    let font_size = 15.0;
+    let max_text_width = (window_size[0] - 80.0).max(0.0);
+
+    let mut stencils = Vec::new();
+    let mut bottom = window_size[1] - 24.0;
+    for toast in toasts.iter().rev() {
+        let (text_w, text_h) = measure_text_size(
+            &toast.message,
+            font_size,
+            default_font,
+            Some(max_text_width),
+        );
+        let size = [text_w + 32.0, text_h + 20.0];
+        let position = [(window_size[0] - size[0]) * 0.5, bottom - size[1]];
+        stencils.push(Stencil::RoundedRect {
+            position,
+            width: size[0],
+            height: size[1],
+            radius: 6.0,
+            color: [0.15, 0.15, 0.17, 0.92],
+            scroll: false,
+            depth: 0.999,
+        });
+        stencils.push(Stencil::Text {
+            content: toast.message.clone(),
+            position: [position[0] + 16.0, position[1] + 10.0],
+            size: font_size,
+            color: [1.0, 1.0, 1.0, 1.0],
+            font: default_font.to_string(),
+            max_width: Some(max_text_width),
+            scroll: false,
+            depth: 0.998,
+        });
+        bottom = position[1] - 8.0;
+    }
+    stencils
+}
+
+/// ★ ホットリロードの読み込みエラー（画面上部に帯で表示。修正して保存し直すと消える）
+pub fn render_reload_error(
+    message: &str,
+    window_size: [f32; 2],
+    default_font: &str,
+) -> Vec<Stencil> {
+    use crate::ui::text_measurement::measure_text_size;
+    let font_size = 14.0;
+    let max_text_width = (window_size[0] - 32.0).max(0.0);
+    let content = format!(
+        "ホットリロードに失敗しました（前回の内容で実行中）\n{}",
+        message
+    );
+    let (_, text_h) = measure_text_size(&content, font_size, default_font, Some(max_text_width));
+
+    vec![
+        Stencil::Rect {
+            position: [0.0, 0.0],
+            width: window_size[0],
+            height: text_h + 24.0,
+            color: [0.55, 0.08, 0.08, 0.95],
+            scroll: false,
+            depth: 0.999,
+        },
+        Stencil::Text {
+            content,
+            position: [16.0, 12.0],
+            size: font_size,
+            color: [1.0, 1.0, 1.0, 1.0],
+            font: default_font.to_string(),
+            max_width: Some(max_text_width),
+            scroll: false,
+            depth: 0.998,
+        },
+    ]
+}
+
 /// 軽量化されたテキスト描画
 pub fn render_text_lightweight<S>(
     lnode: &LayoutedNode<'_>,
@@ -0,0 +1,105 @@
+//! ファイルに定義した関数（`fn full_name(u) = u.first + " " + u.last`）の呼び出し
+//!
+//! 引数は評価済みの値で受け取り、本体の式の中の引数名・`let` の名前を値のリテラルに置き換えてから
+//! 呼び出し側の評価関数で評価する。Rust関数の登録を経由しないので、どの式からでもそのまま呼べる。
+
+use crate::engine::match_pattern::bind_value;
+use crate::parser::ast::{Expr, FunctionDef};
+
+/// 再帰呼び出しの深さの上限（超えたら null を返す）
+const MAX_CALL_DEPTH: usize = 64;
+
+/// 関数を呼び出す（不足した引数は null として扱う）
+///
+/// `depth` は呼び出し元の深さ。本体は `depth + 1` で評価するので、
+/// 本体の中の呼び出しも `eval` に渡された深さで呼び出すこと
+pub fn call_function<F>(def: &FunctionDef, args: &[String], depth: usize, eval: &F) -> String
+where
+    F: Fn(&Expr, usize) -> String,
+{
+    if depth >= MAX_CALL_DEPTH {
+        log::warn!(
+            "関数 '{}' の呼び出しが深すぎます（{} 段）。再帰の終了条件を確認してください",
+            def.name,
+            MAX_CALL_DEPTH
+        );
+        return String::new();
+    }
+
+    let mut bindings: Vec<(&str, String)> = def
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            (
+                param.name.as_str(),
+                args.get(i).cloned().unwrap_or_default(),
+            )
+        })
+        .collect();
+    for (name, expr) in &def.lets {
+        let value = eval(&bind_all(expr, &bindings), depth + 1);
+        bindings.push((name.as_str(), value));
+    }
+    eval(&bind_all(&def.body, &bindings), depth + 1)
+}
+
+/// 束縛をすべて置き換える（後から束縛した同名の `let` が優先）
+fn bind_all(expr: &Expr, bindings: &[(&str, String)]) -> Expr {
+    bindings
+        .iter()
+        .rev()
+        .fold(expr.clone(), |e, (name, value)| bind_value(&e, name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::state::AppState;
+    use crate::parser::ast::Expr;
+    use crate::parser::parse::parse_nilo;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Person {
+        first: String,
+        last: String,
+    }
+
+    #[derive(nilo_state_access_derive::StateAccess)]
+    struct Account {
+        user: Person,
+    }
+
+    #[test]
+    fn test_recursive_functions() {
+        let app = parse_nilo(
+            r#"
+            flow { start: Main }
+            fn fact(n) = n <= 1 ? 1 : n * fact(n - 1)
+            fn forever(n) = forever(n + 1)
+            fn full_name(u) = u.first + " " + u.last
+            timeline Main {}
+            "#,
+        )
+        .unwrap();
+        let account = Account {
+            user: Person {
+                first: "Ada".into(),
+                last: "Lovelace".into(),
+            },
+        };
+        let mut state = AppState::new(account, "Main".into());
+        state.set_functions(&app.functions);
+        let call = |name: &str, args: Vec<Expr>| {
+            state.eval_expr_from_ast(&Expr::FunctionCall {
+                name: name.into(),
+                args,
+            })
+        };
+
+        assert_eq!(call("fact", vec![Expr::Number(5.0)]), "120");
+        // 終わらない再帰は上限の深さで null になる
+        assert_eq!(call("forever", vec![Expr::Number(0.0)]), "");
+        let user = Expr::Path("state.user".into());
+        assert_eq!(call("full_name", vec![user]), "Ada Lovelace");
+    }
+}
@@ -0,0 +1,370 @@
+//! フローの遷移の検証と条件付き遷移（`Cart -> Checkout if state.logged_in else Login`）、
+//! Rustから登録するルートガード
+//!
+//! `navigate_to` の遷移先は `resolve_transition` でフローの定義と照らし合わせ、
+//! その後 `check_route_guards` で登録済みのガードに問い合わせてから遷移する。
+
+use crate::engine::routing::RouteMatch;
+use crate::parser::ast::{Expr, Flow};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// フローに定義されていない遷移の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlowMode {
+    /// 遷移しない（エラーログを出す）
+    Strict,
+    /// 警告を出して遷移する
+    #[default]
+    Warn,
+}
+
+/// ガードの判定
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuardDecision {
+    Allow,
+    Block,
+    /// 別のタイムラインへ遷移させる（パラメータなし）
+    Redirect(String),
+}
+
+/// ガードに渡す遷移の情報
+#[derive(Debug)]
+pub struct Navigation<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub params: &'a HashMap<String, String>,
+}
+
+type RouteGuardFn = dyn Fn(&dyn Any, &Navigation) -> Option<GuardDecision> + Send + Sync;
+
+/// リダイレクトが続く回数の上限（ガード同士の無限ループ防止）
+const MAX_REDIRECTS: usize = 8;
+
+lazy_static::lazy_static! {
+    // 登録順に問い合わせる
+    static ref ROUTE_GUARDS: Mutex<Vec<(String, Arc<RouteGuardFn>)>> = Mutex::new(Vec::new());
+}
+
+/// ルートガードを登録する（同じ名前なら置き換え）
+///
+/// ```ignore
+/// register_route_guard("auth", |state: &MyState, nav: &Navigation| {
+///     if nav.to == "Checkout" && !state.logged_in {
+///         GuardDecision::Redirect("Login".into())
+///     } else {
+///         GuardDecision::Allow
+///     }
+/// });
+/// ```
+pub fn register_route_guard<S, F>(name: &str, guard: F)
+where
+    S: 'static,
+    F: Fn(&S, &Navigation) -> GuardDecision + Send + Sync + 'static,
+{
+    let guard: Arc<RouteGuardFn> = Arc::new(move |state: &dyn Any, nav: &Navigation| {
+        state.downcast_ref::<S>().map(|state| guard(state, nav))
+    });
+    if let Ok(mut guards) = ROUTE_GUARDS.lock() {
+        match guards.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = guard,
+            None => guards.push((name.to_string(), guard)),
+        }
+    }
+}
+
+/// ルートガードの登録を解除する
+pub fn unregister_route_guard(name: &str) {
+    if let Ok(mut guards) = ROUTE_GUARDS.lock() {
+        guards.retain(|(n, _)| n != name);
+    }
+}
+
+/// 登録済みのガードに問い合わせ、実際に遷移する先を決める（遷移しない場合はNone）
+pub fn check_route_guards(
+    state: &dyn Any,
+    from: &str,
+    mut route: RouteMatch,
+) -> Option<RouteMatch> {
+    // ガードの中から登録・解除や遷移ができるよう、ロックを外してから呼び出す
+    let guards = match ROUTE_GUARDS.lock() {
+        Ok(guards) => guards.clone(),
+        Err(_) => {
+            log::error!(
+                "ルートガードの一覧を読めませんでした。遷移を中止します（{} から）",
+                from
+            );
+            return None;
+        }
+    };
+    for _ in 0..=MAX_REDIRECTS {
+        let nav = Navigation {
+            from,
+            to: &route.timeline,
+            params: &route.params,
+        };
+        let decision = guards
+            .iter()
+            .filter_map(|(name, guard)| guard(state, &nav).map(|d| (name, d)))
+            .find(|(_, decision)| *decision != GuardDecision::Allow);
+        match decision {
+            None => return Some(route),
+            Some((name, GuardDecision::Redirect(timeline))) => {
+                log::info!(
+                    "ルートガード '{}' が {} -> {} を {} へリダイレクトしました",
+                    name,
+                    from,
+                    route.timeline,
+                    timeline
+                );
+                route = RouteMatch {
+                    timeline,
+                    params: HashMap::new(),
+                    query: HashMap::new(),
+                    url: None,
+                };
+            }
+            Some((name, _)) => {
+                log::info!(
+                    "ルートガード '{}' が {} -> {} を拒否しました",
+                    name,
+                    from,
+                    route.timeline
+                );
+                return None;
+            }
+        }
+    }
+    log::warn!(
+        "ルートガードのリダイレクトが {} 回を超えました（{} から）。遷移を中止します",
+        MAX_REDIRECTS,
+        from
+    );
+    None
+}
+
+/// フローの定義に従って `navigate_to(to)` の遷移先を決める（遷移しない場合はNone）
+///
+/// - 遷移元に遷移の定義が1つもなければ制限しない
+/// - 条件付きの遷移は条件が偽なら `else` の遷移先（なければ遷移しない）。`else` の遷移先へは直接遷移できる
+/// - 定義されていない遷移は `FlowMode::Strict` なら遷移しない、`Warn` なら警告して遷移する
+pub fn resolve_transition<F>(
+    flow: &Flow,
+    from: &str,
+    to: &str,
+    mode: FlowMode,
+    eval: &F,
+) -> Option<String>
+where
+    F: Fn(&Expr) -> String,
+{
+    let mut outgoing = flow
+        .transitions
+        .iter()
+        .filter(|t| t.from.iter().any(|f| f == from))
+        .peekable();
+    if outgoing.peek().is_none() {
+        return Some(to.to_string());
+    }
+
+    let mut declared = false;
+    for transition in outgoing {
+        if !transition.to.iter().any(|t| t.timeline == to) {
+            // else 側の遷移先へは直接遷移してもよい
+            if transition
+                .fallback
+                .as_ref()
+                .is_some_and(|t| t.timeline == to)
+            {
+                return Some(to.to_string());
+            }
+            continue;
+        }
+        declared = true;
+        let Some(condition) = &transition.condition else {
+            return Some(to.to_string());
+        };
+        if is_truthy(&eval(condition)) {
+            return Some(to.to_string());
+        }
+        if let Some(fallback) = &transition.fallback {
+            log::info!(
+                "{} -> {} の条件を満たさないため {} へ遷移します",
+                from,
+                to,
+                fallback.timeline
+            );
+            return Some(fallback.timeline.clone());
+        }
+    }
+
+    if declared {
+        // 条件付きの遷移しかなく、どれも条件を満たさなかった
+        log::info!("{} -> {} の条件を満たさないため遷移しません", from, to);
+        return None;
+    }
+    match mode {
+        FlowMode::Strict => {
+            log::error!("{} -> {} はフローに定義されていない遷移です", from, to);
+            None
+        }
+        FlowMode::Warn => {
+            log::warn!("{} -> {} はフローに定義されていない遷移です", from, to);
+            Some(to.to_string())
+        }
+    }
+}
+
+/// 条件の評価結果を真偽として読む（`if` と同じ規則）
+fn is_truthy(value: &str) -> bool {
+    matches!(value, "true" | "1" | "True" | "TRUE")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::{FlowTarget, FlowTransition};
+
+    fn target(timeline: &str) -> FlowTarget {
+        FlowTarget {
+            timeline: timeline.to_string(),
+            url: None,
+            params: HashMap::new(),
+        }
+    }
+
+    /// Cart -> Checkout if state.logged_in else Login / Cart -> Home
+    fn cart_flow() -> Flow {
+        Flow {
+            start: "Cart".into(),
+            start_url: None,
+            not_found: None,
+            sub_flows: vec![],
+            transitions: vec![
+                FlowTransition {
+                    from: vec!["Cart".into()],
+                    to: vec![target("Checkout")],
+                    condition: Some(Expr::Path("state.logged_in".into())),
+                    fallback: Some(target("Login")),
+                },
+                FlowTransition {
+                    from: vec!["Cart".into()],
+                    to: vec![target("Home")],
+                    condition: None,
+                    fallback: None,
+                },
+            ],
+        }
+    }
+
+    fn resolve(from: &str, to: &str, mode: FlowMode, logged_in: bool) -> Option<String> {
+        let eval = |_: &Expr| logged_in.to_string();
+        resolve_transition(&cart_flow(), from, to, mode, &eval)
+    }
+
+    fn route(timeline: &str) -> RouteMatch {
+        RouteMatch {
+            timeline: timeline.to_string(),
+            params: HashMap::new(),
+            query: HashMap::new(),
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_conditional_transition() {
+        assert_eq!(
+            resolve("Cart", "Checkout", FlowMode::Strict, true).as_deref(),
+            Some("Checkout")
+        );
+        assert_eq!(
+            resolve("Cart", "Checkout", FlowMode::Strict, false).as_deref(),
+            Some("Login")
+        );
+        // else 側の遷移先へは直接遷移できる
+        assert_eq!(
+            resolve("Cart", "Login", FlowMode::Strict, false).as_deref(),
+            Some("Login")
+        );
+        assert_eq!(
+            resolve("Cart", "Home", FlowMode::Strict, false).as_deref(),
+            Some("Home")
+        );
+    }
+
+    #[test]
+    fn test_undeclared_transition_by_mode() {
+        assert_eq!(resolve("Cart", "Admin", FlowMode::Strict, true), None);
+        assert_eq!(
+            resolve("Cart", "Admin", FlowMode::Warn, true).as_deref(),
+            Some("Admin")
+        );
+        // 遷移の定義がないタイムラインからは制限しない
+        assert_eq!(
+            resolve("Login", "Admin", FlowMode::Strict, false).as_deref(),
+            Some("Admin")
+        );
+    }
+
+    // ガードは全体で共有されるので、テストごとに別の状態の型を使って干渉しないようにする
+
+    #[test]
+    fn test_guard_redirects() {
+        struct Session {
+            logged_in: bool,
+        }
+        let check = |logged_in: bool, to: &str| {
+            check_route_guards(&Session { logged_in }, "Cart", route(to)).map(|r| r.timeline)
+        };
+        register_route_guard("test_auth", |session: &Session, nav: &Navigation| {
+            if nav.to == "Checkout" && !session.logged_in {
+                GuardDecision::Redirect("Login".into())
+            } else {
+                GuardDecision::Allow
+            }
+        });
+
+        assert_eq!(check(false, "Checkout").as_deref(), Some("Login"));
+        assert_eq!(check(true, "Checkout").as_deref(), Some("Checkout"));
+
+        unregister_route_guard("test_auth");
+        assert_eq!(check(false, "Checkout").as_deref(), Some("Checkout"));
+    }
+
+    #[test]
+    fn test_guard_can_unregister_itself() {
+        struct Visitor;
+        // ガードの中から登録を解除してもデッドロックしない
+        register_route_guard("test_once", |_: &Visitor, nav: &Navigation| {
+            if nav.to == "Welcome" {
+                unregister_route_guard("test_once");
+                GuardDecision::Block
+            } else {
+                GuardDecision::Allow
+            }
+        });
+        let check = || check_route_guards(&Visitor, "Home", route("Welcome"));
+        assert!(check().is_none());
+        assert_eq!(check().map(|r| r.timeline).as_deref(), Some("Welcome"));
+    }
+
+    #[test]
+    fn test_redirect_loop_is_aborted() {
+        struct Looping;
+        register_route_guard("test_loop", |_: &Looping, nav: &Navigation| {
+            GuardDecision::Redirect(if nav.to == "A" { "B" } else { "A" }.into())
+        });
+        assert!(check_route_guards(&Looping, "Home", route("A")).is_none());
+        unregister_route_guard("test_loop");
+    }
+
+    #[test]
+    fn test_guard_ignores_other_state_types() {
+        struct Other;
+        assert_eq!(
+            check_route_guards(&Other, "Cart", route("Checkout")).map(|r| r.timeline),
+            Some("Checkout".into())
+        );
+    }
+}
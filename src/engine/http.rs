@@ -0,0 +1,375 @@
+//! 組み込みのHTTPクライアント（`http_get` / `http_post`）
+//!
+//! ネイティブ版は共有のtokioランタイムの上で使い回しの `reqwest::Client` を、WASM版は
+//! ブラウザの `fetch`（reqwestのWASM実装）を使う。完了は `AsyncEvent::HttpResponse` で
+//! メインスレッドへ届き、`step_whens` が `into` への代入と on_success / on_error を実行する。
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::engine::async_call::background_runtime;
+use crate::engine::async_call::{AsyncEvent, send_async_event};
+use crate::parser::ast::{ActionBlock, HttpMethod};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// タイムアウトを指定しないリクエストの既定値
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+#[derive(Debug, Clone)]
+struct HttpConfig {
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+    timeout_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            headers: Vec::new(),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref HTTP_CONFIG: Mutex<HttpConfig> = Mutex::new(HttpConfig::default());
+    // 完了したリクエスト（`UIEvent::HttpResponse` のidで取り出す）
+    static ref HTTP_COMPLETIONS: Mutex<HashMap<u64, HttpCompletion>> = Mutex::new(HashMap::new());
+    // EventLoopProxyがない環境（WASMのDOMランタイム）で、次のフレームまで溜めておく完了通知
+    static ref PENDING_HTTP_RESPONSES: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+    // 接続を使い回すため、すべてのリクエストで共有するクライアント
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 相対URL（`/api/items`）の前に付けるベースURLを設定
+pub fn set_http_base_url(url: &str) {
+    if let Ok(mut config) = HTTP_CONFIG.lock() {
+        config.base_url = Some(url.to_string());
+    }
+}
+
+/// すべてのリクエストに付けるヘッダーを設定（同じ名前のものは置き換える）
+pub fn set_http_header(name: &str, value: &str) {
+    if let Ok(mut config) = HTTP_CONFIG.lock() {
+        config
+            .headers
+            .retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        config.headers.push((name.to_string(), value.to_string()));
+    }
+}
+
+/// `timeout:` を指定しないリクエストのタイムアウト（ミリ秒）
+pub fn set_http_timeout(timeout_ms: u64) {
+    if let Ok(mut config) = HTTP_CONFIG.lock() {
+        config.timeout_ms = timeout_ms;
+    }
+}
+
+/// 送信するリクエスト（値はすべて評価済み）
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+    pub timeout_ms: u64,
+}
+
+impl HttpRequest {
+    /// 設定（ベースURL・共通ヘッダー・タイムアウト）を反映したリクエスト
+    pub fn new(method: HttpMethod, url: &str) -> Self {
+        let config = HTTP_CONFIG.lock().map(|c| c.clone()).unwrap_or_default();
+        Self {
+            method,
+            url: join_url(config.base_url.as_deref(), url),
+            headers: config.headers,
+            body: None,
+            timeout_ms: config.timeout_ms,
+        }
+    }
+
+    /// ヘッダーを設定（同じ名前のものは置き換える）
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+}
+
+/// `base` と相対URLをつなぐ（`http://` などで始まるURLはそのまま）
+fn join_url(base: Option<&str>, url: &str) -> String {
+    if url.contains("://") {
+        return url.to_string();
+    }
+    #[cfg(target_arch = "wasm32")]
+    let origin = web_sys::window().and_then(|w| w.location().origin().ok());
+    #[cfg(not(target_arch = "wasm32"))]
+    let origin: Option<String> = None;
+
+    match base.map(str::to_string).or(origin) {
+        Some(base) => format!(
+            "{}/{}",
+            base.trim_end_matches('/'),
+            url.trim_start_matches('/')
+        ),
+        None => url.to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// 接続を使い回すための共有のクライアント（購読のSSEでも使う）
+pub(crate) fn client() -> &'static reqwest::Client {
+    &HTTP_CLIENT
+}
+
+/// リクエストを送って応答の本文を読む（2xx以外はエラー）
+pub async fn send(request: HttpRequest) -> Result<HttpResponse, String> {
+    let method = match request.method {
+        HttpMethod::Get => reqwest::Method::GET,
+        HttpMethod::Post => reqwest::Method::POST,
+    };
+    // 既定のヘッダーは、同じ名前のヘッダーが指定されていなければ付ける
+    let has_header = |name: &str| {
+        request
+            .headers
+            .iter()
+            .any(|(n, _)| n.eq_ignore_ascii_case(name))
+    };
+    let mut builder = HTTP_CLIENT.request(method, &request.url);
+    if !has_header("Accept") {
+        builder = builder.header("Accept", "application/json");
+    }
+    if let Some(body) = request.body {
+        if !has_header("Content-Type") {
+            builder = builder.header("Content-Type", "application/json");
+        }
+        builder = builder.body(body);
+    }
+    for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let response = builder
+        .timeout(std::time::Duration::from_millis(request.timeout_ms))
+        .send()
+        .await;
+    #[cfg(target_arch = "wasm32")]
+    let response = with_timeout(builder.send(), request.timeout_ms).await;
+
+    let response = response.map_err(|e| format!("{}: {}", request.url, e))?;
+    let status = response.status().as_u16();
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    if !(200..300).contains(&status) {
+        return Err(format!("HTTP {}: {}", status, body));
+    }
+    Ok(HttpResponse { status, body })
+}
+
+/// fetchにはタイムアウトがないため、タイマーと競争させる
+#[cfg(target_arch = "wasm32")]
+async fn with_timeout<F>(future: F, timeout_ms: u64) -> Result<reqwest::Response, String>
+where
+    F: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+{
+    use std::task::Poll;
+
+    let mut future = std::pin::pin!(future);
+    let mut timer = gloo_timers::future::TimeoutFuture::new(timeout_ms as u32);
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(result) = future.as_mut().poll(cx) {
+            return Poll::Ready(result.map_err(|e| e.to_string()));
+        }
+        std::pin::Pin::new(&mut timer)
+            .poll(cx)
+            .map(|_| Err(format!("timed out after {}ms", timeout_ms)))
+    })
+    .await
+}
+
+/// 完了時にメインスレッドで行う後処理
+#[derive(Debug)]
+pub struct HttpHandlers {
+    /// リクエストを送ったタイムライン（離れていたらハンドラーは実行しない）
+    pub timeline: String,
+    pub into: Option<String>,
+    pub on_success: Option<ActionBlock>,
+    pub on_error: Option<ActionBlock>,
+}
+
+/// 完了したリクエスト
+#[derive(Debug)]
+pub struct HttpCompletion {
+    pub handlers: HttpHandlers,
+    pub result: Result<HttpResponse, String>,
+}
+
+/// リクエストを送り、完了したらメインスレッドへ通知する
+pub fn spawn_request(request: HttpRequest, handlers: HttpHandlers) {
+    let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+    log::debug!("🌐 {:?} {} (#{})", request.method, request.url, id);
+
+    let task = async move {
+        let result = send(request).await;
+        store_completion(id, HttpCompletion { handlers, result });
+        notify_http_response(id);
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    background_runtime().spawn(task);
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(task);
+}
+
+fn notify_http_response(id: u64) {
+    if send_async_event(AsyncEvent::HttpResponse(id)) {
+        return;
+    }
+    if let Ok(mut pending) = PENDING_HTTP_RESPONSES.lock() {
+        pending.push(id);
+    }
+}
+
+/// EventLoopProxyがない環境で溜めた完了通知を取り出す（古い順）
+pub fn take_http_responses() -> Vec<u64> {
+    PENDING_HTTP_RESPONSES
+        .lock()
+        .map(|mut pending| std::mem::take(&mut *pending))
+        .unwrap_or_default()
+}
+
+/// 完了したリクエストを `UIEvent::HttpResponse` のidで取り出せるようにする
+pub(crate) fn store_completion(id: u64, completion: HttpCompletion) {
+    if let Ok(mut completions) = HTTP_COMPLETIONS.lock() {
+        completions.insert(id, completion);
+    }
+}
+
+/// 完了したリクエストを取り出す
+pub fn take_completion(id: u64) -> Option<HttpCompletion> {
+    HTTP_COMPLETIONS.lock().ok()?.remove(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpListener};
+    use std::thread::JoinHandle;
+
+    /// 1回だけ応答するローカルのHTTPサーバー（受け取ったリクエストを返す）
+    fn serve_once(status: &'static str, body: &'static str) -> (SocketAddr, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+        (addr, server)
+    }
+
+    #[test]
+    fn test_join_url() {
+        assert_eq!(
+            join_url(Some("http://localhost:8080/"), "/api/items"),
+            "http://localhost:8080/api/items"
+        );
+        assert_eq!(
+            join_url(Some("http://localhost:8080"), "https://example.com/a"),
+            "https://example.com/a"
+        );
+        assert_eq!(join_url(None, "/api/items"), "/api/items");
+    }
+
+    #[test]
+    fn test_set_header_replaces_same_name() {
+        let mut request = HttpRequest::new(HttpMethod::Get, "http://localhost/");
+        request.set_header("X-Token", "a");
+        request.set_header("x-token", "b");
+        let tokens: Vec<_> = request
+            .headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("x-token"))
+            .collect();
+        assert_eq!(tokens, vec![&("x-token".to_string(), "b".to_string())]);
+    }
+
+    #[test]
+    fn test_send_to_local_server() {
+        let (addr, server) = serve_once("201 Created", r#"{"items":["a","b"]}"#);
+        let mut request = HttpRequest {
+            method: HttpMethod::Post,
+            url: format!("http://{}/api/items", addr),
+            headers: Vec::new(),
+            body: Some(r#"{"name":"c"}"#.into()),
+            timeout_ms: 5_000,
+        };
+        request.set_header("X-Token", "secret");
+        let response = background_runtime().block_on(send(request)).unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.body, r#"{"items":["a","b"]}"#);
+
+        let received = server.join().unwrap().to_ascii_lowercase();
+        assert!(received.starts_with("post /api/items http/1.1"));
+        assert!(received.contains("x-token: secret"));
+        assert!(received.contains("content-type: application/json"));
+    }
+
+    #[test]
+    fn test_headers_override_defaults() {
+        let (addr, server) = serve_once("200 OK", "ok");
+        let mut request = HttpRequest {
+            method: HttpMethod::Post,
+            url: format!("http://{}/api/notes", addr),
+            headers: Vec::new(),
+            body: Some("hello".into()),
+            timeout_ms: 5_000,
+        };
+        request.set_header("Content-Type", "text/plain");
+        request.set_header("Accept", "text/plain");
+        background_runtime().block_on(send(request)).unwrap();
+
+        let received = server.join().unwrap().to_ascii_lowercase();
+        let lines: Vec<&str> = received.lines().collect();
+        let content_types: Vec<_> = lines
+            .iter()
+            .filter(|l| l.starts_with("content-type:"))
+            .collect();
+        assert_eq!(content_types, vec![&"content-type: text/plain"]);
+        let accepts: Vec<_> = lines.iter().filter(|l| l.starts_with("accept:")).collect();
+        assert_eq!(accepts, vec![&"accept: text/plain"]);
+    }
+
+    #[test]
+    fn test_error_status_is_an_error() {
+        let (addr, server) = serve_once("404 Not Found", r#"{"error":"missing"}"#);
+        let request = HttpRequest {
+            method: HttpMethod::Get,
+            url: format!("http://{}/api/items/9", addr),
+            headers: Vec::new(),
+            body: None,
+            timeout_ms: 5_000,
+        };
+        let error = background_runtime().block_on(send(request)).unwrap_err();
+        assert_eq!(error, r#"HTTP 404: {"error":"missing"}"#);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+//! matchの `case` の照合（`case "a" | "b"`, `case 0..=9`, `case n if n > 100`）
+//!
+//! 式の評価は呼び出し側の評価関数に任せる（式のmatchは `AppState`、ビューのmatchはレイアウトの評価関数）。
+//! 束縛した名前（`case n`）はガードや本体の式の中で一致した値のリテラルに置き換えてから評価する。
+
+use crate::engine::state_path;
+use crate::parser::ast::{Expr, MatchArm, MatchCase, MatchPattern};
+use std::cmp::Ordering;
+
+/// 値が `case` に一致するか（ガードも含めて判定）
+pub fn case_matches<F>(case: &MatchCase, value: &str, eval: &F) -> bool
+where
+    F: Fn(&Expr) -> String,
+{
+    let pattern_matches = case.patterns.iter().any(|pattern| match pattern {
+        MatchPattern::Value(expr) => eval(expr) == value,
+        MatchPattern::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            compare(value, &eval(start)) != Ordering::Less
+                && match compare(value, &eval(end)) {
+                    Ordering::Less => true,
+                    Ordering::Equal => *inclusive,
+                    Ordering::Greater => false,
+                }
+        }
+        MatchPattern::Wildcard => true,
+    });
+
+    pattern_matches
+        && case.guard.as_ref().is_none_or(|guard| {
+            let guard = match &case.binding {
+                Some(name) => bind_value(guard, name, value),
+                None => guard.clone(),
+            };
+            is_truthy(&eval(&guard))
+        })
+}
+
+/// 式の中の束縛名（`n`, `n.name`, `n[0]`）を一致した値で置き換える
+pub fn bind_value(expr: &Expr, name: &str, value: &str) -> Expr {
+    let bind = |e: &Expr| bind_value(e, name, value);
+    match expr {
+        Expr::Path(path) | Expr::Ident(path) if path == name => literal(value),
+        Expr::Path(path) => match path
+            .strip_prefix(name)
+            .filter(|rest| rest.starts_with(['.', '[']))
+        {
+            // オブジェクトの値のプロパティ（例: n.title）
+            Some(property_path) => serde_json::from_str(value)
+                .ok()
+                .and_then(|item| {
+                    state_path::lookup(&item, &state_path::parse_path(property_path)).map(|v| {
+                        match v {
+                            serde_json::Value::Null => Expr::Null,
+                            v => literal(&state_path::value_to_string(v)),
+                        }
+                    })
+                })
+                .unwrap_or(Expr::Null),
+            None => expr.clone(),
+        },
+        Expr::Array(items) => Expr::Array(items.iter().map(bind).collect()),
+        Expr::Object(entries) => {
+            Expr::Object(entries.iter().map(|(k, v)| (k.clone(), bind(v))).collect())
+        }
+        Expr::CalcExpr(inner) => Expr::CalcExpr(Box::new(bind(inner))),
+        Expr::FunctionCall { name: func, args } => Expr::FunctionCall {
+            name: func.clone(),
+            args: args.iter().map(bind).collect(),
+        },
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+            left: Box::new(bind(left)),
+            op: op.clone(),
+            right: Box::new(bind(right)),
+        },
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => Expr::Conditional {
+            condition: Box::new(bind(condition)),
+            then_expr: Box::new(bind(then_expr)),
+            else_expr: Box::new(bind(else_expr)),
+        },
+        Expr::Match {
+            expr: inner,
+            arms,
+            default,
+        } => Expr::Match {
+            expr: Box::new(bind(inner)),
+            arms: arms
+                .iter()
+                .map(|arm| {
+                    let mut case = arm.case.clone();
+                    for e in case.exprs_mut() {
+                        *e = bind(e);
+                    }
+                    // 内側のcaseが同じ名前を束縛していれば、そちらが優先
+                    let shadowed = arm.case.binding.as_deref() == Some(name);
+                    MatchArm {
+                        case,
+                        value: if shadowed {
+                            arm.value.clone()
+                        } else {
+                            bind(&arm.value)
+                        },
+                    }
+                })
+                .collect(),
+            default: default.as_ref().map(|d| Box::new(bind(d))),
+        },
+        _ => expr.clone(),
+    }
+}
+
+/// ガードの評価結果を真偽として読む（`if` と同じ規則）
+fn is_truthy(value: &str) -> bool {
+    matches!(value, "true" | "1" | "True" | "TRUE") || value.parse::<f32>().unwrap_or(0.0) != 0.0
+}
+
+/// 範囲の比較（どちらも数値なら数値として、それ以外は文字列として比較）
+fn compare(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+/// 束縛した値を式にする（数値として読めるものは数値、それ以外は文字列）
+fn literal(value: &str) -> Expr {
+    match value.parse::<f32>() {
+        Ok(n) if n.to_string() == value => Expr::Number(n),
+        _ => Expr::String(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::BinaryOperator;
+
+    /// 数値・文字列リテラルと `>` だけを扱う簡易評価
+    fn eval(expr: &Expr) -> String {
+        match expr {
+            Expr::String(s) => s.clone(),
+            Expr::Number(n) => n.to_string(),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Gt,
+                right,
+            } => (eval(left).parse::<f32>().unwrap() > eval(right).parse::<f32>().unwrap())
+                .to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn case(patterns: Vec<MatchPattern>, binding: Option<&str>, guard: Option<Expr>) -> MatchCase {
+        MatchCase {
+            patterns,
+            binding: binding.map(str::to_string),
+            guard,
+        }
+    }
+
+    fn digits(inclusive: bool) -> MatchCase {
+        case(
+            vec![MatchPattern::Range {
+                start: Expr::Number(0.0),
+                end: Expr::Number(9.0),
+                inclusive,
+            }],
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_multi_value_pattern() {
+        let either = case(
+            vec![
+                MatchPattern::Value(Expr::String("a".into())),
+                MatchPattern::Value(Expr::String("b".into())),
+            ],
+            None,
+            None,
+        );
+        assert!(case_matches(&either, "a", &eval));
+        assert!(case_matches(&either, "b", &eval));
+        assert!(!case_matches(&either, "c", &eval));
+    }
+
+    #[test]
+    fn test_range_pattern() {
+        assert!(case_matches(&digits(true), "0", &eval));
+        assert!(case_matches(&digits(true), "9", &eval));
+        assert!(!case_matches(&digits(true), "10", &eval));
+        assert!(!case_matches(&digits(true), "-1", &eval));
+        // `0..9` は終端を含まない
+        assert!(!case_matches(&digits(false), "9", &eval));
+        assert!(case_matches(&digits(false), "8.5", &eval));
+    }
+
+    #[test]
+    fn test_guard_sees_binding() {
+        let large = case(
+            vec![MatchPattern::Wildcard],
+            Some("n"),
+            Some(Expr::BinaryOp {
+                left: Box::new(Expr::Path("n".into())),
+                op: BinaryOperator::Gt,
+                right: Box::new(Expr::Number(100.0)),
+            }),
+        );
+        assert!(case_matches(&large, "150", &eval));
+        assert!(!case_matches(&large, "42", &eval));
+        // ガード付きのワイルドカードは default 扱いにしない
+        assert!(!large.is_catch_all());
+        assert!(case(vec![MatchPattern::Wildcard], Some("n"), None).is_catch_all());
+    }
+
+    #[test]
+    fn test_bind_value_reads_fields() {
+        let title = bind_value(&Expr::Path("n.title".into()), "n", r#"{"title":"nilo"}"#);
+        assert!(matches!(title, Expr::String(s) if s == "nilo"));
+        let missing = bind_value(&Expr::Path("n.author".into()), "n", r#"{"title":"nilo"}"#);
+        assert!(matches!(missing, Expr::Null));
+    }
+}
@@ -6,6 +6,19 @@ pub mod rust_call;
 pub mod state;
 pub mod timeline_processor;
 pub mod async_call;
+pub mod builtins;
+pub mod timer;
+pub mod state_path;
+pub mod match_pattern;
+pub mod dsl_function;
+pub mod flow_guard;
+pub mod sub_flow;
+pub mod app_diff;
+pub mod timeline_timer;
+pub mod resource;
+pub mod app_handle;
+pub mod http;
+pub mod subscription;
 
 #[cfg(target_arch = "wasm32")]
 pub mod runtime_dom;
@@ -0,0 +1,345 @@
+//! タイムラインに書いたリソース（`resource user = fetch_user!(route.params.id)`）
+//!
+//! 登録された非同期関数の結果を `user.loading` / `user.error` / `user.value` として
+//! ビューから読めるようにする。引数の値が変わると取得し直し、前の取得は取り消す。
+//! 結果は `apply_async_results` でまとめて反映し、取り消した取得の結果は世代で捨てる。
+
+use crate::engine::async_call::{AsyncFuture, async_call_future, notify_async_result_ready};
+use crate::engine::core::event::value_to_literal_expr;
+use crate::engine::state::{AppState, StateAccess};
+use crate::engine::state_path;
+use crate::parser::ast::{Expr, ResourceDecl};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+
+lazy_static::lazy_static! {
+    static ref RESOURCE_RESULTS: Arc<Mutex<Vec<ResourceResult>>> = Arc::new(Mutex::new(Vec::new()));
+}
+
+/// 非同期関数から届いたリソースの結果
+#[derive(Debug)]
+pub struct ResourceResult {
+    name: String,
+    generation: u64,
+    result: Result<String, String>,
+}
+
+/// キューに溜まっているリソースの結果を取り出す（古い順）
+pub fn take_resource_results() -> Vec<ResourceResult> {
+    RESOURCE_RESULTS
+        .lock()
+        .map(|mut results| std::mem::take(&mut *results))
+        .unwrap_or_default()
+}
+
+fn queue_resource_result(result: ResourceResult) {
+    if let Ok(mut results) = RESOURCE_RESULTS.lock() {
+        results.push(result);
+    }
+    notify_async_result_ready();
+}
+
+/// 取得を始めるリソース（`Resources::sync` が返し、`start_resource_requests` で実行する）
+#[derive(Debug)]
+pub struct ResourceRequest {
+    name: String,
+    call: String,
+    args: Vec<String>,
+    /// 引数の元の式（評価済みの値を型付きで渡すため）
+    arg_exprs: Vec<Expr>,
+    generation: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Default)]
+struct ResourceEntry {
+    loading: bool,
+    error: Option<String>,
+    value: Option<String>,
+    /// 取得に使った引数（評価済みの値）
+    args: Vec<String>,
+    generation: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Default)]
+pub struct Resources {
+    /// リソースを取得したタイムライン（止めた後はNone）
+    timeline: Option<String>,
+    generation: u64,
+    entries: HashMap<String, ResourceEntry>,
+}
+
+impl Clone for Resources {
+    /// 複製した状態では取得を持たない（次の `sync` で取得し直す）
+    fn clone(&self) -> Self {
+        Self {
+            timeline: None,
+            generation: self.generation,
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 表示中のタイムラインのリソースのうち、取得し直すものを返す
+    ///
+    /// `args` は各リソースの評価済みの引数。前回と同じなら取得し直さない
+    pub fn sync(
+        &mut self,
+        timeline: &str,
+        decls: &[ResourceDecl],
+        args: Vec<Vec<String>>,
+    ) -> Vec<ResourceRequest> {
+        if self.timeline.as_deref() != Some(timeline) {
+            self.stop_all();
+            self.timeline = Some(timeline.to_string());
+        }
+
+        let mut requests = Vec::new();
+        for (decl, args) in decls.iter().zip(args) {
+            if let Some(entry) = self.entries.get(&decl.name)
+                && entry.args == args
+            {
+                continue;
+            }
+
+            self.generation += 1;
+            let cancelled = Arc::new(AtomicBool::new(false));
+            let entry = self.entries.entry(decl.name.clone()).or_default();
+            // 引数が変わったら前の取得を取り消し、前の値も捨てる（別のデータを表示しないため）
+            entry.cancelled.store(true, Ordering::SeqCst);
+            *entry = ResourceEntry {
+                loading: true,
+                error: None,
+                value: None,
+                args: args.clone(),
+                generation: self.generation,
+                cancelled: Arc::clone(&cancelled),
+            };
+            log::debug!("📡 Fetching resource '{}' via {}!", decl.name, decl.call);
+            requests.push(ResourceRequest {
+                name: decl.name.clone(),
+                call: decl.call.clone(),
+                args,
+                arg_exprs: decl.args.clone(),
+                generation: self.generation,
+                cancelled,
+            });
+        }
+        requests
+    }
+
+    /// すべての取得を取り消して結果を捨てる（タイムラインの遷移・ホットリロード時）
+    pub fn stop_all(&mut self) {
+        for entry in self.entries.values() {
+            entry.cancelled.store(true, Ordering::SeqCst);
+        }
+        self.entries.clear();
+        self.timeline = None;
+    }
+
+    /// 届いた結果を反映する（取り消した取得の結果は無視）。1つでも反映したらtrue
+    pub fn apply_results(&mut self, results: Vec<ResourceResult>) -> bool {
+        let mut applied = false;
+        for result in results {
+            let Some(entry) = self
+                .entries
+                .get_mut(&result.name)
+                .filter(|entry| entry.generation == result.generation)
+            else {
+                log::debug!("Ignoring stale result for resource '{}'", result.name);
+                continue;
+            };
+            entry.loading = false;
+            match result.result {
+                Ok(value) => {
+                    entry.value = Some(value);
+                    entry.error = None;
+                }
+                Err(e) => {
+                    log::warn!("Resource '{}' failed: {}", result.name, e);
+                    entry.error = Some(e);
+                }
+            }
+            applied = true;
+        }
+        applied
+    }
+
+    /// `user.loading` / `user.error` / `user.value`（`user.value.name` のような深いパスも可）を読む
+    ///
+    /// リソース名でなければNone
+    pub fn get(&self, path: &str) -> Option<String> {
+        let (root, rest) = state_path::split_root(path);
+        let entry = self.entries.get(root)?;
+        let value = entry.value.clone().unwrap_or_default();
+        match rest.strip_prefix('.').unwrap_or(rest) {
+            "" | "value" => Some(value),
+            "loading" => Some(entry.loading.to_string()),
+            "error" => Some(entry.error.clone().unwrap_or_default()),
+            nested => {
+                let rest = nested.strip_prefix("value")?;
+                Some(state_path::read_nested(&value, rest).unwrap_or_default())
+            }
+        }
+    }
+}
+
+impl Drop for Resources {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+/// `Resources::sync` が返したリソースの取得を始める
+pub fn start_resource_requests<S>(state: &mut AppState<S>, requests: Vec<ResourceRequest>)
+where
+    S: StateAccess + 'static,
+{
+    for request in requests {
+        let args: Vec<Expr> = request
+            .arg_exprs
+            .iter()
+            .zip(request.args)
+            .map(|(source, value)| value_to_literal_expr(source, value))
+            .collect();
+        let Some(future) = async_call_future(&request.call, state, &args) else {
+            let message = format!("非同期関数 '{}' が登録されていません", request.call);
+            log::error!("Resource '{}': {}", request.name, message);
+            state.resources.apply_results(vec![ResourceResult {
+                name: request.name,
+                generation: request.generation,
+                result: Err(message),
+            }]);
+            continue;
+        };
+        spawn_request(request.name, request.generation, request.cancelled, future);
+    }
+}
+
+fn spawn_request(
+    name: String,
+    generation: u64,
+    cancelled: Arc<AtomicBool>,
+    mut future: AsyncFuture,
+) {
+    let task = async move {
+        // 取り消されたら結果を待たずに終える
+        let result = std::future::poll_fn(|cx| {
+            if cancelled.load(Ordering::SeqCst) {
+                return Poll::Ready(None);
+            }
+            future.as_mut().poll(cx).map(Some)
+        })
+        .await;
+
+        match result {
+            Some(result) => queue_resource_result(ResourceResult {
+                name,
+                generation,
+                result,
+            }),
+            None => log::debug!("Resource '{}' request was cancelled", name),
+        }
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(move || pollster::block_on(task));
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(task);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decls() -> Vec<ResourceDecl> {
+        vec![ResourceDecl {
+            name: "user".into(),
+            call: "fetch_user".into(),
+            args: vec![Expr::Path("route.params.id".into())],
+        }]
+    }
+
+    fn result(generation: u64, result: Result<String, String>) -> ResourceResult {
+        ResourceResult {
+            name: "user".into(),
+            generation,
+            result,
+        }
+    }
+
+    fn id(value: &str) -> Vec<Vec<String>> {
+        vec![vec![value.to_string()]]
+    }
+
+    #[test]
+    fn test_sync_starts_loading() {
+        let mut resources = Resources::new();
+        let requests = resources.sync("User", &decls(), id("1"));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].call, "fetch_user");
+        assert_eq!(resources.get("user.loading").as_deref(), Some("true"));
+        assert_eq!(resources.get("user.value").as_deref(), Some(""));
+        assert_eq!(resources.get("other.value"), None);
+    }
+
+    #[test]
+    fn test_same_args_do_not_refetch() {
+        let mut resources = Resources::new();
+        resources.sync("User", &decls(), id("1"));
+        assert!(resources.sync("User", &decls(), id("1")).is_empty());
+    }
+
+    #[test]
+    fn test_stale_generation_is_discarded() {
+        let mut resources = Resources::new();
+        let first = resources.sync("User", &decls(), id("1"));
+        let second = resources.sync("User", &decls(), id("2"));
+        // 引数が変わると前の取得を取り消す
+        assert!(first[0].cancelled.load(Ordering::SeqCst));
+        assert!(!second[0].cancelled.load(Ordering::SeqCst));
+
+        // 遅れて届いた前の結果は無視する
+        let (old, new) = (first[0].generation, second[0].generation);
+        assert!(!resources.apply_results(vec![result(old, Ok(r#"{"name":"A"}"#.into()))]));
+        assert_eq!(resources.get("user.loading").as_deref(), Some("true"));
+
+        assert!(resources.apply_results(vec![
+            result(new, Ok(r#"{"name":"B"}"#.into())),
+            result(old, Ok(r#"{"name":"A"}"#.into())),
+        ]));
+        assert_eq!(resources.get("user.loading").as_deref(), Some("false"));
+        assert_eq!(resources.get("user.value.name").as_deref(), Some("B"));
+    }
+
+    #[test]
+    fn test_error_result() {
+        let mut resources = Resources::new();
+        let requests = resources.sync("User", &decls(), id("1"));
+        assert!(resources.apply_results(vec![result(requests[0].generation, Err("404".into()))]));
+        assert_eq!(resources.get("user.loading").as_deref(), Some("false"));
+        assert_eq!(resources.get("user.error").as_deref(), Some("404"));
+    }
+
+    #[test]
+    fn test_leaving_timeline_discards_results() {
+        let mut resources = Resources::new();
+        let requests = resources.sync("User", &decls(), id("1"));
+        resources.sync("Home", &[], vec![]);
+        assert!(requests[0].cancelled.load(Ordering::SeqCst));
+        assert!(!resources.apply_results(vec![result(requests[0].generation, Ok("{}".into()))]));
+        assert_eq!(resources.get("user.value"), None);
+
+        // 戻ってきたら同じ引数でも取得し直す
+        assert_eq!(resources.sync("User", &decls(), id("1")).len(), 1);
+    }
+}
@@ -1,239 +1,682 @@
 // ========================================
 // Nilo フレームワーク: ルーティングシステム
 // ========================================
+//
+// URLの照合・組み立ては `RouteTable` にまとめ、ネイティブ版とWASM版で同じものを使う。
+// WASM版はそれに加えてブラウザのURLバーと履歴を更新するだけ。
 
 use crate::parser::ast::{App, Flow};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
-pub struct RouteInfo {
-    pub pattern: String,
+// ========================================
+// ルーターの中核（プラットフォーム共通）
+// ========================================
+
+/// パラメータの型（`:id<number>`）
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamKind {
+    String,
+    Number,
 }
 
-// WASM環境でのルーター実装
-#[cfg(target_arch = "wasm32")]
+impl ParamKind {
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            ParamKind::String => true,
+            ParamKind::Number => value.parse::<f64>().is_ok(),
+        }
+    }
+}
+
+/// URLパターンの1セグメント
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteSegment {
+    Static(String),
+    Param {
+        name: String,
+        kind: ParamKind,
+        optional: bool,
+    },
+    /// 残りのパスすべて（`*` または `*name`）
+    Wildcard(Option<String>),
+}
+
+/// URLパターン（`/users/:id<number>/:tab?`, `/docs/*path`）
 #[derive(Debug, Clone)]
-pub struct WasmRouter {
-    routes: HashMap<String, RouteInfo>, // timeline_name -> RouteInfo
-    url_to_timeline: HashMap<String, String>, // url_pattern -> timeline_name
-    current_route: Option<String>,
-    history: Vec<String>,
+pub struct RoutePattern {
+    pub source: String,
+    pub segments: Vec<RouteSegment>,
 }
 
-#[cfg(target_arch = "wasm32")]
-impl WasmRouter {
-    /// AppからタイムラインのURL定義を読み取ってルーターを構築
-    pub fn from_app(app: &App) -> Self {
-        let mut routes = HashMap::new();
-        let mut url_to_timeline = HashMap::new();
+impl RoutePattern {
+    pub fn parse(source: &str) -> Self {
+        let segments = split_path(source)
+            .into_iter()
+            .map(|part| {
+                if let Some(name) = part.strip_prefix('*') {
+                    return RouteSegment::Wildcard((!name.is_empty()).then(|| name.to_string()));
+                }
+                let Some(param) = part.strip_prefix(':') else {
+                    return RouteSegment::Static(part.to_string());
+                };
+                let (param, optional) = match param.strip_suffix('?') {
+                    Some(p) => (p, true),
+                    None => (param, false),
+                };
+                let (name, kind) = match param.split_once('<') {
+                    Some((name, ty)) => {
+                        let kind = match ty.trim_end_matches('>') {
+                            "number" => ParamKind::Number,
+                            "string" => ParamKind::String,
+                            other => {
+                                log::warn!(
+                                    "URLパターン '{}' の型 '{}' は未対応です（string として扱います）",
+                                    source,
+                                    other
+                                );
+                                ParamKind::String
+                            }
+                        };
+                        (name, kind)
+                    }
+                    None => (param, ParamKind::String),
+                };
+                RouteSegment::Param {
+                    name: name.to_string(),
+                    kind,
+                    optional,
+                }
+            })
+            .collect();
+        Self {
+            source: source.to_string(),
+            segments,
+        }
+    }
+
+    /// パス（クエリ文字列を除く）を照合し、一致すればパラメータを返す
+    pub fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
+        let parts: Vec<String> = split_path(path).into_iter().map(percent_decode).collect();
+        let mut params = HashMap::new();
+        match_segments(&self.segments, &parts, &mut params).then_some(params)
+    }
+
+    /// パラメータを埋めてURLを組み立てる（必須パラメータがない・型が合わない場合はエラー）
+    pub fn build(&self, params: &HashMap<String, String>) -> Result<String, String> {
+        let mut parts = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                RouteSegment::Static(s) => parts.push(s.clone()),
+                RouteSegment::Param {
+                    name,
+                    kind,
+                    optional,
+                } => match params.get(name) {
+                    Some(value) if !kind.accepts(value) => {
+                        return Err(format!(
+                            "URLパラメータ '{}' は number 型ですが '{}' が渡されました（{}）",
+                            name, value, self.source
+                        ));
+                    }
+                    Some(value) => parts.push(percent_encode(value)),
+                    None if *optional => {}
+                    None => {
+                        return Err(format!(
+                            "URLパラメータ '{}' が指定されていません（{}）",
+                            name, self.source
+                        ));
+                    }
+                },
+                RouteSegment::Wildcard(name) => {
+                    if let Some(rest) = name.as_ref().and_then(|n| params.get(n)) {
+                        parts.extend(split_path(rest).into_iter().map(percent_encode));
+                    }
+                }
+            }
+        }
+        Ok(format!("/{}", parts.join("/")))
+    }
+
+    /// 照合の優先度（静的セグメント > パラメータ > 省略可能 > ワイルドカード）
+    fn specificity(&self) -> Vec<u8> {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                RouteSegment::Static(_) => 3,
+                RouteSegment::Param {
+                    optional: false, ..
+                } => 2,
+                RouteSegment::Param { optional: true, .. } => 1,
+                RouteSegment::Wildcard(_) => 0,
+            })
+            .collect()
+    }
+}
 
-        // 各タイムラインからURL定義を取得
+fn match_segments(
+    segments: &[RouteSegment],
+    parts: &[String],
+    params: &mut HashMap<String, String>,
+) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        return parts.is_empty();
+    };
+    match segment {
+        RouteSegment::Static(s) => {
+            parts.first() == Some(s) && match_segments(rest, &parts[1..], params)
+        }
+        RouteSegment::Param {
+            name,
+            kind,
+            optional,
+        } => {
+            if let Some(part) = parts.first().filter(|p| kind.accepts(p)) {
+                params.insert(name.clone(), part.clone());
+                if match_segments(rest, &parts[1..], params) {
+                    return true;
+                }
+                params.remove(name);
+            }
+            *optional && match_segments(rest, parts, params)
+        }
+        RouteSegment::Wildcard(name) => {
+            if let Some(name) = name {
+                params.insert(name.clone(), parts.join("/"));
+            }
+            true
+        }
+    }
+}
+
+/// 照合結果（履歴の1項目にもなる）
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteMatch {
+    pub timeline: String,
+    pub params: HashMap<String, String>,
+    pub query: HashMap<String, String>,
+    pub url: Option<String>, // URL定義のないタイムラインはNone
+}
+
+/// タイムラインとURLパターンの対応表
+#[derive(Debug, Clone, Default)]
+pub struct RouteTable {
+    routes: Vec<(String, RoutePattern)>, // 優先度順（同じ優先度は定義順）
+    not_found: Option<String>,
+}
+
+impl RouteTable {
+    /// タイムラインのURL定義とフロー内のURL指定から構築
+    pub fn from_app(app: &App) -> Self {
+        let mut table = Self::from_flow(&app.flow);
         for timeline in &app.timelines {
             if let Some(url_pattern) = &timeline.url_pattern {
-                routes.insert(
-                    timeline.name.clone(),
-                    RouteInfo {
-                        pattern: url_pattern.clone(),
-                    },
-                );
-                url_to_timeline.insert(url_pattern.clone(), timeline.name.clone());
-                log::info!("Registered route: {} -> {}", url_pattern, timeline.name);
+                table.add_route(&timeline.name, url_pattern);
             }
         }
+        table.sort();
+        table
+    }
 
-        Self {
-            routes,
-            url_to_timeline,
-            current_route: None,
-            history: Vec::new(),
+    /// フロー内のURL指定（`start: Home("/")`, `A -> B("/b")` など）だけから構築
+    pub fn from_flow(flow: &Flow) -> Self {
+        let mut table = Self {
+            routes: Vec::new(),
+            not_found: flow.not_found.clone(),
+        };
+        if let Some(url) = &flow.start_url {
+            table.add_route(&flow.start, url);
         }
+        let targets = flow
+            .transitions
+            .iter()
+            .flat_map(|t| t.to.iter().chain(t.fallback.as_ref()));
+        for target in targets {
+            if let Some(url) = &target.url {
+                table.add_route(&target.timeline, url);
+            }
+        }
+        table.sort();
+        table
     }
 
-    /// 現在のブラウザURLから対応するタイムラインを検索
-    pub fn get_timeline_from_current_url(&self) -> Option<String> {
-        use web_sys::window;
+    fn add_route(&mut self, timeline: &str, pattern: &str) {
+        if self
+            .routes
+            .iter()
+            .any(|(name, p)| name == timeline && p.source == pattern)
+        {
+            return;
+        }
+        log::info!("Registered route: {} -> {}", pattern, timeline);
+        self.routes
+            .push((timeline.to_string(), RoutePattern::parse(pattern)));
+    }
 
-        if let Some(window) = window() {
-            let location = window.location();
-            if let Ok(pathname) = location.pathname() {
-                log::info!("Current URL pathname: {}", pathname);
-                return self.match_route(&pathname).map(|(timeline, _)| timeline);
+    fn sort(&mut self) {
+        self.routes
+            .sort_by_key(|(_, pattern)| std::cmp::Reverse(pattern.specificity()));
+    }
+
+    /// URL（クエリ文字列を含んでよい）に対応するタイムラインを探す
+    /// どれにも一致しなければフロー の `not_found:` に指定したタイムラインになる
+    pub fn resolve(&self, url: &str) -> Option<RouteMatch> {
+        let (path, query) = match url.split_once('?') {
+            Some((path, query)) => (path, parse_query(query)),
+            None => (url, HashMap::new()),
+        };
+        let path = path.split('#').next().unwrap_or_default();
+        let matched = self.routes.iter().find_map(|(timeline, pattern)| {
+            pattern
+                .match_path(path)
+                .map(|params| (timeline.clone(), params))
+        });
+        let (timeline, params) = match matched {
+            Some(found) => found,
+            None => {
+                log::warn!("No matching route found for: {}", path);
+                (self.not_found.clone()?, HashMap::new())
             }
+        };
+        Some(RouteMatch {
+            timeline,
+            params,
+            query,
+            url: Some(url.to_string()),
+        })
+    }
+
+    /// タイムラインのURLを組み立てる（URL定義がなければNone）
+    pub fn build_url(
+        &self,
+        timeline: &str,
+        params: &HashMap<String, String>,
+    ) -> Option<Result<String, String>> {
+        self.pattern_for(timeline)
+            .map(|pattern| pattern.build(params))
+    }
+
+    /// URLがまだなければパターンとクエリから組み立てる（必須パラメータがない・型が合わない場合はエラー）
+    pub fn complete(&self, mut route: RouteMatch) -> Result<RouteMatch, String> {
+        if route.url.is_none()
+            && let Some(path) = self.build_url(&route.timeline, &route.params)
+        {
+            route.url = Some(with_query(path?, &route.query));
         }
-        None
+        Ok(route)
     }
 
-    /// タイムラインに遷移し、ブラウザのURLを更新
-    pub fn navigate_to_timeline(
-        &mut self,
+    /// 履歴の項目を作る（URLを組み立てられなければURLなし）
+    fn entry(
+        &self,
         timeline: &str,
         params: HashMap<String, String>,
-    ) -> Result<(), String> {
-        log::info!("Navigating to timeline: {}", timeline);
+        query: HashMap<String, String>,
+    ) -> RouteMatch {
+        let route = RouteMatch {
+            timeline: timeline.to_string(),
+            params,
+            query,
+            url: None,
+        };
+        self.complete(route.clone()).unwrap_or(route)
+    }
 
-        if let Some(route_info) = self.routes.get(timeline) {
-            let url = self.build_url(&route_info.pattern, &params)?;
-            log::info!("Built URL: {}", url);
-            self.update_browser_url(&url);
-            self.current_route = Some(url.clone());
-            self.history.push(url);
-            Ok(())
-        } else {
-            // URL定義がない場合はネイティブ風に動作
-            log::warn!(
-                "Timeline '{}' has no URL mapping, using native navigation",
-                timeline
-            );
-            Ok(())
-        }
+    pub fn pattern_for(&self, timeline: &str) -> Option<&RoutePattern> {
+        self.routes
+            .iter()
+            .find(|(name, _)| name == timeline)
+            .map(|(_, pattern)| pattern)
     }
 
-    pub fn get_current_route(&self) -> Option<&str> {
-        self.current_route.as_deref()
+    pub fn not_found(&self) -> Option<&str> {
+        self.not_found.as_deref()
     }
+}
 
-    fn build_url(&self, pattern: &str, params: &HashMap<String, String>) -> Result<String, String> {
-        let mut url = pattern.to_string();
+/// クエリ文字列（`?` より後ろ）を読む（`a=1&b=x%20y` → {a: "1", b: "x y"}）
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (
+                percent_decode(&key.replace('+', " ")),
+                percent_decode(&value.replace('+', " ")),
+            )
+        })
+        .collect()
+}
 
-        // パラメータ置換（:param形式）
-        for (key, value) in params {
-            let placeholder = format!(":{}", key);
-            let optional_placeholder = format!("{}?", placeholder);
+/// パスにクエリ文字列を付ける（キーの順に並べる）
+fn with_query(path: String, query: &HashMap<String, String>) -> String {
+    if query.is_empty() {
+        return path;
+    }
+    let mut pairs: Vec<_> = query.iter().collect();
+    pairs.sort();
+    let query: Vec<String> = pairs
+        .into_iter()
+        .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+        .collect();
+    format!("{}?{}", path, query.join("&"))
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    path.split('/').filter(|part| !part.is_empty()).collect()
+}
 
-            if url.contains(&placeholder) {
-                url = url.replace(&placeholder, value);
-            } else if url.contains(&optional_placeholder) {
-                url = url.replace(&optional_placeholder, value);
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
             }
         }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
 
-        // オプショナルパラメータで値が提供されていない場合の処理
-        // :param? を空文字に置換（簡単な実装）
-        while let Some(start) = url.find("/:") {
-            if let Some(end) = url[start..].find('?') {
-                let full_end = start + end + 1;
-                url.replace_range(start..full_end, "");
-            } else {
-                break;
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
             }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// ========================================
+// 履歴（プラットフォーム共通）
+// ========================================
+
+/// 履歴に残す件数の既定値（`Router::set_history_limit` で変更可）
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// 遷移の履歴（`navigate_back()` / `navigate_forward()`）
+#[derive(Debug, Clone)]
+pub struct History {
+    entries: Vec<RouteMatch>,
+    index: usize,
+    limit: usize,
+}
+
+impl History {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            index: 0,
+            limit: limit.max(1),
         }
+    }
 
-        Ok(url)
+    /// 遷移を追加する（進む側の履歴は捨てる。上限を超えたら古いものから捨てる）
+    pub fn push(&mut self, entry: RouteMatch) {
+        if self.current() == Some(&entry) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.index + 1);
+        }
+        self.entries.push(entry);
+        let overflow = self.entries.len().saturating_sub(self.limit);
+        self.entries.drain(..overflow);
+        self.index = self.entries.len() - 1;
     }
 
-    fn update_browser_url(&self, url: &str) {
-        use web_sys::window;
+    /// 現在の項目を置き換える（初回表示のURLを反映するときなど）
+    pub fn replace(&mut self, entry: RouteMatch) {
+        match self.entries.get_mut(self.index) {
+            Some(current) => *current = entry,
+            None => self.push(entry),
+        }
+    }
 
-        log::info!("Updating browser URL to: {}", url);
+    pub fn back(&mut self) -> Option<&RouteMatch> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.index -= 1;
+        self.current()
+    }
 
-        if let Some(window) = window() {
-            if let Ok(history) = window.history() {
-                match history.push_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(url)) {
-                    Ok(_) => log::info!("Browser URL updated successfully"),
-                    Err(e) => log::error!("Failed to update browser URL: {:?}", e),
-                }
-            }
+    pub fn forward(&mut self) -> Option<&RouteMatch> {
+        if !self.can_go_forward() {
+            return None;
         }
+        self.index += 1;
+        self.current()
     }
 
-    pub fn handle_browser_navigation(&mut self) -> Option<(String, HashMap<String, String>)> {
-        // ブラウザの戻る/進むボタン対応
-        use web_sys::window;
+    pub fn current(&self) -> Option<&RouteMatch> {
+        self.entries.get(self.index)
+    }
 
-        if let Some(window) = window() {
-            let location = window.location();
-            if let Ok(pathname) = location.pathname() {
-                return self.match_route(&pathname);
-            }
-        }
-        None
+    pub fn can_go_back(&self) -> bool {
+        self.index > 0
     }
 
-    fn match_route(&self, pathname: &str) -> Option<(String, HashMap<String, String>)> {
-        log::info!("Matching route for pathname: {}", pathname);
+    pub fn can_go_forward(&self) -> bool {
+        self.index + 1 < self.entries.len()
+    }
 
-        // URL正規化: 末尾の/を削除（ルートパス以外）
-        let normalized_path = if pathname != "/" && pathname.ends_with('/') {
-            pathname.trim_end_matches('/')
-        } else {
-            pathname
-        };
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
 
-        log::info!("Normalized pathname: {}", normalized_path);
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 
-        // まず完全一致を試みる
-        if let Some(timeline) = self.url_to_timeline.get(normalized_path) {
-            log::info!("Found exact match: {} -> {}", normalized_path, timeline);
-            return Some((timeline.clone(), HashMap::new()));
-        }
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit.max(1);
+        let overflow = self.entries.len().saturating_sub(self.limit);
+        self.entries.drain(..overflow);
+        self.index = self.index.saturating_sub(overflow);
+    }
+}
+
+// ========================================
+// WASM環境でのルーター実装
+// ========================================
+
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Clone)]
+pub struct WasmRouter {
+    table: RouteTable,
+    history: History,
+}
 
-        // URL パターンマッチング
-        for (timeline, route_info) in &self.routes {
-            if let Some(params) = self.extract_params(&route_info.pattern, normalized_path) {
-                log::info!(
-                    "Found pattern match: {} matches {} -> {}",
-                    normalized_path,
-                    route_info.pattern,
-                    timeline
+#[cfg(target_arch = "wasm32")]
+impl WasmRouter {
+    /// AppからタイムラインのURL定義を読み取ってルーターを構築
+    pub fn from_app(app: &App) -> Self {
+        Self::with_table(RouteTable::from_app(app), &app.flow.start)
+    }
+
+    fn with_table(table: RouteTable, start: &str) -> Self {
+        let mut history = History::new(DEFAULT_HISTORY_LIMIT);
+        history.push(table.entry(start, HashMap::new(), HashMap::new()));
+        Self { table, history }
+    }
+
+    /// 現在のブラウザURLから対応するタイムラインを検索
+    pub fn get_timeline_from_current_url(&self) -> Option<String> {
+        self.resolve_current_url().map(|m| m.timeline)
+    }
+
+    /// 現在のブラウザURL（パス + クエリ文字列）を照合
+    pub fn resolve_current_url(&self) -> Option<RouteMatch> {
+        let location = web_sys::window()?.location();
+        let pathname = location.pathname().ok()?;
+        let search = location.search().unwrap_or_default();
+        log::info!("Current URL: {}{}", pathname, search);
+        self.table.resolve(&format!("{}{}", pathname, search))
+    }
+
+    /// タイムラインに遷移し、ブラウザのURLを更新
+    pub fn navigate(&mut self, route: RouteMatch) -> Result<(), String> {
+        log::info!("Navigating to timeline: {}", route.timeline);
+
+        let route = self.table.complete(route)?;
+        match &route.url {
+            Some(url) => {
+                log::info!("Built URL: {}", url);
+                self.update_browser_url(url);
+            }
+            None => {
+                // URL定義がない場合はネイティブ風に動作
+                log::warn!(
+                    "Timeline '{}' has no URL mapping, using native navigation",
+                    route.timeline
                 );
-                return Some((timeline.clone(), params));
             }
         }
-
-        log::warn!("No matching route found for: {}", normalized_path);
-        None
+        self.history.push(route);
+        Ok(())
     }
 
-    fn extract_params(&self, pattern: &str, pathname: &str) -> Option<HashMap<String, String>> {
-        // 単純なパターンマッチング実装
-        let pattern_parts: Vec<&str> = pattern.split('/').collect();
-        let path_parts: Vec<&str> = pathname.split('/').collect();
+    /// 履歴を戻る（ブラウザの履歴も合わせて戻す）
+    pub fn back(&mut self) -> Option<RouteMatch> {
+        let entry = self.history.back()?.clone();
+        if let Some(history) = web_sys::window().and_then(|w| w.history().ok()) {
+            let _ = history.back();
+        }
+        Some(entry)
+    }
 
-        if pattern_parts.len() != path_parts.len() {
-            return None;
+    /// 履歴を進む（ブラウザの履歴も合わせて進める）
+    pub fn forward(&mut self) -> Option<RouteMatch> {
+        let entry = self.history.forward()?.clone();
+        if let Some(history) = web_sys::window().and_then(|w| w.history().ok()) {
+            let _ = history.forward();
         }
+        Some(entry)
+    }
 
-        let mut params = HashMap::new();
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut History {
+        &mut self.history
+    }
+
+    pub fn get_current_route(&self) -> Option<&str> {
+        self.history.current().and_then(|r| r.url.as_deref())
+    }
+
+    pub fn table(&self) -> &RouteTable {
+        &self.table
+    }
+
+    pub fn set_table(&mut self, table: RouteTable) {
+        self.table = table;
+    }
+
+    fn update_browser_url(&self, url: &str) {
+        use web_sys::window;
 
-        for (pattern_part, path_part) in pattern_parts.iter().zip(path_parts.iter()) {
-            if pattern_part.starts_with(':') {
-                let param_name = pattern_part.trim_start_matches(':').trim_end_matches('?');
-                params.insert(param_name.to_string(), path_part.to_string());
-            } else if pattern_part != path_part {
-                return None;
+        log::info!("Updating browser URL to: {}", url);
+
+        if let Some(window) = window() {
+            if let Ok(history) = window.history() {
+                match history.push_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(url)) {
+                    Ok(_) => log::info!("Browser URL updated successfully"),
+                    Err(e) => log::error!("Failed to update browser URL: {:?}", e),
+                }
             }
         }
+    }
 
-        Some(params)
+    pub fn handle_browser_navigation(&mut self) -> Option<RouteMatch> {
+        // ブラウザの戻る/進むボタン対応
+        self.resolve_current_url()
     }
 }
 
-// ネイティブ環境での簡易ルーター
+// ========================================
+// ネイティブ環境でのルーター
+// ========================================
+
+/// URLバーはないが、WASM版と同じ対応表で遷移先のURLを組み立て、履歴を保持する
 #[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug, Clone)]
 pub struct NativeRouter {
-    current_timeline: String,
+    table: RouteTable,
+    history: History,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 impl NativeRouter {
     pub fn new(flow: &Flow) -> Self {
-        Self {
-            current_timeline: flow.start.clone(),
-        }
+        Self::with_table(RouteTable::from_flow(flow), &flow.start)
     }
 
-    pub fn navigate_to_timeline(
-        &mut self,
-        timeline: &str,
-        _params: HashMap<String, String>,
-    ) -> Result<(), String> {
-        self.current_timeline = timeline.to_string();
+    pub fn from_app(app: &App) -> Self {
+        Self::with_table(RouteTable::from_app(app), &app.flow.start)
+    }
+
+    fn with_table(table: RouteTable, start: &str) -> Self {
+        let mut history = History::new(DEFAULT_HISTORY_LIMIT);
+        history.push(table.entry(start, HashMap::new(), HashMap::new()));
+        Self { table, history }
+    }
+
+    pub fn navigate(&mut self, route: RouteMatch) -> Result<(), String> {
+        let route = self.table.complete(route)?;
+        self.history.push(route);
         Ok(())
     }
 
+    pub fn back(&mut self) -> Option<RouteMatch> {
+        self.history.back().cloned()
+    }
+
+    pub fn forward(&mut self) -> Option<RouteMatch> {
+        self.history.forward().cloned()
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut History {
+        &mut self.history
+    }
+
     pub fn get_current_timeline(&self) -> &str {
-        &self.current_timeline
+        self.history
+            .current()
+            .map(|r| r.timeline.as_str())
+            .unwrap_or_default()
+    }
+
+    pub fn get_current_route(&self) -> Option<&str> {
+        self.history.current().and_then(|r| r.url.as_deref())
+    }
+
+    pub fn table(&self) -> &RouteTable {
+        &self.table
+    }
+
+    pub fn set_table(&mut self, table: RouteTable) {
+        self.table = table;
     }
 }
 
@@ -247,17 +690,14 @@ pub enum Router {
 }
 
 impl Router {
+    /// フロー内のURL指定だけでルーターを作る（タイムラインのURL定義も使うなら `from_app`）
     pub fn new(flow: &Flow) -> Self {
         #[cfg(target_arch = "wasm32")]
         {
-            log::warn!("Router::new(flow) is deprecated. Use Router::from_app(app) instead.");
-            // ダミーのWasmRouterを作成（URL定義なし）
-            Router::Wasm(WasmRouter {
-                routes: HashMap::new(),
-                url_to_timeline: HashMap::new(),
-                current_route: None,
-                history: Vec::new(),
-            })
+            Router::Wasm(WasmRouter::with_table(
+                RouteTable::from_flow(flow),
+                &flow.start,
+            ))
         }
         #[cfg(not(target_arch = "wasm32"))]
         {
@@ -272,15 +712,7 @@ impl Router {
 
     #[cfg(not(target_arch = "wasm32"))]
     pub fn from_app(app: &App) -> Self {
-        // ネイティブでは最初のタイムラインをデフォルトに
-        let start_timeline = app
-            .timelines
-            .first()
-            .map(|t| t.name.clone())
-            .unwrap_or_else(|| "Home".to_string());
-        Router::Native(NativeRouter {
-            current_timeline: start_timeline,
-        })
+        Router::Native(NativeRouter::from_app(app))
     }
 
     #[cfg(target_arch = "wasm32")]
@@ -295,16 +727,216 @@ impl Router {
         None // ネイティブ版ではブラウザURLがないので常にNone
     }
 
+    /// 現在のURLの照合結果（ネイティブ版はURLバーがないので常にNone）
+    pub fn resolve_current_url(&self) -> Option<RouteMatch> {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Router::Wasm(router) => router.resolve_current_url(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Router::Native(_) => None,
+        }
+    }
+
     pub fn navigate_to_timeline(
         &mut self,
         timeline: &str,
         params: HashMap<String, String>,
     ) -> Result<(), String> {
+        self.navigate(RouteMatch {
+            timeline: timeline.to_string(),
+            params,
+            query: HashMap::new(),
+            url: None,
+        })
+    }
+
+    /// 遷移して履歴に追加する（URLはルートの対応表から組み立てる）
+    pub fn navigate(&mut self, route: RouteMatch) -> Result<(), String> {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Router::Wasm(router) => router.navigate(route),
+            #[cfg(not(target_arch = "wasm32"))]
+            Router::Native(router) => router.navigate(route),
+        }
+    }
+
+    /// 履歴を戻る（戻れなければNone）
+    pub fn back(&mut self) -> Option<RouteMatch> {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Router::Wasm(router) => router.back(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Router::Native(router) => router.back(),
+        }
+    }
+
+    /// 履歴を進む（進めなければNone）
+    pub fn forward(&mut self) -> Option<RouteMatch> {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Router::Wasm(router) => router.forward(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Router::Native(router) => router.forward(),
+        }
+    }
+
+    pub fn history(&self) -> &History {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Router::Wasm(router) => router.history(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Router::Native(router) => router.history(),
+        }
+    }
+
+    fn history_mut(&mut self) -> &mut History {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Router::Wasm(router) => router.history_mut(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Router::Native(router) => router.history_mut(),
+        }
+    }
+
+    /// 現在の履歴項目を置き換える（初回表示のURLを反映するときなど）
+    pub fn replace_current(&mut self, route: RouteMatch) {
+        let route = match self.table().complete(route.clone()) {
+            Ok(completed) => completed,
+            Err(_) => route,
+        };
+        self.history_mut().replace(route);
+    }
+
+    /// 履歴に残す件数を変える（超えた分は古いものから捨てる）
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_mut().set_limit(limit);
+    }
+
+    pub fn get_current_route(&self) -> Option<&str> {
+        self.history().current().and_then(|r| r.url.as_deref())
+    }
+
+    pub fn table(&self) -> &RouteTable {
+        match self {
+            #[cfg(target_arch = "wasm32")]
+            Router::Wasm(router) => router.table(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Router::Native(router) => router.table(),
+        }
+    }
+
+    /// ホットリロード時にURLの対応表だけを作り直す（履歴は保持する）
+    pub fn reload(&mut self, app: &App) {
+        let table = RouteTable::from_app(app);
         match self {
             #[cfg(target_arch = "wasm32")]
-            Router::Wasm(router) => router.navigate_to_timeline(timeline, params),
+            Router::Wasm(router) => router.set_table(table),
             #[cfg(not(target_arch = "wasm32"))]
-            Router::Native(router) => router.navigate_to_timeline(timeline, params),
+            Router::Native(router) => router.set_table(table),
+        }
+    }
+
+    /// URLを照合する（ネイティブ・WASM共通）
+    pub fn resolve(&self, url: &str) -> Option<RouteMatch> {
+        self.table().resolve(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(routes: &[(&str, &str)], not_found: Option<&str>) -> RouteTable {
+        let mut table = RouteTable {
+            routes: Vec::new(),
+            not_found: not_found.map(str::to_string),
+        };
+        for (timeline, pattern) in routes {
+            table.add_route(timeline, pattern);
         }
+        table.sort();
+        table
+    }
+
+    #[test]
+    fn test_resolve_and_build() {
+        let routes = table(
+            &[
+                ("UserPage", "/users/:id<number>/:tab?"),
+                ("NewUser", "/users/new"),
+                ("Search", "/search/:q"),
+                ("Docs", "/docs/*path"),
+                ("Home", "/"),
+            ],
+            Some("NotFound"),
+        );
+
+        let user = routes.resolve("/users/42/posts?sort=new&q=a%20b").unwrap();
+        assert_eq!(user.timeline, "UserPage");
+        assert_eq!(user.params["id"], "42");
+        assert_eq!(user.params["tab"], "posts");
+        assert_eq!(user.query["sort"], "new");
+        assert_eq!(user.query["q"], "a b");
+
+        assert_eq!(
+            routes.resolve("/users/42/").unwrap().params.get("tab"),
+            None
+        );
+        assert_eq!(routes.resolve("/users/new").unwrap().timeline, "NewUser");
+        assert_eq!(routes.resolve("/users/abc").unwrap().timeline, "NotFound");
+        assert_eq!(routes.resolve("/docs/a/b").unwrap().params["path"], "a/b");
+        assert_eq!(routes.resolve("/").unwrap().timeline, "Home");
+        assert_eq!(routes.resolve("/search/a%2Fb").unwrap().params["q"], "a/b");
+
+        let params = HashMap::from([("id".to_string(), "7".to_string())]);
+        assert_eq!(
+            routes.build_url("UserPage", &params),
+            Some(Ok("/users/7".into()))
+        );
+        let bad = HashMap::from([("id".to_string(), "x".to_string())]);
+        assert!(matches!(routes.build_url("UserPage", &bad), Some(Err(_))));
+        assert!(matches!(
+            routes.build_url("UserPage", &HashMap::new()),
+            Some(Err(_))
+        ));
+        let query = HashMap::from([("q".to_string(), "a b".to_string())]);
+        assert_eq!(
+            routes.build_url("Search", &query),
+            Some(Ok("/search/a%20b".into()))
+        );
+        assert_eq!(routes.build_url("Settings", &HashMap::new()), None);
+    }
+
+    #[test]
+    fn test_history() {
+        let routes = table(&[("Detail", "/items/:id<number>")], None);
+        let entry = |timeline: &str, id: Option<&str>| {
+            let params = id
+                .map(|id| HashMap::from([("id".to_string(), id.to_string())]))
+                .unwrap_or_default();
+            routes.entry(timeline, params, HashMap::new())
+        };
+
+        let mut history = History::new(3);
+        history.push(entry("List", None));
+        history.push(entry("Detail", Some("1")));
+        history.push(entry("Detail", Some("1"))); // 同じ遷移は積まない
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.current().unwrap().url.as_deref(), Some("/items/1"));
+
+        assert_eq!(history.back().unwrap().timeline, "List");
+        assert!(history.back().is_none());
+        assert_eq!(history.forward().unwrap().params["id"], "1");
+
+        // 戻ってから別の画面に進むと、進む側の履歴は消える
+        history.back();
+        history.push(entry("Detail", Some("2")));
+        assert!(!history.can_go_forward());
+
+        // 上限を超えたら古いものから捨てる
+        history.push(entry("Detail", Some("3")));
+        history.push(entry("Detail", Some("4")));
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.back().unwrap().params["id"], "3");
     }
 }
@@ -18,14 +18,19 @@ mod native {
 
     use winit::{
         application::ApplicationHandler,
-        event::{ElementState, Ime, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
-        event_loop::{ActiveEventLoop, EventLoop},
+        event::{
+            ElementState, Ime, KeyEvent, MouseButton, MouseScrollDelta, StartCause, WindowEvent,
+        },
+        event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
         keyboard::{KeyCode, PhysicalKey},
         window::{Window, WindowAttributes, WindowId},
     };
     use winit::dpi::PhysicalSize;
 
     use crate::engine::core::Engine;
+    use crate::engine::runtime_hotreload::PendingReload;
+    #[cfg(feature = "wgpu")]
+    use crate::engine::runtime_hotreload::apply_pending_reload;
     use crate::engine::state::{AppState, StateAccess};
 
     pub struct AppHandler<S>
@@ -56,7 +61,8 @@ mod native {
     where
         S: StateAccess + 'static + Clone + std::fmt::Debug,
     {
-        pub fn new(app: Arc<App>, state: AppState<S>, window_title: String) -> Self {
+        pub fn new(app: Arc<App>, mut state: AppState<S>, window_title: String) -> Self {
+            state.set_functions(&app.functions);
             Self {
                 app,
                 state,
@@ -121,6 +127,19 @@ mod native {
             }
         }
 
+        fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+            // ★ ツールチップの表示・トーストの消去の時刻になったら再描画
+            if let StartCause::ResumeTimeReached { .. } = cause
+                && let Some(window) = &self.window
+            {
+                window.request_redraw();
+            }
+        }
+
+        fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+            schedule_timer_redraw(event_loop, &self.state);
+        }
+
         fn window_event(
             &mut self,
             event_loop: &ActiveEventLoop,
@@ -138,6 +157,12 @@ mod native {
 
             let scale_factor = window.scale_factor() as f32;
 
+            // ★ マウスの戻る/進むボタンで履歴を移動
+            if let Some(navigation) = history_navigation_event(&event) {
+                self.event_queue.push(navigation);
+                window.request_redraw();
+            }
+
             match event {
                 WindowEvent::CloseRequested => event_loop.exit(),
                 WindowEvent::Resized(size) => {
@@ -149,6 +174,8 @@ mod native {
                     self.pending_resize = Some(size);
                     window.request_redraw();
                 }
+                // ★ Dialogが開いている間は背面のページをスクロールさせない
+                WindowEvent::MouseWheel { .. } if self.state.has_open_dialog() => {}
                 WindowEvent::MouseWheel { delta, .. } => {
                     let viewport_height = renderer.size().height as f32 / scale_factor;
                     let max_scroll = (self.content_length - viewport_height).max(0.0);
@@ -215,6 +242,15 @@ mod native {
                         (self.mouse_pos_raw[1] / scale_factor) - self.scroll_offset[1],
                     ];
 
+                    // ★ ポインタキャプチャ中（Sliderのドラッグ）は移動位置を通知
+                    if let Some(id) = self.state.pointer_capture.clone() {
+                        self.event_queue.push(UIEvent::PointerDragged {
+                            id,
+                            position: self.mouse_pos,
+                        });
+                        window.request_redraw();
+                    }
+
                     // マウス座標が変わった場合は再描画を要求（ホバー状態が変わる可能性）
                     if (old_mouse_pos[0] - self.mouse_pos[0]).abs() > 0.5
                         || (old_mouse_pos[1] - self.mouse_pos[1]).abs() > 0.5
@@ -231,6 +267,15 @@ mod native {
                     ..
                 } => {
                     self.mouse_down = true;
+
+                    // ★ ドラッグ可能な部品上で押下した場合はポインタをキャプチャ
+                    if let Some(id) = self.state.drag_target_at(self.mouse_pos) {
+                        self.state.pointer_capture = Some(id.clone());
+                        self.event_queue.push(UIEvent::PointerDragged {
+                            id,
+                            position: self.mouse_pos,
+                        });
+                    }
                     window.request_redraw(); // マウス押下時も再描画
                 }
                 WindowEvent::MouseInput {
@@ -239,6 +284,7 @@ mod native {
                     ..
                 } => {
                     self.mouse_down = false;
+                    self.state.pointer_capture = None;
 
                     // テキスト入力フィールドのクリック処理（直近レイアウトのall_text_inputsを使用）
                     let mut text_input_clicked = None;
@@ -431,6 +477,12 @@ mod native {
                         }
                         // ★ テキスト入力時は即座に再描画を要求
                         window.request_redraw();
+                    } else if queue_navigation_key(
+                        &self.state,
+                        &mut self.event_queue,
+                        &physical_key,
+                    ) {
+                        window.request_redraw();
                     }
                 }
                 // ★ IME対応: IME関連のイベント処理
@@ -566,6 +618,7 @@ mod native {
                     // スクロール補正
                     self.scroll_offset[1] +=
                         (self.target_scroll_offset[1] - self.scroll_offset[1]) * self.smoothing;
+                    self.state.scroll_offset = self.scroll_offset;
 
                     // マウス座標の正確な計算（スクロールオフセット考慮）
                     let adjusted_mouse_pos = [
@@ -821,22 +874,106 @@ mod native {
                         window.request_redraw();
                     }
                 }
-                crate::engine::async_call::AsyncEvent::IntervalTick(_name) => {
+                crate::engine::async_call::AsyncEvent::IntervalTick(name) => {
                     log::debug!("⏰ Received IntervalTick event");
+                    // ★ タイムラインのタイマーならwhenと同じくイベントとして処理する
+                    self.event_queue.push(UIEvent::TimerTick { id: name });
                     // 定期実行イベントも再描画を要求
                     if let Some(window) = &self.window {
                         window.request_redraw();
                     }
                 }
+                crate::engine::async_call::AsyncEvent::HttpResponse(id) => {
+                    // ★ http_get / http_post の完了（into への代入とハンドラーは step_whens で行う）
+                    self.event_queue.push(UIEvent::HttpResponse { id });
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
+                crate::engine::async_call::AsyncEvent::SubscriptionMessage(id) => {
+                    // ★ subscribe のメッセージ（アクションは step_whens で実行する）
+                    self.event_queue.push(UIEvent::SubscriptionMessage { id });
+                    if let Some(window) = &self.window {
+                        window.request_redraw();
+                    }
+                }
             }
         }
     }
 
+    /// ★ 次のタイマー時刻（ツールチップ表示・トースト消去）まで待ち、無ければイベントを待つ
+    fn schedule_timer_redraw<S>(event_loop: &ActiveEventLoop, state: &AppState<S>) {
+        match state.next_timer_deadline() {
+            Some(deadline) => {
+                let wait_ms = (deadline - crate::engine::timer::now_ms()).max(0.0);
+                event_loop.set_control_flow(ControlFlow::WaitUntil(
+                    std::time::Instant::now()
+                        + std::time::Duration::from_secs_f64(wait_ms / 1000.0),
+                ));
+            }
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
+    }
+
+    /// ★ マウスの戻る/進むボタンの押下を履歴の移動に変換する
+    fn history_navigation_event(event: &WindowEvent) -> Option<UIEvent> {
+        match event {
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Back,
+                ..
+            } => Some(UIEvent::NavigateBack),
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Forward,
+                ..
+            } => Some(UIEvent::NavigateForward),
+            _ => None,
+        }
+    }
+
+    /// ★ テキスト入力以外へのキー押下を、オーバーレイ・Dialog・フォーム部品のキーボード操作として通知する
+    ///
+    /// 受け取る部品がなければ通知しない。通知したらtrue（再描画が必要）
+    fn queue_navigation_key<S>(
+        state: &AppState<S>,
+        event_queue: &mut EventQueue,
+        key: &PhysicalKey,
+    ) -> bool
+    where
+        S: StateAccess + 'static,
+    {
+        let Some(key) = navigation_key_name(key) else {
+            return false;
+        };
+        let Some(field_id) = state.keyboard_target() else {
+            return false;
+        };
+        event_queue.push(UIEvent::KeyPressed {
+            field_id,
+            key: key.to_string(),
+            modifiers: Default::default(),
+        });
+        true
+    }
+
+    /// ★ テキスト入力にフォーカスがないときに扱うキーの名前（UIEvent::KeyPressed用）
+    fn navigation_key_name(key: &PhysicalKey) -> Option<&'static str> {
+        match key {
+            PhysicalKey::Code(KeyCode::Escape) => Some("Escape"),
+            PhysicalKey::Code(KeyCode::ArrowUp) => Some("ArrowUp"),
+            PhysicalKey::Code(KeyCode::ArrowDown) => Some("ArrowDown"),
+            PhysicalKey::Code(KeyCode::Enter | KeyCode::NumpadEnter) => Some("Enter"),
+            PhysicalKey::Code(KeyCode::Space) => Some("Space"),
+            _ => None,
+        }
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn run<S: StateAccess + 'static + Clone + std::fmt::Debug>(app: App, custom_state: S) {
         let start = app.flow.start.clone();
         let mut state = AppState::new(custom_state, start);
-        state.initialize_router(&app.flow);
+        state.initialize_router_from_app(&app);
         let app = Arc::new(app);
         run_internal(Arc::clone(&app), state);
     }
@@ -868,7 +1005,7 @@ mod native {
     ) {
         let start = app.flow.start.clone();
         let mut state = AppState::new(custom_state, start);
-        state.initialize_router(&app.flow);
+        state.initialize_router_from_app(&app);
         let app = Arc::new(app);
         run_internal_with_restart_flag(Arc::clone(&app), state, restart_flag);
     }
@@ -918,6 +1055,14 @@ mod native {
             self.inner.resumed(event_loop);
         }
 
+        fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+            self.inner.new_events(event_loop, cause);
+        }
+
+        fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+            self.inner.about_to_wait(event_loop);
+        }
+
         fn window_event(
             &mut self,
             event_loop: &ActiveEventLoop,
@@ -968,6 +1113,14 @@ mod native {
             self.inner.resumed(event_loop);
         }
 
+        fn new_events(&mut self, event_loop: &ActiveEventLoop, cause: StartCause) {
+            self.inner.new_events(event_loop, cause);
+        }
+
+        fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+            self.inner.about_to_wait(event_loop);
+        }
+
         fn window_event(
             &mut self,
             event_loop: &ActiveEventLoop,
@@ -992,12 +1145,16 @@ mod native {
         initial_app: Arc<App>,
         initial_state: AppState<S>,
         restart_flag: Arc<Mutex<bool>>,
-        updated_app: Arc<Mutex<Option<App>>>,
+        updated_app: Arc<Mutex<PendingReload>>,
     ) {
         // env_logger::init(); // 削除: lib.rsで既に初期化されている
 
         // 単一のイベントループを作成（一度だけ）
-        let event_loop = EventLoop::new().unwrap();
+        // ★ 非同期結果・タイマーのティックを受け取れるようAsyncEvent型にする
+        let event_loop = EventLoop::<crate::engine::async_call::AsyncEvent>::with_user_event()
+            .build()
+            .unwrap();
+        crate::engine::async_call::set_event_loop_proxy(event_loop.create_proxy());
 
         // ホットリロード対応のAppHandlerを作成
         let mut app_handler =
@@ -1029,7 +1186,7 @@ mod native {
 
         // ホットリロード用
         restart_flag: Arc<Mutex<bool>>,
-        updated_app: Arc<Mutex<Option<App>>>,
+        updated_app: Arc<Mutex<PendingReload>>,
         // リサイズのデバウンス用
         pending_resize: Option<PhysicalSize<u32>>,
     }
@@ -1042,7 +1199,7 @@ mod native {
             app: Arc<App>,
             state: AppState<S>,
             restart_flag: Arc<Mutex<bool>>,
-            updated_app: Arc<Mutex<Option<App>>>,
+            updated_app: Arc<Mutex<PendingReload>>,
         ) -> Self {
             Self {
                 current_app: app,
@@ -1068,41 +1225,40 @@ mod native {
 
         /// ホットリロードされたアプリケーションをチェックして更新
         fn check_and_update_app(&mut self) {
-            if let Ok(flag) = self.restart_flag.try_lock() {
-                if *flag {
-                    // 新しいアプリケーションがあるかチェック
-                    if let Ok(mut app_guard) = self.updated_app.try_lock() {
-                        if let Some(new_app) = app_guard.take() {
-                            info!("🔄 Applying hot reload update..."); // println!をinfo!に変更、coloredの使用を削除
-                            self.current_app = Arc::new(new_app);
-
-                            // 状態をリセット
-                            self.state.static_stencils = None;
-                            self.state.static_buttons.clear();
-                            self.state.static_text_inputs.clear();
-                            self.state.static_text_inputs.clear();
-                            self.state.expanded_body = None;
-                            self.state.cached_window_size = None;
-                            self.button_handlers.clear();
-
-                            // ウィンドウの再描画を要求
-                            if let Some(window) = &self.window {
-                                window.request_redraw();
-                            }
-
-                            info!("✅ Hot reload update applied successfully!"); // println!をinfo!に変更、coloredの使用を削除
-                        }
-                    }
+            // フラグは確認したのと同じロックの中で下ろす（その間に立ったフラグを取りこぼさない）
+            // 読み込み中で変更を取り出せないときはフラグを残し、次の呼び出しで反映する
+            let pending = {
+                let Ok(mut flag) = self.restart_flag.try_lock() else {
+                    return;
+                };
+                if !*flag {
+                    return;
+                }
+                let Ok(mut pending) = self.updated_app.try_lock() else {
+                    return;
+                };
+                *flag = false;
+                std::mem::take(&mut *pending)
+            };
 
-                    // フラグをリセット
-                    drop(flag);
-                    *self.restart_flag.lock().unwrap() = false;
+            // 新しいアプリケーション・読み込みエラー・変更された画像などを反映
+            if apply_pending_reload(
+                &mut self.current_app,
+                &mut self.state,
+                self.renderer.as_mut(),
+                pending,
+            ) {
+                self.button_handlers.clear();
+
+                // ウィンドウの再描画を要求
+                if let Some(window) = &self.window {
+                    window.request_redraw();
                 }
             }
         }
     }
 
-    impl<S> ApplicationHandler for AppHandlerWithDynamicReload<S>
+    impl<S> ApplicationHandler<crate::engine::async_call::AsyncEvent> for AppHandlerWithDynamicReload<S>
     where
         S: StateAccess + 'static + Clone + std::fmt::Debug,
     {
@@ -1120,6 +1276,41 @@ mod native {
             }
         }
 
+        fn user_event(
+            &mut self,
+            _event_loop: &ActiveEventLoop,
+            event: crate::engine::async_call::AsyncEvent,
+        ) {
+            match event {
+                crate::engine::async_call::AsyncEvent::IntervalTick(name) => {
+                    self.event_queue.push(UIEvent::TimerTick { id: name });
+                }
+                crate::engine::async_call::AsyncEvent::HttpResponse(id) => {
+                    self.event_queue.push(UIEvent::HttpResponse { id });
+                }
+                crate::engine::async_call::AsyncEvent::SubscriptionMessage(id) => {
+                    self.event_queue.push(UIEvent::SubscriptionMessage { id });
+                }
+                crate::engine::async_call::AsyncEvent::AsyncResultReady => {}
+            }
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+
+        fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+            // ★ ツールチップの表示・トーストの消去の時刻になったら再描画
+            if let StartCause::ResumeTimeReached { .. } = cause
+                && let Some(window) = &self.window
+            {
+                window.request_redraw();
+            }
+        }
+
+        fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+            schedule_timer_redraw(event_loop, &self.state);
+        }
+
         fn window_event(
             &mut self,
             event_loop: &ActiveEventLoop,
@@ -1140,6 +1331,12 @@ mod native {
 
             let scale_factor = window.scale_factor() as f32;
 
+            // ★ マウスの戻る/進むボタンで履歴を移動
+            if let Some(navigation) = history_navigation_event(&event) {
+                self.event_queue.push(navigation);
+                window.request_redraw();
+            }
+
             match event {
                 WindowEvent::CloseRequested => event_loop.exit(),
                 WindowEvent::Resized(size) => {
@@ -1151,6 +1348,8 @@ mod native {
                     self.pending_resize = Some(size);
                     window.request_redraw();
                 }
+                // ★ Dialogが開いている間は背面のページをスクロールさせない
+                WindowEvent::MouseWheel { .. } if self.state.has_open_dialog() => {}
                 WindowEvent::MouseWheel { delta, .. } => {
                     let viewport_height = renderer.size().height as f32 / scale_factor;
                     let max_scroll = (self.content_length - viewport_height).max(0.0);
@@ -1219,6 +1418,15 @@ mod native {
                         (self.mouse_pos_raw[1] / scale_factor) - self.scroll_offset[1],
                     ];
 
+                    // ★ ポインタキャプチャ中（Sliderのドラッグ）は移動位置を通知
+                    if let Some(id) = self.state.pointer_capture.clone() {
+                        self.event_queue.push(UIEvent::PointerDragged {
+                            id,
+                            position: self.mouse_pos,
+                        });
+                        window.request_redraw();
+                    }
+
                     if (old_mouse_pos[0] - self.mouse_pos[0]).abs() > 0.5
                         || (old_mouse_pos[1] - self.mouse_pos[1]).abs() > 0.5
                     {
@@ -1233,6 +1441,15 @@ mod native {
                     ..
                 } => {
                     self.mouse_down = true;
+
+                    // ★ ドラッグ可能な部品上で押下した場合はポインタをキャプチャ
+                    if let Some(id) = self.state.drag_target_at(self.mouse_pos) {
+                        self.state.pointer_capture = Some(id.clone());
+                        self.event_queue.push(UIEvent::PointerDragged {
+                            id,
+                            position: self.mouse_pos,
+                        });
+                    }
                     window.request_redraw();
                 }
                 WindowEvent::MouseInput {
@@ -1241,6 +1458,7 @@ mod native {
                     ..
                 } => {
                     self.mouse_down = false;
+                    self.state.pointer_capture = None;
 
                     // テキスト入力フィールドのクリック処理（直近レイアウトのall_text_inputsを使用）
                     let mut text_input_clicked = None;
@@ -1429,6 +1647,12 @@ mod native {
                         }
                         // ★ テキスト入力時は即座に再描画を要求
                         window.request_redraw();
+                    } else if queue_navigation_key(
+                        &self.state,
+                        &mut self.event_queue,
+                        &physical_key,
+                    ) {
+                        window.request_redraw();
                     }
                 }
                 // ★ IME対応: IME関連のイベント処理
@@ -1538,6 +1762,7 @@ mod native {
                     // スクロール補正
                     self.scroll_offset[1] +=
                         (self.target_scroll_offset[1] - self.scroll_offset[1]) * self.smoothing;
+                    self.state.scroll_offset = self.scroll_offset;
 
                     // マウス座標の正確な計算（スクロールオフセット考慮）
                     let adjusted_mouse_pos = [
@@ -1763,7 +1988,7 @@ mod native {
     ) {
         let start = app.flow.start.clone();
         let mut state = AppState::new(custom_state, start);
-        state.initialize_router(&app.flow);
+        state.initialize_router_from_app(&app);
         let app = Arc::new(app);
 
         // ★ AsyncEvent型のEventLoopを作成
@@ -1787,13 +2012,17 @@ mod native {
         initial_app: Arc<App>,
         initial_state: AppState<S>,
         restart_flag: Arc<Mutex<bool>>,
-        updated_app: Arc<Mutex<Option<App>>>,
+        updated_app: Arc<Mutex<PendingReload>>,
         window_title: Option<&str>,
     ) {
         // env_logger::init(); // 削除: lib.rsで既に初期化されている
 
         // 単一のイベントループを作成（一度だけ）
-        let event_loop = EventLoop::new().unwrap();
+        // ★ 非同期結果・タイマーのティックを受け取れるようAsyncEvent型にする
+        let event_loop = EventLoop::<crate::engine::async_call::AsyncEvent>::with_user_event()
+            .build()
+            .unwrap();
+        crate::engine::async_call::set_event_loop_proxy(event_loop.create_proxy());
 
         let mut app_handler = AppHandlerWithDynamicReloadAndTitle::new(
             initial_app,
@@ -1831,7 +2060,7 @@ mod native {
         window_title: String,
 
         restart_flag: Arc<Mutex<bool>>,
-        updated_app: Arc<Mutex<Option<App>>>,
+        updated_app: Arc<Mutex<PendingReload>>,
         // リサイズのデバウンス用
         pending_resize: Option<PhysicalSize<u32>>,
     }
@@ -1844,7 +2073,7 @@ mod native {
             app: Arc<App>,
             state: AppState<S>,
             restart_flag: Arc<Mutex<bool>>,
-            updated_app: Arc<Mutex<Option<App>>>,
+            updated_app: Arc<Mutex<PendingReload>>,
             window_title: Option<&str>,
         ) -> Self {
             let title = if let Some(title) = window_title {
@@ -1878,40 +2107,41 @@ mod native {
 
         /// ホットリロードされたアプリケーションをチェックして更新
         fn check_and_update_app(&mut self) {
-            if let Ok(flag) = self.restart_flag.try_lock() {
-                if *flag {
-                    // 新しいアプリケーションがあるかチェック
-                    if let Ok(mut app_guard) = self.updated_app.try_lock() {
-                        if let Some(new_app) = app_guard.take() {
-                            info!("🔄 Applying hot reload update..."); // println!をinfo!に変更、coloredの使用を削除
-                            self.current_app = Arc::new(new_app);
-
-                            // 状態をリセット
-                            self.state.static_stencils = None;
-                            self.state.static_buttons.clear();
-                            self.state.static_text_inputs.clear();
-                            self.state.expanded_body = None;
-                            self.state.cached_window_size = None;
-                            self.button_handlers.clear();
-
-                            // ウィンドウの再描画を要求
-                            if let Some(window) = &self.window {
-                                window.request_redraw();
-                            }
-
-                            info!("✅ Hot reload update applied successfully!"); // println!をinfo!に変更、coloredの使用を削除
-                        }
-                    }
+            // フラグは確認したのと同じロックの中で下ろす（その間に立ったフラグを取りこぼさない）
+            // 読み込み中で変更を取り出せないときはフラグを残し、次の呼び出しで反映する
+            let pending = {
+                let Ok(mut flag) = self.restart_flag.try_lock() else {
+                    return;
+                };
+                if !*flag {
+                    return;
+                }
+                let Ok(mut pending) = self.updated_app.try_lock() else {
+                    return;
+                };
+                *flag = false;
+                std::mem::take(&mut *pending)
+            };
 
-                    // フラグをリセット
-                    drop(flag);
-                    *self.restart_flag.lock().unwrap() = false;
+            // 新しいアプリケーション・読み込みエラー・変更された画像などを反映
+            if apply_pending_reload(
+                &mut self.current_app,
+                &mut self.state,
+                self.renderer.as_mut(),
+                pending,
+            ) {
+                self.button_handlers.clear();
+
+                // ウィンドウの再描画を要求
+                if let Some(window) = &self.window {
+                    window.request_redraw();
                 }
             }
         }
     }
 
-    impl<S> ApplicationHandler for AppHandlerWithDynamicReloadAndTitle<S>
+    impl<S> ApplicationHandler<crate::engine::async_call::AsyncEvent>
+        for AppHandlerWithDynamicReloadAndTitle<S>
     where
         S: StateAccess + 'static + Clone + std::fmt::Debug,
     {
@@ -1929,6 +2159,41 @@ mod native {
             }
         }
 
+        fn user_event(
+            &mut self,
+            _event_loop: &ActiveEventLoop,
+            event: crate::engine::async_call::AsyncEvent,
+        ) {
+            match event {
+                crate::engine::async_call::AsyncEvent::IntervalTick(name) => {
+                    self.event_queue.push(UIEvent::TimerTick { id: name });
+                }
+                crate::engine::async_call::AsyncEvent::HttpResponse(id) => {
+                    self.event_queue.push(UIEvent::HttpResponse { id });
+                }
+                crate::engine::async_call::AsyncEvent::SubscriptionMessage(id) => {
+                    self.event_queue.push(UIEvent::SubscriptionMessage { id });
+                }
+                crate::engine::async_call::AsyncEvent::AsyncResultReady => {}
+            }
+            if let Some(window) = &self.window {
+                window.request_redraw();
+            }
+        }
+
+        fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
+            // ★ ツールチップの表示・トーストの消去の時刻になったら再描画
+            if let StartCause::ResumeTimeReached { .. } = cause
+                && let Some(window) = &self.window
+            {
+                window.request_redraw();
+            }
+        }
+
+        fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+            schedule_timer_redraw(event_loop, &self.state);
+        }
+
         fn window_event(
             &mut self,
             event_loop: &ActiveEventLoop,
@@ -1949,6 +2214,12 @@ mod native {
 
             let scale_factor = window.scale_factor() as f32;
 
+            // ★ マウスの戻る/進むボタンで履歴を移動
+            if let Some(navigation) = history_navigation_event(&event) {
+                self.event_queue.push(navigation);
+                window.request_redraw();
+            }
+
             match event {
                 WindowEvent::CloseRequested => event_loop.exit(),
                 WindowEvent::Resized(size) => {
@@ -1959,6 +2230,8 @@ mod native {
                     self.pending_resize = Some(size);
                     window.request_redraw();
                 }
+                // ★ Dialogが開いている間は背面のページをスクロールさせない
+                WindowEvent::MouseWheel { .. } if self.state.has_open_dialog() => {}
                 WindowEvent::MouseWheel { delta, .. } => {
                     let viewport_height = renderer.size().height as f32 / scale_factor;
                     let max_scroll = (self.content_length - viewport_height).max(0.0);
@@ -2027,6 +2300,15 @@ mod native {
                         (self.mouse_pos_raw[1] / scale_factor) - self.scroll_offset[1],
                     ];
 
+                    // ★ ポインタキャプチャ中（Sliderのドラッグ）は移動位置を通知
+                    if let Some(id) = self.state.pointer_capture.clone() {
+                        self.event_queue.push(UIEvent::PointerDragged {
+                            id,
+                            position: self.mouse_pos,
+                        });
+                        window.request_redraw();
+                    }
+
                     if (old_mouse_pos[0] - self.mouse_pos[0]).abs() > 0.5
                         || (old_mouse_pos[1] - self.mouse_pos[1]).abs() > 0.5
                     {
@@ -2040,6 +2322,15 @@ mod native {
                     ..
                 } => {
                     self.mouse_down = true;
+
+                    // ★ ドラッグ可能な部品上で押下した場合はポインタをキャプチャ
+                    if let Some(id) = self.state.drag_target_at(self.mouse_pos) {
+                        self.state.pointer_capture = Some(id.clone());
+                        self.event_queue.push(UIEvent::PointerDragged {
+                            id,
+                            position: self.mouse_pos,
+                        });
+                    }
                     window.request_redraw();
                 }
                 WindowEvent::MouseInput {
@@ -2048,6 +2339,7 @@ mod native {
                     ..
                 } => {
                     self.mouse_down = false;
+                    self.state.pointer_capture = None;
 
                     // テキスト入力フィールドのクリック処理（直近レイアウトのall_text_inputsを使用）
                     let mut text_input_clicked = None;
@@ -2236,6 +2528,12 @@ mod native {
                         }
                         // ★ テキスト入力時は即座に再描画を要求
                         window.request_redraw();
+                    } else if queue_navigation_key(
+                        &self.state,
+                        &mut self.event_queue,
+                        &physical_key,
+                    ) {
+                        window.request_redraw();
                     }
                 }
                 // ★ IME対応: IME関連のイベント処理
@@ -2345,6 +2643,7 @@ mod native {
                     // スクロール補正
                     self.scroll_offset[1] +=
                         (self.target_scroll_offset[1] - self.scroll_offset[1]) * self.smoothing;
+                    self.state.scroll_offset = self.scroll_offset;
 
                     // マウス座標の正確な計算（スクロールオフセット考慮）
                     let adjusted_mouse_pos = [
@@ -1,7 +1,7 @@
 // DOM Renderer用のランタイム（WASM環境）
 
 #[cfg(target_arch = "wasm32")]
-pub fn run_dom<S>(app: crate::parser::ast::App, state: super::state::AppState<S>)
+pub fn run_dom<S>(app: crate::parser::ast::App, mut state: super::state::AppState<S>)
 where
     S: super::state::StateAccess + 'static + Clone + std::fmt::Debug,
 {
@@ -15,6 +15,7 @@ where
     use web_sys::window;
 
     log::info!("Starting DOM renderer...");
+    state.set_functions(&app.functions);
 
     let app = Arc::new(app);
     let state = Arc::new(Mutex::new(state));
@@ -101,6 +102,8 @@ where
             if let Some(target) = event_target {
                 // マウスムーブイベント
                 let mouse_pos_clone = Arc::clone(&mouse_pos);
+                let event_queue_clone = Arc::clone(&event_queue);
+                let state_clone = Arc::clone(&state);
                 let closure = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
                     // #container要素内の相対座標を取得
                     if let Some(window) = window() {
@@ -112,6 +115,16 @@ where
                                     let mut pos = mouse_pos_clone.lock().unwrap();
                                     pos[0] = (event.client_x() as f64 - rect.left()) as f32;
                                     pos[1] = (event.client_y() as f64 - rect.top()) as f32;
+
+                                    // ★ ポインタキャプチャ中（Sliderのドラッグ）は移動位置を通知
+                                    let captured =
+                                        state_clone.lock().unwrap().pointer_capture.clone();
+                                    if let Some(id) = captured {
+                                        event_queue_clone
+                                            .lock()
+                                            .unwrap()
+                                            .push(UIEvent::PointerDragged { id, position: *pos });
+                                    }
                                 }
                             }
                         }
@@ -150,8 +163,18 @@ where
                         [0.0, 0.0]
                     };
 
+                    let mut state_guard = state_clone.lock().unwrap();
+
+                    // ★ ドラッグ可能な部品上で押下した場合はポインタをキャプチャ
+                    if let Some(id) = state_guard.drag_target_at(pos) {
+                        state_guard.pointer_capture = Some(id.clone());
+                        event_queue_clone
+                            .lock()
+                            .unwrap()
+                            .push(UIEvent::PointerDragged { id, position: pos });
+                    }
+
                     // ボタンのヒットテストを行う
-                    let state_guard = state_clone.lock().unwrap();
 
                     for (id, button_pos, button_size) in &state_guard.all_buttons {
                         let in_bounds = pos[0] >= button_pos[0]
@@ -179,8 +202,10 @@ where
 
                 // マウスアップイベント
                 let mouse_down_clone = Arc::clone(&mouse_down);
+                let state_clone = Arc::clone(&state);
                 let closure = Closure::wrap(Box::new(move |_event: web_sys::MouseEvent| {
                     *mouse_down_clone.lock().unwrap() = false;
+                    state_clone.lock().unwrap().pointer_capture = None;
                 }) as Box<dyn FnMut(_)>);
                 target.add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref())
                     .ok();
@@ -214,6 +239,25 @@ where
         let current_mouse_down = *mouse_down_clone.lock().unwrap();
         let prev_down = *prev_mouse_down_clone.lock().unwrap();
 
+        // ★ タイマー（every / after）のティック（DOM版はEventLoopProxyがないためキューから受け取る）
+        for id in crate::engine::async_call::take_interval_ticks() {
+            event_queue_guard.push(UIEvent::TimerTick { id });
+        }
+        for id in crate::engine::http::take_http_responses() {
+            event_queue_guard.push(UIEvent::HttpResponse { id });
+        }
+        for id in crate::engine::subscription::take_subscription_messages() {
+            event_queue_guard.push(UIEvent::SubscriptionMessage { id });
+        }
+
+        // ★ 非同期結果（リソースなど）を適用
+        if crate::engine::async_call::apply_async_results(&mut state_guard) {
+            state_guard.static_stencils = None;
+            state_guard.static_buttons.clear();
+            state_guard.static_text_inputs.clear();
+            state_guard.needs_redraw = true;
+        }
+
         // イベント処理
         let events: Vec<UIEvent> = event_queue_guard.queue.iter().cloned().collect();
         if !events.is_empty() {
@@ -1,6 +1,10 @@
+use super::app_diff::asset_paths;
 use super::runtime::AppHandler;
 use super::state::{AppState, StateAccess};
 use crate::parser::ast::App;
+#[cfg(feature = "wgpu")]
+use crate::wgpu_renderer::wgpu::WgpuRenderer;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use winit::{
     application::ApplicationHandler, event::WindowEvent, event_loop::ActiveEventLoop,
@@ -104,3 +108,101 @@ where
         self.inner.window_event(event_loop, window_id, event);
     }
 }
+
+/// ファイル監視スレッドからランタイムへ渡す、まだ反映していないホットリロードの結果
+#[derive(Debug, Default)]
+pub struct PendingReload {
+    /// 読み込み直したApp
+    pub app: Option<App>,
+    /// 読み込みに失敗した場合のエラー（前回のAppのまま実行し、ウィンドウ内に表示する）
+    pub error: Option<String>,
+    /// 変更された画像・フォントのファイル
+    pub assets: Vec<PathBuf>,
+}
+
+/// 実行中のAppと状態にホットリロードの結果を反映する（反映するものがなければfalse）
+#[cfg(feature = "wgpu")]
+pub fn apply_pending_reload<S>(
+    current_app: &mut Arc<App>,
+    state: &mut AppState<S>,
+    renderer: Option<&mut WgpuRenderer>,
+    pending: PendingReload,
+) -> bool
+where
+    S: StateAccess + 'static,
+{
+    if pending.app.is_none() && pending.error.is_none() && pending.assets.is_empty() {
+        return false;
+    }
+
+    if let Some(new_app) = pending.app {
+        log::info!("🔄 Applying hot reload update...");
+        let diff = state.apply_hot_reload(current_app, &new_app);
+        log::debug!("hot reload diff: {:?}", diff);
+        *current_app = Arc::new(new_app);
+        log::info!("✅ Hot reload update applied successfully!");
+    }
+    if let Some(error) = pending.error {
+        log::error!("❌ Hot reload failed: {}", error);
+        state.reload_error = Some(error);
+        state.needs_redraw = true;
+    }
+    if !pending.assets.is_empty() {
+        state.invalidate_assets(&pending.assets);
+        if let Some(renderer) = renderer {
+            renderer.reload_assets(&pending.assets, &asset_paths(current_app));
+        }
+    }
+    true
+}
+
+#[cfg(all(test, feature = "wgpu"))]
+mod tests {
+    use super::{PendingReload, apply_pending_reload};
+    use crate::engine::state::AppState;
+    use crate::parser::parse::parse_nilo;
+    use std::sync::Arc;
+
+    #[derive(nilo_state_access_derive::StateAccess)]
+    struct Empty {}
+
+    fn app(text: &str) -> crate::parser::ast::App {
+        let source = format!(
+            "flow {{ start: Main }}\ntimeline Main {{ Text(\"{}\") }}",
+            text
+        );
+        parse_nilo(&source).unwrap()
+    }
+
+    #[test]
+    fn test_failed_reload_keeps_previous_app() {
+        let mut current = Arc::new(app("v1"));
+        let first = Arc::clone(&current);
+        let mut state = AppState::new(Empty {}, "Main".into());
+
+        assert!(!apply_pending_reload(
+            &mut current,
+            &mut state,
+            None,
+            PendingReload::default()
+        ));
+
+        // 読み込みに失敗しても前回のAppのまま、エラーを表示する
+        let failed = PendingReload {
+            error: Some("syntax error".into()),
+            ..Default::default()
+        };
+        assert!(apply_pending_reload(&mut current, &mut state, None, failed));
+        assert!(Arc::ptr_eq(&current, &first));
+        assert_eq!(state.reload_error.as_deref(), Some("syntax error"));
+
+        // 次に読み込みに成功したら反映してエラーを消す
+        let fixed = PendingReload {
+            app: Some(app("v2")),
+            ..Default::default()
+        };
+        assert!(apply_pending_reload(&mut current, &mut state, None, fixed));
+        assert!(!Arc::ptr_eq(&current, &first));
+        assert_eq!(state.reload_error, None);
+    }
+}
@@ -23,10 +23,23 @@ impl FromExpr for String {
     }
 }
 
+// 式の引数は評価済みの文字列で渡されることがあるため、数値・真偽値は引数の型で解釈する
+// （"007" や 2^24 を超えるIDを f32 を経由して丸めないため）
 impl FromExpr for i32 {
     fn from_expr(expr: &Expr) -> Option<Self> {
         match expr {
             Expr::Number(n) => Some(*n as i32),
+            Expr::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromExpr for i64 {
+    fn from_expr(expr: &Expr) -> Option<Self> {
+        match expr {
+            Expr::Number(n) => Some(*n as i64),
+            Expr::String(s) => s.trim().parse().ok(),
             _ => None,
         }
     }
@@ -36,6 +49,7 @@ impl FromExpr for f32 {
     fn from_expr(expr: &Expr) -> Option<Self> {
         match expr {
             Expr::Number(n) => Some(*n),
+            Expr::String(s) => s.trim().parse().ok(),
             _ => None,
         }
     }
@@ -45,6 +59,7 @@ impl FromExpr for f64 {
     fn from_expr(expr: &Expr) -> Option<Self> {
         match expr {
             Expr::Number(n) => Some(*n as f64),
+            Expr::String(s) => s.trim().parse().ok(),
             _ => None,
         }
     }
@@ -54,6 +69,7 @@ impl FromExpr for bool {
     fn from_expr(expr: &Expr) -> Option<Self> {
         match expr {
             Expr::Bool(b) => Some(*b),
+            Expr::String(s) => s.trim().parse().ok(),
             _ => None,
         }
     }
@@ -75,8 +91,42 @@ impl FromExpr for HashMap<String, Expr> {
     }
 }
 
-/// 従来の引数のみを受け取る関数型
-type RustCallFn = dyn Fn(&[Expr]) + Send + Sync;
+// ========================================
+// 戻り値変換トレイト（FromExprの逆）
+// ========================================
+
+/// Rust関数の戻り値を式の評価結果（文字列）に変換するトレイト
+///
+/// serdeでシリアライズできる型すべてに実装されている。
+/// 文字列はそのまま、数値・真偽値は文字列表現、`()` や `None` は空文字列、
+/// `Vec` や構造体はJSONになる（`foreach` の反復対象や `item.field` で参照できる）。
+pub trait IntoExprValue {
+    fn into_expr_value(self) -> String;
+}
+
+impl<T: serde::Serialize> IntoExprValue for T {
+    fn into_expr_value(self) -> String {
+        match serde_json::to_value(&self) {
+            Ok(serde_json::Value::Null) => String::new(),
+            Ok(serde_json::Value::String(s)) => s,
+            // 整数値の浮動小数点は評価器の数値表現（3.0 → "3"）に合わせる
+            Ok(serde_json::Value::Number(n)) => match n.as_f64() {
+                Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < 1e15 => {
+                    (f as i64).to_string()
+                }
+                _ => n.to_string(),
+            },
+            Ok(other) => other.to_string(),
+            Err(e) => {
+                log::error!("Failed to serialize Rust call result: {}", e);
+                String::new()
+            }
+        }
+    }
+}
+
+/// 従来の引数のみを受け取る関数型（戻り値を持つ関数は評価結果を返す）
+type RustCallFn = dyn Fn(&[Expr]) -> Option<String> + Send + Sync;
 
 /// stateアクセス可能な関数型（非推奨 - 危険）
 type StateAccessibleFn = dyn Fn(&mut dyn Any, &[Expr]) + Send + Sync;
@@ -115,10 +165,13 @@ pub fn register_rust_call<F>(name: &str, func: F)
 where
     F: Fn(&[Expr]) + Send + Sync + 'static,
 {
-    RUST_CALL_REGISTRY
-        .lock()
-        .unwrap()
-        .insert(name.to_string(), Box::new(func));
+    RUST_CALL_REGISTRY.lock().unwrap().insert(
+        name.to_string(),
+        Box::new(move |args: &[Expr]| {
+            func(args);
+            None
+        }),
+    );
 }
 
 // ========================================
@@ -130,9 +183,9 @@ pub trait FromExprArgs: Sized {
     fn from_expr_args(args: &[Expr]) -> Result<Self, String>;
 }
 
-/// 関数を呼び出し可能にするトレイト
-pub trait CallableFn<Args>: Send + Sync + 'static {
-    fn call(&self, args: Args);
+/// 関数を呼び出し可能にするトレイト（`Ret` は関数の戻り値型）
+pub trait CallableFn<Args, Ret = ()>: Send + Sync + 'static {
+    fn call(&self, args: Args) -> Ret;
 }
 
 // 引数なし
@@ -146,11 +199,11 @@ impl FromExprArgs for () {
     }
 }
 
-impl<F> CallableFn<()> for F
+impl<F, R> CallableFn<(), R> for F
 where
-    F: Fn() + Send + Sync + 'static,
+    F: Fn() -> R + Send + Sync + 'static,
 {
-    fn call(&self, _args: ()) {
+    fn call(&self, _args: ()) -> R {
         self()
     }
 }
@@ -180,11 +233,11 @@ macro_rules! impl_typed_call {
         }
 
         #[allow(non_snake_case)]
-        impl<F, $($T),+> CallableFn<($($T,)+)> for F
+        impl<F, R, $($T),+> CallableFn<($($T,)+), R> for F
         where
-            F: Fn($($T),+) + Send + Sync + 'static,
+            F: Fn($($T),+) -> R + Send + Sync + 'static,
         {
-            fn call(&self, ($($T,)+): ($($T,)+)) {
+            fn call(&self, ($($T,)+): ($($T,)+)) -> R {
                 self($($T),+)
             }
         }
@@ -218,6 +271,10 @@ impl_typed_call!(T1, T2, T3, T4);
 /// #[nilo_function]
 /// fn add(a: i32, b: i32) { println!("{}", a + b); }
 ///
+/// // 値を返す関数は式として使える（`Text("{}", format_price!(item.price))`）
+/// #[nilo_function]
+/// fn format_price(price: f64) -> String { format!("¥{:.0}", price) }
+///
 /// // Niloの無名構造体を受け取る例
 /// #[nilo_function]
 /// fn create_user(data: HashMap<String, Expr>) {
@@ -230,18 +287,18 @@ impl_typed_call!(T1, T2, T3, T4);
 /// ```nilo
 /// rust_call create_user({ name: "Alice", age: 30, active: true })
 /// ```
-pub fn register_typed_call<F, Args>(name: &str, func: F)
+pub fn register_typed_call<F, Args, Ret>(name: &str, func: F)
 where
     Args: FromExprArgs,
-    F: CallableFn<Args>,
+    Ret: IntoExprValue,
+    F: CallableFn<Args, Ret>,
 {
     let name_owned = name.to_string();
     let wrapper = move |args: &[Expr]| match Args::from_expr_args(args) {
-        Ok(converted_args) => {
-            func.call(converted_args);
-        }
+        Ok(converted_args) => Some(func.call(converted_args).into_expr_value()),
         Err(e) => {
             log::error!("Function '{}': {}", name_owned, e);
+            None
         }
     };
     RUST_CALL_REGISTRY
@@ -311,16 +368,18 @@ where
         .insert(name.to_string(), Box::new(func));
 }
 
-/// 従来の引数のみの関数を実行
-pub fn execute_rust_call(name: &str, args: &[Expr]) {
+/// 従来の引数のみの関数を実行し、戻り値を評価結果として返す
+/// （`register_rust_call` で登録した関数や引数の変換に失敗した場合はNone）
+pub fn execute_rust_call(name: &str, args: &[Expr]) -> Option<String> {
     let registry = RUST_CALL_REGISTRY.lock().unwrap();
     if let Some(func) = registry.get(name) {
-        func(args);
+        func(args)
     } else {
         log::warn!(
             "Warning: Rust call '{}' is not registered in basic registry",
             name
         );
+        None
     }
 }
 
@@ -401,3 +460,99 @@ pub fn list_registered_calls() -> (Vec<String>, Vec<String>, Vec<String>) {
 
     (basic_calls, state_calls, safe_calls)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_return_value() {
+        register_typed_call("test_format_price", |price: f64| format!("¥{:.0}", price));
+        assert_eq!(
+            execute_rust_call("test_format_price", &[Expr::Number(1200.0)]).as_deref(),
+            Some("¥1200")
+        );
+    }
+
+    #[test]
+    fn test_list_return_value() {
+        register_typed_call("test_tags", |count: i32| vec!["tag"; count as usize]);
+        assert_eq!(
+            execute_rust_call("test_tags", &[Expr::Number(2.0)]).as_deref(),
+            Some(r#"["tag","tag"]"#)
+        );
+    }
+
+    #[test]
+    fn test_unit_return_value() {
+        register_typed_call("test_noop", || {});
+        assert_eq!(execute_rust_call("test_noop", &[]).as_deref(), Some(""));
+    }
+
+    #[test]
+    fn test_missing_argument() {
+        register_typed_call("test_double", |n: i32| n * 2);
+        assert_eq!(execute_rust_call("test_double", &[]), None);
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        assert_eq!(3.0f32.into_expr_value(), "3");
+    }
+
+    #[derive(Default, nilo_state_access_derive::StateAccess)]
+    struct Order {
+        code: String,
+        id: i64,
+    }
+
+    #[test]
+    fn test_call_args_converted_by_parameter_type() {
+        register_typed_call("test_echo_code", |code: String| code);
+        register_typed_call("test_next_id", |id: i64| id + 1);
+
+        let order = Order {
+            code: "007".into(),
+            id: 16_777_217,
+        };
+        let state = AppState::new(order, "Main".into());
+        let call = |name: &str, path: &str| {
+            state.eval_expr_from_ast(&Expr::FunctionCall {
+                name: name.into(),
+                args: vec![Expr::Path(path.into())],
+            })
+        };
+
+        // 文字列の引数はゼロ埋めを保ち、整数の引数は f32 の精度に丸めない
+        assert_eq!(call("test_echo_code", "state.code"), "007");
+        assert_eq!(call("test_next_id", "state.id"), "16777218");
+        // 数値は String の引数として受け取らない
+        assert_eq!(
+            execute_rust_call("test_echo_code", &[Expr::Number(7.0)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_call_args_evaluated_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static TICKS: AtomicUsize = AtomicUsize::new(0);
+        register_typed_call("test_tick", || TICKS.fetch_add(1, Ordering::SeqCst) as i32);
+        register_rust_call("test_take_args", |_: &[Expr]| {});
+
+        let tick = Expr::FunctionCall {
+            name: "test_tick".into(),
+            args: vec![],
+        };
+        let state = AppState::new(Order::default(), "Main".into());
+        // 配列・オブジェクトの中の呼び出しも一度だけ評価する
+        state.eval_expr_from_ast(&Expr::FunctionCall {
+            name: "test_take_args".into(),
+            args: vec![
+                Expr::Array(vec![tick.clone()]),
+                Expr::Object(vec![("items".into(), Expr::Array(vec![tick]))]),
+            ],
+        });
+        assert_eq!(TICKS.load(Ordering::SeqCst), 2);
+    }
+}
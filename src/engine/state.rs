@@ -1,12 +1,23 @@
+use crate::engine::app_diff::{AppDiff, remap_instance_key};
+use crate::engine::app_handle::AppHandle;
+use crate::engine::core::event::value_to_literal_expr;
+use crate::engine::flow_guard::{self, FlowMode};
+use crate::engine::resource::Resources;
+use crate::engine::sub_flow::{SubFlowFrame, SubFlowStack};
+use crate::engine::subscription::TimelineSubscriptions;
+use crate::engine::timeline_timer::TimelineTimers;
+use crate::engine::{dsl_function, match_pattern, state_path};
 use crate::parser::ast::{
-    App, ColorValue, Edges, Expr, Rounded, Shadow, Style, Timeline, ViewNode, WithSpan,
+    App, ColorValue, Edges, Expr, FunctionDef, Rounded, Shadow, Style, TextInputType, Timeline,
+    ViewNode, When, WithSpan,
 };
 use crate::stencil::stencil::Stencil;
+use crate::ui::layout::{ForeachLayoutCache, LayoutViewport};
 use crate::ui::layout_diff::LayoutDiffEngine;
 use log;
 use std::any::{Any, TypeId};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 // use std::collections::hash_map::DefaultHasher; // unused
 // removed unused Hasher import
 use std::sync::{Arc, Once, OnceLock, RwLock};
@@ -231,6 +242,178 @@ impl ComponentContext {
     }
 }
 
+/// コンポーネントローカル状態を参照するパスの接頭辞
+/// DSL上の識別子としては書けない文字を含めることで、ユーザー定義の変数と衝突しない
+pub const COMPONENT_STATE_PREFIX: &str = "$local:";
+
+/// コンポーネントローカル状態のパスを生成（$local:<instance>/<name>）
+pub fn component_state_path(instance: &str, name: &str) -> String {
+    format!("{}{}/{}", COMPONENT_STATE_PREFIX, instance, name)
+}
+
+/// foreach内で展開したコンポーネントのインスタンスキーに入れる、要素のキーのプレースホルダー
+/// レイアウト時に各要素のkeyの値（key指定が無ければ番号）で置き換える
+/// `#` を含むためDSL上の変数（`item_index` など）とは衝突しない
+pub fn foreach_key_placeholder(var: &str) -> String {
+    format!("{{{}#key}}", var)
+}
+
+/// オーバーレイ外側（背景）のクリック領域ID
+/// オーバーレイが開いている間は全体を覆い、下層のボタンへのクリックを遮る
+pub const OVERLAY_BACKDROP_ID: &str = "$overlay:backdrop";
+
+/// Dialog外側（背景）のクリック領域ID
+/// 最前面のDialogが開いている間は全体を覆い、クリックでDialogを閉じる
+pub const DIALOG_BACKDROP_ID: &str = "$dialog:backdrop";
+
+/// toast() の既定の表示時間（ミリ秒）
+pub const TOAST_DURATION_MS: f64 = 3000.0;
+
+/// ツールチップを表示するまでのホバー時間（ミリ秒）
+pub const TOOLTIP_DELAY_MS: f64 = 500.0;
+
+/// ★ 開いているオーバーレイ（Selectのドロップダウン等）
+#[derive(Debug, Clone)]
+pub struct OpenOverlay {
+    /// オーバーレイを開いた部品のID
+    pub owner: String,
+    /// キーボード操作で選択中の項目
+    pub highlighted: Option<usize>,
+}
+
+/// ★ ドラッグで値を変更する部品（Slider）のトラック情報
+#[derive(Debug, Clone)]
+pub struct DragTarget {
+    /// 部品ID（user.change の対象）
+    pub control_id: String,
+    /// bind先のパス
+    pub path: String,
+    pub track_x: f32,
+    pub track_width: f32,
+    pub min: f32,
+    pub max: f32,
+    pub step: Option<f32>,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl DragTarget {
+    /// ポインタのx座標に対応する値（stepに丸めてmin..maxに収める）
+    pub fn value_at(&self, x: f32) -> f32 {
+        let ratio = if self.track_width > 0.0 {
+            ((x - self.track_x) / self.track_width).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let (min, max) = (self.min as f64, self.max as f64);
+        let mut value = min + ratio as f64 * (max - min);
+        if let Some(step) = self.step {
+            value = min + ((value - min) / step as f64).round() * step as f64;
+        }
+        value.clamp(min.min(max), max.max(min)) as f32
+    }
+}
+
+/// ★ レイアウト時に生成されるオーバーレイ層（描画内容とクリック領域）
+#[derive(Debug, Clone, Default)]
+pub struct OverlayLayer {
+    pub stencils: Vec<Stencil>,
+    pub hit_rects: Vec<(String, [f32; 2], [f32; 2])>,
+    /// ★ 層内のテキスト入力領域（Dialog内のTextInput）
+    pub text_inputs: Vec<(String, [f32; 2], [f32; 2])>,
+    /// ★ 層内のツールチップ対象
+    pub tooltip_targets: Vec<TooltipTarget>,
+    /// ★ 層の本体（Dialogのパネル・選択肢の一覧）の領域。背景のクリック領域から除く
+    pub bounds: Option<([f32; 2], [f32; 2])>,
+}
+
+/// ★ ツールチップを持つノードの表示領域
+#[derive(Debug, Clone)]
+pub struct TooltipTarget {
+    pub text: String,
+    pub position: [f32; 2],
+    pub size: [f32; 2],
+}
+
+impl TooltipTarget {
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        point[0] >= self.position[0]
+            && point[0] <= self.position[0] + self.size[0]
+            && point[1] >= self.position[1]
+            && point[1] <= self.position[1] + self.size[1]
+    }
+}
+
+/// ★ ホバー中のツールチップ対象と、ホバーを開始した時刻
+#[derive(Debug, Clone)]
+pub struct TooltipHover {
+    pub target: TooltipTarget,
+    pub since: f64,
+}
+
+/// ★ 表示中のトースト通知
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    /// 表示を終える時刻（timer::now_ms 基準）
+    pub expires_at: f64,
+}
+
+/// ★ パスの評価結果（nullと、どこにも見つからない識別子を区別する）
+enum PathValue {
+    Value(String),
+    Null,
+    /// 識別子をそのまま文字列として扱う（`?.` の途中ではnull）
+    Unresolved,
+}
+
+/// コンポーネントローカル状態のパスを (instance, name) に分解
+pub fn split_component_state_path(path: &str) -> Option<(&str, &str)> {
+    path.strip_prefix(COMPONENT_STATE_PREFIX)?.rsplit_once('/')
+}
+
+/// インスタンスキーの最後の区切り（コンポーネントの呼び出し位置 `Name@line:column`）
+/// foreach要素のキーは途中の区切りにしか現れないため、テンプレートと実際のキーで共通になる
+fn component_call_site(instance: &str) -> &str {
+    instance
+        .rsplit_once('>')
+        .map_or(instance, |(_, call_site)| call_site)
+}
+
+/// `{var}` を含むインスタンスキー（パターン）と実際のキーを照合し、変数の値を取り出す
+/// 例: "inc@{item#key}>Counter@3:5" と "inc@a1>Counter@3:5" → [("item#key", "a1")]
+pub fn match_instance_pattern(pattern: &str, actual: &str) -> Option<Vec<(String, String)>> {
+    if !pattern.contains('{') {
+        return (pattern == actual).then(Vec::new);
+    }
+
+    let mut bindings = Vec::new();
+    let mut pattern_rest = pattern;
+    let mut actual_rest = actual;
+    while let Some(start) = pattern_rest.find('{') {
+        let literal = &pattern_rest[..start];
+        actual_rest = actual_rest.strip_prefix(literal)?;
+        let end = start + pattern_rest[start..].find('}')?;
+        let var = &pattern_rest[start + 1..end];
+        pattern_rest = &pattern_rest[end + 1..];
+
+        // 次のリテラル（または末尾）までを変数の値とする
+        let next_literal = &pattern_rest[..pattern_rest.find('{').unwrap_or(pattern_rest.len())];
+        let value_len = if next_literal.is_empty() {
+            actual_rest.len()
+        } else {
+            actual_rest.find(next_literal)?
+        };
+        let value = &actual_rest[..value_len];
+        if value.is_empty() || value.contains('{') {
+            return None;
+        }
+        bindings.push((var.to_string(), value.to_string()));
+        actual_rest = &actual_rest[value_len..];
+    }
+    (pattern_rest == actual_rest).then_some(bindings)
+}
+
 #[derive(Debug, Clone)]
 pub struct AppState<S> {
     pub custom_state: S,
@@ -245,6 +428,7 @@ pub struct AppState<S> {
     // ★ ルーティング関連
     pub router: Option<crate::engine::routing::Router>,
     pub route_params: HashMap<String, String>,
+    pub route_query: HashMap<String, String>, // ★ URLのクエリ文字列（route.query.xxx）
 
     /// ボタンのonclick情報を保存
     pub button_onclick_map: HashMap<String, Expr>,
@@ -304,6 +488,46 @@ pub struct AppState<S> {
     pub text_selections: HashMap<String, (usize, usize)>,
     /// TextInput bindings: id -> state field
     pub text_input_bindings: HashMap<String, String>,
+    /// ★ bind時の入力値の型: id -> TextInputType
+    pub text_input_types: HashMap<String, TextInputType>,
+    /// ★ bind先への変換・書き込みエラー: id -> エラーメッセージ
+    pub text_input_errors: HashMap<String, String>,
+    /// ★ 最後に入力欄へ反映したbind先の値（外部からの変更検出用）
+    pub text_input_synced_values: HashMap<String, String>,
+    /// ★ フォーム部品のクリック時アクション: クリック対象ID -> (部品ID, 実行するアクション)
+    /// RadioGroupは選択肢ごとに `部品ID#インデックス` で登録される
+    pub form_control_actions: HashMap<String, (String, WithSpan<ViewNode>)>,
+    /// ★ 最後に操作したフォーム部品のID（フォーカス表示用）
+    pub focused_form_control: Option<String>,
+    /// ★ ドラッグ操作を受け付ける部品: クリック対象ID -> トラック情報
+    pub drag_targets: HashMap<String, DragTarget>,
+    /// ★ ポインタをキャプチャ中の部品ID（押下からボタンを離すまで移動をこの部品に送る）
+    pub pointer_capture: Option<String>,
+    /// ★ オーバーレイを開く部品: 部品ID -> (項目数, 現在選択中の項目)
+    pub overlay_triggers: HashMap<String, (usize, Option<usize>)>,
+    /// ★ 開いているオーバーレイ（同時に開けるのは1つだけ）
+    pub open_overlay: Option<OpenOverlay>,
+    /// ★ 開いているオーバーレイの描画内容（所有する部品のレイアウト時に生成）
+    pub overlay_layer: Option<OverlayLayer>,
+    /// ★ 開いているDialogの描画内容（後ろほど手前。レイアウト時に生成）
+    pub dialog_layers: Vec<OverlayLayer>,
+    /// ★ ツールチップを持つノード（レイアウト時に登録）
+    pub tooltip_targets: Vec<TooltipTarget>,
+    /// ★ 静的パートが登録したDialog・ツールチップの数（動的パートの分は毎フレーム作り直す）
+    pub static_dialog_count: usize,
+    pub static_tooltip_count: usize,
+    /// ★ ホバー中のツールチップ対象
+    pub tooltip_hover: Option<TooltipHover>,
+    /// ★ 表示中のトースト（古い順）
+    pub toasts: Vec<Toast>,
+    /// ★ ページのスクロール量（ランタイムから設定。Dialogを画面中央に置くために使う）
+    pub scroll_offset: [f32; 2],
+    /// ★ 仮想化したforeachを静的レイアウトした範囲（表示範囲がここを外れたら再レイアウト）
+    pub virtual_render_range: Option<[f32; 2]>,
+    /// ★ 静的レイアウトしたときのScrollContainerのスクロール量（変わったら再レイアウト）
+    pub virtual_container_offsets: HashMap<String, [f32; 2]>,
+    /// ★ foreachの要素ごとのレイアウト結果（keyが同じ要素は並べ替えても再利用）
+    pub foreach_layout_cache: ForeachLayoutCache,
 
     // ★ 新規追加: Timeline処理コンテキスト
     /// Timeline処理の状態（ロジック処理済みノードツリー等）
@@ -312,6 +536,35 @@ pub struct AppState<S> {
     // ★ 新規追加: 再描画要求フラグ
     /// 状態変更があった場合にtrueにする（set/toggle等）
     pub needs_redraw: bool,
+
+    // ★ 新規追加: コンポーネントローカル状態
+    /// インスタンスキー（呼び出し位置 + foreach要素のキー）-> (state名 -> 値)
+    /// レイアウト再計算やコンポーネント再展開では保持され、タイムライン遷移でクリアされる
+    /// foreach要素のインスタンスは、要素が表示されなくなったフレームの終わりに破棄される
+    pub component_states: HashMap<String, HashMap<String, String>>,
+    /// foreach内インスタンスのテンプレート（`{var#key}` を含むキー）の索引
+    /// 呼び出し位置（インスタンスキーの最後の区切り）-> テンプレートのキー
+    component_state_templates: HashMap<String, Vec<String>>,
+    /// 展開済みコンポーネントインスタンスのwhenブロック（展開時に再構築）
+    pub component_whens: Vec<When>,
+
+    /// ★ ファイルに定義した関数（fn）。ランタイムの開始時とホットリロード時に設定する
+    pub functions: HashMap<String, FunctionDef>,
+
+    /// ★ 遷移の検証に使うフロー（ルーターの初期化時に設定する）
+    pub flow: Option<crate::parser::ast::Flow>,
+    /// ★ フローに定義されていない遷移の扱い
+    pub flow_mode: FlowMode,
+    /// ★ 実行中のサブフロー（call_flow / finish）
+    pub sub_flows: SubFlowStack,
+    /// ★ ホットリロードの読み込みエラー（ウィンドウ内に表示し、次の読み込み成功で消える）
+    pub reload_error: Option<String>,
+    /// ★ 表示中のタイムラインのタイマー（every / after）
+    pub timeline_timers: TimelineTimers,
+    /// ★ 表示中のタイムラインのリソース（resource user = fetch_user!(...)）
+    pub resources: Resources,
+    /// ★ 表示中のタイムラインの購読（subscribe ws(...) as msg { ... }）
+    pub subscriptions: TimelineSubscriptions,
 }
 
 impl<S> AppState<S> {
@@ -327,6 +580,7 @@ impl<S> AppState<S> {
             button_onclick_map: HashMap::new(),
             router: None,
             route_params: HashMap::new(),
+            route_query: HashMap::new(),
             static_stencils: None,
             static_buttons: Vec::new(),
             static_text_inputs: Vec::new(),
@@ -349,9 +603,138 @@ impl<S> AppState<S> {
             text_cursor_positions: HashMap::new(),
             text_selections: HashMap::new(),
             text_input_bindings: HashMap::new(),
+            text_input_types: HashMap::new(),
+            text_input_errors: HashMap::new(),
+            text_input_synced_values: HashMap::new(),
+            form_control_actions: HashMap::new(),
+            focused_form_control: None,
+            drag_targets: HashMap::new(),
+            pointer_capture: None,
+            overlay_triggers: HashMap::new(),
+            open_overlay: None,
+            overlay_layer: None,
+            dialog_layers: Vec::new(),
+            tooltip_targets: Vec::new(),
+            static_dialog_count: 0,
+            static_tooltip_count: 0,
+            tooltip_hover: None,
+            toasts: Vec::new(),
+            scroll_offset: [0.0, 0.0],
+            virtual_render_range: None,
+            virtual_container_offsets: HashMap::new(),
+            foreach_layout_cache: ForeachLayoutCache::default(),
             timeline_context: None,
             needs_redraw: false,
+            component_states: HashMap::new(),
+            component_state_templates: HashMap::new(),
+            component_whens: Vec::new(),
+            functions: HashMap::new(),
+            flow: None,
+            flow_mode: FlowMode::default(),
+            sub_flows: SubFlowStack::new(),
+            reload_error: None,
+            timeline_timers: TimelineTimers::new(),
+            resources: Resources::new(),
+            subscriptions: TimelineSubscriptions::new(),
+        }
+    }
+
+    /// ★ Appの関数定義（fn）を式から呼べるようにする
+    pub fn set_functions(&mut self, functions: &[FunctionDef]) {
+        self.functions = functions
+            .iter()
+            .map(|def| (def.name.clone(), def.clone()))
+            .collect();
+    }
+
+    /// ★ `navigate_to` の遷移先を検証するフローを設定する
+    pub fn set_flow(&mut self, flow: &crate::parser::ast::Flow) {
+        self.flow = Some(flow.clone());
+    }
+
+    /// ★ フローに定義されていない遷移を拒否する（Strict）か警告だけにする（Warn）か
+    pub fn set_flow_mode(&mut self, mode: FlowMode) {
+        self.flow_mode = mode;
+    }
+
+    /// ★ ホットリロードで読み込み直したAppを反映する
+    ///
+    /// 現在のタイムライン・ルート・スクロール位置・入力中のテキストとフォーカス・
+    /// コンポーネントのローカル状態は引き継ぎ、変更があった部分だけ展開し直す。
+    /// 現在のタイムラインが削除された場合はフローの開始タイムラインへ戻る
+    pub fn apply_hot_reload(&mut self, old: &App, new: &App) -> AppDiff {
+        let diff = AppDiff::between(old, new);
+        self.reload_error = None;
+        self.set_functions(&new.functions);
+        self.set_flow(&new.flow);
+        if diff.flow_changed
+            && let Some(router) = &mut self.router
+        {
+            router.reload(new);
+        }
+
+        // ★ 行の追加・削除で位置がずれたコンポーネント呼び出しの状態を付け替える
+        if !diff.moved_instances.is_empty() {
+            let moves = &diff.moved_instances;
+            let remap = |key: &String| remap_instance_key(key, moves);
+            self.component_states = std::mem::take(&mut self.component_states)
+                .into_iter()
+                .map(|(key, vars)| (remap(&key), vars))
+                .collect();
+            self.component_state_templates.clear();
+            let templates: Vec<String> = self
+                .component_states
+                .keys()
+                .filter(|key| key.contains('{'))
+                .cloned()
+                .collect();
+            for template in templates {
+                self.index_component_template(template);
+            }
+            self.text_input_values = std::mem::take(&mut self.text_input_values)
+                .into_iter()
+                .map(|(id, value)| (remap(&id), value))
+                .collect();
+            self.text_cursor_positions = std::mem::take(&mut self.text_cursor_positions)
+                .into_iter()
+                .map(|(id, pos)| (remap(&id), pos))
+                .collect();
+            self.focused_text_input = self.focused_text_input.as_ref().map(remap);
         }
+
+        if diff.removed_timelines.contains(&self.current_timeline) {
+            log::warn!(
+                "表示中のタイムライン '{}' が削除されたため '{}' へ戻ります",
+                self.current_timeline,
+                new.flow.start
+            );
+            self.sub_flows = SubFlowStack::new();
+            self.jump_to_timeline(&new.flow.start);
+        } else if diff.affects_timeline(&self.current_timeline) {
+            // ローカル変数・スクロール位置・入力値は保持したまま展開とレイアウトだけやり直す
+            self.invalidate_layout_cache();
+            self.expanded_body = None;
+            self.cached_window_size = None;
+            self.component_whens.clear();
+            // タイマーの間隔・内容やリソース・購読の変更を反映するため開始し直す
+            self.timeline_timers.stop_all();
+            self.resources.stop_all();
+            self.subscriptions.stop_all();
+        }
+        self.needs_redraw = true;
+        diff
+    }
+
+    /// ★ 変更された画像の大きさのキャッシュを捨てる（次の描画で読み込み直す）
+    pub fn invalidate_assets(&mut self, paths: &[std::path::PathBuf]) {
+        let changed: Vec<_> = paths.iter().filter_map(|p| p.canonicalize().ok()).collect();
+        self.image_size_cache.borrow_mut().retain(|path, _| {
+            !std::path::Path::new(path)
+                .canonicalize()
+                .is_ok_and(|p| changed.contains(&p))
+        });
+        self.invalidate_layout_cache();
+        self.cached_window_size = None;
     }
 
     pub fn set_text_input_binding(&mut self, id: &str, field: &str) {
@@ -363,6 +746,161 @@ impl<S> AppState<S> {
         self.text_input_bindings.get(id)
     }
 
+    /// ★ 入力値の型付きでバインドを登録（`TextInput(bind: ..., type: "number")`）
+    pub fn set_text_input_binding_typed(
+        &mut self,
+        id: &str,
+        field: &str,
+        input_type: TextInputType,
+    ) {
+        self.set_text_input_binding(id, field);
+        self.text_input_types.insert(id.to_string(), input_type);
+    }
+
+    /// ★ bind先への書き込みに失敗した場合のエラーメッセージ
+    pub fn get_text_input_error(&self, id: &str) -> Option<&String> {
+        self.text_input_errors.get(id)
+    }
+
+    // ★ 新規追加: コンポーネントローカル状態
+
+    /// インスタンスのstateを初期化（既に存在する値は保持する）
+    pub fn init_component_state(&mut self, instance: &str, name: &str, initial: String) {
+        if !self.component_states.contains_key(instance) && instance.contains('{') {
+            self.index_component_template(instance.to_string());
+        }
+        self.component_states
+            .entry(instance.to_string())
+            .or_default()
+            .entry(name.to_string())
+            .or_insert(initial);
+    }
+
+    fn index_component_template(&mut self, template: String) {
+        self.component_state_templates
+            .entry(component_call_site(&template).to_string())
+            .or_default()
+            .push(template);
+    }
+
+    /// インスタンスキー中の `{var}` をforeach変数の現在値で置換
+    fn resolve_component_instance(&self, instance: &str) -> String {
+        if !instance.contains('{') {
+            return instance.to_string();
+        }
+        let mut resolved = String::with_capacity(instance.len());
+        let mut rest = instance;
+        while let Some(start) = rest.find('{') {
+            resolved.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            let var = &rest[start + 1..start + end];
+            match self.component_context.get_foreach_var(var) {
+                Some(v) => resolved.push_str(v),
+                None => resolved.push_str(&rest[start..start + end + 1]),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        resolved.push_str(rest);
+        resolved
+    }
+
+    /// インスタンスの状態を取得（未登録のforeachインスタンスはテンプレートを参照）
+    fn component_state_vars(&self, instance: &str) -> Option<&HashMap<String, String>> {
+        if let Some(vars) = self.component_states.get(instance) {
+            return Some(vars);
+        }
+        // 同じ呼び出し位置のテンプレートだけを照合する
+        self.component_state_templates
+            .get(component_call_site(instance))?
+            .iter()
+            .find(|template| match_instance_pattern(template, instance).is_some())
+            .and_then(|template| self.component_states.get(template))
+    }
+
+    /// コンポーネントローカル状態を取得（path は component_state_path 形式）
+    pub fn get_component_state(&self, path: &str) -> Option<String> {
+        let (instance, field) = split_component_state_path(path)?;
+        let (name, property_path) = match field.split_once('.') {
+            Some((name, rest)) => (name, Some(rest)),
+            None => (field, None),
+        };
+
+        // foreach内のインスタンスは初回更新までテンプレートの初期値を共有する
+        let resolved = self.resolve_component_instance(instance);
+        let value = self
+            .component_state_vars(&resolved)
+            .and_then(|vars| vars.get(name))?;
+
+        let Some(property_path) = property_path else {
+            return Some(value.clone());
+        };
+        let obj = serde_json::from_str::<serde_json::Value>(value).ok()?;
+        let mut current = &obj;
+        for part in property_path.split('.') {
+            current = current.get(part)?;
+        }
+        Some(match current {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Number(n) => n.to_string(),
+            serde_json::Value::Bool(b) => b.to_string(),
+            serde_json::Value::Null => "null".to_string(),
+            _ => current.to_string(),
+        })
+    }
+
+    /// コンポーネントローカル状態を更新
+    pub fn set_component_state(&mut self, path: &str, value: String) -> Result<(), String> {
+        let (instance, name) = split_component_state_path(path)
+            .ok_or_else(|| format!("Invalid component state path: {}", path))?;
+        if name.contains('.') {
+            return Err(format!(
+                "Nested assignment to component state '{}' is not supported",
+                name
+            ));
+        }
+
+        let resolved = self.resolve_component_instance(instance);
+        let template = self
+            .component_state_vars(&resolved)
+            .ok_or_else(|| format!("Unknown component instance: {}", resolved))?
+            .clone();
+        if !template.contains_key(name) {
+            return Err(format!("Unknown component state: {}", name));
+        }
+
+        self.component_states
+            .entry(resolved)
+            .or_insert(template)
+            .insert(name.to_string(), value);
+        Ok(())
+    }
+
+    /// ★ 表示されなかったforeach要素のインスタンスの状態を破棄する
+    /// rendered はこのフレームで表示したforeachの要素（(keyのプレースホルダー, keyの値)）
+    /// テンプレートとforeachの外のインスタンスは展開時に登録したものなので残す
+    pub fn retain_rendered_component_states(&mut self, rendered: &HashSet<(String, String)>) {
+        let templates = &self.component_state_templates;
+        self.component_states.retain(|instance, _| {
+            if instance.contains('{') {
+                return true;
+            }
+            let Some(candidates) = templates.get(component_call_site(instance)) else {
+                return true;
+            };
+            // テンプレートから作ったインスタンスは、束縛したすべての要素が表示されていれば残す
+            candidates
+                .iter()
+                .find_map(|template| match_instance_pattern(template, instance))
+                .is_none_or(|bindings| {
+                    bindings
+                        .into_iter()
+                        .all(|(var, key)| rendered.contains(&(format!("{{{}}}", var), key)))
+                })
+        });
+    }
+
     #[inline]
     pub fn current_timeline<'a>(&self, app: &'a App) -> Option<&'a Timeline> {
         app.timelines
@@ -390,6 +928,29 @@ impl<S> AppState<S> {
         self.static_text_inputs.clear();
         self.cached_window_size = None;
 
+        // ★ タイマー・リソースの取得・購読はタイムラインを出ると止める（次の描画で遷移先のものを開始）
+        self.timeline_timers.stop_all();
+        self.resources.stop_all();
+        self.subscriptions.stop_all();
+
+        // ★ コンポーネントローカル状態もタイムライン単位で破棄
+        self.component_states.clear();
+        self.component_state_templates.clear();
+        self.component_whens.clear();
+        self.form_control_actions.clear();
+        self.focused_form_control = None;
+        self.drag_targets.clear();
+        self.pointer_capture = None;
+        self.overlay_triggers.clear();
+        self.open_overlay = None;
+        self.overlay_layer = None;
+        self.dialog_layers.clear();
+        self.tooltip_targets.clear();
+        self.tooltip_hover = None;
+        self.virtual_render_range = None;
+        self.virtual_container_offsets.clear();
+        // トーストはタイムラインをまたいで表示し続ける
+
         // ★ ローカル変数をクリア（新しいtimelineに入るため）
         // ★ 実際のローカル変数の宣言は Engine::initialize_local_variables で行われる
         // ★ レイアウト再計算時には再宣言されない
@@ -429,6 +990,111 @@ impl<S> AppState<S> {
         self.focused_text_input = None;
     }
 
+    /// ★ オーバーレイを開く（開いているものがあれば置き換える）
+    pub fn show_overlay(&mut self, owner: &str, highlighted: Option<usize>) {
+        self.open_overlay = Some(OpenOverlay {
+            owner: owner.to_string(),
+            highlighted,
+        });
+        self.overlay_layer = None;
+        self.invalidate_layout_cache();
+    }
+
+    /// ★ 開いているオーバーレイを閉じる
+    pub fn close_overlay(&mut self) {
+        if self.open_overlay.take().is_some() {
+            self.overlay_layer = None;
+            self.invalidate_layout_cache();
+        }
+    }
+
+    /// ★ 表示中の範囲（コンテンツ座標のy上端・下端）
+    pub fn visible_range(&self, window_size: [f32; 2]) -> [f32; 2] {
+        let top = -self.scroll_offset[1];
+        [top, top + window_size[1]]
+    }
+
+    /// ★ レイアウトに渡す表示範囲（ページの表示範囲とScrollContainerのスクロール量）
+    pub fn layout_viewport(&self, window_size: [f32; 2]) -> LayoutViewport {
+        LayoutViewport {
+            range: self.visible_range(window_size),
+            container_offsets: self.scroll_container_offsets.clone(),
+            foreach_cache: self.foreach_layout_cache.clone(),
+        }
+    }
+
+    /// ★ Dialogが開いているかどうか
+    pub fn has_open_dialog(&self) -> bool {
+        !self.dialog_layers.is_empty()
+    }
+
+    /// ★ テキスト入力以外へのキー操作を受け取る部品のID
+    ///
+    /// フォーカス中のフォーム部品、開いているオーバーレイの持ち主、最前面のDialogの順。どれもなければNone
+    pub fn keyboard_target(&self) -> Option<String> {
+        self.focused_form_control
+            .clone()
+            .or_else(|| self.open_overlay.as_ref().map(|open| open.owner.clone()))
+            .or_else(|| {
+                self.form_control_actions
+                    .get(DIALOG_BACKDROP_ID)
+                    .filter(|_| self.has_open_dialog())
+                    .map(|(dialog_id, _)| dialog_id.clone())
+            })
+    }
+
+    /// ★ トースト通知を表示する（duration_msが無ければ既定の表示時間）
+    pub fn show_toast(&mut self, message: String, duration_ms: Option<f64>) {
+        let duration = duration_ms.unwrap_or(TOAST_DURATION_MS);
+        self.toasts.push(Toast {
+            message,
+            expires_at: crate::engine::timer::now_ms() + duration,
+        });
+        self.needs_redraw = true;
+    }
+
+    /// ★ 次に再描画が必要になる時刻（ツールチップの表示・トーストの消去）
+    /// ランタイムはこの時刻までイベント待ちしてよい
+    pub fn next_timer_deadline(&self) -> Option<f64> {
+        let tooltip = self
+            .tooltip_hover
+            .as_ref()
+            .map(|hover| hover.since + TOOLTIP_DELAY_MS)
+            .filter(|&at| at > crate::engine::timer::now_ms());
+        let toast = self
+            .toasts
+            .iter()
+            .map(|toast| toast.expires_at)
+            .reduce(f64::min);
+        match (tooltip, toast) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// ★ 指定位置にあるドラッグ可能な部品のID（直近のクリック領域から判定）
+    /// オーバーレイが開いている間は下層のクリック領域が無いため見つからない
+    pub fn drag_target_at(&self, position: [f32; 2]) -> Option<String> {
+        self.all_buttons
+            .iter()
+            .find(|(id, pos, size)| {
+                self.drag_targets.contains_key(id)
+                    && position[0] >= pos[0]
+                    && position[0] <= pos[0] + size[0]
+                    && position[1] >= pos[1]
+                    && position[1] <= pos[1] + size[1]
+            })
+            .map(|(id, _, _)| id.clone())
+    }
+
+    /// オーバーレイの開閉時など、静的パートを含めて再レイアウトさせる
+    fn invalidate_layout_cache(&mut self) {
+        self.needs_redraw = true;
+        self.static_stencils = None;
+        self.static_buttons.clear();
+        self.static_text_inputs.clear();
+    }
+
     /// 現在フォーカスされているテキスト入力フィールドのIDを取得
     pub fn get_focused_text_input(&self) -> Option<&String> {
         self.focused_text_input.as_ref()
@@ -442,10 +1108,132 @@ impl<S> AppState<S> {
         let cursor_pos = value.chars().count();
         self.text_cursor_positions.insert(field_id.clone(), cursor_pos);
         if let Some(bound_field) = self.text_input_bindings.get(&field_id).cloned() {
-            crate::engine::state::with_custom_state(self, |ctx| { let _ = ctx.set(&bound_field, value.clone()); });
+            let input_type = self
+                .text_input_types
+                .get(&field_id)
+                .copied()
+                .unwrap_or_default();
+            match self.write_bound_value(&bound_field, input_type, &value) {
+                Ok(()) => {
+                    self.text_input_errors.remove(&field_id);
+                    // 書き込み後の値（正規化済み）を記録し、入力中のテキストを上書きしないようにする
+                    if let Some(current) = self.get_bound_value(&bound_field) {
+                        self.text_input_synced_values
+                            .insert(field_id.clone(), current);
+                    }
+                }
+                Err(e) => {
+                    log::warn!(
+                        "TextInput '{}' の値を '{}' に反映できません: {}",
+                        field_id,
+                        bound_field,
+                        e
+                    );
+                    self.text_input_errors.insert(field_id.clone(), e);
+                }
+            }
+            self.needs_redraw = true;
+        }
+    }
+
+    /// ★ bind先の現在値を取得（state.xxx のフィールド名、コンポーネントローカル状態のパス、または flow.xxx）
+    pub fn get_bound_value(&self, field: &str) -> Option<String>
+    where
+        S: StateAccess,
+    {
+        if field.starts_with(COMPONENT_STATE_PREFIX) {
+            self.get_component_state(field)
+        } else if let Some(key) = field.strip_prefix("flow.") {
+            // 未設定は空（呼び出しごとに入力欄が空に戻る）
+            Some(self.sub_flows.var(key).cloned().unwrap_or_default())
+        } else {
+            self.get_state_field(field)
+        }
+    }
+
+    /// ★ stateのパス中の添字の式（`users[i]`, `map[state.key]`）を評価してリテラルにする
+    /// set / append / remove などの書き込み先を StateAccess に渡す前に使う
+    pub fn resolve_state_path(&self, path: &str) -> String
+    where
+        S: StateAccess + 'static,
+    {
+        state_path::resolve_indices(path, &|index| {
+            self.eval_expr_from_ast(&Expr::Path(index.to_string()))
+        })
+    }
+
+    /// ★ stateのフィールドの値を取得（`users[0].address.city` のような深いパスも読める）
+    pub fn get_state_field(&self, path: &str) -> Option<String>
+    where
+        S: StateAccess,
+    {
+        self.custom_state
+            .get_field(path)
+            .or_else(|| state_path::get_nested_field(&self.custom_state, path))
+    }
+
+    /// bind先へ入力値を書き込む（number型は数値として検証してから StateAccess::set に渡す）
+    fn write_bound_value(
+        &mut self,
+        field: &str,
+        input_type: TextInputType,
+        value: &str,
+    ) -> Result<(), String>
+    where
+        S: StateAccess,
+    {
+        let value = match input_type {
+            TextInputType::Text => value.to_string(),
+            TextInputType::Number => {
+                let trimmed = value.trim();
+                if trimmed.is_empty() {
+                    return Err("数値を入力してください".to_string());
+                }
+                if !trimmed.parse::<f64>().is_ok_and(f64::is_finite) {
+                    return Err(format!("'{}' は数値ではありません", trimmed));
+                }
+                trimmed.to_string()
+            }
+        };
+
+        if field.starts_with(COMPONENT_STATE_PREFIX) {
+            self.set_component_state(field, value)
+        } else if let Some(key) = field.strip_prefix("flow.") {
+            if self.sub_flows.set_var(key, value) {
+                Ok(())
+            } else {
+                Err("サブフローの外では flow.xxx に書き込めません".to_string())
+            }
+        } else {
+            self.custom_state.set(field, value)
         }
     }
 
+    /// ★ bind先の値が外部で変更されていれば入力欄に反映する
+    /// 入力による書き込みは記録済みの値と一致するため、入力途中のテキスト（"1." など）は保持される
+    pub fn sync_text_input_from_binding(&mut self, field_id: &str)
+    where
+        S: StateAccess,
+    {
+        let Some(field) = self.text_input_bindings.get(field_id) else {
+            return;
+        };
+        let Some(current) = self.get_bound_value(field) else {
+            return;
+        };
+        if self.text_input_synced_values.get(field_id) == Some(&current) {
+            return;
+        }
+
+        self.text_input_errors.remove(field_id);
+        self.text_cursor_positions
+            .insert(field_id.to_string(), current.chars().count());
+        self.text_input_values
+            .insert(field_id.to_string(), current.clone());
+        self.text_input_synced_values
+            .insert(field_id.to_string(), current);
+    }
+
     pub fn get_text_input_value(&self, field_id: &str) -> String {
         self.text_input_values
             .get(field_id)
@@ -500,6 +1288,11 @@ impl<S> AppState<S> {
 impl<S: StateAccess + 'static> AppState<S> {
     /// 値評価（軽量化版）
     pub fn eval_expr_from_ast(&self, e: &Expr) -> String {
+        self.eval_expr_at_depth(e, 0)
+    }
+
+    /// `depth` はファイルに定義した関数（fn）の呼び出しの深さ（再帰の上限の判定に使う）
+    fn eval_expr_at_depth(&self, e: &Expr, depth: usize) -> String {
         match e {
             Expr::String(s) => s.clone(),
             Expr::Number(n) => n.to_string(),
@@ -510,6 +1303,18 @@ impl<S: StateAccess + 'static> AppState<S> {
                     "false".into()
                 }
             }
+            Expr::Conditional {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                let condition = self.eval_expr_at_depth(condition, depth);
+                if matches!(condition.as_str(), "true" | "1" | "True" | "TRUE") {
+                    self.eval_expr_at_depth(then_expr, depth)
+                } else {
+                    self.eval_expr_at_depth(else_expr, depth)
+                }
+            }
             Expr::Ident(s) => {
                 // ★ 優先順位: 1. ローカル変数 → 2. foreach変数 → 3. コンポーネント引数 → 4. そのまま返す
 
@@ -526,143 +1331,15 @@ impl<S: StateAccess + 'static> AppState<S> {
                 // 3. 識別子をそのまま返す
                 s.clone()
             }
-            Expr::Path(s) => {
-                // ★ 修正: path専用の処理
-
-                // ★ ルートパラメータアクセス: route.params.xxx, route.current
-                if s.starts_with("route.") {
-                    let route_path = s.strip_prefix("route.").unwrap();
-                    if route_path == "current" {
-                        return self.current_timeline.clone();
-                    } else if route_path.starts_with("params.") {
-                        let param_name = route_path.strip_prefix("params.").unwrap();
-                        return self
-                            .route_params
-                            .get(param_name)
-                            .cloned()
-                            .unwrap_or_default();
-                    }
-                }
-
-                // ★ レスポンシブ対応: window.width と window.height の評価
-                if s == "window.width" {
-                    if let Some([w, _]) = self.cached_window_size {
-                        return w.to_string();
-                    }
-                    return "0".to_string();
-                }
-                if s == "window.height" {
-                    if let Some([_, h]) = self.cached_window_size {
-                        return h.to_string();
-                    }
-                    return "0".to_string();
-                }
-
-                // ★ .len()プロパティアクセスの処理
-                if s.ends_with(".len()") {
-                    let base_path = s.strip_suffix(".len()").unwrap();
-
-                    // state.items.len() の場合
-                    if base_path.starts_with("state.") {
-                        let field_name = base_path.strip_prefix("state.").unwrap();
-                        if let Some(v) = <S as crate::engine::state::StateAccess>::get_field(
-                            &self.custom_state,
-                            field_name,
-                        ) {
-                            // 配列の場合は要素数を返す
-                            if let Ok(arr) = serde_json::from_str::<Vec<serde_json::Value>>(&v) {
-                                return arr.len().to_string();
-                            }
-                        }
-                    } else {
-                        // 通常の変数の場合
-                        if let Some(v) = self.variables.get(base_path) {
-                            if let Ok(arr) = serde_json::from_str::<Vec<serde_json::Value>>(v) {
-                                return arr.len().to_string();
-                            }
-                        }
-                    }
-                    return "0".to_string();
-                }
-
-                // state.プレフィックスがある場合のみカスタム状態を参照
-                if s.starts_with("state.") {
-                    let field_name = s.strip_prefix("state.").unwrap();
-                    if let Some(v) = <S as crate::engine::state::StateAccess>::get_field(
-                        &self.custom_state,
-                        field_name,
-                    ) {
-                        return v;
-                    }
-                    return s.clone();
-                }
-
-                // ★ ローカル変数もチェック（pathの場合）
-                if let Some(v) = self.component_context.get_local_var(s) {
-                    return v.clone();
-                }
-
-                // foreach変数やコンポーネント引数もチェック
-                if let Some(v) = self.component_context.get_var(s) {
-                    return v.clone();
-                }
-
-                // ★ オブジェクトプロパティアクセス（例: user.name）の処理
-                if let Some(dot_pos) = s.find('.') {
-                    let obj_name = &s[..dot_pos];
-                    let property_path = &s[dot_pos + 1..];
-
-                    // ローカル変数からオブジェクトを取得
-                    if let Some(obj_value) = self.component_context.get_local_var(obj_name) {
-                        if let Ok(obj) = serde_json::from_str::<serde_json::Value>(&obj_value) {
-                            let mut current = &obj;
-                            for part in property_path.split('.') {
-                                if let Some(next) = current.get(part) {
-                                    current = next;
-                                } else {
-                                    return s.clone();
-                                }
-                            }
-                            return match current {
-                                serde_json::Value::String(s) => s.clone(),
-                                serde_json::Value::Number(n) => n.to_string(),
-                                serde_json::Value::Bool(b) => b.to_string(),
-                                serde_json::Value::Null => "null".to_string(),
-                                _ => current.to_string(),
-                            };
-                        }
-                    }
-
-                    // コンポーネント引数からオブジェクトを取得
-                    if let Some(obj_value) = self.component_context.get_var(obj_name) {
-                        if let Ok(obj) = serde_json::from_str::<serde_json::Value>(&obj_value) {
-                            let mut current = &obj;
-                            for part in property_path.split('.') {
-                                if let Some(next) = current.get(part) {
-                                    current = next;
-                                } else {
-                                    return s.clone();
-                                }
-                            }
-                            return match current {
-                                serde_json::Value::String(s) => s.clone(),
-                                serde_json::Value::Number(n) => n.to_string(),
-                                serde_json::Value::Bool(b) => b.to_string(),
-                                serde_json::Value::Null => "null".to_string(),
-                                _ => current.to_string(),
-                            };
-                        }
-                    }
-                }
-
-                // 識別子をそのまま返す
-                s.clone()
-            }
+            // ★ nullは "null" と表示する（`??` では値なしとして扱う）
+            Expr::Null | Expr::Path(_) => self
+                .eval_nullable_at_depth(e, depth)
+                .unwrap_or_else(|| "null".to_string()),
             Expr::Array(xs) => {
                 let vs: Vec<String> = xs
                     .iter()
                     .map(|x| {
-                        let val = self.eval_expr_from_ast(x);
+                        let val = self.eval_expr_at_depth(x, depth);
                         // 文字列の場合はクォートで囲む（JSON形式にする）
                         // 数値やDimensionの場合は、純粋な数値として扱う
                         match x {
@@ -703,45 +1380,66 @@ impl<S: StateAccess + 'static> AppState<S> {
             }
             Expr::CalcExpr(inner) => {
                 // CalcExprは内部の式を評価する
-                self.eval_expr_from_ast(inner)
+                self.eval_expr_at_depth(inner, depth)
             }
             Expr::Match {
                 expr,
                 arms,
                 default,
             } => {
-                let match_value = self.eval_expr_from_ast(expr);
+                let match_value = self.eval_expr_at_depth(expr, depth);
+                let eval = |e: &Expr| self.eval_expr_at_depth(e, depth);
 
                 for arm in arms {
-                    let pattern_value = self.eval_expr_from_ast(&arm.pattern);
-                    if pattern_value == match_value {
-                        return self.eval_expr_from_ast(&arm.value);
+                    if match_pattern::case_matches(&arm.case, &match_value, &eval) {
+                        return match &arm.case.binding {
+                            Some(name) => self.eval_expr_at_depth(
+                                &match_pattern::bind_value(&arm.value, name, &match_value),
+                                depth,
+                            ),
+                            None => self.eval_expr_at_depth(&arm.value, depth),
+                        };
                     }
                 }
 
                 if let Some(default_expr) = default {
-                    return self.eval_expr_from_ast(default_expr);
+                    return self.eval_expr_at_depth(default_expr, depth);
                 }
 
                 String::new()
             }
             Expr::FunctionCall { name, args } => {
                 // 関数呼び出しを実行
-                self.execute_function_call(name, args)
+                self.execute_function_call(name, args, depth)
             }
             Expr::BinaryOp { left, op, right } => {
-                let left_val = self.eval_expr_from_ast(left);
-                let right_val = self.eval_expr_from_ast(right);
+                let left_val = || self.eval_expr_at_depth(left, depth);
+                let right_val = || self.eval_expr_at_depth(right, depth);
 
                 match op {
+                    // ★ null合体: 左がnullのときだけ右を評価する
+                    crate::parser::ast::BinaryOperator::Coalesce => self
+                        .eval_nullable_at_depth(left, depth)
+                        .unwrap_or_else(right_val),
+
                     // 算術演算
                     crate::parser::ast::BinaryOperator::Add
                     | crate::parser::ast::BinaryOperator::Sub
                     | crate::parser::ast::BinaryOperator::Mul
                     | crate::parser::ast::BinaryOperator::Div => {
+                        let left_str = left_val();
+                        let right_str = right_val();
+                        // ★ `+` はどちらかが数値でなければ文字列の連結
+                        if matches!(op, crate::parser::ast::BinaryOperator::Add)
+                            && (left_str.parse::<f32>().is_err()
+                                || right_str.parse::<f32>().is_err())
+                        {
+                            return format!("{}{}", left_str, right_str);
+                        }
+
                         // 数値に変換して計算
-                        let left_num = left_val.parse::<f32>().unwrap_or(0.0);
-                        let right_num = right_val.parse::<f32>().unwrap_or(0.0);
+                        let left_num = left_str.parse::<f32>().unwrap_or(0.0);
+                        let right_num = right_str.parse::<f32>().unwrap_or(0.0);
 
                         let result = match op {
                             crate::parser::ast::BinaryOperator::Add => left_num + right_num,
@@ -767,6 +1465,7 @@ impl<S: StateAccess + 'static> AppState<S> {
                     | crate::parser::ast::BinaryOperator::Le
                     | crate::parser::ast::BinaryOperator::Gt
                     | crate::parser::ast::BinaryOperator::Ge => {
+                        let (left_val, right_val) = (left_val(), right_val());
                         // 数値として比較を試行し、失敗したら文字列として比較
                         let result = if let (Ok(left_num), Ok(right_num)) =
                             (left_val.parse::<f32>(), right_val.parse::<f32>())
@@ -805,18 +1504,203 @@ impl<S: StateAccess + 'static> AppState<S> {
         }
     }
 
-    fn execute_function_call(&self, name: &str, args: &[Expr]) -> String {
-        // 引数を評価
-        let _arg_values: Vec<String> = args
-            .iter()
-            .map(|arg| self.eval_expr_from_ast(arg))
-            .collect();
+    /// ★ null を None として評価する（`??` の左辺と `?.` で値のない場合を区別するため）
+    fn eval_nullable_at_depth(&self, e: &Expr, depth: usize) -> Option<String> {
+        match e {
+            Expr::Null => None,
+            Expr::Path(s) => match self.eval_path_at_depth(s, depth) {
+                PathValue::Value(v) => Some(v),
+                PathValue::Null => None,
+                PathValue::Unresolved => Some(s.clone()),
+            },
+            Expr::BinaryOp {
+                left,
+                op: crate::parser::ast::BinaryOperator::Coalesce,
+                right,
+            } => self
+                .eval_nullable_at_depth(left, depth)
+                .or_else(|| self.eval_nullable_at_depth(right, depth)),
+            _ => Some(self.eval_expr_at_depth(e, depth)),
+        }
+    }
+
+    /// ★ パス（state.xxx, route.xxx, item.name など）を評価する
+    fn eval_path_at_depth(&self, s: &str, depth: usize) -> PathValue {
+        // ★ オプショナルチェーン（user?.profile?.name）: 途中で解決できなければnull
+        if s.contains("?.") {
+            return match self.eval_path_at_depth(&s.replace("?.", "."), depth) {
+                PathValue::Unresolved => PathValue::Null,
+                value => value,
+            };
+        }
+
+        // ★ 添字の式（users[i], map[state.key]）を先に評価してリテラルにする
+        if s.contains('[') {
+            let resolved = state_path::resolve_indices(s, &|index| {
+                self.eval_expr_at_depth(&Expr::Path(index.to_string()), depth)
+            });
+            if resolved != s {
+                return self.eval_path_at_depth(&resolved, depth);
+            }
+        }
+
+        // ★ ルートパラメータアクセス: route.params.xxx, route.query.xxx, route.current, route.url
+        if s.starts_with("route.") {
+            let route_path = s.strip_prefix("route.").unwrap();
+            if route_path == "current" {
+                return PathValue::Value(self.current_timeline.clone());
+            } else if route_path == "url" {
+                return PathValue::Value(
+                    self.router
+                        .as_ref()
+                        .and_then(|r| r.get_current_route())
+                        .unwrap_or_default()
+                        .to_string(),
+                );
+            } else if route_path.starts_with("params.") {
+                let param_name = route_path.strip_prefix("params.").unwrap();
+                return PathValue::Value(
+                    self.route_params
+                        .get(param_name)
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+            } else if let Some(key) = route_path.strip_prefix("query.") {
+                return PathValue::Value(self.route_query.get(key).cloned().unwrap_or_default());
+            }
+        }
+
+        // ★ サブフロー内の変数: flow.xxx（サブフローの外では空）
+        if let Some(key) = s.strip_prefix("flow.") {
+            return PathValue::Value(self.sub_flows.var(key).cloned().unwrap_or_default());
+        }
+
+        // ★ リソース: user.loading, user.error, user.value
+        if let Some(v) = self.resources.get(s) {
+            return PathValue::Value(v);
+        }
+
+        // ★ レスポンシブ対応: window.width と window.height の評価
+        if s == "window.width" {
+            if let Some([w, _]) = self.cached_window_size {
+                return PathValue::Value(w.to_string());
+            }
+            return PathValue::Value("0".to_string());
+        }
+        if s == "window.height" {
+            if let Some([_, h]) = self.cached_window_size {
+                return PathValue::Value(h.to_string());
+            }
+            return PathValue::Value("0".to_string());
+        }
+
+        // ★ .len()プロパティアクセスの処理
+        if s.ends_with(".len()") {
+            let base_path = s.strip_suffix(".len()").unwrap();
+
+            // state.items.len() の場合
+            if base_path.starts_with("state.") {
+                let field_name = base_path.strip_prefix("state.").unwrap();
+                if let Some(v) = self.get_state_field(field_name) {
+                    // 配列の場合は要素数を返す
+                    if let Ok(arr) = serde_json::from_str::<Vec<serde_json::Value>>(&v) {
+                        return PathValue::Value(arr.len().to_string());
+                    }
+                }
+            } else {
+                // 通常の変数の場合
+                if let Some(v) = self.variables.get(base_path)
+                    && let Ok(arr) = serde_json::from_str::<Vec<serde_json::Value>>(v)
+                {
+                    return PathValue::Value(arr.len().to_string());
+                }
+            }
+            return PathValue::Value("0".to_string());
+        }
+
+        // ★ コンポーネントローカル状態（展開時に書き換えられたパス）
+        if s.starts_with(COMPONENT_STATE_PREFIX) {
+            return PathValue::Value(self.get_component_state(s).unwrap_or_default());
+        }
+
+        // state.プレフィックスがある場合のみカスタム状態を参照
+        if s.starts_with("state.") {
+            let field_name = s.strip_prefix("state.").unwrap();
+            // JSONのnullは文字列の"null"と区別する
+            match state_path::get_nested_value(&self.custom_state, field_name) {
+                Some(serde_json::Value::Null) => return PathValue::Null,
+                Some(v) => return PathValue::Value(state_path::value_to_string(&v)),
+                None => {}
+            }
+            if let Some(v) = self.get_state_field(field_name) {
+                return PathValue::Value(v);
+            }
+            // ★ 存在しないフィールドはnull（パス文字列を画面に出さない）
+            log::debug!("Unresolved state path: {}", s);
+            return PathValue::Null;
+        }
+
+        // ★ ローカル変数もチェック（pathの場合）
+        if let Some(v) = self.component_context.get_local_var(s) {
+            return PathValue::Value(v.clone());
+        }
+
+        // foreach変数やコンポーネント引数もチェック
+        if let Some(v) = self.component_context.get_var(s) {
+            return PathValue::Value(v.clone());
+        }
+
+        // ★ オブジェクトのプロパティ・要素アクセス（例: user.name, item.tags[0]）の処理
+        let (obj_name, property_path) = state_path::split_root(s);
+        if !property_path.is_empty() {
+            // ローカル変数 → コンポーネント引数の順にオブジェクトを取得
+            let obj_value = self
+                .component_context
+                .get_local_var(obj_name)
+                .cloned()
+                .or_else(|| self.component_context.get_var(obj_name).cloned());
+            if let Some(obj) = obj_value {
+                // 途中で辿れないパスもnull
+                return match state_path::read_nested_value(&obj, property_path) {
+                    Some(serde_json::Value::Null) | None => PathValue::Null,
+                    Some(v) => PathValue::Value(state_path::value_to_string(&v)),
+                };
+            }
+        }
+
+        // 識別子をそのまま返す
+        PathValue::Unresolved
+    }
+
+    fn execute_function_call(&self, name: &str, args: &[Expr], depth: usize) -> String {
+        // 引数を評価（Rust関数は resolve_call_arg が要素ごとに一度だけ評価する）
+        let eval_args = || -> Vec<String> {
+            args.iter()
+                .map(|arg| self.eval_expr_at_depth(arg, depth))
+                .collect()
+        };
+
+        // ★ ファイルに定義した関数（fn）はRustを経由せずにその場で評価する
+        if let Some(def) = self.functions.get(name) {
+            return dsl_function::call_function(def, &eval_args(), depth, &|e: &Expr, depth| {
+                self.eval_expr_at_depth(e, depth)
+            });
+        }
 
         use crate::engine::rust_call::{execute_rust_call, has_rust_call};
 
         if has_rust_call(name) {
-            execute_rust_call(name, args);
-            return format!("{}_executed", name);
+            // ★ 引数は評価済みの値で渡し、関数の戻り値を式の結果にする
+            let args: Vec<Expr> = args
+                .iter()
+                .map(|arg| self.resolve_call_arg(arg, depth))
+                .collect();
+            return execute_rust_call(name, &args).unwrap_or_default();
+        }
+
+        // ★ 組み込み関数（upper, sum, format_number など）
+        if let Some(result) = crate::engine::builtins::call_builtin(name, &eval_args()) {
+            return result;
         }
 
         // フォールバック：内部関数
@@ -836,6 +1720,28 @@ impl<S: StateAccess + 'static> AppState<S> {
         }
     }
 
+    /// Rust関数に渡す引数を評価済みのリテラルにする
+    /// リテラルはそのまま、オブジェクト・配列は要素ごとに評価する
+    /// それ以外は評価結果を渡し、数値・真偽値への変換は登録された関数の引数の型に任せる
+    fn resolve_call_arg(&self, arg: &Expr, depth: usize) -> Expr {
+        match arg {
+            Expr::String(_) | Expr::Number(_) | Expr::Bool(_) => arg.clone(),
+            Expr::Object(fields) => Expr::Object(
+                fields
+                    .iter()
+                    .map(|(key, field)| (key.clone(), self.resolve_call_arg(field, depth)))
+                    .collect(),
+            ),
+            Expr::Array(items) => Expr::Array(
+                items
+                    .iter()
+                    .map(|item| self.resolve_call_arg(item, depth))
+                    .collect(),
+            ),
+            _ => value_to_literal_expr(arg, self.eval_expr_at_depth(arg, depth)),
+        }
+    }
+
     /// onclick属性で使用される関数呼び出しを実行（stateアクセス可能）
     #[allow(deprecated)]
     pub fn execute_onclick_function_call(&mut self, name: &str, args: &[Expr]) -> bool {
@@ -1576,6 +2482,32 @@ where
     });
 }
 
+/// ★ 状態を書き換えた後、値が変わったフィールドのウォッチャーを呼ぶ（どのフィールドを変えるか分からない更新用）
+pub fn update_with_watchers<S, F>(state: &mut S, update: F)
+where
+    S: StateAccess + 'static,
+    F: FnOnce(&mut S),
+{
+    let fields: Vec<String> = STATE_WATCHERS
+        .get()
+        .and_then(|registry| {
+            let guard = registry.read().expect("STATE_WATCHERS read lock poisoned");
+            guard
+                .get(&TypeId::of::<S>())
+                .map(|map| map.keys().cloned().collect())
+        })
+        .unwrap_or_default();
+    let before: Vec<Option<String>> = fields.iter().map(|field| state.get_field(field)).collect();
+
+    update(state);
+
+    for (field, before) in fields.iter().zip(before) {
+        if state.get_field(field) != before {
+            notify_state_watchers(state, field);
+        }
+    }
+}
+
 /// linkmeで収集されたウォッチャー初期化関数を実行する。
 pub fn initialize_state_watchers() {
     #[cfg(not(target_arch = "wasm32"))]
@@ -1652,6 +2584,14 @@ impl<'a, S: StateAccess> CustomStateContext<'a, S> {
     pub fn list_clear(&mut self, path: &str) -> Result<(), String> {
         self.state.list_clear(path)
     }
+
+    /// 非同期処理の完了後に状態を更新するためのハンドル（Futureや別スレッドに渡せる）
+    pub fn handle(&self) -> AppHandle<S>
+    where
+        S: 'static,
+    {
+        AppHandle::new()
+    }
 }
 
 // 公開ヘルパー: AppState から安全に CustomStateContext を作り、コールバックに渡す
@@ -1719,6 +2659,8 @@ fn adjust_stencil_depth_dynamic(stencil: &mut Stencil, depth_counter: &mut f32)
                 adjust_stencil_depth_dynamic(child, depth_counter);
             }
         }
+        // オーバーレイ層は下層とは独立した深度を持つ
+        Stencil::Overlay { .. } => {}
     }
 }
 
@@ -1727,43 +2669,203 @@ impl<S: StateAccess + 'static> AppState<S> {
     /// ルーターを初期化
     pub fn initialize_router(&mut self, flow: &crate::parser::ast::Flow) {
         self.router = Some(crate::engine::routing::Router::new(flow));
+        self.set_flow(flow);
     }
 
     /// Appからルーターを初期化し、必要ならURLから初期タイムラインを設定
     pub fn initialize_router_from_app(&mut self, app: &crate::parser::ast::App) -> Option<String> {
         let router = crate::engine::routing::Router::from_app(app);
+        self.set_flow(&app.flow);
 
-        // 現在のURLから初期タイムラインを取得
-        let initial_timeline = router.get_timeline_from_current_url();
+        // 現在のURLから初期タイムラインとパラメータを取得
+        let initial = router.resolve_current_url();
 
         self.router = Some(router);
-        initial_timeline
+        initial.map(|route| {
+            let timeline = route.timeline.clone();
+            if let Some(router) = &mut self.router {
+                router.replace_current(route.clone());
+            }
+            self.route_params = route.params;
+            self.route_query = route.query;
+            timeline
+        })
+    }
+
+    /// パラメータ付きでタイムラインに遷移（遷移しなかった場合はfalse）
+    ///
+    /// フローの遷移定義（条件付き遷移を含む）とルートガードで遷移先を決める
+    pub fn navigate_with_params(
+        &mut self,
+        timeline: &str,
+        params: HashMap<String, String>,
+    ) -> bool {
+        let target = match &self.flow {
+            Some(flow) => flow_guard::resolve_transition(
+                flow,
+                &self.current_timeline,
+                timeline,
+                self.flow_mode,
+                &|expr: &Expr| self.eval_expr_from_ast(expr),
+            ),
+            None => Some(timeline.to_string()),
+        };
+        let Some(target) = target else {
+            return false;
+        };
+        // `else` 側へ遷移する場合、元の遷移先向けのパラメータは渡さない
+        let params = if target == timeline {
+            params
+        } else {
+            HashMap::new()
+        };
+        self.navigate_to_route(crate::engine::routing::RouteMatch {
+            timeline: target,
+            params,
+            query: HashMap::new(),
+            url: None,
+        })
+    }
+
+    /// URL（`/users/42?tab=posts`）に対応するタイムラインへ遷移
+    /// どのルートにも一致せず `not_found:` もなければ何もしない
+    /// URLからの遷移はフローの遷移定義では制限しない（ルートガードは通す）
+    pub fn navigate_to_url(&mut self, url: &str) -> bool {
+        let Some(route) = self.router.as_ref().and_then(|r| r.resolve(url)) else {
+            log::warn!("URL '{}' に対応するタイムラインがありません", url);
+            return false;
+        };
+        self.navigate_to_route(route)
+    }
+
+    fn navigate_to_route(&mut self, route: crate::engine::routing::RouteMatch) -> bool {
+        let state: &dyn Any = &self.custom_state;
+        let Some(route) = flow_guard::check_route_guards(state, &self.current_timeline, route)
+        else {
+            return false;
+        };
+        // URLを組み立てられない遷移（必須パラメータの不足・型違い）は行わない
+        if let Some(router) = &mut self.router
+            && let Err(e) = router.navigate(route.clone())
+        {
+            log::error!("'{}' のURLを組み立てられません: {}", route.timeline, e);
+            return false;
+        }
+        self.show_route(route);
+        true
+    }
+
+    /// 履歴を戻る（戻れなければfalse）
+    /// 履歴の移動は一度許可された画面に戻るだけなので、フローの検証とルートガードは通さない
+    pub fn navigate_back(&mut self) -> bool {
+        match self.router.as_mut().and_then(|r| r.back()) {
+            Some(route) => {
+                self.show_route(route);
+                true
+            }
+            None => false,
+        }
     }
 
-    /// パラメータ付きでタイムラインに遷移
-    pub fn navigate_with_params(&mut self, timeline: &str, params: HashMap<String, String>) {
-        if let Some(router) = &mut self.router {
-            if let Ok(()) = router.navigate_to_timeline(timeline, params.clone()) {
-                self.route_params = params;
+    /// 履歴を進む（進めなければfalse）
+    pub fn navigate_forward(&mut self) -> bool {
+        match self.router.as_mut().and_then(|r| r.forward()) {
+            Some(route) => {
+                self.show_route(route);
+                true
             }
+            None => false,
+        }
+    }
+
+    /// ルートのパラメータを反映してタイムラインを切り替える（履歴は変えない）
+    fn show_route(&mut self, route: crate::engine::routing::RouteMatch) {
+        if let Some(flow) = &self.flow {
+            self.sub_flows.abandon_outside(flow, &route.timeline);
+        }
+        self.route_params = route.params;
+        self.route_query = route.query;
+        self.jump_to_timeline(&route.timeline);
+    }
+
+    /// サブフローを呼び出す（呼び出せなければfalse）
+    ///
+    /// 現在のタイムラインとパラメータを戻り先として積み、サブフローの開始タイムラインへ遷移する。
+    /// `vars` はサブフロー内で `flow.xxx` として参照できる
+    pub fn call_flow(
+        &mut self,
+        name: &str,
+        into: Option<String>,
+        vars: HashMap<String, String>,
+    ) -> bool {
+        let start = self
+            .flow
+            .as_ref()
+            .and_then(|flow| flow.sub_flows.iter().find(|sub| sub.name == name))
+            .map(|sub| sub.start.clone());
+        let Some(start) = start else {
+            log::error!("サブフロー '{}' が定義されていません", name);
+            return false;
+        };
+        let frame = SubFlowFrame {
+            flow: name.to_string(),
+            caller: crate::engine::routing::RouteMatch {
+                timeline: self.current_timeline.clone(),
+                params: self.route_params.clone(),
+                query: self.route_query.clone(),
+                url: None,
+            },
+            into,
+            vars,
+        };
+        if !self.sub_flows.push(frame) {
+            return false;
+        }
+        let entered = self.navigate_to_route(crate::engine::routing::RouteMatch {
+            timeline: start,
+            params: HashMap::new(),
+            query: HashMap::new(),
+            url: None,
+        });
+        if !entered {
+            // ルートガードで拒否された
+            self.sub_flows.pop();
         }
+        entered
+    }
 
-        self.jump_to_timeline(timeline);
+    /// 実行中のサブフローを終えて呼び出し元へ戻る（サブフローの外ではNone）
+    ///
+    /// 戻り値の `into` への代入は呼び出し側で行う（呼び出し元のスコープで代入するため）
+    pub fn finish_flow(&mut self) -> Option<SubFlowFrame> {
+        let Some(frame) = self.sub_flows.pop() else {
+            log::warn!("finish() がサブフローの外で呼ばれました");
+            return None;
+        };
+        if let Some(router) = &mut self.router
+            && let Err(e) = router.navigate(frame.caller.clone())
+        {
+            log::warn!(
+                "'{}' のURLを組み立てられません: {}",
+                frame.caller.timeline,
+                e
+            );
+        }
+        self.show_route(frame.caller.clone());
+        Some(frame)
     }
 
     /// 現在のルート情報を取得
     pub fn get_route_info(&self) -> RouteInfo {
         RouteInfo {
             current_timeline: self.current_timeline.clone(),
-            current_url: self.router.as_ref().and_then(|r| match r {
-                #[cfg(target_arch = "wasm32")]
-                crate::engine::routing::Router::Wasm(wr) => {
-                    wr.get_current_route().map(|s| s.to_string())
-                }
-                #[cfg(not(target_arch = "wasm32"))]
-                crate::engine::routing::Router::Native(_) => None,
-            }),
+            current_url: self
+                .router
+                .as_ref()
+                .and_then(|r| r.get_current_route())
+                .map(|s| s.to_string()),
             params: self.route_params.clone(),
+            query: self.route_query.clone(),
         }
     }
 }
@@ -1773,7 +2875,170 @@ pub struct RouteInfo {
     pub current_timeline: String,
     pub current_url: Option<String>,
     pub params: HashMap<String, String>,
+    pub query: HashMap<String, String>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::AppState;
+    use crate::parser::ast::Expr;
+    use crate::parser::parse::parse_nilo;
+
+    #[derive(Default, serde::Serialize, serde::Deserialize)]
+    struct User {
+        name: String,
+        nick: Option<String>,
+    }
+
+    #[derive(Default, nilo_state_access_derive::StateAccess)]
+    struct Profile {
+        user: User,
+        title: Option<String>,
+    }
+
+    #[test]
+    fn test_null_is_distinct_from_empty_string() {
+        let app = parse_nilo(
+            r#"
+            flow { start: Main }
+            fn name_or() = state.user.name ?? "anon"
+            fn nick_or() = state.user.nick ?? "anon"
+            fn nick() = state.user.nick
+            fn title_or() = state.title ?? "untitled"
+            fn missing_or() = state.missing ?? "none"
+            fn city_or() = state.user?.address?.city ?? "none"
+            fn chain() = null ?? state.user.nick ?? "last"
+            timeline Main {}
+            "#,
+        )
+        .unwrap();
+        let mut state = AppState::new(Profile::default(), "Main".into());
+        state.set_functions(&app.functions);
+        let call = |name: &str| {
+            state.eval_expr_from_ast(&Expr::FunctionCall {
+                name: name.into(),
+                args: vec![],
+            })
+        };
 
+        // 空文字列は値なので置き換えない
+        assert_eq!(call("name_or"), "");
+        assert_eq!(call("nick_or"), "anon");
+        // 文字列の "null" は null ではない
+        assert_eq!(call("title_or"), "null");
+        assert_eq!(call("missing_or"), "none");
+        assert_eq!(call("city_or"), "none");
+        assert_eq!(call("chain"), "last");
+        // 表示するときは "null"
+        assert_eq!(call("nick"), "null");
+        assert_eq!(state.eval_expr_from_ast(&Expr::Null), "null");
+    }
+
+    #[test]
+    fn test_navigation_aborts_when_url_cannot_be_built() {
+        let app = parse_nilo(
+            r#"
+            flow {
+                start: List
+                List -> Detail("/items/:id<number>")
+            }
+            timeline List {}
+            timeline Detail {}
+            "#,
+        )
+        .unwrap();
+        let mut state = AppState::new(Profile::default(), "List".into());
+        state.set_flow(&app.flow);
+        state.initialize_router_from_app(&app);
+
+        let bad = [("id".to_string(), "abc".to_string())].into();
+        assert!(!state.navigate_with_params("Detail", bad));
+        assert_eq!(state.current_timeline, "List");
+        assert!(!state.navigate_back());
+
+        let good = [("id".to_string(), "42".to_string())].into();
+        assert!(state.navigate_with_params("Detail", good));
+        assert_eq!(state.current_timeline, "Detail");
+        assert_eq!(
+            state.get_route_info().current_url.as_deref(),
+            Some("/items/42")
+        );
+    }
 
+    #[test]
+    fn test_leaving_timeline_stops_its_timers() {
+        let app = parse_nilo(
+            r#"
+            flow {
+                start: Clock
+                Clock -> Settings
+            }
+            timeline Clock {
+                every 1s { log("tick") }
+            }
+            timeline Settings {}
+            "#,
+        )
+        .unwrap();
+        let mut state = AppState::new(Profile::default(), "Clock".into());
+        state.timeline_timers.sync(&app.timelines[0]);
+        assert!(state.timeline_timers.is_running());
+
+        state.jump_to_timeline("Settings");
+        assert!(!state.timeline_timers.is_running());
+        assert_eq!(state.timeline_timers.fire("Clock#0@1"), None);
+    }
+
+    const RELOAD_OLD: &str = r#"
+        flow { start: Home }
+        timeline Home {
+            Counter()
+            TextInput(id: name)
+        }
+        timeline Other { Text("a") }
+        component Counter() { Text("c") }
+        "#;
+
+    const RELOAD_NEW: &str = r#"
+        flow { start: Home }
+        timeline Home {
+            Text("new line")
+            Counter()
+            TextInput(id: name)
+        }
+        component Counter() { Text("c") }
+        "#;
+
+    #[test]
+    fn test_hot_reload_keeps_timeline_and_local_state() {
+        let old = parse_nilo(RELOAD_OLD).unwrap();
+        let new = parse_nilo(RELOAD_NEW).unwrap();
+        let mut state = AppState::new(Profile::default(), "Home".into());
+        state.reload_error = Some("syntax error".into());
+        state.text_input_values.insert("name".into(), "abc".into());
+        state.focused_text_input = Some("name".into());
+
+        let diff = crate::engine::app_diff::AppDiff::between(&old, &new);
+        let (from, to) = diff.moved_instances[0].clone();
+        let count = [("count".to_string(), "3".to_string())].into();
+        state.component_states.insert(from.clone(), count);
+
+        state.apply_hot_reload(&old, &new);
+        assert_eq!(state.current_timeline, "Home");
+        assert_eq!(state.reload_error, None);
+        assert_eq!(state.text_input_values["name"], "abc");
+        assert_eq!(state.focused_text_input.as_deref(), Some("name"));
+        // 行がずれたコンポーネント呼び出しのローカル状態は新しい位置へ付け替える
+        assert!(!state.component_states.contains_key(&from));
+        assert_eq!(state.component_states[&to]["count"], "3");
+    }
+
+    #[test]
+    fn test_hot_reload_leaves_removed_timeline() {
+        let old = parse_nilo(RELOAD_OLD).unwrap();
+        let new = parse_nilo(RELOAD_NEW).unwrap();
+        let mut state = AppState::new(Profile::default(), "Other".into());
+        state.apply_hot_reload(&old, &new);
+        assert_eq!(state.current_timeline, "Home");
+    }
+}
@@ -0,0 +1,585 @@
+//! stateのパス（`users[0].address.city`, `map["key"]`, `tags[i]`）の解析と読み書き
+//!
+//! 評価器はフィールドのJSON表現をたどって値を読み出し、
+//! `StateAccess` のderiveはリスト・マップ型のフィールドの要素や、
+//! 構造体などその他の型のフィールドをこのモジュール経由で読み書きする。
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// パスの1要素
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathSegment {
+    /// `.name`
+    Field(String),
+    /// `[0]`, `["key"]`, `[i]`（括弧内の文字列そのまま）
+    Index(String),
+}
+
+/// パスを要素に分解（`users[0].name` → Field("users"), Index("0"), Field("name")）
+pub fn parse_path(path: &str) -> Vec<PathSegment> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix('[') {
+            let end = closing_bracket(inner).unwrap_or(inner.len());
+            segments.push(PathSegment::Index(inner[..end].to_string()));
+            rest = inner.get(end + 1..).unwrap_or("");
+        } else {
+            let rest_field = rest.strip_prefix('.').unwrap_or(rest);
+            let end = rest_field.find(['.', '[']).unwrap_or(rest_field.len());
+            segments.push(PathSegment::Field(rest_field[..end].to_string()));
+            rest = &rest_field[end..];
+        }
+    }
+    segments
+}
+
+/// `[` の直後から対応する `]` の位置（文字列リテラル内の `]` は無視）
+fn closing_bracket(s: &str) -> Option<usize> {
+    let mut in_string = false;
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string && depth == 0 => return Some(i),
+            ']' if !in_string => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 要素の列をパス文字列に戻す
+pub fn join_path(segments: &[PathSegment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            PathSegment::Field(name) if path.is_empty() => path.push_str(name),
+            PathSegment::Field(name) => {
+                path.push('.');
+                path.push_str(name);
+            }
+            PathSegment::Index(index) => {
+                path.push('[');
+                path.push_str(index);
+                path.push(']');
+            }
+        }
+    }
+    path
+}
+
+/// 添字がリテラル（数値か文字列）かどうか
+pub fn is_literal_index(index: &str) -> bool {
+    index.parse::<usize>().is_ok()
+        || (index.len() >= 2 && index.starts_with('"') && index.ends_with('"'))
+}
+
+/// リテラルでない添字（`[i]`, `[state.selected]`）を評価してリテラルに置き換える
+/// 数値はそのまま、それ以外は文字列キー（`["..."]`）になる
+pub fn resolve_indices(path: &str, eval: &dyn Fn(&str) -> String) -> String {
+    if !path.contains('[') {
+        return path.to_string();
+    }
+    let segments: Vec<PathSegment> = parse_path(path)
+        .into_iter()
+        .map(|segment| match segment {
+            PathSegment::Index(index) if !is_literal_index(&index) => {
+                let value = eval(&resolve_indices(&index, eval));
+                if value.parse::<usize>().is_ok() {
+                    PathSegment::Index(value)
+                } else {
+                    PathSegment::Index(format!("\"{}\"", value))
+                }
+            }
+            other => other,
+        })
+        .collect();
+    join_path(&segments)
+}
+
+/// パスの先頭のフィールド名と残り（`users[0].name` → ("users", "[0].name")）
+/// オプショナルチェーン（`user?.name`）の `?` は残りの側に含まれる
+pub fn split_root(path: &str) -> (&str, &str) {
+    let end = path.find(['.', '[', '?']).unwrap_or(path.len());
+    (&path[..end], &path[end..])
+}
+
+/// JSON値をパスに沿ってたどる（配列は番号、オブジェクトはキーで参照）
+pub fn lookup<'v>(value: &'v Value, segments: &[PathSegment]) -> Option<&'v Value> {
+    segments
+        .iter()
+        .try_fold(value, |current, segment| match segment {
+            PathSegment::Field(name) => current.get(name),
+            PathSegment::Index(index) => match current {
+                Value::Array(items) => items.get(index.parse::<usize>().ok()?),
+                Value::Object(map) => map.get(index.trim_matches('"')),
+                _ => None,
+            },
+        })
+}
+
+/// 評価結果の文字列表現（文字列はクォートなし、nullは"null"、それ以外はJSON）
+pub fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// JSON文字列として読めるフィールドの値を、残りのパスに沿って読み出す
+pub fn read_nested(field_value: &str, rest: &str) -> Option<String> {
+    let root = serde_json::from_str::<Value>(field_value).ok()?;
+    lookup(&root, &parse_path(rest)).map(value_to_string)
+}
+
+/// ★ `read_nested` のJSON値版（nullを文字列の"null"と区別する）
+pub fn read_nested_value(field_value: &str, rest: &str) -> Option<Value> {
+    let root = serde_json::from_str::<Value>(field_value).ok()?;
+    lookup(&root, &parse_path(rest)).cloned()
+}
+
+/// `get_field` で直接読めない深いパスを、先頭のフィールドのJSON表現から読み出す
+pub fn get_nested_field<S>(state: &S, path: &str) -> Option<String>
+where
+    S: crate::engine::state::StateAccess + ?Sized,
+{
+    let (root, rest) = split_root(path);
+    if rest.is_empty() {
+        return None;
+    }
+    read_nested(&state.get_field(root)?, rest)
+}
+
+/// ★ `get_nested_field` のJSON値版
+pub fn get_nested_value<S>(state: &S, path: &str) -> Option<Value>
+where
+    S: crate::engine::state::StateAccess + ?Sized,
+{
+    let (root, rest) = split_root(path);
+    if rest.is_empty() {
+        return None;
+    }
+    read_nested_value(&state.get_field(root)?, rest)
+}
+
+/// `path` が `field` の要素を指すか（`users[0]` や `users.0` は `users` の要素）
+pub fn is_nested_path_of(path: &str, field: &str) -> bool {
+    path.strip_prefix(field)
+        .is_some_and(|rest| rest.starts_with('[') || rest.starts_with('.'))
+}
+
+/// 深いパスの先にある値への操作
+#[derive(Debug, Clone)]
+pub enum PathOp {
+    Set(String),
+    Toggle,
+    Append(String),
+    Insert(usize, String),
+    Remove(String),
+    Clear,
+}
+
+/// 入力文字列を、書き換え先の既存の値と同じ種類のJSON値にする
+fn coerce_like(existing: Option<&Value>, input: String) -> Result<Value, String> {
+    match existing {
+        Some(Value::String(_)) => Ok(Value::String(input)),
+        Some(Value::Bool(_)) => Ok(Value::Bool(matches!(
+            input.as_str(),
+            "true" | "1" | "True" | "TRUE"
+        ))),
+        Some(Value::Number(_)) => serde_json::from_str::<serde_json::Number>(&input)
+            .map(Value::Number)
+            .map_err(|e| format!("parse {}: {}", input, e)),
+        _ => Ok(serde_json::from_str(&input).unwrap_or(Value::String(input))),
+    }
+}
+
+/// JSON値の中のパスの先に操作を適用
+fn apply_op(target: &mut Value, segments: &[PathSegment], op: PathOp) -> Result<(), String> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return apply_op_here(target, op);
+    };
+    let child = match (segment, &mut *target) {
+        (PathSegment::Field(name), Value::Object(map)) => map.get_mut(name),
+        (PathSegment::Index(index), Value::Array(items)) => {
+            let i = index
+                .parse::<usize>()
+                .map_err(|_| format!("invalid index: {}", index))?;
+            items.get_mut(i)
+        }
+        (PathSegment::Index(key), Value::Object(map)) => {
+            let key = key.trim_matches('"');
+            // マップへの代入は新しいキーを追加できる
+            if rest.is_empty()
+                && !map.contains_key(key)
+                && let PathOp::Set(value) = op
+            {
+                map.insert(key.to_string(), coerce_like(None, value)?);
+                return Ok(());
+            }
+            map.get_mut(key)
+        }
+        _ => None,
+    };
+    let child = child.ok_or_else(|| format!("path not found: {}", join_path(segments)))?;
+    apply_op(child, rest, op)
+}
+
+fn apply_op_here(target: &mut Value, op: PathOp) -> Result<(), String> {
+    match op {
+        PathOp::Set(value) => *target = coerce_like(Some(target), value)?,
+        PathOp::Toggle => match target {
+            Value::Bool(b) => *b = !*b,
+            _ => return Err("not a bool".to_string()),
+        },
+        PathOp::Clear => as_list(target)?.clear(),
+        PathOp::Append(value) => {
+            let items = as_list(target)?;
+            let value = coerce_like(items.first(), value)?;
+            items.push(value);
+        }
+        PathOp::Insert(index, value) => {
+            let items = as_list(target)?;
+            if index > items.len() {
+                return Err("Index out of bounds".to_string());
+            }
+            let value = coerce_like(items.first(), value)?;
+            items.insert(index, value);
+        }
+        PathOp::Remove(value) => {
+            let items = as_list(target)?;
+            let value = coerce_like(items.first(), value)?;
+            let pos = items
+                .iter()
+                .position(|item| *item == value)
+                .ok_or_else(|| format!("Item {} not found", value))?;
+            items.remove(pos);
+        }
+    }
+    Ok(())
+}
+
+fn as_list(target: &mut Value) -> Result<&mut Vec<Value>, String> {
+    match target {
+        Value::Array(items) => Ok(items),
+        _ => Err("not a list".to_string()),
+    }
+}
+
+/// JSON値を要素の型に戻す（文字列はそのまま、それ以外はJSON表現を `FromStr` で読む）
+fn parse_item<T>(value: Value) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value_to_string(&value)
+        .parse::<T>()
+        .map_err(|e| format!("parse item: {}", e))
+}
+
+/// ★ JSON配列の文字列をリストにする（要素は `list_append` と同じく `FromStr` で読む）
+///
+/// 読めない要素があればエラーにし、リスト全体を1回で置き換えられるようにする
+pub fn parse_list<T>(input: &str) -> Result<Vec<T>, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let items: Vec<Value> =
+        serde_json::from_str(input).map_err(|e| format!("not a list: {}", e))?;
+    items.into_iter().map(parse_item).collect()
+}
+
+/// 入力文字列をデシリアライズ（JSONとして読めなければ文字列として扱う）
+fn deserialize_input<T>(input: String) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_str::<Value>(&input)
+        .ok()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .map_or_else(
+            || serde_json::from_value(Value::String(input)).map_err(|e| e.to_string()),
+            Ok,
+        )
+}
+
+/// 要素1つに操作を適用
+/// パスが要素自体を指す代入は `from_input`、それ以外はJSON上で書き換えて `from_value` で戻す
+fn update_item<T>(
+    item: &mut T,
+    rest: &[PathSegment],
+    op: PathOp,
+    from_input: impl Fn(String) -> Result<T, String>,
+    from_value: impl Fn(Value) -> Result<T, String>,
+) -> Result<(), String>
+where
+    T: Serialize,
+{
+    if let (true, PathOp::Set(value)) = (rest.is_empty(), &op) {
+        *item = from_input(value.clone())?;
+        return Ok(());
+    }
+    let mut value = serde_json::to_value(&*item).map_err(|e| e.to_string())?;
+    apply_op(&mut value, rest, op)?;
+    *item = from_value(value)?;
+    Ok(())
+}
+
+/// リスト型フィールドの要素（`[0].name` など）に操作を適用
+/// 要素は `list_append` と同じく `FromStr` で読み直す（構造体はJSONを受け付ける `FromStr` を実装する）
+pub fn update_list_path<T>(list: &mut [T], rest: &str, op: PathOp) -> Result<(), String>
+where
+    T: Serialize + FromStr,
+    T::Err: Display,
+{
+    let segments = parse_path(rest);
+    let Some((PathSegment::Index(index), rest)) = segments.split_first() else {
+        return Err(format!("list path must start with an index: {}", rest));
+    };
+    let i = index
+        .parse::<usize>()
+        .map_err(|_| format!("invalid index: {}", index))?;
+    let item = list.get_mut(i).ok_or("Index out of bounds")?;
+    update_item(
+        item,
+        rest,
+        op,
+        |input| input.parse::<T>().map_err(|e| format!("parse item: {}", e)),
+        parse_item,
+    )
+}
+
+/// マップ型フィールド（キーは文字列）
+pub trait PathMap<V> {
+    fn item_mut(&mut self, key: &str) -> Option<&mut V>;
+    fn put(&mut self, key: String, value: V);
+}
+
+impl<V> PathMap<V> for HashMap<String, V> {
+    fn item_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+    fn put(&mut self, key: String, value: V) {
+        self.insert(key, value);
+    }
+}
+
+impl<V> PathMap<V> for BTreeMap<String, V> {
+    fn item_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.get_mut(key)
+    }
+    fn put(&mut self, key: String, value: V) {
+        self.insert(key, value);
+    }
+}
+
+/// マップ型フィールドの要素（`["key"].name` など）に操作を適用
+/// 値はserdeで読み直す。存在しないキーへの代入は要素を追加する
+pub fn update_map_path<M, V>(map: &mut M, rest: &str, op: PathOp) -> Result<(), String>
+where
+    M: PathMap<V>,
+    V: Serialize + DeserializeOwned,
+{
+    let segments = parse_path(rest);
+    let (key, rest) = match segments.split_first() {
+        Some((PathSegment::Index(key), rest)) => (key.trim_matches('"'), rest),
+        Some((PathSegment::Field(key), rest)) => (key.as_str(), rest),
+        None => return Err("map path must start with a key".to_string()),
+    };
+    let from_value = |value: Value| serde_json::from_value::<V>(value).map_err(|e| e.to_string());
+    match map.item_mut(key) {
+        Some(item) => update_item(item, rest, op, deserialize_input, from_value),
+        None => match op {
+            PathOp::Set(value) if rest.is_empty() => {
+                map.put(key.to_string(), deserialize_input(value)?);
+                Ok(())
+            }
+            _ => Err(format!("key not found: {}", key)),
+        },
+    }
+}
+
+/// ★ その他の型（構造体・列挙型・`Option` など）のフィールドの文字列表現
+/// serdeでJSON値にしてから `value_to_string` と同じ規則で文字列にする
+pub fn field_to_string<T>(value: &T) -> String
+where
+    T: Serialize,
+{
+    serde_json::to_value(value)
+        .map(|value| value_to_string(&value))
+        .unwrap_or_default()
+}
+
+/// ★ その他の型のフィールド（`profile.age` など）に操作を適用
+/// 値はserdeで読み直す。`rest` が空ならフィールド自体への代入
+pub fn update_value_path<T>(item: &mut T, rest: &str, op: PathOp) -> Result<(), String>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let from_value = |value: Value| serde_json::from_value::<T>(value).map_err(|e| e.to_string());
+    update_item(item, &parse_path(rest), op, deserialize_input, from_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_join_path() {
+        let segments = parse_path("users[0].address.city");
+        assert_eq!(
+            segments,
+            vec![
+                PathSegment::Field("users".into()),
+                PathSegment::Index("0".into()),
+                PathSegment::Field("address".into()),
+                PathSegment::Field("city".into()),
+            ]
+        );
+        assert_eq!(join_path(&segments), "users[0].address.city");
+        assert_eq!(split_root("users[0].name"), ("users", "[0].name"));
+        assert!(is_nested_path_of("users[0]", "users"));
+        assert!(!is_nested_path_of("users_count", "users"));
+    }
+
+    #[test]
+    fn test_resolve_indices() {
+        assert_eq!(
+            resolve_indices("map[state.key].tags[i]", &|e| match e {
+                "i" => "2".into(),
+                _ => "a]b".into(),
+            }),
+            r#"map["a]b"].tags[2]"#
+        );
+        assert_eq!(resolve_indices("items[0]", &|_| unreachable!()), "items[0]");
+    }
+
+    #[test]
+    fn test_read_nested() {
+        let users = r#"[{"name":"Ann","tags":["x"]}]"#;
+        assert_eq!(read_nested(users, "[0].name").as_deref(), Some("Ann"));
+        assert_eq!(read_nested(users, "[0].tags[0]").as_deref(), Some("x"));
+        assert_eq!(read_nested(users, "[1].name"), None);
+    }
+
+    #[test]
+    fn test_update_list_path() {
+        let mut scores = vec![1, 2];
+        update_list_path(&mut scores, "[1]", PathOp::Set("5".into())).unwrap();
+        assert_eq!(scores, vec![1, 5]);
+        assert!(update_list_path(&mut scores, "[2]", PathOp::Set("5".into())).is_err());
+        assert!(update_list_path(&mut scores, "[0]", PathOp::Set("x".into())).is_err());
+        assert_eq!(scores, vec![1, 5]);
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+    struct Address {
+        city: String,
+        verified: bool,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, serde::Deserialize)]
+    struct Profile {
+        age: u32,
+        address: Address,
+    }
+
+    #[derive(nilo_state_access_derive::StateAccess)]
+    struct Account {
+        profile: Profile,
+        nickname: Option<String>,
+    }
+
+    fn account() -> Account {
+        Account {
+            profile: Profile {
+                age: 30,
+                address: Address {
+                    city: "Tokyo".into(),
+                    verified: false,
+                    tags: vec!["home".into()],
+                },
+            },
+            nickname: None,
+        }
+    }
+
+    #[test]
+    fn test_derived_struct_field_read() {
+        use crate::engine::state::StateAccess;
+        let account = account();
+        let profile = account.get_field("profile").unwrap();
+        assert_eq!(
+            serde_json::from_str::<Profile>(&profile).unwrap(),
+            account.profile
+        );
+        assert_eq!(account.get_field("profile.age").as_deref(), Some("30"));
+        assert_eq!(
+            account.get_field("profile.address.city").as_deref(),
+            Some("Tokyo")
+        );
+        assert_eq!(account.get_field("nickname").as_deref(), Some("null"));
+    }
+
+    #[test]
+    fn test_derived_struct_field_set() {
+        use crate::engine::state::StateAccess;
+        let mut account = account();
+        account.set("profile.age", "31".into()).unwrap();
+        account.set("profile.address.city", "Osaka".into()).unwrap();
+        assert_eq!(account.profile.age, 31);
+        assert_eq!(account.profile.address.city, "Osaka");
+        // 型が合わない値は書き換えない
+        assert!(account.set("profile.age", "x".into()).is_err());
+        assert!(account.set("profile.missing", "1".into()).is_err());
+        assert_eq!(account.profile.age, 31);
+
+        // フィールド自体にはJSON、Optionには値をそのまま代入できる
+        let profile = r#"{"age":5,"address":{"city":"Kyoto","verified":true,"tags":[]}}"#;
+        account.set("profile", profile.into()).unwrap();
+        assert_eq!(account.profile.age, 5);
+        assert!(account.profile.address.verified);
+        account.set("nickname", "Ada".into()).unwrap();
+        assert_eq!(account.nickname.as_deref(), Some("Ada"));
+        assert_eq!(account.get_field("nickname").as_deref(), Some("Ada"));
+    }
+
+    #[test]
+    fn test_derived_struct_field_toggle_and_list_ops() {
+        use crate::engine::state::StateAccess;
+        let mut account = account();
+        account.toggle("profile.address.verified").unwrap();
+        assert!(account.profile.address.verified);
+        assert!(account.toggle("profile.age").is_err());
+
+        account
+            .list_append("profile.address.tags", "work".into())
+            .unwrap();
+        account
+            .list_insert("profile.address.tags", 0, "first".into())
+            .unwrap();
+        account
+            .list_remove("profile.address.tags", "home".into())
+            .unwrap();
+        assert_eq!(account.profile.address.tags, vec!["first", "work"]);
+        account.list_clear("profile.address.tags").unwrap();
+        assert!(account.profile.address.tags.is_empty());
+    }
+
+    #[test]
+    fn test_update_map_path() {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        map.insert("fruits".into(), vec!["apple".into()]);
+        update_map_path(&mut map, r#"["fruits"]"#, PathOp::Append("pear".into())).unwrap();
+        assert_eq!(map["fruits"], vec!["apple".to_string(), "pear".to_string()]);
+    }
+}
@@ -0,0 +1,251 @@
+//! サブフロー（`call_flow(Signup, into: state.user)` / `finish(value)`）の呼び出しスタック
+//!
+//! 呼び出しごとに呼び出し元のルートと、サブフロー内だけで使う変数（`flow.xxx`）を積む。
+//! `finish` で1段戻り、サブフローのタイムラインの外へ移動した場合はその呼び出しを破棄する。
+
+use crate::engine::routing::RouteMatch;
+use crate::parser::ast::Flow;
+use std::collections::HashMap;
+
+/// 入れ子にできるサブフローの深さの上限（自分自身を呼び続ける場合の保護）
+pub const MAX_SUB_FLOW_DEPTH: usize = 32;
+
+/// 実行中のサブフロー1件分
+#[derive(Debug, Clone)]
+pub struct SubFlowFrame {
+    pub flow: String,
+    /// 戻り先（呼び出し時のタイムラインとパラメータ）
+    pub caller: RouteMatch,
+    /// finish(value) の値を代入するパス
+    pub into: Option<String>,
+    /// サブフロー内の変数（flow.xxx）。呼び出し時の名前付き引数で初期化される
+    pub vars: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SubFlowStack {
+    frames: Vec<SubFlowFrame>,
+}
+
+impl SubFlowStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 呼び出しを積む（深さの上限を超える場合はfalse）
+    pub fn push(&mut self, frame: SubFlowFrame) -> bool {
+        if self.frames.len() >= MAX_SUB_FLOW_DEPTH {
+            log::error!(
+                "サブフロー '{}' を呼び出せません: 入れ子が {} 段を超えました",
+                frame.flow,
+                MAX_SUB_FLOW_DEPTH
+            );
+            return false;
+        }
+        self.frames.push(frame);
+        true
+    }
+
+    pub fn pop(&mut self) -> Option<SubFlowFrame> {
+        self.frames.pop()
+    }
+
+    /// 実行中（最も内側）のサブフロー
+    pub fn current(&self) -> Option<&SubFlowFrame> {
+        self.frames.last()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// 実行中のサブフローの変数（flow.xxx）
+    pub fn var(&self, key: &str) -> Option<&String> {
+        self.current().and_then(|frame| frame.vars.get(key))
+    }
+
+    /// 実行中のサブフローの変数を設定する（サブフローの外ではfalse）
+    pub fn set_var(&mut self, key: &str, value: String) -> bool {
+        match self.frames.last_mut() {
+            Some(frame) => {
+                frame.vars.insert(key.to_string(), value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `timeline` へ移動する前に、そのタイムラインを含まないサブフローを内側から破棄する
+    /// （履歴の戻る・navigate_to などでサブフローの外へ出た場合）
+    pub fn abandon_outside(&mut self, flow: &Flow, timeline: &str) {
+        while let Some(frame) = self.frames.last() {
+            let inside = flow
+                .sub_flows
+                .iter()
+                .find(|sub| sub.name == frame.flow)
+                .is_some_and(|sub| sub.timelines.iter().any(|t| t == timeline));
+            if inside {
+                break;
+            }
+            log::info!(
+                "サブフロー '{}' の外（{}）へ移動したため呼び出しを破棄します",
+                frame.flow,
+                timeline
+            );
+            self.frames.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::core::event::apply_action;
+    use crate::engine::state::AppState;
+    use crate::parser::ast::{App, SubFlow};
+    use crate::parser::parse::parse_nilo;
+
+    fn frame(flow: &str, caller: &str) -> SubFlowFrame {
+        SubFlowFrame {
+            flow: flow.to_string(),
+            caller: RouteMatch {
+                timeline: caller.to_string(),
+                params: HashMap::new(),
+                query: HashMap::new(),
+                url: None,
+            },
+            into: None,
+            vars: HashMap::new(),
+        }
+    }
+
+    fn nested_flow() -> Flow {
+        let sub_flow = |name: &str, timelines: &[&str]| SubFlow {
+            name: name.to_string(),
+            start: timelines[0].to_string(),
+            timelines: timelines.iter().map(|t| t.to_string()).collect(),
+        };
+        Flow {
+            start: "Home".into(),
+            start_url: None,
+            not_found: None,
+            transitions: vec![],
+            sub_flows: vec![
+                sub_flow("Signup", &["Signup::Email", "Signup::Profile"]),
+                sub_flow("Address", &["Address::Form"]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_vars_belong_to_innermost_frame() {
+        let mut stack = SubFlowStack::new();
+        assert!(!stack.set_var("zip", "x".into()));
+        stack.push(frame("Signup", "Home"));
+        stack.push(frame("Address", "Signup::Profile"));
+        assert!(stack.set_var("zip", "100-0001".into()));
+        assert_eq!(stack.var("zip").map(String::as_str), Some("100-0001"));
+        stack.pop();
+        assert_eq!(stack.var("zip"), None);
+    }
+
+    #[test]
+    fn test_push_stops_at_max_depth() {
+        let mut stack = SubFlowStack::new();
+        for _ in 0..MAX_SUB_FLOW_DEPTH {
+            assert!(stack.push(frame("Signup", "Signup::Email")));
+        }
+        assert!(!stack.push(frame("Signup", "Signup::Email")));
+        assert_eq!(stack.depth(), MAX_SUB_FLOW_DEPTH);
+    }
+
+    #[test]
+    fn test_abandon_outside() {
+        let flow = nested_flow();
+        let mut stack = SubFlowStack::new();
+        stack.push(frame("Signup", "Home"));
+        stack.push(frame("Address", "Signup::Profile"));
+
+        stack.abandon_outside(&flow, "Address::Form");
+        assert_eq!(stack.depth(), 2);
+        // 呼び出し元のサブフローへ戻ると内側の呼び出しだけ破棄される
+        stack.abandon_outside(&flow, "Signup::Profile");
+        assert_eq!(stack.current().map(|f| f.flow.as_str()), Some("Signup"));
+        stack.abandon_outside(&flow, "Home");
+        assert!(stack.is_empty());
+    }
+
+    #[derive(Default, nilo_state_access_derive::StateAccess)]
+    struct Account {
+        user: String,
+    }
+
+    fn signup_app(finish: &str) -> App {
+        parse_nilo(&format!(
+            r#"
+            flow {{
+                start: Home
+            }}
+            flow Signup {{
+                start: Email
+                Email -> Profile
+            }}
+            namespace Signup {{
+                timeline Email {{}}
+                timeline Profile {{
+                    Button(id: done_btn, label: "完了")
+                    when user.click(done_btn) {{ {} }}
+                }}
+            }}
+            timeline Home {{
+                Button(id: signup_btn, label: "登録")
+                when user.click(signup_btn) {{ call_flow(Signup, into: state.user, email: "a@example.com") }}
+            }}
+            "#,
+            finish
+        ))
+        .unwrap()
+    }
+
+    /// Homeから呼び出してProfileで `finish` を実行し、戻った後の状態を返す
+    fn run_signup(finish: &str) -> AppState<Account> {
+        let app = signup_app(finish);
+        let action = |name: &str| {
+            let timeline = app.timelines.iter().find(|t| t.name == name).unwrap();
+            timeline.whens[0].actions[0].clone()
+        };
+        let mut state = AppState::new(Account::default(), "Home".into());
+        state.set_flow(&app.flow);
+
+        assert_eq!(
+            apply_action(&app, &mut state, &action("Home")).as_deref(),
+            Some("Signup::Email")
+        );
+        assert_eq!(state.sub_flows.depth(), 1);
+        assert!(state.navigate_with_params("Signup::Profile", HashMap::new()));
+
+        assert_eq!(
+            apply_action(&app, &mut state, &action("Signup::Profile")).as_deref(),
+            Some("Home")
+        );
+        assert!(state.sub_flows.is_empty());
+        state
+    }
+
+    #[test]
+    fn test_finish_assigns_return_value_to_into() {
+        // 値はサブフローのスコープで評価される（flow.xxx を返せる）
+        let state = run_signup("finish(flow.email)");
+        assert_eq!(state.custom_state.user, "a@example.com");
+    }
+
+    #[test]
+    fn test_finish_without_value_leaves_into_unchanged() {
+        let state = run_signup("finish()");
+        assert_eq!(state.custom_state.user, "");
+    }
+}
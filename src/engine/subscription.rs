@@ -0,0 +1,758 @@
+//! サーバーからのプッシュの購読（WebSocket / Server-Sent Events）
+//!
+//! ネイティブ版は共有のtokioランタイムの上で接続を保ち、WASM版はブラウザの
+//! `WebSocket` / `EventSource` を使う。切れたら待ち時間を倍にしながら再接続する。
+//! DSLの `subscribe` はタイムラインに入ると接続して出ると切り、メッセージは
+//! `AsyncEvent::SubscriptionMessage` でメインスレッドへ届いて `step_whens` がアクションを実行する。
+//! Rustの `Subscription` は受け取ったメッセージを非同期結果のキューから状態に反映する。
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::engine::async_call::background_runtime;
+use crate::engine::async_call::{AsyncEvent, StateUpdate, queue_state_updates, send_async_event};
+use crate::engine::http::HttpRequest;
+use crate::engine::state::StateAccess;
+use crate::parser::ast::{HttpMethod, SubscribeBlock, SubscriptionKind};
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 停止の確認と、受信待ちを区切る間隔
+const POLL_INTERVAL_MS: u64 = 200;
+
+lazy_static::lazy_static! {
+    // 受信したメッセージ（`UIEvent::SubscriptionMessage` のidで取り出す）
+    static ref SUBSCRIPTION_MESSAGES: Mutex<HashMap<u64, SubscriptionMessage>> = Mutex::new(HashMap::new());
+    // EventLoopProxyがない環境（WASMのDOMランタイム）で、次のフレームまで溜めておく受信通知
+    static ref PENDING_SUBSCRIPTION_MESSAGES: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+}
+
+static NEXT_MESSAGE_ID: AtomicU64 = AtomicU64::new(1);
+
+type MessageHandler = Arc<dyn Fn(String) + Send + Sync>;
+type StateMessageHandler<S> = Arc<dyn Fn(&mut S, &str) + Send + Sync>;
+/// 接続を始める関数（通常は `spawn_connection`。テストでは接続せずに記録する）
+type Connector = fn(SubscriptionKind, &str, Arc<AtomicBool>, Backoff, MessageHandler);
+
+/// 再接続までの待ち時間（失敗するたびに倍にして上限で止め、接続できたら最初に戻す）
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial_ms: u64,
+    max_ms: u64,
+    next_ms: u64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(500, 30_000)
+    }
+}
+
+impl Backoff {
+    pub fn new(initial_ms: u64, max_ms: u64) -> Self {
+        Self {
+            initial_ms,
+            max_ms,
+            next_ms: initial_ms,
+        }
+    }
+
+    pub fn next_delay(&mut self) -> u64 {
+        let delay = self.next_ms;
+        self.next_ms = self.next_ms.saturating_mul(2).min(self.max_ms);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next_ms = self.initial_ms;
+    }
+}
+
+/// 受信したメッセージ
+#[derive(Debug, Clone)]
+pub struct SubscriptionMessage {
+    /// 受け取った購読のid（"タイムライン#番号@世代"）
+    pub subscription: String,
+    pub text: String,
+}
+
+#[derive(Debug)]
+struct RunningSubscription {
+    /// "タイムライン#番号@世代"（切った後に届いた古いメッセージを無視するため世代を含める）
+    id: String,
+    index: usize,
+    url: String,
+    running: Arc<AtomicBool>,
+}
+
+/// タイムラインに書いた購読（`subscribe ws(...) as msg { ... }`）
+#[derive(Debug)]
+pub struct TimelineSubscriptions {
+    /// 接続したタイムライン（切った後はNone）
+    timeline: Option<String>,
+    generation: u64,
+    running: Vec<RunningSubscription>,
+    connect: Connector,
+}
+
+impl Default for TimelineSubscriptions {
+    fn default() -> Self {
+        Self::with_connector(spawn_connection)
+    }
+}
+
+impl Clone for TimelineSubscriptions {
+    /// 複製した状態では接続しない（次の `sync` で接続し直す）
+    fn clone(&self) -> Self {
+        Self {
+            timeline: None,
+            generation: self.generation,
+            running: Vec::new(),
+            connect: self.connect,
+        }
+    }
+}
+
+impl TimelineSubscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 接続の始め方を差し替える（`sync` / `fire` の管理だけを確かめるため）
+    pub(crate) fn with_connector(connect: Connector) -> Self {
+        Self {
+            timeline: None,
+            generation: 0,
+            running: Vec::new(),
+            connect,
+        }
+    }
+
+    /// 表示中のタイムラインの購読に接続する（`urls` は評価済みのURL式。変わったら接続し直す）
+    pub fn sync(&mut self, timeline: &str, subscriptions: &[SubscribeBlock], urls: Vec<String>) {
+        let unchanged = self.running.len() == urls.len()
+            && self.running.iter().zip(&urls).all(|(r, url)| &r.url == url);
+        if self.timeline.as_deref() == Some(timeline) && unchanged {
+            return;
+        }
+        self.stop_all();
+        self.timeline = Some(timeline.to_string());
+        self.generation += 1;
+
+        for (index, (sub, url)) in subscriptions.iter().zip(urls).enumerate() {
+            let id = format!("{}#{}@{}", timeline, index, self.generation);
+            let running = Arc::new(AtomicBool::new(true));
+            let message_id = id.clone();
+            (self.connect)(
+                sub.kind,
+                &url,
+                Arc::clone(&running),
+                Backoff::default(),
+                Arc::new(move |text| deliver_message(&message_id, text)),
+            );
+            self.running.push(RunningSubscription {
+                id,
+                index,
+                url,
+                running,
+            });
+        }
+    }
+
+    /// すべての接続を切る（タイムラインの遷移・ホットリロード時）
+    pub fn stop_all(&mut self) {
+        for sub in self.running.drain(..) {
+            sub.running.store(false, Ordering::SeqCst);
+        }
+        self.timeline = None;
+    }
+
+    /// メッセージを受け取った購読の番号（`Timeline::subscriptions` の添字）
+    ///
+    /// 切った購読に届いた古いメッセージならNone
+    pub fn fire(&self, id: &str) -> Option<usize> {
+        self.running.iter().find(|s| s.id == id).map(|s| s.index)
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+}
+
+impl Drop for TimelineSubscriptions {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+fn deliver_message(subscription: &str, text: String) {
+    let id = NEXT_MESSAGE_ID.fetch_add(1, Ordering::SeqCst);
+    store_message(
+        id,
+        SubscriptionMessage {
+            subscription: subscription.to_string(),
+            text,
+        },
+    );
+    if send_async_event(AsyncEvent::SubscriptionMessage(id)) {
+        return;
+    }
+    if let Ok(mut pending) = PENDING_SUBSCRIPTION_MESSAGES.lock() {
+        pending.push(id);
+    }
+}
+
+/// EventLoopProxyがない環境で溜めた受信通知を取り出す（古い順）
+pub fn take_subscription_messages() -> Vec<u64> {
+    PENDING_SUBSCRIPTION_MESSAGES
+        .lock()
+        .map(|mut pending| std::mem::take(&mut *pending))
+        .unwrap_or_default()
+}
+
+/// 受信したメッセージを `UIEvent::SubscriptionMessage` のidで取り出せるようにする
+pub(crate) fn store_message(id: u64, message: SubscriptionMessage) {
+    if let Ok(mut messages) = SUBSCRIPTION_MESSAGES.lock() {
+        messages.insert(id, message);
+    }
+}
+
+/// 受信したメッセージを取り出す
+pub fn take_message(id: u64) -> Option<SubscriptionMessage> {
+    SUBSCRIPTION_MESSAGES.lock().ok()?.remove(&id)
+}
+
+/// Rustからの購読
+///
+/// ```ignore
+/// let handle = Subscription::<State>::websocket("wss://example.com/chat")
+///     .append_to("messages")
+///     .start();
+/// ```
+pub struct Subscription<S> {
+    kind: SubscriptionKind,
+    url: String,
+    backoff: Backoff,
+    on_message: Option<StateMessageHandler<S>>,
+}
+
+impl<S> Subscription<S>
+where
+    S: StateAccess + 'static,
+{
+    pub fn websocket(url: &str) -> Self {
+        Self::new(SubscriptionKind::WebSocket, url)
+    }
+
+    pub fn sse(url: &str) -> Self {
+        Self::new(SubscriptionKind::Sse, url)
+    }
+
+    fn new(kind: SubscriptionKind, url: &str) -> Self {
+        Self {
+            kind,
+            url: url.to_string(),
+            backoff: Backoff::default(),
+            on_message: None,
+        }
+    }
+
+    /// 再接続の待ち時間（最初の値と上限、ミリ秒）
+    pub fn backoff(mut self, initial_ms: u64, max_ms: u64) -> Self {
+        self.backoff = Backoff::new(initial_ms, max_ms);
+        self
+    }
+
+    /// メッセージを受け取るたびにUIスレッドで状態を書き換える
+    pub fn on_message<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&mut S, &str) + Send + Sync + 'static,
+    {
+        self.on_message = Some(Arc::new(handler));
+        self
+    }
+
+    /// 受け取ったメッセージを状態のリストに追加する
+    pub fn append_to(self, path: &str) -> Self {
+        let path = path.to_string();
+        self.on_message(move |state, text| {
+            if let Err(e) = state.list_append(&path, text.to_string()) {
+                log::error!("Failed to append subscription message to {}: {}", path, e);
+            }
+        })
+    }
+
+    /// 接続を開始する（止めるまで再接続を続ける）
+    pub fn start(self) -> SubscriptionHandle {
+        let running = Arc::new(AtomicBool::new(true));
+        let Some(handler) = self.on_message else {
+            log::warn!("Subscription to {} has no message handler", self.url);
+            running.store(false, Ordering::SeqCst);
+            return SubscriptionHandle { running };
+        };
+
+        spawn_connection(
+            self.kind,
+            &self.url,
+            Arc::clone(&running),
+            self.backoff,
+            Arc::new(move |text| {
+                let handler = Arc::clone(&handler);
+                queue_state_updates(vec![StateUpdate::Apply(Box::new(
+                    move |state: &mut dyn Any| match state.downcast_mut::<S>() {
+                        Some(state) => handler(state, &text),
+                        None => log::error!("State type mismatch in Subscription"),
+                    },
+                ))]);
+            }),
+        );
+        SubscriptionHandle { running }
+    }
+}
+
+/// `Subscription::start` で開始した接続（捨てても接続は続く。切るには `stop` を呼ぶ）
+#[derive(Debug, Clone)]
+pub struct SubscriptionHandle {
+    running: Arc<AtomicBool>,
+}
+
+impl SubscriptionHandle {
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
+/// WebSocketのURL（ベースURLから組み立てた `http(s)://` を `ws(s)://` にする）
+fn websocket_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        url.to_string()
+    }
+}
+
+/// 止めるまで接続を保ち、受け取ったメッセージを `on_message` に渡す
+///
+/// 相対URLには `set_http_base_url` のベースURLを付け、共通ヘッダーも送る（WASM版はヘッダーを送れない）
+fn spawn_connection(
+    kind: SubscriptionKind,
+    url: &str,
+    running: Arc<AtomicBool>,
+    backoff: Backoff,
+    on_message: MessageHandler,
+) {
+    let mut request = HttpRequest::new(HttpMethod::Get, url);
+    if kind == SubscriptionKind::WebSocket {
+        request.url = websocket_url(&request.url);
+    }
+    log::debug!("🔌 Subscribing to {:?} {}", kind, request.url);
+
+    let task = run_connection(kind, request, running, backoff, on_message);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    background_runtime().spawn(task);
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(task);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn run_connection(
+    kind: SubscriptionKind,
+    request: HttpRequest,
+    running: Arc<AtomicBool>,
+    mut backoff: Backoff,
+    on_message: MessageHandler,
+) {
+    while running.load(Ordering::SeqCst) {
+        let result = match kind {
+            SubscriptionKind::WebSocket => {
+                read_websocket(&request, &running, &mut backoff, &on_message).await
+            }
+            SubscriptionKind::Sse => read_sse(&request, &running, &mut backoff, &on_message).await,
+        };
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let delay = backoff.next_delay();
+        match result {
+            Ok(()) => log::info!("🔌 {} closed, reconnecting in {}ms", request.url, delay),
+            Err(e) => log::warn!("🔌 {}: {}, reconnecting in {}ms", request.url, e, delay),
+        }
+        // 待っている間も止められるよう、短く区切って眠る
+        let mut waited = 0;
+        while waited < delay && running.load(Ordering::SeqCst) {
+            let step = POLL_INTERVAL_MS.min(delay - waited);
+            tokio::time::sleep(std::time::Duration::from_millis(step)).await;
+            waited += step;
+        }
+    }
+    log::debug!("🔌 Unsubscribed from {}", request.url);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_websocket(
+    request: &HttpRequest,
+    running: &AtomicBool,
+    backoff: &mut Backoff,
+    on_message: &MessageHandler,
+) -> Result<(), String> {
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+
+    let mut ws_request = request
+        .url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| e.to_string())?;
+    for (name, value) in &request.headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            ws_request.headers_mut().insert(name, value);
+        }
+    }
+    let (mut socket, _) = tokio_tungstenite::connect_async(ws_request)
+        .await
+        .map_err(|e| e.to_string())?;
+    log::info!("🔌 Connected to {}", request.url);
+    backoff.reset();
+
+    let poll = std::time::Duration::from_millis(POLL_INTERVAL_MS);
+    while running.load(Ordering::SeqCst) {
+        let Ok(message) = tokio::time::timeout(poll, socket.next()).await else {
+            continue;
+        };
+        match message {
+            Some(Ok(Message::Text(text))) => on_message(text.to_string()),
+            Some(Ok(Message::Binary(bytes))) => {
+                on_message(String::from_utf8_lossy(&bytes).into_owned())
+            }
+            Some(Ok(Message::Close(_))) | None => return Ok(()),
+            Some(Ok(_)) => {}
+            Some(Err(e)) => return Err(e.to_string()),
+        }
+    }
+    let _ = socket.close(None).await;
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_sse(
+    request: &HttpRequest,
+    running: &AtomicBool,
+    backoff: &mut Backoff,
+    on_message: &MessageHandler,
+) -> Result<(), String> {
+    let mut builder = crate::engine::http::client()
+        .get(&request.url)
+        .header("Accept", "text/event-stream");
+    for (name, value) in &request.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    let mut response = builder.send().await.map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    if !(200..300).contains(&status) {
+        return Err(format!("HTTP {}", status));
+    }
+    log::info!("🔌 Connected to {}", request.url);
+    backoff.reset();
+
+    let poll = std::time::Duration::from_millis(POLL_INTERVAL_MS);
+    let mut parser = SseParser::default();
+    while running.load(Ordering::SeqCst) {
+        let Ok(chunk) = tokio::time::timeout(poll, response.chunk()).await else {
+            continue;
+        };
+        let Some(bytes) = chunk.map_err(|e| e.to_string())? else {
+            return Ok(());
+        };
+        for data in parser.push(&bytes) {
+            on_message(data);
+        }
+    }
+    Ok(())
+}
+
+/// `text/event-stream` を行ごとに読み、イベント（`data:` 行を改行でつないだもの）を取り出す
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default)]
+struct SseParser {
+    buf: Vec<u8>,
+    data: Vec<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SseParser {
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buf.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                // 空行でイベントが終わる
+                if !self.data.is_empty() {
+                    events.push(std::mem::take(&mut self.data).join("\n"));
+                }
+            } else if line == "data" {
+                self.data.push(String::new());
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data
+                    .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+            // event: / id: / retry: と ":" で始まるコメントは使わない
+        }
+        events
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn run_connection(
+    kind: SubscriptionKind,
+    request: HttpRequest,
+    running: Arc<AtomicBool>,
+    mut backoff: Backoff,
+    on_message: MessageHandler,
+) {
+    use gloo_timers::future::TimeoutFuture;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    while running.load(Ordering::SeqCst) {
+        let opened = Rc::new(Cell::new(false));
+        let closed = Rc::new(Cell::new(false));
+        match BrowserSource::open(kind, &request.url, &on_message, &opened, &closed) {
+            Ok(source) => {
+                while running.load(Ordering::SeqCst) && !closed.get() {
+                    TimeoutFuture::new(POLL_INTERVAL_MS as u32).await;
+                }
+                source.close();
+                if opened.get() {
+                    backoff.reset();
+                }
+            }
+            Err(e) => log::warn!("🔌 {}: {}", request.url, e),
+        }
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let delay = backoff.next_delay();
+        log::info!("🔌 {} closed, reconnecting in {}ms", request.url, delay);
+        TimeoutFuture::new(delay as u32).await;
+    }
+    log::debug!("🔌 Unsubscribed from {}", request.url);
+}
+
+#[cfg(target_arch = "wasm32")]
+enum BrowserSocket {
+    WebSocket(web_sys::WebSocket),
+    EventSource(web_sys::EventSource),
+}
+
+/// ブラウザの接続と、それに登録したコールバック（接続を閉じるまで保持する）
+#[cfg(target_arch = "wasm32")]
+struct BrowserSource {
+    socket: BrowserSocket,
+    _callbacks: Vec<wasm_bindgen::closure::Closure<dyn FnMut(wasm_bindgen::JsValue)>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl BrowserSource {
+    fn open(
+        kind: SubscriptionKind,
+        url: &str,
+        on_message: &MessageHandler,
+        opened: &std::rc::Rc<std::cell::Cell<bool>>,
+        closed: &std::rc::Rc<std::cell::Cell<bool>>,
+    ) -> Result<Self, String> {
+        use wasm_bindgen::JsCast;
+        use wasm_bindgen::JsValue;
+        use wasm_bindgen::closure::Closure;
+
+        let on_message = Arc::clone(on_message);
+        let message = Closure::wrap(Box::new(move |event: JsValue| {
+            if let Some(text) = event
+                .dyn_ref::<web_sys::MessageEvent>()
+                .and_then(|e| e.data().as_string())
+            {
+                on_message(text);
+            }
+        }) as Box<dyn FnMut(JsValue)>);
+        let opened = std::rc::Rc::clone(opened);
+        let open =
+            Closure::wrap(Box::new(move |_: JsValue| opened.set(true)) as Box<dyn FnMut(JsValue)>);
+        // EventSourceは自分で再接続するが、待ち時間をそろえるため閉じてこちらで接続し直す
+        let closed = std::rc::Rc::clone(closed);
+        let close =
+            Closure::wrap(Box::new(move |_: JsValue| closed.set(true)) as Box<dyn FnMut(JsValue)>);
+
+        let socket = match kind {
+            SubscriptionKind::WebSocket => {
+                let ws = web_sys::WebSocket::new(url).map_err(|e| format!("{:?}", e))?;
+                ws.set_onmessage(Some(message.as_ref().unchecked_ref()));
+                ws.set_onopen(Some(open.as_ref().unchecked_ref()));
+                ws.set_onclose(Some(close.as_ref().unchecked_ref()));
+                ws.set_onerror(Some(close.as_ref().unchecked_ref()));
+                BrowserSocket::WebSocket(ws)
+            }
+            SubscriptionKind::Sse => {
+                let es = web_sys::EventSource::new(url).map_err(|e| format!("{:?}", e))?;
+                es.set_onmessage(Some(message.as_ref().unchecked_ref()));
+                es.set_onopen(Some(open.as_ref().unchecked_ref()));
+                es.set_onerror(Some(close.as_ref().unchecked_ref()));
+                BrowserSocket::EventSource(es)
+            }
+        };
+        Ok(Self {
+            socket,
+            _callbacks: vec![message, open, close],
+        })
+    }
+
+    fn close(&self) {
+        match &self.socket {
+            BrowserSocket::WebSocket(ws) => {
+                let _ = ws.close();
+            }
+            BrowserSocket::EventSource(es) => es.close(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::Expr;
+
+    fn subscribe_block(url: &str) -> SubscribeBlock {
+        SubscribeBlock {
+            kind: SubscriptionKind::WebSocket,
+            url: Expr::String(url.into()),
+            var: "msg".into(),
+            actions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new(500, 3_000);
+        let delays: Vec<_> = (0..5).map(|_| backoff.next_delay()).collect();
+        assert_eq!(delays, vec![500, 1_000, 2_000, 3_000, 3_000]);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), 500);
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        assert_eq!(
+            websocket_url("https://example.com/ws"),
+            "wss://example.com/ws"
+        );
+        assert_eq!(websocket_url("ws://localhost:9000"), "ws://localhost:9000");
+    }
+
+    #[test]
+    fn test_sse_parser_joins_split_chunks() {
+        // 行やUTF-8の文字が途中で切れて届いても、空行までを1つのイベントにする
+        let mut parser = SseParser::default();
+        let stream =
+            ": ping\n\ndata: {\"text\":\"こんにちは\"}\r\n\r\ndata: a\ndata:b\n\n".as_bytes();
+        let (head, tail) = stream.split_at(25);
+        let mut events = parser.push(head);
+        events.extend(parser.push(tail));
+        assert_eq!(events, vec![r#"{"text":"こんにちは"}"#, "a\nb"]);
+    }
+
+    #[test]
+    fn test_sync_reconnects_only_when_urls_change() {
+        static CONNECTED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        fn record(
+            _: SubscriptionKind,
+            url: &str,
+            _: Arc<AtomicBool>,
+            _: Backoff,
+            _: MessageHandler,
+        ) {
+            CONNECTED.lock().unwrap().push(url.to_string());
+        }
+
+        let sub = subscribe_block("ws://chat");
+        let mut subs = TimelineSubscriptions::with_connector(record);
+        subs.sync(
+            "Chat",
+            std::slice::from_ref(&sub),
+            vec!["ws://chat/1".into()],
+        );
+        subs.sync(
+            "Chat",
+            std::slice::from_ref(&sub),
+            vec!["ws://chat/1".into()],
+        );
+        assert_eq!(subs.fire("Chat#0@1"), Some(0));
+
+        // URLが変わると接続し直し、前の接続のメッセージは無視する
+        subs.sync(
+            "Chat",
+            std::slice::from_ref(&sub),
+            vec!["ws://chat/2".into()],
+        );
+        assert_eq!(subs.fire("Chat#0@1"), None);
+        assert_eq!(subs.fire("Chat#0@2"), Some(0));
+        assert_eq!(
+            *CONNECTED.lock().unwrap(),
+            vec!["ws://chat/1", "ws://chat/2"]
+        );
+
+        subs.stop_all();
+        assert!(!subs.is_running());
+        assert_eq!(subs.fire("Chat#0@2"), None);
+    }
+
+    #[test]
+    fn test_websocket_messages_reach_main_thread() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        // 接続されたら1通送り、閉じられるまで待つローカルのWebSocketサーバー
+        let runtime = background_runtime();
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        runtime.spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            socket.send(Message::text("hello")).await.unwrap();
+            while let Some(Ok(_)) = socket.next().await {}
+        });
+
+        let mut subs = TimelineSubscriptions::new();
+        subs.sync("Chat", &[subscribe_block(&url)], vec![url.clone()]);
+
+        let mut received = None;
+        for _ in 0..100 {
+            if let Some(id) = take_subscription_messages().into_iter().next() {
+                received = take_message(id);
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        let message = received.expect("no message from the local server");
+        assert_eq!(message.text, "hello");
+        assert_eq!(subs.fire(&message.subscription), Some(0));
+        subs.stop_all();
+    }
+}
@@ -150,18 +150,10 @@ impl TimelineProcessor {
 
                 // foreach: timeline初期化時は展開せず、マーカーとして保持
                 // レイアウト時に実際の値で展開する
-                ViewNode::ForEach {
-                    var,
-                    iterable,
-                    body,
-                } => {
+                ViewNode::ForEach { .. } => {
                     // foreachノードをそのまま保持（レイアウト時に処理）
                     result.push(ProcessedNode {
-                        node: ViewNode::ForEach {
-                            var: var.clone(),
-                            iterable: iterable.clone(),
-                            body: body.clone(),
-                        },
+                        node: node.node.clone(),
                         style: node.style.clone(),
                         line: node.line,
                         column: node.column,
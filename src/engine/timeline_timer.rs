@@ -0,0 +1,189 @@
+//! タイムラインに書いたタイマー（`every 1s { ... }` / `after 500ms { ... }`）
+//!
+//! 表示中のタイムラインのタイマーだけを動かし、タイムラインを出ると止める。
+//! ティックは `async_call` の定期実行タイマーと同じ `AsyncEvent::IntervalTick` で
+//! メインスレッドへ届き、ランタイムが `UIEvent::TimerTick` として `step_whens` に渡す。
+
+use crate::engine::async_call::spawn_interval_ticker;
+use crate::parser::ast::{Timeline, TimerKind};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug)]
+struct RunningTimer {
+    /// "タイムライン#番号@世代"（止めた後に届いた古いティックを無視するため世代を含める）
+    id: String,
+    index: usize,
+    repeat: bool,
+    running: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Default)]
+pub struct TimelineTimers {
+    /// タイマーを開始したタイムライン（止めた後はNone）
+    timeline: Option<String>,
+    generation: u64,
+    running: Vec<RunningTimer>,
+}
+
+impl Clone for TimelineTimers {
+    /// 複製した状態ではタイマーを動かさない（次の `sync` で開始し直す）
+    fn clone(&self) -> Self {
+        Self {
+            timeline: None,
+            generation: self.generation,
+            running: Vec::new(),
+        }
+    }
+}
+
+impl TimelineTimers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 表示中のタイムラインのタイマーを動かす（開始済みなら何もしない）
+    pub fn sync(&mut self, timeline: &Timeline) {
+        if self.timeline.as_deref() == Some(timeline.name.as_str()) {
+            return;
+        }
+        self.stop_all();
+        self.timeline = Some(timeline.name.clone());
+        self.generation += 1;
+
+        for (index, timer) in timeline.timers.iter().enumerate() {
+            let id = format!("{}#{}@{}", timeline.name, index, self.generation);
+            let repeat = timer.kind == TimerKind::Every;
+            let running = Arc::new(AtomicBool::new(true));
+            spawn_interval_ticker(id.clone(), timer.interval_ms, repeat, Arc::clone(&running));
+            log::debug!("⏰ Started timer '{}' ({}ms)", id, timer.interval_ms);
+            self.running.push(RunningTimer {
+                id,
+                index,
+                repeat,
+                running,
+            });
+        }
+    }
+
+    /// すべてのタイマーを止める（タイムラインの遷移・ホットリロード時）
+    pub fn stop_all(&mut self) {
+        for timer in self.running.drain(..) {
+            timer.running.store(false, Ordering::SeqCst);
+        }
+        self.timeline = None;
+    }
+
+    /// ティックを受け取ったタイマーの番号（`Timeline::timers` の添字）
+    ///
+    /// 止めたタイマーの古いティックならNone。`after` は1回で終了する
+    pub fn fire(&mut self, id: &str) -> Option<usize> {
+        let pos = self.running.iter().position(|t| t.id == id)?;
+        let index = self.running[pos].index;
+        if !self.running[pos].repeat {
+            self.running.remove(pos);
+        }
+        Some(index)
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+}
+
+impl Drop for TimelineTimers {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::TimerBlock;
+
+    fn timeline(name: &str, kinds: &[TimerKind]) -> Timeline {
+        Timeline {
+            name: name.into(),
+            url_pattern: None,
+            font: None,
+            background: None,
+            body: vec![],
+            whens: vec![],
+            timers: kinds
+                .iter()
+                .map(|&kind| TimerBlock {
+                    kind,
+                    interval_ms: 60_000,
+                    actions: vec![],
+                })
+                .collect(),
+            resources: vec![],
+            subscriptions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_every_fires_repeatedly() {
+        let mut timers = TimelineTimers::new();
+        timers.sync(&timeline("Home", &[TimerKind::Every]));
+        assert_eq!(timers.fire("Home#0@1"), Some(0));
+        assert_eq!(timers.fire("Home#0@1"), Some(0));
+        assert!(timers.is_running());
+    }
+
+    #[test]
+    fn test_after_fires_once() {
+        let mut timers = TimelineTimers::new();
+        timers.sync(&timeline("Home", &[TimerKind::Every, TimerKind::After]));
+        assert_eq!(timers.fire("Home#1@1"), Some(1));
+        assert_eq!(timers.fire("Home#1@1"), None);
+        assert_eq!(timers.fire("Home#0@1"), Some(0));
+    }
+
+    #[test]
+    fn test_sync_same_timeline_keeps_timers() {
+        let home = timeline("Home", &[TimerKind::Every]);
+        let mut timers = TimelineTimers::new();
+        timers.sync(&home);
+        timers.sync(&home);
+        assert_eq!(timers.fire("Home#0@1"), Some(0));
+    }
+
+    #[test]
+    fn test_leaving_timeline_stops_tickers() {
+        let mut timers = TimelineTimers::new();
+        timers.sync(&timeline("Home", &[TimerKind::Every, TimerKind::After]));
+        let flags: Vec<_> = timers
+            .running
+            .iter()
+            .map(|t| Arc::clone(&t.running))
+            .collect();
+
+        timers.sync(&timeline("Settings", &[]));
+        assert!(flags.iter().all(|f| !f.load(Ordering::SeqCst)));
+        assert!(!timers.is_running());
+        // 止めた後に届いたティックは無視する
+        assert_eq!(timers.fire("Home#0@1"), None);
+    }
+
+    #[test]
+    fn test_reentering_ignores_stale_ticks() {
+        let home = timeline("Home", &[TimerKind::Every, TimerKind::After]);
+        let mut timers = TimelineTimers::new();
+        timers.sync(&home);
+        timers.stop_all();
+        timers.sync(&home);
+        assert_eq!(timers.fire("Home#0@1"), None);
+        assert_eq!(timers.fire("Home#1@2"), Some(1));
+    }
+
+    #[test]
+    fn test_drop_stops_tickers() {
+        let mut timers = TimelineTimers::new();
+        timers.sync(&timeline("Home", &[TimerKind::Every]));
+        let flag = Arc::clone(&timers.running[0].running);
+        drop(timers);
+        assert!(!flag.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,23 @@
+//! 時間計測ユーティリティ
+//!
+//! ツールチップの表示遅延やトーストの表示期限など、フレームをまたぐ時間判定に使う。
+//! wasm32では`std::time::Instant`が使えないため`performance.now()`を使用する。
+
+#[cfg(not(target_arch = "wasm32"))]
+lazy_static::lazy_static! {
+    static ref START: std::time::Instant = std::time::Instant::now();
+}
+
+/// 起動からの経過時間（ミリ秒）
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    START.elapsed().as_secs_f64() * 1000.0
+}
+
+/// ページ読み込みからの経過時間（ミリ秒）
+#[cfg(target_arch = "wasm32")]
+pub fn now_ms() -> f64 {
+    web_sys::window()
+        .and_then(|w| w.performance())
+        .map_or(0.0, |p| p.now())
+}
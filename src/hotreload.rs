@@ -1,14 +1,16 @@
 use log::{info, error};
 use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
+type ReloadCallback = Box<dyn Fn(&[PathBuf]) + Send + 'static>;
+
 pub struct HotReloader {
-    _watcher: RecommendedWatcher,
-    reload_callback: Arc<Mutex<Option<Box<dyn Fn() + Send + 'static>>>>,
+    watcher: RecommendedWatcher,
+    reload_callback: Arc<Mutex<Option<ReloadCallback>>>,
 }
 
 impl HotReloader {
@@ -37,8 +39,7 @@ impl HotReloader {
             watch_path.as_ref().display()
         );
 
-        let reload_callback: Arc<Mutex<Option<Box<dyn Fn() + Send + 'static>>>> =
-            Arc::new(Mutex::new(None));
+        let reload_callback: Arc<Mutex<Option<ReloadCallback>>> = Arc::new(Mutex::new(None));
 
         // ファイル監視を別スレッドで開始
         let callback_clone = Arc::clone(&reload_callback);
@@ -46,16 +47,23 @@ impl HotReloader {
             loop {
                 match rx.recv_timeout(Duration::from_millis(50)) {
                     Ok(Ok(event)) => {
-                        if should_reload(&event) {
+                        let mut changed = reload_paths(&event);
+                        if !changed.is_empty() {
                             info!("🔄 File changed, reloading...");
 
                             // 少し待ってからリロード（ファイル書き込みが完了するのを待つ）
                             thread::sleep(Duration::from_millis(100));
+                            // 待っている間に届いた変更もまとめて1回でリロードする
+                            while let Ok(Ok(event)) = rx.try_recv() {
+                                changed.extend(reload_paths(&event));
+                            }
+                            changed.sort();
+                            changed.dedup();
 
-                            if let Ok(callback_guard) = callback_clone.lock() {
-                                if let Some(ref cb) = *callback_guard {
-                                    cb();
-                                }
+                            if let Ok(callback_guard) = callback_clone.lock()
+                                && let Some(ref cb) = *callback_guard
+                            {
+                                cb(&changed);
                             }
                         }
                     }
@@ -74,35 +82,50 @@ impl HotReloader {
         });
 
         Ok(HotReloader {
-            _watcher: watcher,
+            watcher,
             reload_callback,
         })
     }
 
-    /// リロード時のコールバック関数を設定
+    /// 監視するディレクトリを追加（.niloファイルの外にある画像・フォント用）
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        self.watcher
+            .watch(path.as_ref(), RecursiveMode::Recursive)?;
+        info!("🔥 Hot reload enabled for: {}", path.as_ref().display());
+        Ok(())
+    }
+
+    /// リロード時のコールバック関数を設定（変更されたファイルのパスを受け取る）
     pub fn set_reload_callback<F>(&self, callback: F)
     where
-        F: Fn() + Send + 'static,
+        F: Fn(&[PathBuf]) + Send + 'static,
     {
         let mut cb = self.reload_callback.lock().unwrap();
         *cb = Some(Box::new(callback));
     }
 }
 
-/// ファイル変更イベントがリロードをトリガーすべきかを判断
-fn should_reload(event: &Event) -> bool {
+/// リロードの対象になる拡張子（.niloファイルと、そこから参照する画像・フォント）
+const RELOAD_EXTENSIONS: &[&str] = &[
+    "nilo", "png", "jpg", "jpeg", "gif", "bmp", "webp", "svg", "ttf", "otf",
+];
+
+/// ファイル変更イベントのうち、リロードをトリガーすべきファイルのパス
+fn reload_paths(event: &Event) -> Vec<PathBuf> {
     use notify::EventKind;
 
     match event.kind {
-        EventKind::Modify(_) | EventKind::Create(_) => {
-            // .niloファイルの変更のみを監視
-            event.paths.iter().any(|path| {
+        EventKind::Modify(_) | EventKind::Create(_) => event
+            .paths
+            .iter()
+            .filter(|path| {
                 path.extension()
                     .and_then(|ext| ext.to_str())
-                    .map(|ext| ext == "nilo")
+                    .map(|ext| RELOAD_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
                     .unwrap_or(false)
             })
-        }
-        _ => false,
+            .cloned()
+            .collect(),
+        _ => Vec::new(),
     }
 }
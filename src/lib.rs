@@ -30,6 +30,10 @@ pub static NILO_FUNCTION_REGISTRY: [fn()] = [..];
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+// deriveマクロが生成する `::nilo::...` をクレート内のテストからも解決できるようにする
+#[cfg(test)]
+extern crate self as nilo;
+
 pub use engine::exec::{AppState, StateAccess};
 #[cfg(not(target_arch = "wasm32"))]
 pub use engine::runtime::run;
@@ -38,6 +42,9 @@ pub use renderer_abstract::RendererType;
 // 安全なカスタムステートアクセス API の公開
 pub use engine::state::CustomStateContext;
 pub use engine::state::register_state_watcher;
+pub use engine::app_handle::AppHandle;
+pub use engine::http::{set_http_base_url, set_http_header, set_http_timeout};
+pub use engine::subscription::{Subscription, SubscriptionHandle};
 
 // 非同期関数登録 API の公開
 pub use engine::async_call::{
@@ -48,7 +55,9 @@ pub use engine::async_call::{
     has_async_onclick,
     has_pending_async_results,
     set_event_loop_proxy,
+    queue_state_updates,
     AsyncEvent,
+    StateUpdate,
     // 定期実行用の関数
     register_async_interval,
     start_async_interval,
@@ -63,6 +72,7 @@ pub use engine::rust_call::{
     CallableFn,
     FromExpr,
     FromExprArgs,
+    IntoExprValue,
     register_rust_call,
     register_safe_state_call,
     register_state_accessible_call,
@@ -588,12 +598,13 @@ pub fn run_with_hotreload<S, P>(
     S: StateAccess + Clone + Send + 'static + std::fmt::Debug,
     P: AsRef<std::path::Path> + Send + 'static,
 {
+    use engine::runtime_hotreload::PendingReload;
     use hotreload::HotReloader;
     use std::sync::{Arc, Mutex};
 
     let file_path = path.as_ref().to_path_buf();
     let should_restart = Arc::new(Mutex::new(false));
-    let current_app = Arc::new(Mutex::new(None));
+    let pending_reload = Arc::new(Mutex::new(PendingReload::default()));
 
     let app = load_nilo_app(&file_path, enable_lint, enable_debug, quiet)
         .expect("Failed to load initial application");
@@ -601,29 +612,58 @@ pub fn run_with_hotreload<S, P>(
     let watch_dir = file_path
         .parent()
         .unwrap_or_else(|| std::path::Path::new(""));
-    let hotreloader = HotReloader::new(watch_dir).expect("Failed to setup hot reloader");
+    let mut hotreloader = HotReloader::new(watch_dir).expect("Failed to setup hot reloader");
+
+    // .niloファイルのディレクトリの外にある画像・フォントのディレクトリも監視する
+    let watch_root = watch_dir.canonicalize().ok();
+    let mut asset_dirs: Vec<_> = engine::app_diff::asset_paths(&app)
+        .iter()
+        .filter_map(|path| std::path::Path::new(path).parent()?.canonicalize().ok())
+        .filter(|dir| !watch_root.as_ref().is_some_and(|root| dir.starts_with(root)))
+        .collect();
+    asset_dirs.sort();
+    asset_dirs.dedup();
+    for dir in asset_dirs {
+        if let Err(e) = hotreloader.watch(&dir) {
+            error!("Failed to watch asset directory {}: {}", dir.display(), e);
+        }
+    }
 
     let restart_flag = Arc::clone(&should_restart);
     let file_path_clone = file_path.clone();
-    let app_ref = Arc::clone(&current_app);
-
-    hotreloader.set_reload_callback(move || {
-        if let Ok(new_app) = load_nilo_app(&file_path_clone, enable_lint, enable_debug, quiet) {
-            *app_ref.lock().unwrap() = Some(new_app);
-            *restart_flag.lock().unwrap() = true;
+    let pending_ref = Arc::clone(&pending_reload);
+
+    hotreloader.set_reload_callback(move |changed| {
+        let mut pending = pending_ref.lock().unwrap();
+        let (sources, assets): (Vec<_>, Vec<_>) = changed
+            .iter()
+            .cloned()
+            .partition(|path| path.extension().is_some_and(|ext| ext == "nilo"));
+        if !sources.is_empty() {
+            // 読み込みに失敗した場合は前回のAppのまま、エラーをウィンドウ内に表示する
+            // （まだ反映していない、読み込みに成功したAppがあればそれも捨てない）
+            match load_nilo_app(&file_path_clone, enable_lint, enable_debug, quiet) {
+                Ok(new_app) => {
+                    pending.app = Some(new_app);
+                    pending.error = None;
+                }
+                Err(e) => pending.error = Some(e),
+            }
         }
+        pending.assets.extend(assets);
+        *restart_flag.lock().unwrap() = true;
     });
 
     let start = app.flow.start.clone();
     let mut state = engine::state::AppState::new(initial_state, start);
-    state.initialize_router(&app.flow);
+    state.initialize_router_from_app(&app);
     let app = Arc::new(app);
 
     engine::runtime::run_with_hotreload_support_and_title(
         app,
         state,
         should_restart,
-        current_app,
+        pending_reload,
         window_title,
     );
 }
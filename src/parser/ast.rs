@@ -8,18 +8,21 @@ use log::debug; // debug!マクロを使用するためのインポートを追
 // メインアプリケーション構造
 // ========================================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct App {
     pub flow: Flow,
     pub timelines: Vec<Timeline>,
     pub components: Vec<Component>,
+    pub functions: Vec<FunctionDef>, // ★ ファイルレベルの関数定義（fn）
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Flow {
     pub start: String,
     pub start_url: Option<String>, // WASM用URL
+    pub not_found: Option<String>, // ★ どのURLにも一致しないときのタイムライン（not_found: NotFound）
     pub transitions: Vec<FlowTransition>,
+    pub sub_flows: Vec<SubFlow>, // ★ call_flow で呼び出せる名前付きフロー（flow Signup { ... }）
 }
 
 impl Flow {
@@ -38,6 +41,8 @@ impl Flow {
                     expanded_transitions.push(FlowTransition {
                         from: vec![from_timeline.clone()],
                         to: transition.to.clone(),
+                        condition: transition.condition.clone(),
+                        fallback: transition.fallback.clone(),
                     });
                 }
             }
@@ -48,20 +53,22 @@ impl Flow {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FlowTransition {
     pub from: Vec<String>,
     pub to: Vec<FlowTarget>,
+    pub condition: Option<Expr>, // ★ 遷移の条件（Cart -> Checkout if state.logged_in）
+    pub fallback: Option<FlowTarget>, // ★ 条件が偽のときの遷移先（else Login）
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FlowTarget {
     pub timeline: String,
     pub url: Option<String>, // WASM用URL
     pub params: std::collections::HashMap<String, UrlParam>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum UrlParam {
     Required(String), // :userId
     Optional(String), // :id?
@@ -72,29 +79,37 @@ pub enum UrlParam {
 // 階層的フロー糖衣構文用の型定義
 // ========================================
 
+/// 展開後の名前付きフロー。call_flow(Signup) で呼び出し、finish(value) で呼び出し元へ戻る
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubFlow {
+    pub name: String,
+    pub start: String,          // 修飾済みの開始タイムライン（Signup::Email）
+    pub timelines: Vec<String>, // 修飾済みの所属タイムライン
+}
+
 /// 階層的フロー定義の中間表現 (flow Login { ... })
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NamespacedFlow {
     pub name: String,
     pub start: String,
     pub transitions: Vec<NamespacedTransition>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct NamespacedTransition {
     pub from: Vec<String>,
     pub to: Vec<String>,
 }
 
 /// 名前空間定義 (namespace Login { ... })
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Namespace {
     pub name: String,
     pub timelines: Vec<Timeline>,
     pub components: Vec<Component>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Timeline {
     pub name: String,
     pub url_pattern: Option<String>, // ★ 追加: タイムラインのURLパターン
@@ -102,20 +117,109 @@ pub struct Timeline {
     pub background: Option<String>,  // ★ 追加: タイムラインの背景色
     pub body: Vec<WithSpan<ViewNode>>,
     pub whens: Vec<When>,
+    pub timers: Vec<TimerBlock>,      // ★ 追加: every / after のタイマー
+    pub resources: Vec<ResourceDecl>, // ★ 追加: 非同期リソース
+    pub subscriptions: Vec<SubscribeBlock>, // ★ 追加: WebSocket / SSE の購読
+}
+
+/// ★ タイムラインに入ると開始し、出ると止まるタイマー（`every 1s { ... }` / `after 500ms { ... }`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimerBlock {
+    pub kind: TimerKind,
+    pub interval_ms: u64,
+    pub actions: Vec<WithSpan<ViewNode>>,
+}
+
+/// ★ タイムラインの間だけ接続を保つ購読（`subscribe ws("ws://...") as msg { ... }`）
+///
+/// メッセージを受け取るたびに本文を `var` に入れてアクションを実行する。切れたら間隔を空けて再接続する
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscribeBlock {
+    pub kind: SubscriptionKind,
+    pub url: Expr,
+    pub var: String,
+    pub actions: Vec<WithSpan<ViewNode>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    WebSocket,
+    /// Server-Sent Events（`text/event-stream`）
+    Sse,
 }
 
-#[derive(Debug, Clone)]
+/// ★ 引数を受け取るアクションブロック（`|res| { ... }`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionBlock {
+    pub params: Vec<String>,
+    pub actions: Vec<WithSpan<ViewNode>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpMethod {
+    Get,
+    Post,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerKind {
+    /// 一定間隔で繰り返す
+    Every,
+    /// タイムラインに入ってから一度だけ
+    After,
+}
+
+/// ★ 非同期関数の結果をビューから読むリソース（`resource user = fetch_user!(route.params.id)`）
+///
+/// `user.loading` / `user.error` / `user.value` で状態を参照し、引数の値が変わると取得し直す
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceDecl {
+    pub name: String,
+    /// 呼び出す非同期関数の名前（`register_async_call` で登録したもの）
+    pub call: String,
+    pub args: Vec<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Component {
     pub name: String,
     pub params: Vec<ComponentParam>, // ★ 変更: String から ComponentParam へ
     pub font: Option<String>,        // ★ 追加: コンポーネント全体で使用するフォント
     pub default_style: Option<Style>, // ★ 追加: デフォルトスタイル
+    pub state: Vec<ComponentStateDecl>, // ★ 追加: インスタンスごとのローカル状態
     pub body: Vec<WithSpan<ViewNode>>,
     pub whens: Vec<When>,
 }
 
+/// ★ ファイルレベルの関数定義（`fn full_name(u) = u.first + " " + u.last`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDef {
+    pub name: String,
+    pub params: Vec<FunctionParam>,
+    pub return_type: Option<NiloType>,
+    pub lets: Vec<(String, Expr)>, // 本体の `let`（上から順に評価）
+    pub body: Expr,                // 戻り値の式
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionParam {
+    pub name: String,
+    pub param_type: Option<NiloType>, // 省略時は型チェックしない
+}
+
+/// コンポーネントローカル状態の宣言（state count: Number = 0）
+/// 値は呼び出し位置（とforeachのインデックス）ごとに AppState に保持される
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentStateDecl {
+    pub name: String,
+    pub declared_type: Option<NiloType>,
+    pub initial: Expr,
+}
+
 /// コンポーネントパラメータ定義（Phase 2対応）
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ComponentParam {
     pub name: String,
     pub param_type: ComponentParamType,
@@ -124,7 +228,7 @@ pub struct ComponentParam {
 }
 
 /// コンポーネントパラメータの型定義
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ComponentParamType {
     String,
     Number,
@@ -137,13 +241,19 @@ pub enum ComponentParamType {
 }
 
 /// コンポーネント呼び出し時の引数（位置引数または名前付き引数）
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ComponentArg {
     Positional(Expr),    // 位置引数: Card("value")
     Named(String, Expr), // 名前付き引数: Card(content: "value")
+    /// コールバック引数: ItemRow(on_select: |id| { ... })
+    Handler {
+        name: String,
+        params: Vec<String>,
+        actions: Vec<WithSpan<ViewNode>>,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct When {
     pub event: EventExpr,
     pub actions: Vec<WithSpan<ViewNode>>,
@@ -153,9 +263,10 @@ pub struct When {
 // イベント式
 // ========================================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum EventExpr {
     ButtonPressed(String),
+    ValueChanged(String), // ★ フォーム部品（Checkbox/Switch/RadioGroup）の値が変更された
 
     // ★ 新規追加: テキスト入力関連のイベント
     TextChanged(String),            // テキスト入力フィールドの値が変更された
@@ -170,7 +281,7 @@ pub enum EventExpr {
 // スパン情報付きノード
 // ========================================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WithSpan<T> {
     pub node: T,
     pub line: usize,
@@ -182,7 +293,7 @@ pub struct WithSpan<T> {
 // ビューノード（制御構造を含む）
 // ========================================
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ViewNode {
     // 基本UI要素
     Text {
@@ -203,12 +314,60 @@ pub enum ViewNode {
         id: String,                  // 一意識別子
         placeholder: Option<String>, // プレースホルダーテキスト
         value: Option<Expr>,         // 現在の値（state.field_nameなど）
+        bind: Option<Expr>,          // ★ 双方向バインド先（state.profile.ageなど）
+        input_type: TextInputType,   // ★ 入力値の型（bind時の変換に使用）
         on_change: Option<Expr>,     // 値変更時のコールバック
         multiline: bool,             // 複数行入力対応
         max_length: Option<usize>,   // 最大文字数
         ime_enabled: bool,           // IME機能の有効/無効
     },
 
+    // ★ フォーム部品（クリックでbind先の状態を直接更新する）
+    Checkbox {
+        id: String,            // 一意識別子（when user.change(id) で参照）
+        bind: Expr,            // Bool値のバインド先
+        label: Option<String>, // 右側に表示するラベル
+    },
+    Switch {
+        id: String,
+        bind: Expr,
+        label: Option<String>,
+    },
+    RadioGroup {
+        id: String,
+        bind: Expr,    // 選択中の値のバインド先
+        options: Expr, // 選択肢の配列（リテラルまたはstateのパス）
+    },
+    // ★ 選択肢をオーバーレイのドロップダウンで表示する
+    Select {
+        id: String,
+        bind: Expr,
+        options: Expr,
+        placeholder: Option<String>, // 未選択時の表示
+    },
+    // ★ 数値入力（Sliderはドラッグ、Stepperは −/+ ボタンでbind先を更新する）
+    Slider {
+        id: String,
+        bind: Expr,
+        min: Option<Expr>,  // 省略時 0
+        max: Option<Expr>,  // 省略時 100
+        step: Option<Expr>, // 省略時は連続値
+    },
+    Stepper {
+        id: String,
+        bind: Expr,
+        min: Option<Expr>,  // 省略時は下限なし
+        max: Option<Expr>,  // 省略時は上限なし
+        step: Option<Expr>, // 省略時 1
+    },
+
+    // ★ モーダルダイアログ（open が真の間、タイムラインより手前のオーバーレイ層に表示）
+    Dialog {
+        id: String, // 背景クリック・Escape で閉じたときの user.change(id) の対象
+        open: Expr, // 表示状態（Bool）のバインド先
+        body: Vec<WithSpan<ViewNode>>,
+    },
+
     // レイアウト要素
     VStack(Vec<WithSpan<ViewNode>>),
     HStack(Vec<WithSpan<ViewNode>>),
@@ -241,14 +400,16 @@ pub enum ViewNode {
     // 制御構造
     Match {
         expr: Expr,
-        arms: Vec<(Expr, Vec<WithSpan<ViewNode>>)>,
+        arms: Vec<(MatchCase, Vec<WithSpan<ViewNode>>)>,
         default: Option<Vec<WithSpan<ViewNode>>>,
     },
 
     // ★ 新規追加: foreach制御
     ForEach {
-        var: String,    // 繰り返し変数名 (e.g., "item")
-        iterable: Expr, // 繰り返し対象 (e.g., "state.items")
+        var: String,               // 繰り返し変数名 (e.g., "item")
+        index_var: Option<String>, // ★ インデックス変数名 (e.g., `(i, item)` の "i"。オブジェクトではキー)
+        iterable: Expr,            // 繰り返し対象 (e.g., "state.items", `0..10`)
+        key: Option<Expr>,         // ★ 要素の識別キー (e.g., `key item.id`)
         body: Vec<WithSpan<ViewNode>>,
     },
 
@@ -262,11 +423,46 @@ pub enum ViewNode {
     // アクション
     NavigateTo {
         target: String,
+        params: Vec<(String, Expr)>, // ★ navigate_to(Detail, id: item.id) → route.params.id
+    },
+    NavigateBack,    // ★ navigate_back()
+    NavigateForward, // ★ navigate_forward()
+    /// サブフローを呼び出す（call_flow(Signup, into: state.user, plan: "pro")）
+    /// 名前付き引数はサブフロー内で flow.plan として参照できる
+    CallFlow {
+        flow: String,
+        into: Option<String>, // finish(value) の値を代入するパス
+        params: Vec<(String, Expr)>,
+    },
+    /// サブフローを終えて呼び出し元へ戻る（finish(state.draft) / finish()）
+    Finish {
+        value: Option<Expr>,
+    },
+    /// HTTPリクエストを送る（http_get("/api/items", into: state.items)）
+    /// JSONの応答は into のパスへ代入し、完了後に on_success / on_error のブロックを実行する
+    Http {
+        method: HttpMethod,
+        url: Expr,
+        into: Option<String>,
+        params: Vec<(String, Expr)>, // body / headers / timeout
+        on_success: Option<ActionBlock>,
+        on_error: Option<ActionBlock>,
     },
     RustCall {
         name: String,
         args: Vec<Expr>,
     },
+    /// コンポーネントから呼び出し元のハンドラーを呼ぶ（emit on_select(item.id)）
+    /// 展開後の name は親が渡したrust_call名（アクションブロックの場合は展開時に置換済み）
+    Emit {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// トースト通知を表示する（toast("保存しました") / toast("保存しました", 5000)）
+    Toast {
+        message: Expr,
+        duration: Option<Expr>, // 表示時間（ミリ秒）、省略時は既定値
+    },
 
     // 状態操作
     Set {
@@ -315,11 +511,20 @@ pub enum ViewNode {
 // 式
 // ========================================
 
-#[derive(Debug, Clone)]
+/// ★ TextInputの入力値の型（`type: "number"` など）
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TextInputType {
+    #[default]
+    Text,
+    Number,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     String(String),
     Number(f32),
     Bool(bool),
+    Null, // ★ `null`（評価結果は空文字列）
     Ident(String),
     Path(String),
     Array(Vec<Expr>),
@@ -340,9 +545,15 @@ pub enum Expr {
         op: BinaryOperator,
         right: Box<Expr>,
     },
+    // ★ 条件式 `condition ? then_expr : else_expr`
+    Conditional {
+        condition: Box<Expr>,
+        then_expr: Box<Expr>,
+        else_expr: Box<Expr>,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BinaryOperator {
     Add,
     Sub,
@@ -355,19 +566,79 @@ pub enum BinaryOperator {
     Le, // <=
     Gt, // >
     Ge, // >=
+    // null合体
+    Coalesce, // ??
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MatchArm {
-    pub pattern: Expr,
+    pub case: MatchCase,
     pub value: Expr,
 }
 
+/// ★ matchの `case` 部分（`case "a" | "b"`, `case 0..=9`, `case n if n > 100`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchCase {
+    pub patterns: Vec<MatchPattern>, // `|` 区切り。いずれかに一致すればよい
+    pub binding: Option<String>,     // 一致した値を束縛する名前（`case n` / `case n @ 0..=9`）
+    pub guard: Option<Expr>,         // `if` ガード（束縛した名前を使える）
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchPattern {
+    Value(Expr),
+    Range {
+        start: Expr,
+        end: Expr,
+        inclusive: bool, // `..=` なら終端を含む
+    },
+    Wildcard, // `_` または名前の束縛のみ（常に一致）
+}
+
+impl MatchCase {
+    /// パターンとガードに含まれる式
+    pub fn exprs(&self) -> Vec<&Expr> {
+        let mut exprs = Vec::new();
+        for pattern in &self.patterns {
+            match pattern {
+                MatchPattern::Value(expr) => exprs.push(expr),
+                MatchPattern::Range { start, end, .. } => exprs.extend([start, end]),
+                MatchPattern::Wildcard => {}
+            }
+        }
+        exprs.extend(&self.guard);
+        exprs
+    }
+
+    /// パターンとガードに含まれる式（書き換え用）
+    pub fn exprs_mut(&mut self) -> Vec<&mut Expr> {
+        let mut exprs = Vec::new();
+        for pattern in &mut self.patterns {
+            match pattern {
+                MatchPattern::Value(expr) => exprs.push(expr),
+                MatchPattern::Range { start, end, .. } => exprs.extend([start, end]),
+                MatchPattern::Wildcard => {}
+            }
+        }
+        exprs.extend(&mut self.guard);
+        exprs
+    }
+
+    /// ガードなしで必ず一致する（`case _` / `case n`）
+    pub fn is_catch_all(&self) -> bool {
+        self.guard.is_none()
+            && self
+                .patterns
+                .iter()
+                .any(|p| matches!(p, MatchPattern::Wildcard))
+    }
+}
+
 // ========================================
 // 値型（単位付き数値）
 // ========================================
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct DimensionValue {
     pub value: f32,
     pub unit: Unit,
@@ -468,7 +739,7 @@ pub enum Unit {
 // スタイル関連
 // ========================================
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Style {
     pub color: Option<ColorValue>,
     pub background: Option<ColorValue>,
@@ -532,6 +803,13 @@ pub struct Style {
 
     // ★ レスポンシブ対応: 条件付きスタイル
     pub responsive_rules: Vec<ResponsiveRule>,
+
+    // ★ ホバーが続いたときに表示するツールチップ（`tooltip:` 引数からも設定される）
+    pub tooltip: Option<Expr>,
+
+    // ★ foreachの仮想化（表示範囲の行だけをレイアウト）と1行の高さ
+    pub virtual_list: Option<bool>,
+    pub item_height: Option<f32>,
 }
 
 /// テキスト折り返しモード
@@ -551,7 +829,7 @@ pub enum OverflowMode {
 }
 
 /// レスポンシブデザイン用の条件付きスタイル
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ResponsiveRule {
     pub condition: Expr, // 例: window.width <= 1000
     pub style: Box<Style>,
@@ -717,18 +995,27 @@ impl Style {
         if !other.responsive_rules.is_empty() {
             result.responsive_rules = other.responsive_rules.clone();
         }
+        if other.tooltip.is_some() {
+            result.tooltip = other.tooltip.clone();
+        }
+        if other.virtual_list.is_some() {
+            result.virtual_list = other.virtual_list;
+        }
+        if other.item_height.is_some() {
+            result.item_height = other.item_height;
+        }
 
         result
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ColorValue {
     Rgba([f32; 4]),
     Hex(String),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Align {
     Left,
     Center,
@@ -737,13 +1024,13 @@ pub enum Align {
     Bottom,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Rounded {
     On,
     Px(f32),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Shadow {
     On,
     Spec {
@@ -753,7 +1040,7 @@ pub enum Shadow {
     },
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct Edges {
     pub top: f32,
     pub right: f32,
@@ -781,7 +1068,7 @@ impl Edges {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct RelativeEdges {
     pub top: Option<DimensionValue>,
     pub right: Option<DimensionValue>,
@@ -880,10 +1167,13 @@ impl Default for App {
             flow: Flow {
                 start: "Default".to_string(),
                 start_url: None,
+                not_found: None,
                 transitions: vec![],
+                sub_flows: vec![],
             },
             timelines: vec![],
             components: vec![],
+            functions: vec![],
         }
     }
 }
@@ -940,7 +1230,7 @@ impl NiloType {
 }
 
 /// 型付き式（パーサーで生成）
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TypedExpr {
     pub expr: Expr,
     pub inferred_type: NiloType,
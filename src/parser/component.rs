@@ -8,6 +8,8 @@ use crate::parser::ast::*;
 use crate::parser::expr::parse_expr;
 use crate::parser::parse::Rule;
 use crate::parser::style::style_from_expr;
+use crate::parser::timeline::parse_when_block;
+use crate::parser::types::{infer_expr_type, parse_type_expr};
 use crate::parser::utils::unquote;
 use pest::iterators::Pair;
 
@@ -69,8 +71,9 @@ pub fn parse_component_def(pair: Pair<Rule>) -> Component {
     }
 
     let mut font: Option<String> = None;
+    let mut state: Vec<ComponentStateDecl> = Vec::new();
     let mut body: Vec<WithSpan<ViewNode>> = Vec::new();
-    let whens = Vec::new();
+    let mut whens = Vec::new();
 
     for node_pair in inner {
         match node_pair.as_rule() {
@@ -79,9 +82,16 @@ pub fn parse_component_def(pair: Pair<Rule>) -> Component {
                 let font_str = node_pair.into_inner().next().unwrap().as_str();
                 font = Some(unquote(font_str));
             }
+            Rule::component_state_decl => {
+                state.push(parse_component_state_decl(node_pair));
+            }
             Rule::view_nodes => {
                 for p in node_pair.into_inner() {
-                    body.push(parse_view_node(p));
+                    match p.as_rule() {
+                        // ★ whenブロックはインスタンスごとに登録されるイベントハンドラー
+                        Rule::when_block => whens.push(parse_when_block(p)),
+                        _ => body.push(parse_view_node(p)),
+                    }
                 }
             }
             _ => body.push(parse_view_node(node_pair)),
@@ -93,11 +103,55 @@ pub fn parse_component_def(pair: Pair<Rule>) -> Component {
         params,
         font,
         default_style,
+        state,
         body,
         whens,
     }
 }
 
+/// コンポーネントローカル状態の宣言をパースする (state name: Type = initial)
+pub fn parse_component_state_decl(pair: Pair<Rule>) -> ComponentStateDecl {
+    let (line, col) = pair.as_span().start_pos().line_col();
+    let mut name = String::new();
+    let mut declared_type: Option<NiloType> = None;
+    let mut initial: Option<Expr> = None;
+
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::state_keyword => {}
+            Rule::ident => name = p.as_str().to_string(),
+            Rule::type_annotation => {
+                let type_inner = p.into_inner().next().unwrap();
+                declared_type = Some(parse_type_expr(type_inner));
+            }
+            Rule::expr => initial = Some(parse_expr(p)),
+            _ => {}
+        }
+    }
+
+    let initial = initial.expect("state宣言に初期値がありません");
+
+    if let Some(expected_type) = &declared_type {
+        let inferred_type = infer_expr_type(&initial);
+        if !expected_type.is_compatible_with(&inferred_type) {
+            eprintln!(
+                "[Type Warning] {}:{} - 型の不一致: state '{}' は {} 型として宣言されていますが、{} 型の初期値が指定されました",
+                line,
+                col,
+                name,
+                expected_type.display(),
+                inferred_type.display()
+            );
+        }
+    }
+
+    ComponentStateDecl {
+        name,
+        declared_type,
+        initial,
+    }
+}
+
 /// ★ Phase 2: 型付きパラメータをパース (name: Type = default)
 pub fn parse_typed_param(pair: Pair<Rule>) -> ComponentParam {
     let mut inner = pair.into_inner();
@@ -137,25 +191,12 @@ pub fn parse_enum_param(pair: Pair<Rule>) -> ComponentParam {
 
     for p in inner {
         match p.as_rule() {
-            Rule::string => {
-                let val = unquote(p.as_str());
-                if default_value.is_none() {
-                    enum_values.push(val);
-                } else {
-                    // 最後のstringはデフォルト値
-                    default_value = Some(Expr::String(val));
-                }
-            }
+            Rule::string => enum_values.push(unquote(p.as_str())),
+            Rule::enum_default => default_value = Some(Expr::String(unquote(p.as_str()))),
             _ => {}
         }
     }
 
-    // 最後の要素をデフォルト値として取り出す
-    if default_value.is_none() && !enum_values.is_empty() {
-        let last = enum_values.pop().unwrap();
-        default_value = Some(Expr::String(last));
-    }
-
     ComponentParam {
         name,
         param_type: ComponentParamType::Enum(enum_values),
@@ -25,6 +25,7 @@ pub fn parse_event_expr(pair: Pair<Rule>) -> EventExpr {
         .to_string();
     match kind {
         "click" => EventExpr::ButtonPressed(target),
+        "change" => EventExpr::ValueChanged(target),
         _ => panic!("不明なevent_kind: {:?}", kind),
     }
 }
@@ -175,6 +176,7 @@ pub fn parse_expr(pair: Pair<Rule>) -> Expr {
             }
         }
         Rule::bool => Expr::Bool(pair.as_str() == "true"),
+        Rule::null_lit => Expr::Null,
         Rule::ident => Expr::Ident(pair.as_str().to_string()),
         Rule::path => Expr::Path(pair.as_str().to_string()),
         Rule::array => {
@@ -210,9 +212,9 @@ pub fn parse_expr(pair: Pair<Rule>) -> Expr {
                 match arm_pair.as_rule() {
                     Rule::expr_match_arm => {
                         let mut arm_inner = arm_pair.into_inner();
-                        let pattern = parse_expr(arm_inner.next().unwrap());
+                        let case = parse_match_case(arm_inner.next().unwrap());
                         let value = parse_expr(arm_inner.next().unwrap());
-                        arms.push(MatchArm { pattern, value });
+                        arms.push(MatchArm { case, value });
                     }
                     Rule::expr_default_arm => {
                         let mut default_inner = arm_pair.into_inner();
@@ -229,6 +231,8 @@ pub fn parse_expr(pair: Pair<Rule>) -> Expr {
                 default,
             }
         }
+        Rule::conditional_expr => parse_conditional_expr(pair),
+        Rule::coalesce_expr => parse_coalesce_expr(pair),
         _ => {
             // 比較式として解析を試行
             parse_comparison_expr(pair)
@@ -236,6 +240,84 @@ pub fn parse_expr(pair: Pair<Rule>) -> Expr {
     }
 }
 
+/// 条件式（`cond ? a : b`）をパースする
+pub fn parse_conditional_expr(pair: Pair<Rule>) -> Expr {
+    let mut inner = pair.into_inner();
+    let condition = parse_coalesce_expr(inner.next().unwrap());
+    match (inner.next(), inner.next()) {
+        (Some(then_pair), Some(else_pair)) => Expr::Conditional {
+            condition: Box::new(condition),
+            then_expr: Box::new(parse_expr(then_pair)),
+            else_expr: Box::new(parse_expr(else_pair)),
+        },
+        _ => condition,
+    }
+}
+
+/// null合体（`a ?? b ?? c`）をパースする
+pub fn parse_coalesce_expr(pair: Pair<Rule>) -> Expr {
+    let mut inner = pair.into_inner();
+    let mut left = parse_comparison_expr(inner.next().unwrap());
+    for right_pair in inner {
+        left = Expr::BinaryOp {
+            left: Box::new(left),
+            op: BinaryOperator::Coalesce,
+            right: Box::new(parse_comparison_expr(right_pair)),
+        };
+    }
+    left
+}
+
+/// matchの `case` 部分をパースする
+/// 単独の名前（`case n`）は値を束縛して常に一致するパターンとして扱う
+pub fn parse_match_case(pair: Pair<Rule>) -> MatchCase {
+    let mut case = MatchCase {
+        patterns: Vec::new(),
+        binding: None,
+        guard: None,
+    };
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::match_binding => case.binding = Some(part.as_str().to_string()),
+            Rule::match_guard => case.guard = Some(parse_expr(part.into_inner().last().unwrap())),
+            Rule::match_pattern => {
+                let pattern = part.into_inner().next().unwrap();
+                let pattern = match pattern.as_rule() {
+                    Rule::match_wildcard => MatchPattern::Wildcard,
+                    Rule::match_range => {
+                        let mut parts = pattern.into_inner();
+                        let start = parse_range_bound(parts.next().unwrap());
+                        let inclusive = parts.next().unwrap().as_str() == "..=";
+                        let end = parse_range_bound(parts.next().unwrap());
+                        MatchPattern::Range {
+                            start,
+                            end,
+                            inclusive,
+                        }
+                    }
+                    _ => match parse_expr(pattern) {
+                        Expr::Path(name) | Expr::Ident(name)
+                            if case.binding.is_none() && !name.contains(['.', '[', ':']) =>
+                        {
+                            case.binding = Some(name);
+                            MatchPattern::Wildcard
+                        }
+                        expr => MatchPattern::Value(expr),
+                    },
+                };
+                case.patterns.push(pattern);
+            }
+            _ => {}
+        }
+    }
+    case
+}
+
+/// 範囲の端（`range_bound`）をパースする
+fn parse_range_bound(pair: Pair<Rule>) -> Expr {
+    parse_expr(pair.into_inner().next().unwrap())
+}
+
 /// 比較式をパースする
 pub fn parse_comparison_expr(pair: Pair<Rule>) -> Expr {
     let mut inner = pair.into_inner();
@@ -356,9 +438,9 @@ pub fn parse_primary(pair: Pair<Rule>) -> Expr {
                 match arm_pair.as_rule() {
                     Rule::expr_match_arm => {
                         let mut arm_inner = arm_pair.into_inner();
-                        let pattern = parse_expr(arm_inner.next().unwrap());
+                        let case = parse_match_case(arm_inner.next().unwrap());
                         let value = parse_expr(arm_inner.next().unwrap());
-                        arms.push(MatchArm { pattern, value });
+                        arms.push(MatchArm { case, value });
                     }
                     Rule::expr_default_arm => {
                         let mut default_inner = arm_pair.into_inner();
@@ -426,6 +508,7 @@ pub fn parse_primary(pair: Pair<Rule>) -> Expr {
             Expr::Number(v)
         }
         Rule::bool => Expr::Bool(pair.as_str() == "true"),
+        Rule::null_lit => Expr::Null,
         Rule::path => Expr::Path(pair.as_str().to_string()),
         Rule::ident => Expr::Ident(pair.as_str().to_string()),
         // ★ 通常の関数呼び出し（onclick用）
@@ -5,6 +5,7 @@
 // このモジュールはフロー定義と遷移の解析を担当します。
 
 use crate::parser::ast::*;
+use crate::parser::expr::parse_expr;
 use crate::parser::parse::Rule;
 use crate::parser::utils::unquote;
 use pest::iterators::Pair;
@@ -15,6 +16,7 @@ pub fn parse_flow_def(pair: Pair<Rule>) -> Result<Flow, String> {
 
     let mut start = None;
     let mut start_url = None;
+    let mut not_found = None;
     let mut transitions = Vec::new();
 
     for inner in pair.into_inner() {
@@ -35,6 +37,10 @@ pub fn parse_flow_def(pair: Pair<Rule>) -> Result<Flow, String> {
                     _ => return Err("Invalid start definition".to_string()),
                 }
             }
+            Rule::not_found_def => {
+                // どのURLにも一致しないときのタイムライン
+                not_found = Some(inner.into_inner().next().unwrap().as_str().to_string());
+            }
             Rule::transition_def => {
                 // 遷移定義を実際に解析
                 let transition = parse_transition_def(inner)?;
@@ -51,7 +57,9 @@ pub fn parse_flow_def(pair: Pair<Rule>) -> Result<Flow, String> {
     let flow = Flow {
         start,
         start_url,
+        not_found,
         transitions,
+        sub_flows: Vec::new(),
     };
     Ok(flow.normalize())
 }
@@ -86,8 +94,8 @@ pub fn parse_flow_target(pair: Pair<Rule>) -> Result<FlowTarget, String> {
                     let (timeline, url) = parse_timeline_with_url(inner)?;
                     Ok(FlowTarget {
                         timeline,
+                        params: url_params(&url),
                         url: Some(url),
-                        params: std::collections::HashMap::new(),
                     })
                 }
                 Rule::qualified_ident => Ok(FlowTarget {
@@ -105,8 +113,8 @@ pub fn parse_flow_target(pair: Pair<Rule>) -> Result<FlowTarget, String> {
             let (timeline, url) = parse_timeline_with_url(pair)?;
             Ok(FlowTarget {
                 timeline,
+                params: url_params(&url),
                 url: Some(url),
-                params: std::collections::HashMap::new(),
             })
         }
         Rule::qualified_ident => Ok(FlowTarget {
@@ -118,6 +126,30 @@ pub fn parse_flow_target(pair: Pair<Rule>) -> Result<FlowTarget, String> {
     }
 }
 
+/// URLパターンのパラメータを取り出す（`/users/:id<number>/:tab?/*rest`）
+fn url_params(url: &str) -> std::collections::HashMap<String, UrlParam> {
+    url.split('/')
+        .filter_map(|segment| {
+            if let Some(name) = segment.strip_prefix('*') {
+                let name = if name.is_empty() { "*" } else { name };
+                return Some((name.to_string(), UrlParam::Wildcard));
+            }
+            let param = segment.strip_prefix(':')?;
+            let (param, optional) = match param.strip_suffix('?') {
+                Some(p) => (p, true),
+                None => (param, false),
+            };
+            let name = param.split('<').next().unwrap_or(param).to_string();
+            let kind = if optional {
+                UrlParam::Optional(name.clone())
+            } else {
+                UrlParam::Required(name.clone())
+            };
+            Some((name, kind))
+        })
+        .collect()
+}
+
 /// 遷移定義を解析する新しい関数
 pub fn parse_transition_def(pair: Pair<Rule>) -> Result<FlowTransition, String> {
     assert_eq!(pair.as_rule(), Rule::transition_def);
@@ -132,7 +164,25 @@ pub fn parse_transition_def(pair: Pair<Rule>) -> Result<FlowTransition, String>
     let target_pair = inner.next().ok_or("遷移定義に遷移先がありません")?;
     let to = parse_transition_targets_new(target_pair)?;
 
-    Ok(FlowTransition { from, to })
+    // 条件（if ... else ...）の解析
+    let mut condition = None;
+    let mut fallback = None;
+    if let Some(condition_pair) = inner.next() {
+        for part in condition_pair.into_inner() {
+            match part.as_rule() {
+                Rule::expr => condition = Some(parse_expr(part)),
+                Rule::flow_target => fallback = Some(parse_flow_target(part)?),
+                _ => {}
+            }
+        }
+    }
+
+    Ok(FlowTransition {
+        from,
+        to,
+        condition,
+        fallback,
+    })
 }
 
 /// 遷移元の解析
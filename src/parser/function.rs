@@ -0,0 +1,68 @@
+// ========================================
+// 関数定義パーサーモジュール
+// ========================================
+//
+// このモジュールはファイルレベルの関数定義（fn）の解析を担当します。
+
+use crate::parser::ast::*;
+use crate::parser::expr::parse_expr;
+use crate::parser::parse::Rule;
+use crate::parser::types::parse_type_expr;
+use pest::iterators::Pair;
+
+/// 関数定義をパースする
+/// 形式: fn name(a, b: Number): String = <expr>
+///       fn name(a) { let x = <expr> ... <expr> }
+pub fn parse_function_def(pair: Pair<Rule>) -> FunctionDef {
+    let (line, column) = pair.as_span().start_pos().line_col();
+
+    let mut name = String::new();
+    let mut params = Vec::new();
+    let mut return_type = None;
+    let mut lets = Vec::new();
+    let mut body = Expr::Null;
+
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::ident => name = p.as_str().to_string(),
+            Rule::function_param => {
+                let mut param_inner = p.into_inner();
+                let param_name = param_inner.next().unwrap().as_str().to_string();
+                params.push(FunctionParam {
+                    name: param_name,
+                    param_type: param_inner.next().map(parse_type_annotation),
+                });
+            }
+            Rule::type_annotation => return_type = Some(parse_type_annotation(p)),
+            Rule::function_expr_body => body = parse_expr(p.into_inner().next().unwrap()),
+            Rule::function_block_body => {
+                for statement in p.into_inner() {
+                    match statement.as_rule() {
+                        Rule::function_let => {
+                            let mut let_inner = statement.into_inner();
+                            let let_name = let_inner.next().unwrap().as_str().to_string();
+                            lets.push((let_name, parse_expr(let_inner.next().unwrap())));
+                        }
+                        _ => body = parse_expr(statement),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    FunctionDef {
+        name,
+        params,
+        return_type,
+        lets,
+        body,
+        line,
+        column,
+    }
+}
+
+/// `: Type` を型にする
+fn parse_type_annotation(pair: Pair<Rule>) -> NiloType {
+    parse_type_expr(pair.into_inner().next().unwrap())
+}
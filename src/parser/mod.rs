@@ -5,6 +5,7 @@ pub mod parse;
 pub mod component;
 pub mod expr;
 pub mod flow;
+pub mod function;
 pub mod namespace;
 pub mod style;
 pub mod timeline;
@@ -239,33 +239,32 @@ pub fn expand_namespaced_structures(
                 })
                 .collect();
 
-            let resolved_to: Vec<FlowTarget> = transition
-                .to
-                .iter()
-                .map(|target| {
-                    // 遷移先がNamespacedFlow名の場合、そのstartタイムラインに展開
-                    let resolved_timeline =
-                        if let Some(flow_start) = flow_start_map.get(&target.timeline) {
-                            log::info!(
-                                "Resolved transition target '{}' to '{}'",
-                                target.timeline,
-                                flow_start
-                            );
-                            flow_start.clone()
-                        } else {
-                            target.timeline.clone()
-                        };
-                    FlowTarget {
-                        timeline: resolved_timeline,
-                        url: target.url.clone(),
-                        params: target.params.clone(),
-                    }
-                })
-                .collect();
+            // 遷移先がNamespacedFlow名の場合、そのstartタイムラインに展開
+            let resolve_target = |target: &FlowTarget| {
+                let resolved_timeline =
+                    if let Some(flow_start) = flow_start_map.get(&target.timeline) {
+                        log::info!(
+                            "Resolved transition target '{}' to '{}'",
+                            target.timeline,
+                            flow_start
+                        );
+                        flow_start.clone()
+                    } else {
+                        target.timeline.clone()
+                    };
+                FlowTarget {
+                    timeline: resolved_timeline,
+                    url: target.url.clone(),
+                    params: target.params.clone(),
+                }
+            };
+            let resolved_to: Vec<FlowTarget> = transition.to.iter().map(resolve_target).collect();
 
             all_transitions.push(FlowTransition {
                 from: expanded_from,
                 to: resolved_to,
+                condition: transition.condition.clone(),
+                fallback: transition.fallback.as_ref().map(resolve_target),
             });
         }
     }
@@ -307,6 +306,8 @@ pub fn expand_namespaced_structures(
             all_transitions.push(FlowTransition {
                 from: from_qualified,
                 to: to_qualified,
+                condition: None,
+                fallback: None,
             });
         }
 
@@ -318,10 +319,26 @@ pub fn expand_namespaced_structures(
 
     let start = global_start.ok_or("start定義が見つかりません")?;
 
+    // ★ 名前付きフローは call_flow で呼び出せるサブフローとしても登録する
+    let sub_flows = namespaced_flows
+        .iter()
+        .map(|nf| {
+            let mut timelines = flow_states_map.remove(&nf.name).unwrap_or_default();
+            timelines.sort();
+            SubFlow {
+                name: nf.name.clone(),
+                start: flow_start_map[&nf.name].clone(),
+                timelines,
+            }
+        })
+        .collect();
+
     let flow = Flow {
         start,
         start_url: None,
+        not_found: None,
         transitions: all_transitions,
+        sub_flows,
     };
 
     Ok((flow.normalize(), timelines))
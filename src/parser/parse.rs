@@ -25,6 +25,7 @@ use crate::parser::ast::*;
 // モジュール化された関数をインポート
 pub use super::component::parse_component_def;
 pub use super::flow::parse_flow_def;
+pub use super::function::parse_function_def;
 pub use super::timeline::parse_timeline_def;
 
 use super::namespace::{
@@ -60,7 +61,7 @@ pub type ParseRule = Rule;
 ///
 /// # 処理フロー
 /// 1. Pestパーサーでソースコードを字句解析・構文解析
-/// 2. フロー定義、タイムライン定義、コンポーネント定義を抽出
+/// 2. フロー定義、タイムライン定義、コンポーネント定義、関数定義を抽出
 /// 3. 名前空間定義を展開
 /// 4. App ASTを構築して返す
 pub fn parse_nilo(source: &str) -> Result<App, String> {
@@ -80,6 +81,7 @@ pub fn parse_nilo(source: &str) -> Result<App, String> {
     let mut flow: Option<Flow> = None;
     let mut timelines = Vec::new();
     let mut components = Vec::new();
+    let mut functions = Vec::new();
     let mut namespaced_flows = Vec::new();
     let mut namespaces = Vec::new();
 
@@ -107,6 +109,9 @@ pub fn parse_nilo(source: &str) -> Result<App, String> {
                 let component = parse_component_def(pair);
                 components.push(component);
             }
+            Rule::function_def => {
+                functions.push(parse_function_def(pair));
+            }
             _ => {} // その他のルールは無視
         }
     }
@@ -126,11 +131,13 @@ pub fn parse_nilo(source: &str) -> Result<App, String> {
     log::debug!("   - Flow start: {}", flow.start);
     log::debug!("   - Timelines: {}", timelines.len());
     log::debug!("   - Components: {}", components.len());
+    log::debug!("   - Functions: {}", functions.len());
 
     Ok(App {
         flow,
         timelines,
         components,
+        functions,
     })
 }
 
@@ -250,6 +257,843 @@ mod tests {
         assert_eq!(app.timelines[0].name, "TopTimeline");
     }
 
+    #[test]
+    fn test_parse_component_local_state() {
+        let source = r#"
+            component Counter(label: String) {
+                state count: Number = 0
+                state open = false
+
+                Text("{}: {}", label, count)
+                state.total = 0
+                Button(id: "inc", label: "+")
+
+                when user.click(inc) {
+                    set count = count + 1
+                }
+            }
+
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                Counter("A")
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let comp = &result.unwrap().components[0];
+        let names: Vec<&str> = comp.state.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["count", "open"]);
+        assert_eq!(comp.state[0].declared_type, Some(NiloType::Number));
+        assert_eq!(comp.whens.len(), 1);
+        // state.xxx への代入はローカル状態の宣言として扱われない
+        assert_eq!(comp.body.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_emit_and_handler_arg() {
+        let source = r#"
+            component ItemRow(label: String, on_select: Function) {
+                Button(id: "row", label: label)
+
+                when user.click(row) {
+                    emit on_select(label)
+                }
+            }
+
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                ItemRow("a", on_select: |id| {
+                    set state.selected = id
+                })
+                ItemRow("b", on_select: select_item)
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let app = result.unwrap();
+        let comp = &app.components[0];
+        assert!(matches!(
+            &comp.whens[0].actions[0].node,
+            ViewNode::Emit { name, args } if name == "on_select" && args.len() == 1
+        ));
+
+        let body = &app.timelines[0].body;
+        match &body[0].node {
+            ViewNode::ComponentCall { args, .. } => match &args[1] {
+                ComponentArg::Handler {
+                    name,
+                    params,
+                    actions,
+                } => {
+                    assert_eq!(name, "on_select");
+                    assert_eq!(params, &vec!["id".to_string()]);
+                    assert_eq!(actions.len(), 1);
+                }
+                other => panic!("Expected handler arg, got {:?}", other),
+            },
+            other => panic!("Expected component call, got {:?}", other),
+        }
+        match &body[1].node {
+            ViewNode::ComponentCall { args, .. } => {
+                assert!(matches!(&args[1], ComponentArg::Named(name, _) if name == "on_select"))
+            }
+            other => panic!("Expected component call, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_text_input_bind() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                TextInput("age", bind: state.profile.age, type: "number")
+                TextInput("name", bind: state.profile.name)
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let body = &result.unwrap().timelines[0].body;
+        match &body[0].node {
+            ViewNode::TextInput {
+                id,
+                bind: Some(Expr::Path(path)),
+                input_type,
+                ..
+            } => {
+                assert_eq!(id, "age");
+                assert_eq!(path, "state.profile.age");
+                assert_eq!(*input_type, TextInputType::Number);
+            }
+            other => panic!("Expected bound text input, got {:?}", other),
+        }
+        assert!(matches!(
+            &body[1].node,
+            ViewNode::TextInput {
+                input_type: TextInputType::Text,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_form_controls() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                Checkbox(bind: state.profile.agree, label: "同意する")
+                Switch(notify_switch, bind: state.notify)
+                RadioGroup(bind: state.size, options: ["S", "M", "L"])
+
+                when user.change(size) {
+                    set state.changed = true
+                }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let timeline = &result.unwrap().timelines[0];
+        match &timeline.body[0].node {
+            ViewNode::Checkbox { id, label, .. } => {
+                // idを省略した場合はbind先パスの末尾
+                assert_eq!(id, "agree");
+                assert_eq!(label.as_deref(), Some("同意する"));
+            }
+            other => panic!("Expected checkbox, got {:?}", other),
+        }
+        assert!(matches!(
+            &timeline.body[1].node,
+            ViewNode::Switch { id, label: None, .. } if id == "notify_switch"
+        ));
+        assert!(matches!(
+            &timeline.body[2].node,
+            ViewNode::RadioGroup { id, options: Expr::Array(items), .. } if id == "size" && items.len() == 3
+        ));
+        assert!(matches!(
+            &timeline.whens[0].event,
+            EventExpr::ValueChanged(target) if target == "size"
+        ));
+    }
+
+    #[test]
+    fn test_parse_select() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                Select(bind: state.country, options: state.countries, placeholder: "国を選択")
+                Select(size_select, bind: state.size, options: ["S", "M", "L"])
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let timeline = &result.unwrap().timelines[0];
+        match &timeline.body[0].node {
+            ViewNode::Select {
+                id,
+                options,
+                placeholder,
+                ..
+            } => {
+                assert_eq!(id, "country");
+                assert!(matches!(options, Expr::Path(p) if p == "state.countries"));
+                assert_eq!(placeholder.as_deref(), Some("国を選択"));
+            }
+            other => panic!("Expected select, got {:?}", other),
+        }
+        assert!(matches!(
+            &timeline.body[1].node,
+            ViewNode::Select { id, placeholder: None, .. } if id == "size_select"
+        ));
+    }
+
+    #[test]
+    fn test_parse_slider_stepper() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                Slider(bind: state.volume, min: 0, max: 100, step: 5)
+                Stepper(qty, bind: state.quantity, min: 1)
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let timeline = &result.unwrap().timelines[0];
+        match &timeline.body[0].node {
+            ViewNode::Slider {
+                id, min, max, step, ..
+            } => {
+                assert_eq!(id, "volume");
+                assert!(matches!(min, Some(Expr::Number(n)) if *n == 0.0));
+                assert!(matches!(max, Some(Expr::Number(n)) if *n == 100.0));
+                assert!(matches!(step, Some(Expr::Number(n)) if *n == 5.0));
+            }
+            other => panic!("Expected slider, got {:?}", other),
+        }
+        assert!(matches!(
+            &timeline.body[1].node,
+            ViewNode::Stepper { id, min: Some(_), max: None, step: None, .. } if id == "qty"
+        ));
+    }
+
+    #[test]
+    fn test_parse_dialog_toast_tooltip() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                Text("Hover me", tooltip: "説明")
+                Dialog(confirm, open: state.show_confirm) {
+                    Text("Delete?")
+                    Button(id: ok, label: "OK", tooltip: "削除します")
+                }
+
+                when user.click(ok) {
+                    toast("Deleted", 2000)
+                }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let timeline = &result.unwrap().timelines[0];
+        let tooltip =
+            |node: &WithSpan<ViewNode>| node.style.as_ref().and_then(|s| s.tooltip.clone());
+        assert!(matches!(tooltip(&timeline.body[0]), Some(Expr::String(s)) if s == "説明"));
+        match &timeline.body[1].node {
+            ViewNode::Dialog { id, open, body } => {
+                assert_eq!(id, "confirm");
+                assert!(matches!(open, Expr::Path(p) if p == "state.show_confirm"));
+                assert_eq!(body.len(), 2);
+                assert!(matches!(tooltip(&body[1]), Some(Expr::String(s)) if s == "削除します"));
+            }
+            other => panic!("Expected dialog, got {:?}", other),
+        }
+        assert!(matches!(
+            &timeline.whens[0].actions[0].node,
+            ViewNode::Toast { message: Expr::String(m), duration: Some(_) } if m == "Deleted"
+        ));
+    }
+
+    #[test]
+    fn test_parse_virtual_foreach() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                foreach item in state.logs (virtual: true, item_height: 24px) {
+                    Text("{}", item)
+                }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let node = &result.unwrap().timelines[0].body[0];
+        assert!(
+            matches!(&node.node, ViewNode::ForEach { var, body, .. } if var == "item" && body.len() == 1)
+        );
+        let style = node.style.as_ref().expect("foreach style");
+        assert_eq!(style.virtual_list, Some(true));
+        assert_eq!(style.item_height, Some(24.0));
+    }
+
+    #[test]
+    fn test_parse_keyed_foreach_and_range() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                foreach (i, item) in state.items key item.id {
+                    Text("{}. {}", i + 1, item.title)
+                }
+                foreach n in 0..state.count {
+                    Text("{}", n)
+                }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let timeline = &result.unwrap().timelines[0];
+        match &timeline.body[0].node {
+            ViewNode::ForEach {
+                var,
+                index_var,
+                iterable,
+                key,
+                ..
+            } => {
+                assert_eq!(var, "item");
+                assert_eq!(index_var.as_deref(), Some("i"));
+                assert!(matches!(iterable, Expr::Path(p) if p == "state.items"));
+                assert!(matches!(key, Some(Expr::Path(p)) if p == "item.id"));
+            }
+            other => panic!("Expected foreach, got {:?}", other),
+        }
+        match &timeline.body[1].node {
+            ViewNode::ForEach { iterable, .. } => assert!(matches!(
+                iterable,
+                Expr::FunctionCall { name, args } if name == "range" && args.len() == 2
+            )),
+            other => panic!("Expected foreach, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_indexed_paths() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                Text("{}", state.users[0].address.city)
+                Button(id: save, label: "Save")
+                when user.click(save) {
+                    set state.labels["title"] = "Hello"
+                    append(state.users[state.selected].tags, "new")
+                }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let timeline = &result.unwrap().timelines[0];
+        match &timeline.body[0].node {
+            ViewNode::Text { args, .. } => assert!(matches!(
+                &args[0],
+                Expr::Path(p) if p == "state.users[0].address.city"
+            )),
+            other => panic!("Expected text, got {:?}", other),
+        }
+        let actions = &timeline.whens[0].actions;
+        assert!(matches!(
+            &actions[0].node,
+            ViewNode::Set { path, .. } if path == r#"state.labels["title"]"#
+        ));
+        assert!(matches!(
+            &actions[1].node,
+            ViewNode::ListAppend { path, .. } if path == "state.users[state.selected].tags"
+        ));
+    }
+
+    #[test]
+    fn test_parse_conditional_and_null_safety() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                Text("{}", state.count > 0 ? state.count : null)
+                Text("{}", state.user?.profile?.name ?? state.nickname ?? "guest")
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+
+        let timeline = &result.unwrap().timelines[0];
+        match &timeline.body[0].node {
+            ViewNode::Text { args, .. } => match &args[0] {
+                Expr::Conditional {
+                    condition,
+                    else_expr,
+                    ..
+                } => {
+                    assert!(matches!(
+                        condition.as_ref(),
+                        Expr::BinaryOp {
+                            op: BinaryOperator::Gt,
+                            ..
+                        }
+                    ));
+                    assert!(matches!(else_expr.as_ref(), Expr::Null));
+                }
+                other => panic!("Expected conditional, got {:?}", other),
+            },
+            other => panic!("Expected text, got {:?}", other),
+        }
+        match &timeline.body[1].node {
+            ViewNode::Text { args, .. } => match &args[0] {
+                Expr::BinaryOp {
+                    left,
+                    op: BinaryOperator::Coalesce,
+                    right,
+                } => {
+                    assert!(matches!(right.as_ref(), Expr::String(s) if s == "guest"));
+                    assert!(matches!(
+                        left.as_ref(),
+                        Expr::BinaryOp { left, op: BinaryOperator::Coalesce, .. }
+                            if matches!(left.as_ref(), Expr::Path(p) if p == "state.user?.profile?.name")
+                    ));
+                }
+                other => panic!("Expected coalesce, got {:?}", other),
+            },
+            other => panic!("Expected text, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_match_patterns() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            timeline TopTimeline {
+                match state.score {
+                    case 0..=9 { Text("一桁") }
+                    case n if n > 100 { Text("{}点", n) }
+                    default { Text("その他") }
+                }
+            }
+
+            component Badge(size: ("small" | "medium" | "large")) {
+                Text("{}", match size { case "small" | "medium" { "S" } case _ { "L" } })
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        match &app.timelines[0].body[0].node {
+            ViewNode::Match { arms, default, .. } => {
+                assert_eq!(arms.len(), 2);
+                assert!(matches!(
+                    arms[0].0.patterns[..],
+                    [MatchPattern::Range {
+                        inclusive: true,
+                        ..
+                    }]
+                ));
+                assert_eq!(arms[1].0.binding.as_deref(), Some("n"));
+                assert!(arms[1].0.guard.is_some());
+                assert!(default.is_some());
+            }
+            other => panic!("Expected match, got {:?}", other),
+        }
+
+        let badge = &app.components[0];
+        assert!(matches!(
+            &badge.params[0].param_type,
+            ComponentParamType::Enum(values) if values.len() == 3
+        ));
+        match &badge.body[0].node {
+            ViewNode::Text { args, .. } => match &args[0] {
+                Expr::Match { arms, .. } => {
+                    assert_eq!(arms[0].case.patterns.len(), 2);
+                    assert!(arms[1].case.is_catch_all());
+                }
+                other => panic!("Expected match expression, got {:?}", other),
+            },
+            other => panic!("Expected text, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_function_defs() {
+        let source = r#"
+            flow {
+                start: TopTimeline
+            }
+
+            fn full_name(u) = u.first + " " + u.last
+
+            fn price_label(price: Number, currency: String): String {
+                let rounded = round(price, 2)
+                currency + format_number(rounded, 2)
+            }
+
+            timeline TopTimeline {
+                Text("{}", full_name(state.user))
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        assert_eq!(app.functions.len(), 2);
+        let full_name = &app.functions[0];
+        assert_eq!(full_name.name, "full_name");
+        assert_eq!(full_name.params.len(), 1);
+        assert!(full_name.params[0].param_type.is_none());
+        assert!(full_name.lets.is_empty());
+        assert!(matches!(full_name.body, Expr::BinaryOp { .. }));
+
+        let price_label = &app.functions[1];
+        assert!(matches!(
+            price_label.params[0].param_type,
+            Some(NiloType::Number)
+        ));
+        assert!(matches!(price_label.return_type, Some(NiloType::String)));
+        assert_eq!(price_label.lets.len(), 1);
+        assert_eq!(price_label.lets[0].0, "rounded");
+        assert!(matches!(price_label.body, Expr::BinaryOp { .. }));
+    }
+
+    #[test]
+    fn test_parse_flow_routes() {
+        let source = r#"
+            flow {
+                start: Home("/")
+                not_found: Missing
+                Home -> UserPage("/users/:id<number>/:tab?/*rest")
+            }
+
+            timeline Home { Text("home") }
+            timeline UserPage { Text("{}", route.params.id) }
+            timeline Missing { Text("404") }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        assert_eq!(app.flow.not_found.as_deref(), Some("Missing"));
+        let target = &app.flow.transitions[0].to[0];
+        assert!(matches!(&target.params["id"], UrlParam::Required(name) if name == "id"));
+        assert!(matches!(target.params["tab"], UrlParam::Optional(_)));
+        assert!(matches!(target.params["rest"], UrlParam::Wildcard));
+    }
+
+    #[test]
+    fn test_parse_navigate_with_params() {
+        let source = r#"
+            flow {
+                start: List
+                List -> Detail("/items/:id<number>")
+            }
+
+            timeline List {
+                Button(id: open_btn, label: "開く")
+                when user.click(open_btn) { navigate_to(Detail, id: state.selected, from: "list") }
+            }
+
+            timeline Detail {
+                Button(id: back_btn, label: "戻る")
+                when user.click(back_btn) { navigate_back() }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        match &app.timelines[0].whens[0].actions[0].node {
+            ViewNode::NavigateTo { target, params } => {
+                assert_eq!(target, "Detail");
+                assert_eq!(params.len(), 2);
+                assert_eq!(params[0].0, "id");
+                assert!(matches!(&params[1].1, Expr::String(s) if s == "list"));
+            }
+            other => panic!("Expected navigate_to, got {:?}", other),
+        }
+        assert!(matches!(
+            app.timelines[1].whens[0].actions[0].node,
+            ViewNode::NavigateBack
+        ));
+    }
+
+    #[test]
+    fn test_parse_conditional_transition() {
+        let source = r#"
+            flow {
+                start: Cart
+                Cart -> Checkout("/checkout") if state.logged_in else Login("/login")
+                Login -> Cart
+            }
+
+            timeline Cart { Text("cart") }
+            timeline Checkout { Text("checkout") }
+            timeline Login { Text("login") }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        let transition = &app.flow.transitions[0];
+        assert_eq!(transition.to[0].timeline, "Checkout");
+        assert!(matches!(&transition.condition, Some(Expr::Path(p)) if p == "state.logged_in"));
+        let fallback = transition.fallback.as_ref().expect("else target");
+        assert_eq!(fallback.timeline, "Login");
+        assert_eq!(fallback.url.as_deref(), Some("/login"));
+        assert!(app.flow.transitions[1].condition.is_none());
+    }
+
+    #[test]
+    fn test_parse_sub_flow() {
+        let source = r#"
+            flow {
+                start: Home
+            }
+            flow Signup {
+                start: Email
+                Email -> Profile
+            }
+            namespace Signup {
+                timeline Email { Text("email") }
+                timeline Profile {
+                    Button(id: done_btn, label: "完了")
+                    when user.click(done_btn) { finish(flow.email) }
+                }
+            }
+            timeline Home {
+                Button(id: signup_btn, label: "登録")
+                when user.click(signup_btn) { call_flow(Signup, into: state.user, plan: "pro") }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        let sub_flow = &app.flow.sub_flows[0];
+        assert_eq!(sub_flow.name, "Signup");
+        assert_eq!(sub_flow.start, "Signup::Email");
+        assert_eq!(sub_flow.timelines, vec!["Signup::Email", "Signup::Profile"]);
+
+        let home = app.timelines.iter().find(|t| t.name == "Home").unwrap();
+        match &home.whens[0].actions[0].node {
+            ViewNode::CallFlow { flow, into, params } => {
+                assert_eq!(flow, "Signup");
+                assert_eq!(into.as_deref(), Some("state.user"));
+                assert_eq!(params[0].0, "plan");
+            }
+            other => panic!("Expected call_flow, got {:?}", other),
+        }
+        let profile = app
+            .timelines
+            .iter()
+            .find(|t| t.name == "Signup::Profile")
+            .unwrap();
+        assert!(matches!(
+            &profile.whens[0].actions[0].node,
+            ViewNode::Finish { value: Some(Expr::Path(p)) } if p == "flow.email"
+        ));
+    }
+
+    #[test]
+    fn test_parse_timers() {
+        let source = r#"
+            flow { start: Clock }
+            timeline Clock {
+                Text("{}", state.tick)
+                every 1s { set state.tick = state.tick + 1 }
+                VStack() { Text("after") }
+                after 1.5s { navigate_to(Clock) }
+                every 250ms { toast("tick") }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        let timeline = &app.timelines[0];
+        assert_eq!(timeline.body.len(), 2);
+        let timers: Vec<_> = timeline
+            .timers
+            .iter()
+            .map(|t| (t.kind, t.interval_ms, t.actions.len()))
+            .collect();
+        assert_eq!(
+            timers,
+            vec![
+                (TimerKind::Every, 1000, 1),
+                (TimerKind::After, 1500, 1),
+                (TimerKind::Every, 250, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_subscribe() {
+        let source = r#"
+            flow { start: Chat }
+            timeline Chat {
+                subscribe ws("ws://localhost:9000/chat") as msg {
+                    append(state.messages, msg)
+                }
+                foreach m in state.messages { Text("{}", m) }
+                subscribe sse("/events") as ev { toast(ev) set state.last = ev }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        let timeline = &app.timelines[0];
+        assert_eq!(timeline.body.len(), 1);
+        let subs: Vec<_> = timeline
+            .subscriptions
+            .iter()
+            .map(|s| (s.kind, s.var.as_str(), s.actions.len()))
+            .collect();
+        assert_eq!(
+            subs,
+            vec![
+                (SubscriptionKind::WebSocket, "msg", 1),
+                (SubscriptionKind::Sse, "ev", 2),
+            ]
+        );
+        assert!(
+            matches!(&timeline.subscriptions[0].url, Expr::String(url) if url == "ws://localhost:9000/chat")
+        );
+    }
+
+    #[test]
+    fn test_parse_resource() {
+        let source = r#"
+            flow { start: UserPage }
+            timeline UserPage("/users/:id") {
+                resource user = fetch_user!(route.params.id, "full")
+                if user.loading { Text("Loading...") }
+                Text("{}", user.value)
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        let timeline = &app.timelines[0];
+        assert_eq!(timeline.body.len(), 2);
+        assert_eq!(timeline.resources.len(), 1);
+        let resource = &timeline.resources[0];
+        assert_eq!(resource.name, "user");
+        assert_eq!(resource.call, "fetch_user");
+        assert!(matches!(
+            resource.args.as_slice(),
+            [Expr::Path(p), Expr::String(s)] if p == "route.params.id" && s == "full"
+        ));
+    }
+
+    #[test]
+    fn test_parse_http() {
+        let source = r#"
+            flow { start: Items }
+            timeline Items {
+                when user.click(reload_btn) {
+                    http_get("/api/items", into: state.items, timeout: 5000)
+                    http_post("/api/items", body: { name: state.draft },
+                        headers: { "X-Token": state.token },
+                        on_success: |res| { toast(res.id) },
+                        on_error: |err| { set state.error = err })
+                }
+            }
+        "#;
+
+        let result = parse_nilo(source);
+        assert!(result.is_ok(), "Failed to parse: {:?}", result.err());
+        let app = result.unwrap();
+
+        let actions = &app.timelines[0].whens[0].actions;
+        assert!(matches!(
+            &actions[0].node,
+            ViewNode::Http { method: HttpMethod::Get, into: Some(into), params, on_success: None, .. }
+                if into == "state.items" && params.len() == 1
+        ));
+        let ViewNode::Http {
+            method,
+            params,
+            on_success,
+            on_error,
+            ..
+        } = &actions[1].node
+        else {
+            panic!("expected http_post, got {:?}", actions[1].node);
+        };
+        assert_eq!(*method, HttpMethod::Post);
+        let names: Vec<_> = params.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["body", "headers"]);
+        assert_eq!(on_success.as_ref().unwrap().params, vec!["res"]);
+        assert_eq!(on_error.as_ref().unwrap().actions.len(), 1);
+    }
+
     #[test]
     fn test_parse_error_no_flow() {
         let source = r#"
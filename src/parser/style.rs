@@ -292,6 +292,19 @@ pub fn style_from_expr(expr: Expr) -> Style {
                             };
                         }
                     }
+                    // ★ ツールチップ（式のまま保持し、表示時に評価する）
+                    "tooltip" => s.tooltip = Some(resolved_value),
+                    // ★ foreachの仮想化: `foreach x in xs (virtual: true, item_height: 24px)`
+                    "virtual" => {
+                        if let Expr::Bool(b) = resolved_value {
+                            s.virtual_list = Some(b);
+                        }
+                    }
+                    "item_height" => match resolved_value {
+                        Expr::Number(h) => s.item_height = Some(h),
+                        Expr::Dimension(d) if d.unit == Unit::Px => s.item_height = Some(d.value),
+                        _ => {}
+                    },
                     _ => {
                         // 未知のプロパティは無視
                     }
@@ -5,7 +5,7 @@
 // このモジュールはタイムライン定義とwhenブロックの解析を担当します。
 
 use crate::parser::ast::*;
-use crate::parser::expr::parse_event_expr;
+use crate::parser::expr::{parse_event_expr, parse_expr};
 use crate::parser::parse::Rule;
 use crate::parser::utils::unquote;
 use pest::iterators::Pair;
@@ -23,6 +23,9 @@ pub fn parse_timeline_def(pair: Pair<Rule>) -> Timeline {
     let mut background: Option<String> = None;
     let mut body: Vec<WithSpan<ViewNode>> = Vec::new();
     let mut whens = Vec::new(); // whenイベントを正しく解析するように修正
+    let mut timers = Vec::new();
+    let mut resources = Vec::new();
+    let mut subscriptions = Vec::new();
 
     for node_pair in inner {
         match node_pair.as_rule() {
@@ -71,6 +74,15 @@ pub fn parse_timeline_def(pair: Pair<Rule>) -> Timeline {
                 // 直接のwhenブロックを解析
                 whens.push(parse_when_block(node_pair));
             }
+            Rule::timer_block => {
+                timers.push(parse_timer_block(node_pair));
+            }
+            Rule::resource_decl => {
+                resources.push(parse_resource_decl(node_pair));
+            }
+            Rule::subscribe_block => {
+                subscriptions.push(parse_subscribe_block(node_pair));
+            }
             _ => {
                 body.push(parse_view_node(node_pair));
             }
@@ -84,9 +96,93 @@ pub fn parse_timeline_def(pair: Pair<Rule>) -> Timeline {
         background,
         body,
         whens,
+        timers,
+        resources,
+        subscriptions,
+    }
+}
+
+/// タイマー（every 1s { ... } / after 500ms { ... }）の解析
+pub fn parse_timer_block(pair: Pair<Rule>) -> TimerBlock {
+    let mut inner = pair.into_inner();
+    let kind = match inner.next().unwrap().as_str() {
+        "every" => TimerKind::Every,
+        _ => TimerKind::After,
+    };
+    let interval_ms = parse_duration_ms(inner.next().unwrap().as_str());
+
+    let mut actions = Vec::new();
+    for p in inner {
+        if p.as_rule() == Rule::view_nodes {
+            for vn in p.into_inner() {
+                actions.push(parse_view_node(vn));
+            }
+        }
+    }
+
+    TimerBlock {
+        kind,
+        interval_ms,
+        actions,
+    }
+}
+
+/// リソース宣言（resource user = fetch_user!(route.params.id)）の解析
+pub fn parse_resource_decl(pair: Pair<Rule>) -> ResourceDecl {
+    let mut inner = pair.into_inner();
+    let _keyword = inner.next();
+    let name = inner.next().unwrap().as_str().to_string();
+    let mut call = inner.next().unwrap().into_inner();
+    let call_name = call.next().unwrap().as_str().to_string();
+    let args = call.map(parse_expr).collect();
+
+    ResourceDecl {
+        name,
+        call: call_name,
+        args,
     }
 }
 
+/// 購読（subscribe ws("ws://...") as msg { ... }）の解析
+pub fn parse_subscribe_block(pair: Pair<Rule>) -> SubscribeBlock {
+    let mut inner = pair.into_inner();
+    let _keyword = inner.next();
+    let kind = match inner.next().unwrap().as_str() {
+        "ws" => SubscriptionKind::WebSocket,
+        _ => SubscriptionKind::Sse,
+    };
+    let url = parse_expr(inner.next().unwrap());
+    let var = inner.next().unwrap().as_str().to_string();
+
+    let mut actions = Vec::new();
+    for p in inner {
+        if p.as_rule() == Rule::view_nodes {
+            for vn in p.into_inner() {
+                actions.push(parse_view_node(vn));
+            }
+        }
+    }
+
+    SubscribeBlock {
+        kind,
+        url,
+        var,
+        actions,
+    }
+}
+
+/// "500ms" / "1.5s" / "2min" をミリ秒にする
+fn parse_duration_ms(s: &str) -> u64 {
+    let (value, scale) = if let Some(v) = s.strip_suffix("ms") {
+        (v, 1.0)
+    } else if let Some(v) = s.strip_suffix("min") {
+        (v, 60_000.0)
+    } else {
+        (s.trim_end_matches('s'), 1000.0)
+    };
+    (value.parse::<f64>().unwrap_or(0.0) * scale).round() as u64
+}
+
 /// Whenブロック（イベントハンドラー）の解析
 pub fn parse_when_block(pair: Pair<Rule>) -> When {
     let mut inner = pair.into_inner();
@@ -15,6 +15,7 @@ pub fn infer_expr_type(expr: &Expr) -> NiloType {
         Expr::Number(_) => NiloType::Number,
         Expr::String(_) => NiloType::String,
         Expr::Bool(_) => NiloType::Bool,
+        Expr::Null => NiloType::Any, // nullはどの型にも代入できる
 
         // 配列の型推論
         Expr::Array(items) => {
@@ -54,16 +55,37 @@ pub fn infer_expr_type(expr: &Expr) -> NiloType {
                     // 比較演算: 常にBool
                     NiloType::Bool
                 }
+                // null合体: 左がnullなら右の値になる
+                BinaryOperator::Coalesce => common_type(left_ty, right_ty),
             }
         }
 
+        // 条件式: 両方の分岐の型が一致すればその型
+        Expr::Conditional {
+            then_expr,
+            else_expr,
+            ..
+        } => common_type(infer_expr_type(then_expr), infer_expr_type(else_expr)),
+
         // その他の式は型が不明
         Expr::Path(_) | Expr::Ident(_) => NiloType::Unknown,
         Expr::Object(_) => NiloType::Unknown,
         Expr::Dimension(_) => NiloType::Number, // 次元値は数値として扱う
         Expr::CalcExpr(inner) => infer_expr_type(inner),
         Expr::Match { .. } => NiloType::Unknown, // Matchは複雑なので後で実装
-        Expr::FunctionCall { .. } => NiloType::Unknown, // 関数の戻り値は不明
+        // 組み込み関数はシグネチャの戻り値型、それ以外（Rust関数など）は不明
+        Expr::FunctionCall { name, .. } => {
+            builtin_signature(name).map_or(NiloType::Unknown, |sig| sig.returns)
+        }
+    }
+}
+
+/// 2つの分岐の値の型（`null` 側は無視し、一致しなければ不明）
+fn common_type(a: NiloType, b: NiloType) -> NiloType {
+    match (a, b) {
+        (NiloType::Any, other) | (other, NiloType::Any) => other,
+        (a, b) if a == b => a,
+        _ => NiloType::Unknown,
     }
 }
 
@@ -86,6 +108,132 @@ pub fn check_type_compatibility(expected: &NiloType, actual: &NiloType) -> Resul
     }
 }
 
+/// 組み込み関数のシグネチャ（`engine::builtins` の関数に対応）
+#[derive(Debug, Clone)]
+pub struct BuiltinSignature {
+    pub params: Vec<NiloType>,
+    /// 末尾から数えて省略可能な引数の数
+    pub optional: usize,
+    /// 最後の引数を何個でも渡せるか
+    pub variadic: bool,
+    pub returns: NiloType,
+}
+
+impl BuiltinSignature {
+    /// 診断メッセージ用の表記（例: `substring(string, number, number?) -> string`）
+    pub fn usage(&self, name: &str) -> String {
+        let required = self.params.len() - self.optional;
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                if self.variadic && i + 1 == self.params.len() {
+                    format!("{}...", ty.display())
+                } else if i >= required {
+                    format!("{}?", ty.display())
+                } else {
+                    ty.display()
+                }
+            })
+            .collect();
+        format!(
+            "{}({}) -> {}",
+            name,
+            params.join(", "),
+            self.returns.display()
+        )
+    }
+}
+
+/// 組み込み関数のシグネチャを取得（組み込み関数でなければNone）
+pub fn builtin_signature(name: &str) -> Option<BuiltinSignature> {
+    use NiloType::{Any, Bool, Number, String as Str};
+    let array = |ty| NiloType::Array(Box::new(ty));
+    let list = || array(Any);
+    let (params, optional, variadic, returns) = match name {
+        "upper" | "lower" | "trim" => (vec![Str], 0, false, Str),
+        "contains" => (vec![Any, Str], 0, false, Bool),
+        "substring" => (vec![Str, Number, Number], 1, false, Str),
+        "split" => (vec![Str, Str], 0, false, array(Str)),
+        "join" => (vec![list(), Str], 1, false, Str),
+        "replace" => (vec![Str, Str, Str], 0, false, Str),
+        "min" | "max" => (vec![Any], 0, true, Number),
+        "round" => (vec![Number, Number], 1, false, Number),
+        "abs" => (vec![Number], 0, false, Number),
+        "clamp" => (vec![Number, Number, Number], 0, false, Number),
+        "len" => (vec![Any], 0, false, Number),
+        "first" | "last" => (vec![list()], 0, false, Any),
+        "filter_by" => (vec![list(), Str, Any], 0, false, list()),
+        "sort_by" => (vec![list(), Str], 1, false, list()),
+        "sum" => (vec![list(), Str], 1, false, Number),
+        "range" => (vec![Number, Number], 0, false, array(Number)),
+        "format_number" => (vec![Number, Number], 1, false, Str),
+        "pad" => (vec![Any, Number, Str], 1, false, Str),
+        _ => return None,
+    };
+    Some(BuiltinSignature {
+        params,
+        optional,
+        variadic,
+        returns,
+    })
+}
+
+/// 組み込み関数の呼び出しの引数の個数と型をチェック（組み込み関数でなければ何もしない）
+pub fn check_builtin_call(name: &str, args: &[Expr]) -> Result<(), String> {
+    match builtin_signature(name) {
+        Some(sig) => check_call(name, &sig, args),
+        None => Ok(()),
+    }
+}
+
+/// ファイルに定義した関数（fn）のシグネチャ（型を省略した引数・戻り値は any）
+pub fn function_signature(def: &FunctionDef) -> BuiltinSignature {
+    BuiltinSignature {
+        params: def
+            .params
+            .iter()
+            .map(|p| p.param_type.clone().unwrap_or(NiloType::Any))
+            .collect(),
+        optional: 0,
+        variadic: false,
+        returns: def.return_type.clone().unwrap_or(NiloType::Any),
+    }
+}
+
+/// ファイルに定義した関数（fn）の呼び出しの引数の個数と型をチェック
+pub fn check_function_call(def: &FunctionDef, args: &[Expr]) -> Result<(), String> {
+    check_call(&def.name, &function_signature(def), args)
+}
+
+fn check_call(name: &str, sig: &BuiltinSignature, args: &[Expr]) -> Result<(), String> {
+    let required = sig.params.len() - sig.optional;
+    if args.len() < required || (!sig.variadic && args.len() > sig.params.len()) {
+        return Err(format!(
+            "{}: 引数の数が正しくありません（{} 個渡されました）。使い方: {}",
+            name,
+            args.len(),
+            sig.usage(name)
+        ));
+    }
+    for (i, arg) in args.iter().enumerate() {
+        let expected = &sig.params[i.min(sig.params.len() - 1)];
+        let actual = infer_expr_type(arg);
+        if !expected.is_compatible_with(&actual) {
+            return Err(format!(
+                "{}: {} 番目の引数は {} 型ですが、{} 型が渡されました。使い方: {}",
+                name,
+                i + 1,
+                expected.display(),
+                actual.display(),
+                sig.usage(name)
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// 型式をパースする
 pub fn parse_type_expr(pair: Pair<Rule>) -> NiloType {
     let type_str = pair.as_str();
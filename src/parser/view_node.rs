@@ -5,7 +5,7 @@
 // このモジュールは各種ビューノード（Text, Button, Image等）の解析を担当します。
 
 use crate::parser::ast::*;
-use crate::parser::expr::parse_expr;
+use crate::parser::expr::{parse_expr, parse_match_case};
 use crate::parser::parse::Rule;
 use crate::parser::style::style_from_expr;
 use crate::parser::types::{infer_expr_type, parse_type_expr};
@@ -28,15 +28,28 @@ pub fn parse_view_node(pair: Pair<Rule>) -> WithSpan<ViewNode> {
         Rule::text => parse_text(pair),
         Rule::button => parse_button(pair),
         Rule::text_input => parse_text_input(pair),
+        Rule::checkbox
+        | Rule::switch_node
+        | Rule::radio_group
+        | Rule::select_node
+        | Rule::slider
+        | Rule::stepper
+        | Rule::dialog_node => parse_form_control(pair),
         Rule::image => parse_image(pair),
         Rule::vstack_node => parse_vstack_node(pair),
         Rule::hstack_node => parse_hstack_node(pair),
         Rule::rust_call => parse_rust_call(pair),
+        Rule::emit_action => parse_emit_action(pair),
+        Rule::toast_action => parse_toast_action(pair),
+        Rule::http_action => parse_http_action(pair),
         Rule::component_call => parse_component_call(pair),
         Rule::slot_node => parse_slot_node(pair),
         Rule::dynamic_section => parse_dynamic_section(pair),
         Rule::match_block => parse_match_block(pair),
         Rule::navigate_action => parse_navigate_action(pair),
+        Rule::navigate_history_action => parse_navigate_history_action(pair),
+        Rule::call_flow_action => parse_call_flow_action(pair),
+        Rule::finish_action => parse_finish_action(pair),
         Rule::spacing_node => {
             let span = pair.as_span();
             let (line, col) = span.start_pos().line_col();
@@ -156,6 +169,7 @@ pub fn parse_text(pair: Pair<Rule>) -> WithSpan<ViewNode> {
 
     let mut args: Vec<Expr> = Vec::new();
     let mut style: Option<Style> = None;
+    let mut tooltip: Option<Expr> = None;
 
     for p in it {
         match p.as_rule() {
@@ -167,6 +181,7 @@ pub fn parse_text(pair: Pair<Rule>) -> WithSpan<ViewNode> {
                         let expr = parse_expr(inner.into_inner().next().unwrap());
                         style = Some(style_from_expr(expr));
                     }
+                    Rule::tooltip_arg => tooltip = Some(parse_tooltip_arg(inner)),
                     _ => {}
                 }
             }
@@ -183,12 +198,12 @@ pub fn parse_text(pair: Pair<Rule>) -> WithSpan<ViewNode> {
         node: ViewNode::Text { format, args },
         line,
         column: col,
-        style,
+        style: with_tooltip(style, tooltip),
     }
 }
 
 /// ボタンノードの解析
-/// 形式: Button(id: "button_id", label: "Button Label", [onclick: function!()], [style: {...}])
+/// 形式: Button(id: "button_id", label: "Button Label", [onclick: function!()], [tooltip: "..."], [style: {...}])
 pub fn parse_button(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     let span = pair.as_span();
     let (line, col) = span.start_pos().line_col();
@@ -197,6 +212,7 @@ pub fn parse_button(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     let mut label: Option<String> = None;
     let mut onclick: Option<Expr> = None;
     let mut style: Option<Style> = None;
+    let mut tooltip: Option<Expr> = None;
 
     for p in pair.into_inner() {
         match p.as_rule() {
@@ -236,6 +252,7 @@ pub fn parse_button(pair: Pair<Rule>) -> WithSpan<ViewNode> {
             Rule::style_arg => {
                 style = Some(style_from_expr(parse_expr(p.into_inner().next().unwrap())));
             }
+            Rule::tooltip_arg => tooltip = Some(parse_tooltip_arg(p)),
             Rule::arg_item => {
                 let mut it = p.into_inner();
                 if let Some(inner) = it.next() {
@@ -272,7 +289,7 @@ pub fn parse_button(pair: Pair<Rule>) -> WithSpan<ViewNode> {
         node: ViewNode::Button { id, label, onclick },
         line,
         column: col,
-        style,
+        style: with_tooltip(style, tooltip),
     }
 }
 
@@ -284,6 +301,7 @@ pub fn parse_image(pair: Pair<Rule>) -> WithSpan<ViewNode> {
 
     let mut path: Option<String> = None;
     let mut style: Option<Style> = None;
+    let mut tooltip: Option<Expr> = None;
 
     for arg in pair.into_inner() {
         match arg.as_rule() {
@@ -302,6 +320,8 @@ pub fn parse_image(pair: Pair<Rule>) -> WithSpan<ViewNode> {
                         style = Some(style_from_expr(parse_expr(
                             inner.into_inner().next().unwrap(),
                         )));
+                    } else if inner.as_rule() == Rule::tooltip_arg {
+                        tooltip = Some(parse_tooltip_arg(inner));
                     }
                 }
             }
@@ -314,7 +334,7 @@ pub fn parse_image(pair: Pair<Rule>) -> WithSpan<ViewNode> {
         node: ViewNode::Image { path },
         line,
         column: col,
-        style,
+        style: with_tooltip(style, tooltip),
     }
 }
 
@@ -405,6 +425,13 @@ pub fn parse_component_call(pair: Pair<Rule>) -> WithSpan<ViewNode> {
                             let expr = parse_expr(named_it.next().unwrap());
                             args.push(ComponentArg::Named(param_name, expr));
                         }
+                        // `tooltip` という名前のパラメータにも渡せるよう名前付き引数として扱う
+                        Rule::tooltip_arg => {
+                            args.push(ComponentArg::Named(
+                                "tooltip".to_string(),
+                                parse_tooltip_arg(x),
+                            ));
+                        }
                         Rule::expr => {
                             // 位置引数
                             args.push(ComponentArg::Positional(parse_expr(x)));
@@ -419,6 +446,9 @@ pub fn parse_component_call(pair: Pair<Rule>) -> WithSpan<ViewNode> {
             Rule::expr => {
                 args.push(ComponentArg::Positional(parse_expr(p)));
             }
+            Rule::handler_arg => {
+                args.push(parse_handler_arg(p));
+            }
             _ => {}
         }
     }
@@ -435,6 +465,34 @@ pub fn parse_component_call(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     }
 }
 
+/// コールバック引数の解析
+/// 形式: name: |param1, param2| { actions }
+fn parse_handler_arg(pair: Pair<Rule>) -> ComponentArg {
+    let mut inner = pair.into_inner();
+    let name = inner.next().unwrap().as_str().to_string();
+    let block = inner.next().unwrap();
+
+    let mut params = Vec::new();
+    let mut actions = Vec::new();
+    for p in block.into_inner() {
+        match p.as_rule() {
+            Rule::ident => params.push(p.as_str().to_string()),
+            Rule::view_nodes => {
+                for vn in p.into_inner() {
+                    actions.push(parse_view_node(vn));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ComponentArg::Handler {
+        name,
+        params,
+        actions,
+    }
+}
+
 /// dynamic_section の解析
 /// 形式: dynamic_section section_name ([style: {...}]) { ... }
 pub fn parse_dynamic_section(pair: Pair<Rule>) -> WithSpan<ViewNode> {
@@ -468,14 +526,14 @@ pub fn parse_dynamic_section(pair: Pair<Rule>) -> WithSpan<ViewNode> {
 }
 
 /// matchブロックの解析
-/// 形式: match <expr> ([style: {...}]) { case value1 { ... } case value2 { ... } default { ... } }
+/// 形式: match <expr> ([style: {...}]) { case value1 { ... } case "a" | "b" { ... } case n if n > 9 { ... } default { ... } }
 pub fn parse_match_block(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     let span = pair.as_span();
     let (line, col) = span.start_pos().line_col();
 
     let mut expr: Option<Expr> = None;
     let mut style: Option<Style> = None;
-    let mut arms: Vec<(Expr, Vec<WithSpan<ViewNode>>)> = Vec::new();
+    let mut arms: Vec<(MatchCase, Vec<WithSpan<ViewNode>>)> = Vec::new();
     let mut default: Option<Vec<WithSpan<ViewNode>>> = None;
 
     for p in pair.into_inner() {
@@ -486,7 +544,7 @@ pub fn parse_match_block(pair: Pair<Rule>) -> WithSpan<ViewNode> {
             }
             Rule::match_arm => {
                 let mut arm_inner = p.into_inner();
-                let case_val = parse_expr(arm_inner.next().unwrap());
+                let case = parse_match_case(arm_inner.next().unwrap());
                 let mut nodes = Vec::new();
                 for n in arm_inner {
                     match n.as_rule() {
@@ -498,7 +556,7 @@ pub fn parse_match_block(pair: Pair<Rule>) -> WithSpan<ViewNode> {
                         _ => nodes.push(parse_view_node(n)),
                     }
                 }
-                arms.push((case_val, nodes));
+                arms.push((case, nodes));
             }
             Rule::default_arm => {
                 let mut nodes = Vec::new();
@@ -531,14 +589,139 @@ pub fn parse_match_block(pair: Pair<Rule>) -> WithSpan<ViewNode> {
 }
 
 /// navigate actionのパース
-/// 形式: navigate_to(TargetState)
+/// 形式: navigate_to(TargetState) / navigate_to(TargetState, id: expr, ...)
 pub fn parse_navigate_action(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     let span = pair.as_span();
     let (line, col) = span.start_pos().line_col();
     let mut inner = pair.into_inner();
     let target = inner.next().unwrap().as_str().to_string();
+    let params = inner
+        .map(|param| {
+            let mut param_inner = param.into_inner();
+            let name = param_inner.next().unwrap().as_str().to_string();
+            (name, parse_expr(param_inner.next().unwrap()))
+        })
+        .collect();
     WithSpan {
-        node: ViewNode::NavigateTo { target },
+        node: ViewNode::NavigateTo { target, params },
+        line,
+        column: col,
+        style: None,
+    }
+}
+
+/// サブフロー呼び出しのパース
+/// 形式: call_flow(Signup) / call_flow(Signup, into: state.user, plan: "pro")
+fn parse_call_flow_action(pair: Pair<Rule>) -> WithSpan<ViewNode> {
+    let (line, col) = pair.as_span().start_pos().line_col();
+    let mut inner = pair.into_inner();
+    inner.next(); // call_flow_keyword
+    let flow = inner.next().unwrap().as_str().to_string();
+    let mut into = None;
+    let mut params = Vec::new();
+    for arg in inner {
+        match arg.as_rule() {
+            Rule::call_flow_into => {
+                into = Some(arg.into_inner().next().unwrap().as_str().to_string());
+            }
+            _ => {
+                let mut param_inner = arg.into_inner();
+                let name = param_inner.next().unwrap().as_str().to_string();
+                params.push((name, parse_expr(param_inner.next().unwrap())));
+            }
+        }
+    }
+    WithSpan {
+        node: ViewNode::CallFlow { flow, into, params },
+        line,
+        column: col,
+        style: None,
+    }
+}
+
+/// HTTPリクエストのパース
+/// 形式: http_get(url, into: state.items, on_error: |err| { ... }) / http_post(url, body: {...})
+fn parse_http_action(pair: Pair<Rule>) -> WithSpan<ViewNode> {
+    let (line, col) = pair.as_span().start_pos().line_col();
+    let mut inner = pair.into_inner();
+    let method = match inner.next().unwrap().as_str() {
+        "http_post" => HttpMethod::Post,
+        _ => HttpMethod::Get,
+    };
+    let url = parse_expr(inner.next().unwrap());
+    let mut into = None;
+    let mut params = Vec::new();
+    let mut on_success = None;
+    let mut on_error = None;
+    for arg in inner {
+        match arg.as_rule() {
+            Rule::call_flow_into => {
+                into = Some(arg.into_inner().next().unwrap().as_str().to_string());
+            }
+            Rule::handler_arg => {
+                let ComponentArg::Handler {
+                    name,
+                    params,
+                    actions,
+                } = parse_handler_arg(arg)
+                else {
+                    continue;
+                };
+                let block = Some(ActionBlock { params, actions });
+                match name.as_str() {
+                    "on_success" => on_success = block,
+                    "on_error" => on_error = block,
+                    _ => log::warn!("http: 不明なハンドラー '{}'", name),
+                }
+            }
+            _ => {
+                let mut param_inner = arg.into_inner();
+                let name = param_inner.next().unwrap().as_str().to_string();
+                params.push((name, parse_expr(param_inner.next().unwrap())));
+            }
+        }
+    }
+    WithSpan {
+        node: ViewNode::Http {
+            method,
+            url,
+            into,
+            params,
+            on_success,
+            on_error,
+        },
+        line,
+        column: col,
+        style: None,
+    }
+}
+
+/// サブフロー終了のパース
+/// 形式: finish(value) / finish()
+fn parse_finish_action(pair: Pair<Rule>) -> WithSpan<ViewNode> {
+    let (line, col) = pair.as_span().start_pos().line_col();
+    let value = pair
+        .into_inner()
+        .find(|p| p.as_rule() == Rule::expr)
+        .map(parse_expr);
+    WithSpan {
+        node: ViewNode::Finish { value },
+        line,
+        column: col,
+        style: None,
+    }
+}
+
+/// 履歴移動のパース
+/// 形式: navigate_back() / navigate_forward()
+pub fn parse_navigate_history_action(pair: Pair<Rule>) -> WithSpan<ViewNode> {
+    let (line, col) = pair.as_span().start_pos().line_col();
+    let node = match pair.into_inner().next().unwrap().as_str() {
+        "navigate_back" => ViewNode::NavigateBack,
+        _ => ViewNode::NavigateForward,
+    };
+    WithSpan {
+        node,
         line,
         column: col,
         style: None,
@@ -1015,6 +1198,51 @@ fn parse_rust_call(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     }
 }
 
+/// emit の解析
+/// 形式: emit on_select(item.id)
+fn parse_emit_action(pair: Pair<Rule>) -> WithSpan<ViewNode> {
+    let span = pair.as_span();
+    let (line, col) = span.start_pos().line_col();
+
+    let mut name = String::new();
+    let mut args: Vec<Expr> = Vec::new();
+    for p in pair.into_inner() {
+        match p.as_rule() {
+            Rule::ident => name = p.as_str().to_string(),
+            Rule::expr => args.push(parse_expr(p)),
+            _ => {}
+        }
+    }
+
+    WithSpan {
+        node: ViewNode::Emit { name, args },
+        line,
+        column: col,
+        style: None,
+    }
+}
+
+/// toast の解析
+/// 形式: toast("保存しました") / toast("保存しました", 5000)
+fn parse_toast_action(pair: Pair<Rule>) -> WithSpan<ViewNode> {
+    let span = pair.as_span();
+    let (line, col) = span.start_pos().line_col();
+
+    let mut exprs = pair
+        .into_inner()
+        .filter(|p| p.as_rule() == Rule::expr)
+        .map(parse_expr);
+    let message = exprs.next().unwrap();
+    let duration = exprs.next();
+
+    WithSpan {
+        node: ViewNode::Toast { message, duration },
+        line,
+        column: col,
+        style: None,
+    }
+}
+
 fn parse_text_input(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     let span = pair.as_span();
     let (line, col) = span.start_pos().line_col();
@@ -1022,7 +1250,10 @@ fn parse_text_input(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     let mut id: Option<String> = None;
     let mut placeholder: Option<String> = None;
     let mut style: Option<Style> = None;
+    let mut tooltip: Option<Expr> = None;
     let mut value: Option<Expr> = None;
+    let mut bind: Option<Expr> = None;
+    let mut input_type = TextInputType::Text;
     let mut on_change: Option<Expr> = None;
     let multiline = false;
     let max_length: Option<usize> = None;
@@ -1059,6 +1290,9 @@ fn parse_text_input(pair: Pair<Rule>) -> WithSpan<ViewNode> {
                     if key_rule == Rule::style_arg {
                         style = Some(style_from_expr(parse_expr(key_pair.into_inner().next().unwrap())));
                     }
+                    else if key_rule == Rule::tooltip_arg {
+                        tooltip = Some(parse_tooltip_arg(key_pair));
+                    }
                     // named_arg の場合
                     else if key_rule == Rule::named_arg {
                         let mut named_arg_inner = key_pair.into_inner();
@@ -1081,8 +1315,21 @@ fn parse_text_input(pair: Pair<Rule>) -> WithSpan<ViewNode> {
                                         on_change = Some(parse_expr(val_pair));
                                     }
                                     "bind" => {
-                                        value = Some(parse_expr(val_pair));
+                                        bind = Some(parse_expr(val_pair));
                                     }
+                                    "type" => match parse_expr(val_pair) {
+                                        Expr::String(t) | Expr::Ident(t) | Expr::Path(t) => {
+                                            match t.as_str() {
+                                                "number" => input_type = TextInputType::Number,
+                                                "text" => input_type = TextInputType::Text,
+                                                other => log::warn!(
+                                                    "TextInputの未知のtype: {}（text / number のみ対応）",
+                                                    other
+                                                ),
+                                            }
+                                        }
+                                        _ => log::warn!("TextInputのtypeには文字列が必要です"),
+                                    },
                                     _ => {}
                                 }
                             }
@@ -1113,6 +1360,8 @@ fn parse_text_input(pair: Pair<Rule>) -> WithSpan<ViewNode> {
             id,
             placeholder,
             value,
+            bind,
+            input_type,
             on_change,
             multiline,
             max_length,
@@ -1120,7 +1369,148 @@ fn parse_text_input(pair: Pair<Rule>) -> WithSpan<ViewNode> {
         },
         line,
         column: col,
-        style,
+        style: with_tooltip(style, tooltip),
+    }
+}
+
+/// フォーム部品（Checkbox / Switch / RadioGroup / Select / Slider / Stepper）の解析
+/// 形式: Checkbox(bind: state.agree, label: "同意する", [id: agree], [style: {...}])
+///       RadioGroup(bind: state.size, options: ["S", "M", "L"])
+///       Select(bind: state.country, options: state.countries, [placeholder: "選択してください"])
+///       Slider(bind: state.volume, [min: 0], [max: 100], [step: 1])
+///       Dialog(open: state.show_confirm) { ... }（bindの代わりにopen）
+/// idを省略した場合はbind先パスの末尾をidとする（state.profile.agree -> agree）
+fn parse_form_control(pair: Pair<Rule>) -> WithSpan<ViewNode> {
+    let span = pair.as_span();
+    let (line, col) = span.start_pos().line_col();
+    let rule = pair.as_rule();
+
+    let mut id: Option<String> = None;
+    let mut bind: Option<Expr> = None;
+    let mut label: Option<String> = None;
+    let mut options: Option<Expr> = None;
+    let mut placeholder: Option<String> = None;
+    let mut min: Option<Expr> = None;
+    let mut max: Option<Expr> = None;
+    let mut step: Option<Expr> = None;
+    let mut style: Option<Style> = None;
+    let mut tooltip: Option<Expr> = None;
+    let mut body: Vec<WithSpan<ViewNode>> = Vec::new();
+
+    for p in pair.into_inner() {
+        // Dialog の本体
+        if p.as_rule() == Rule::view_nodes {
+            body = p.into_inner().map(parse_view_node).collect();
+            continue;
+        }
+        let Some(arg) = p.into_inner().next() else {
+            continue;
+        };
+        match arg.as_rule() {
+            Rule::style_arg => {
+                style = Some(style_from_expr(parse_expr(
+                    arg.into_inner().next().unwrap(),
+                )));
+            }
+            Rule::tooltip_arg => tooltip = Some(parse_tooltip_arg(arg)),
+            Rule::named_arg => {
+                let mut kv = arg.into_inner();
+                let key = kv.next().unwrap().as_str();
+                let value = parse_expr(kv.next().unwrap());
+                match key {
+                    "id" => match value {
+                        Expr::String(s) | Expr::Ident(s) | Expr::Path(s) => id = Some(s),
+                        _ => log::warn!("フォーム部品のidには文字列または識別子が必要です"),
+                    },
+                    "bind" => bind = Some(value),
+                    "open" if rule == Rule::dialog_node => bind = Some(value),
+                    "label" => match value {
+                        Expr::String(s) => label = Some(s),
+                        _ => log::warn!("フォーム部品のlabelには文字列が必要です"),
+                    },
+                    "options" => options = Some(value),
+                    "min" => min = Some(value),
+                    "max" => max = Some(value),
+                    "step" => step = Some(value),
+                    "placeholder" => match value {
+                        Expr::String(s) => placeholder = Some(s),
+                        _ => log::warn!("Selectのplaceholderには文字列が必要です"),
+                    },
+                    "style" => style = Some(style_from_expr(value)),
+                    other => log::warn!("フォーム部品の未知の引数: {}", other),
+                }
+            }
+            // 位置引数はidとして扱う（TextInputと同じ）
+            _ => match parse_expr(arg) {
+                Expr::String(s) | Expr::Ident(s) | Expr::Path(s) if id.is_none() => id = Some(s),
+                _ => log::warn!("フォーム部品の位置引数はidのみ指定できます"),
+            },
+        }
+    }
+
+    let bind = bind.expect("フォーム部品にbind（Dialogはopen）が必要です");
+    let id = id.unwrap_or_else(|| match &bind {
+        Expr::Path(p) | Expr::Ident(p) => p.rsplit('.').next().unwrap_or(p).to_string(),
+        _ => panic!("フォーム部品のbindにはパスが必要です"),
+    });
+
+    let node = match rule {
+        Rule::checkbox => ViewNode::Checkbox { id, bind, label },
+        Rule::switch_node => ViewNode::Switch { id, bind, label },
+        Rule::slider => ViewNode::Slider {
+            id,
+            bind,
+            min,
+            max,
+            step,
+        },
+        Rule::stepper => ViewNode::Stepper {
+            id,
+            bind,
+            min,
+            max,
+            step,
+        },
+        Rule::select_node => ViewNode::Select {
+            id,
+            bind,
+            options: options.expect("Selectにoptionsが必要です"),
+            placeholder,
+        },
+        Rule::dialog_node => ViewNode::Dialog {
+            id,
+            open: bind,
+            body,
+        },
+        _ => ViewNode::RadioGroup {
+            id,
+            bind,
+            options: options.expect("RadioGroupにoptionsが必要です"),
+        },
+    };
+
+    WithSpan {
+        node,
+        line,
+        column: col,
+        style: with_tooltip(style, tooltip),
+    }
+}
+
+/// `tooltip: <expr>` 引数の式を取り出す
+fn parse_tooltip_arg(pair: Pair<Rule>) -> Expr {
+    parse_expr(pair.into_inner().next().unwrap())
+}
+
+/// ★ ツールチップはノードのスタイルに持たせる（style引数がなくても付与できるようにする）
+fn with_tooltip(style: Option<Style>, tooltip: Option<Expr>) -> Option<Style> {
+    match tooltip {
+        Some(tooltip) => {
+            let mut style = style.unwrap_or_default();
+            style.tooltip = Some(tooltip);
+            Some(style)
+        }
+        None => style,
     }
 }
 
@@ -1129,16 +1519,22 @@ fn parse_foreach_node(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     let (line, col) = span.start_pos().line_col();
 
     let mut var: Option<String> = None;
+    let mut index_var: Option<String> = None;
     let mut iterable: Option<Expr> = None;
+    let mut key: Option<Expr> = None;
     let mut style: Option<Style> = None;
     let mut body: Vec<WithSpan<ViewNode>> = Vec::new();
 
     let mut inner = pair.into_inner();
 
-    if let Some(var_pair) = inner.next() {
-        if var_pair.as_rule() == Rule::ident {
-            var = Some(var_pair.as_str().to_string());
-        }
+    // ★ `foreach item in ...` または `foreach (i, item) in ...`
+    if let Some(vars_pair) = inner.next() {
+        let mut names: Vec<String> = vars_pair
+            .into_inner()
+            .map(|p| p.as_str().to_string())
+            .collect();
+        var = names.pop();
+        index_var = names.pop();
     }
 
     if let Some(iterable_pair) = inner.next() {
@@ -1153,6 +1549,17 @@ fn parse_foreach_node(pair: Pair<Rule>) -> WithSpan<ViewNode> {
                         Rule::ident => {
                             iterable = Some(Expr::Ident(actual_iterable.as_str().to_string()));
                         }
+                        Rule::foreach_range => {
+                            // ★ `a..b` は組み込みの range(a, b) として評価する（終端を含まない）
+                            let args = actual_iterable
+                                .into_inner()
+                                .map(|bound| parse_expr(bound.into_inner().next().unwrap()))
+                                .collect();
+                            iterable = Some(Expr::FunctionCall {
+                                name: "range".to_string(),
+                                args,
+                            });
+                        }
                         _ => {
                             iterable = Some(parse_expr(actual_iterable));
                         }
@@ -1168,15 +1575,36 @@ fn parse_foreach_node(pair: Pair<Rule>) -> WithSpan<ViewNode> {
     for p in inner {
         match p.as_rule() {
             Rule::foreach_style => {
-                let mut style_inner = p.into_inner();
-                if let Some(style_arg) = style_inner.next() {
-                    if style_arg.as_rule() == Rule::style_arg {
-                        let mut style_arg_inner = style_arg.into_inner();
-                        if let Some(expr_pair) = style_arg_inner.next() {
-                            style = Some(style_from_expr(parse_expr(expr_pair)));
+                // ★ style: {...} に加えて virtual / item_height などの名前付き引数もスタイルとして扱う
+                let mut options = Vec::new();
+                for arg in p.into_inner() {
+                    let Some(arg) = arg.into_inner().next() else {
+                        continue;
+                    };
+                    let rule = arg.as_rule();
+                    let mut arg_inner = arg.into_inner();
+                    match rule {
+                        Rule::style_arg => {
+                            if let Some(expr_pair) = arg_inner.next() {
+                                style = Some(style_from_expr(parse_expr(expr_pair)));
+                            }
+                        }
+                        _ => {
+                            let key = arg_inner.next().unwrap().as_str().to_string();
+                            options.push((key, parse_expr(arg_inner.next().unwrap())));
                         }
                     }
                 }
+                if !options.is_empty() {
+                    let base = style.take().unwrap_or_default();
+                    style = Some(base.merged(&style_from_expr(Expr::Object(options))));
+                }
+            }
+            Rule::foreach_key => {
+                key = p
+                    .into_inner()
+                    .next()
+                    .map(|k| Expr::Path(k.as_str().to_string()));
             }
             Rule::view_nodes => {
                 body = p.into_inner().map(parse_view_node).collect();
@@ -1185,10 +1613,22 @@ fn parse_foreach_node(pair: Pair<Rule>) -> WithSpan<ViewNode> {
         }
     }
 
+    if let Some(st) = &style
+        && st.virtual_list == Some(true)
+        && st.item_height.is_none()
+    {
+        log::warn!(
+            "virtual: true のforeachには item_height の指定が必要です（{}行目）。仮想化せずに表示します",
+            line
+        );
+    }
+
     WithSpan {
         node: ViewNode::ForEach {
             var: var.expect("foreach variable not found"),
+            index_var,
             iterable: iterable.expect("foreach iterable not found"),
+            key,
             body,
         },
         line,
@@ -53,6 +53,8 @@ pub enum DrawCommand {
         scroll_offset: [f32; 2],  // ScrollContainer専用のローカルスクロール
         depth: f32,
     },
+    /// ★ オーバーレイ層（下層の描画後に深度をリセットして描画する）
+    Overlay { children: Vec<DrawCommand> },
 }
 
 #[derive(Debug)]
@@ -89,6 +91,8 @@ impl DrawList {
                 DrawCommand::ScrollContainer {
                     position, height, ..
                 } => position[1] + *height,
+                // オーバーレイはスクロール範囲に含めない
+                DrawCommand::Overlay { .. } => 0.0,
             })
             .fold(0.0, f32::max)
     }
@@ -116,6 +120,11 @@ fn update_command_scroll_offset(
                 update_command_scroll_offset(child, scroll_offsets);
             }
         }
+        DrawCommand::Overlay { children } => {
+            for child in children {
+                update_command_scroll_offset(child, scroll_offsets);
+            }
+        }
         _ => {}
     }
 }
@@ -1,6 +1,6 @@
 use crate::renderer_abstract::command::{DrawCommand, DrawList};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Stencil {
     Rect {
         position: [f32; 2],
@@ -73,6 +73,12 @@ pub enum Stencil {
         children: Vec<Stencil>,
         depth: f32,
     },
+
+    /// ★ オーバーレイ層（ドロップダウン等のポップアップ）
+    /// 下層をすべて描画した後、深度をリセットして最前面に描画される
+    Overlay {
+        children: Vec<Stencil>,
+    },
 }
 
 pub fn stencil_to_wgpu_draw_list(stencils: &[Stencil]) -> DrawList {
@@ -415,6 +421,11 @@ pub fn stencil_to_wgpu_draw_list(stencils: &[Stencil]) -> DrawList {
                     }
                 }
             }
+            Stencil::Overlay { children } => {
+                draw_list.push(DrawCommand::Overlay {
+                    children: stencil_to_wgpu_draw_list(children).0,
+                });
+            }
         }
     }
 
@@ -436,6 +447,8 @@ fn get_stencil_depth(stencil: &Stencil) -> f32 {
         Stencil::ScrollBar { depth, .. } => *depth,
         Stencil::RoundedRect { depth, .. } => *depth,
         Stencil::ScrollContainer { depth, .. } => *depth,
+        // オーバーレイ層は最前面
+        Stencil::Overlay { .. } => 0.0,
         Stencil::Group(_) => 0.5, // デフォルト値
     }
 }
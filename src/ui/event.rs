@@ -6,6 +6,11 @@ pub enum UIEvent {
     ButtonReleased {
         id: String,
     },
+    // ★ ポインタをキャプチャした部品上でのドラッグ（押下時と移動ごとに発生）
+    PointerDragged {
+        id: String,
+        position: [f32; 2],
+    },
 
     // ★ 新規追加: テキスト入力とIME関連のイベント
     TextChanged {
@@ -46,6 +51,22 @@ pub enum UIEvent {
     ImeDisabled {
         field_id: String,
     },
+
+    // ★ 履歴移動（マウスの戻る/進むボタン）
+    NavigateBack,
+    NavigateForward,
+    // ★ タイムラインのタイマー（every / after）のティック
+    TimerTick {
+        id: String,
+    },
+    // ★ HTTPリクエスト（http_get / http_post）の完了
+    HttpResponse {
+        id: u64,
+    },
+    // ★ 購読（subscribe ws / sse）のメッセージ受信
+    SubscriptionMessage {
+        id: u64,
+    },
     // 今後追加で...
     // MouseMoved { pos: [f32; 2] },
 }
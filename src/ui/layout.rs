@@ -2,8 +2,10 @@
 // 子要素から親要素への計算（Bottom-Up）を基本とし、
 // width/height の優先度を明確化した汎用レイアウトエンジン
 
-use crate::engine::state::format_text;
-use crate::parser::ast::{App, Expr, ViewNode, WithSpan};
+use crate::engine::match_pattern;
+use crate::engine::state::{foreach_key_placeholder, format_text};
+use crate::engine::state_path::{self, PathSegment};
+use crate::parser::ast::{App, Expr, MatchArm, MatchCase, ViewNode, WithSpan};
 use crate::parser::ast::{DimensionValue, Edges, RelativeEdges, Style, Unit};
 use crate::stencil::stencil::Stencil as DrawStencil;
 
@@ -11,7 +13,9 @@ use crate::stencil::stencil::Stencil as DrawStencil;
 #[cfg(any(feature = "glyphon", target_arch = "wasm32"))]
 use crate::ui::text_measurement::{TextMeasurement, get_text_measurement_system};
 
-use std::collections::HashMap;
+use crate::ui::layout_diff::NodeHash;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 /// 2つのスタイルをマージ（second が first を上書き）
 fn merge_styles(first: Option<&Style>, second: Option<&Style>) -> Style {
@@ -58,6 +62,516 @@ fn merge_styles(first: Option<&Style>, second: Option<&Style>) -> Style {
     }
 }
 
+/// foreach内で展開されたコンポーネントのインスタンスキー（`{var#key}`）を要素のキーで確定
+fn bind_foreach_key(s: &str, var: &str, item_key: &str) -> String {
+    s.replace(&foreach_key_placeholder(var), item_key)
+}
+
+/// bind先パス中のインスタンスキーを確定
+fn bind_foreach_path(expr: &Expr, var: &str, item_key: &str) -> Expr {
+    match expr {
+        Expr::Path(path) => Expr::Path(bind_foreach_key(path, var, item_key)),
+        other => other.clone(),
+    }
+}
+
+// ★ フォーム部品（Checkbox / Switch / RadioGroup）の共通寸法
+/// インジケータとラベルの間隔・RadioGroupの選択肢間の間隔
+pub const FORM_CONTROL_GAP: f32 = 8.0;
+
+/// チェックボックス・ラジオボタンのインジケータの一辺（Switchは幅がこの1.8倍）
+pub fn form_indicator_size(font_size: f32) -> f32 {
+    (font_size * 1.25).max(16.0)
+}
+
+/// 選択肢の評価結果（JSON配列文字列）を表示用の文字列リストに変換
+pub fn parse_option_list(value: &str) -> Vec<String> {
+    match serde_json::from_str::<Vec<serde_json::Value>>(value) {
+        Ok(items) => items
+            .into_iter()
+            .map(|v| match v {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect(),
+        Err(_) if value.is_empty() => Vec::new(),
+        Err(_) => vec![value.to_string()],
+    }
+}
+
+/// RadioGroupの各選択肢の矩形（縦並び・等分割）
+pub fn radio_option_rects(
+    position: [f32; 2],
+    size: [f32; 2],
+    count: usize,
+) -> Vec<([f32; 2], [f32; 2])> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let gaps = FORM_CONTROL_GAP * (count - 1) as f32;
+    let row_height = ((size[1] - gaps) / count as f32).max(0.0);
+    (0..count)
+        .map(|i| {
+            let y = position[1] + i as f32 * (row_height + FORM_CONTROL_GAP);
+            ([position[0], y], [size[0], row_height])
+        })
+        .collect()
+}
+
+/// Selectのドロップダウンの各選択肢の矩形（本体の直下に本体と同じ大きさで縦に並べる）
+pub fn select_popup_rects(
+    position: [f32; 2],
+    size: [f32; 2],
+    count: usize,
+) -> Vec<([f32; 2], [f32; 2])> {
+    let top = position[1] + size[1] + 4.0;
+    (0..count)
+        .map(|i| ([position[0], top + i as f32 * size[1]], size))
+        .collect()
+}
+
+/// Slider / Stepper の範囲（min, max, step）を評価（省略時は部品ごとの既定値）
+pub fn numeric_range<F>(node: &ViewNode, eval: F) -> (f32, f32, Option<f32>)
+where
+    F: Fn(&Expr) -> String,
+{
+    let number = |expr: &Option<Expr>| {
+        expr.as_ref()
+            .and_then(|e| eval(e).trim().parse::<f32>().ok())
+    };
+    match node {
+        ViewNode::Slider { min, max, step, .. } => (
+            number(min).unwrap_or(0.0),
+            number(max).unwrap_or(100.0),
+            number(step).filter(|s| *s > 0.0),
+        ),
+        ViewNode::Stepper { min, max, step, .. } => (
+            number(min).unwrap_or(f32::NEG_INFINITY),
+            number(max).unwrap_or(f32::INFINITY),
+            Some(number(step).filter(|s| *s > 0.0).unwrap_or(1.0)),
+        ),
+        _ => (0.0, 0.0, None),
+    }
+}
+
+/// Sliderのトラックの左端xと幅（両端はつまみの半径分だけ内側）
+pub fn slider_track(position: [f32; 2], size: [f32; 2], font_size: f32) -> (f32, f32) {
+    let radius = form_indicator_size(font_size) * 0.5;
+    (position[0] + radius, (size[0] - radius * 2.0).max(0.0))
+}
+
+/// Stepperの −/+ ボタンの矩形（左右端の正方形）
+pub fn stepper_button_rects(position: [f32; 2], size: [f32; 2]) -> [([f32; 2], [f32; 2]); 2] {
+    let side = size[1].min(size[0] * 0.5);
+    [
+        (position, [side, size[1]]),
+        ([position[0] + size[0] - side, position[1]], [side, size[1]]),
+    ]
+}
+
+/// Dialogのパネル内側の余白
+pub const DIALOG_PADDING: f32 = 24.0;
+
+/// Dialogのパネルの位置と幅（幅指定が無ければ画面幅に合わせて最大480）
+/// 表示中の画面（scroll_yはページのスクロール量、0以下）の中央に置き、画面より高い場合は上端に揃える
+pub fn dialog_panel_rect(
+    window_size: [f32; 2],
+    scroll_y: f32,
+    width: Option<f32>,
+    content_height: f32,
+) -> ([f32; 2], [f32; 2]) {
+    let margin = DIALOG_PADDING * 2.0;
+    let width = width.unwrap_or_else(|| (window_size[0] - margin).clamp(0.0, 480.0));
+    let height = content_height + DIALOG_PADDING * 2.0;
+    let x = ((window_size[0] - width) * 0.5).max(0.0);
+    let y = ((window_size[1] - height) * 0.5).max(DIALOG_PADDING) - scroll_y;
+    ([x, y], [width, height])
+}
+
+/// 仮想化したforeachの1行の高さ（virtual: true かつ item_height 指定時のみ）
+pub fn virtual_item_height(style: Option<&Style>) -> Option<f32> {
+    let style = style?;
+    if style.virtual_list == Some(true) {
+        style.item_height.filter(|h| *h > 0.0)
+    } else {
+        None
+    }
+}
+
+/// 仮想化したforeachが実際にレイアウトする範囲（表示範囲の上下に1画面分の余裕を持たせる）
+pub fn virtual_render_range(viewport: [f32; 2]) -> [f32; 2] {
+    let overscan = viewport[1] - viewport[0];
+    [viewport[0] - overscan, viewport[1] + overscan]
+}
+
+/// 仮想化したforeachのリスト末尾に置く透明な目印（幅・高さ0の矩形）
+/// リスト全体の高さをスクロール範囲と後続の要素の位置に反映する。静的なのでレイアウトのたびに確保しない
+static VIRTUAL_LIST_END: WithSpan<ViewNode> = WithSpan {
+    node: ViewNode::Stencil(DrawStencil::Rect {
+        position: [0.0, 0.0],
+        width: 0.0,
+        height: 0.0,
+        color: [0.0, 0.0, 0.0, 0.0],
+        scroll: true,
+        depth: 1.0,
+    }),
+    line: 0,
+    column: 0,
+    style: None,
+};
+
+/// overflow を指定したノード（ScrollContainer）のID（`LayoutViewport::container_offsets` のキー）
+pub fn scroll_container_id(node: &WithSpan<ViewNode>) -> String {
+    format!("scroll@{}:{}", node.line, node.column)
+}
+
+/// 仮想化したforeachが行を選ぶための表示範囲（と、フレームをまたぐforeachのキャッシュ）
+#[derive(Debug, Clone, Default)]
+pub struct LayoutViewport {
+    /// ページの表示範囲（コンテンツ座標のy上端・下端）
+    pub range: [f32; 2],
+    /// ScrollContainerごとのスクロール量（`scroll_container_id` → offset）
+    pub container_offsets: HashMap<String, [f32; 2]>,
+    /// ★ foreachの要素ごとのレイアウト結果（フレームをまたいで共有する）
+    pub foreach_cache: ForeachLayoutCache,
+}
+
+/// ★ foreachの要素ごとのレイアウト結果のキャッシュ
+/// 要素は (foreachの位置, keyの値) で識別し、値とボディが変わっていなければ
+/// 並べ替え・スクロールで位置が変わっても再レイアウトせずに移動だけする
+#[derive(Debug, Clone, Default)]
+pub struct ForeachLayoutCache(Arc<Mutex<ForeachCacheMaps>>);
+
+#[derive(Debug, Default)]
+struct ForeachCacheMaps {
+    /// 前のフレームで使った要素（今回まだ使っていないもの）
+    prev: HashMap<String, ForeachCacheEntry>,
+    /// 今回のフレームで使った要素
+    current: HashMap<String, ForeachCacheEntry>,
+    /// 今回のフレームで表示したforeachの要素（(keyのプレースホルダー, keyの値)）
+    rendered: HashSet<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+struct ForeachCacheEntry {
+    hash: NodeHash,
+    /// レイアウトしたときの親要素の幅（幅が変わったらレイアウトし直す）
+    width: f32,
+    origin: [f32; 2],
+    height: f32,
+    layouted: Vec<LayoutedNode<'static>>,
+}
+
+impl ForeachLayoutCache {
+    /// フレームの終わりに呼ぶ。今回表示しなかった要素の結果は破棄する
+    /// 戻り値は今回表示したforeachの要素（(keyのプレースホルダー, keyの値)）
+    pub fn end_frame(&self) -> HashSet<(String, String)> {
+        let mut maps = self.0.lock().unwrap();
+        maps.prev = std::mem::take(&mut maps.current);
+        std::mem::take(&mut maps.rendered)
+    }
+
+    /// foreachの要素を表示したことを記録する（仮想化で表示範囲の外にある要素も含む）
+    fn mark_rendered(&self, var: &str, items: &[ForeachItem]) {
+        let placeholder = foreach_key_placeholder(var);
+        let mut maps = self.0.lock().unwrap();
+        for item in items {
+            maps.rendered
+                .insert((placeholder.clone(), item.key.clone()));
+        }
+    }
+
+    /// 保持している要素の数
+    pub fn len(&self) -> usize {
+        let maps = self.0.lock().unwrap();
+        maps.current.len() + maps.prev.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// keyの結果を origin に移して返す（ハッシュか幅が違えばNone）
+    fn reuse(
+        &self,
+        key: &str,
+        hash: &NodeHash,
+        width: f32,
+        origin: [f32; 2],
+    ) -> Option<(Vec<LayoutedNode<'static>>, f32)> {
+        let mut maps = self.0.lock().unwrap();
+        let entry = match maps.current.get(key) {
+            Some(entry) => entry.clone(),
+            None => maps.prev.remove(key)?,
+        };
+        if entry.hash != *hash || entry.width != width {
+            return None;
+        }
+
+        let offset = [origin[0] - entry.origin[0], origin[1] - entry.origin[1]];
+        let layouted = entry
+            .layouted
+            .iter()
+            .map(|n| LayoutedNode {
+                node: n.node,
+                position: [n.position[0] + offset[0], n.position[1] + offset[1]],
+                size: n.size,
+            })
+            .collect();
+        let height = entry.height;
+        maps.current.insert(key.to_string(), entry);
+        Some((layouted, height))
+    }
+
+    fn store(&self, key: String, entry: ForeachCacheEntry) {
+        self.0.lock().unwrap().current.insert(key, entry);
+    }
+}
+
+/// foreachの反復対象の評価結果を (インデックス, 要素の値) の列に分解
+/// 配列は0始まりの番号、オブジェクトは (キー, 値) の組になる。JSONとして読めなければ簡易パース
+fn foreach_entries(iterable_value: String) -> Vec<(String, String)> {
+    match serde_json::from_str::<serde_json::Value>(&iterable_value) {
+        Ok(serde_json::Value::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), state_path::value_to_string(v)))
+            .collect(),
+        Ok(serde_json::Value::Object(entries)) => entries
+            .iter()
+            .map(|(k, v)| (k.clone(), state_path::value_to_string(v)))
+            .collect(),
+        _ if iterable_value.starts_with('[') && iterable_value.ends_with(']') => {
+            // 簡単な配列パース：[1, 2, 3] -> ["1", "2", "3"]
+            let inner = &iterable_value[1..iterable_value.len() - 1];
+            if inner.trim().is_empty() {
+                vec![]
+            } else {
+                inner
+                    .split(',')
+                    .enumerate()
+                    .map(|(i, s)| (i.to_string(), s.trim().to_string()))
+                    .collect()
+            }
+        }
+        _ => vec![("0".to_string(), iterable_value)],
+    }
+}
+
+/// foreachの1要素分の変数の束縛
+struct ForeachScope<'b> {
+    var: &'b str,
+    index_var: Option<&'b str>,
+    value: &'b str,
+    /// インデックス変数の値（配列は番号、オブジェクトはキー）
+    index: &'b str,
+    /// 先頭からの番号（item_index）
+    position: usize,
+}
+
+/// ★ foreachの1要素（要素を識別するキー・インデックス変数の値・要素の値）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeachItem {
+    /// keyの評価結果（key指定が無ければ先頭からの番号）
+    pub key: String,
+    /// インデックス変数の値（配列は番号、オブジェクトはキー）
+    pub index: String,
+    pub value: String,
+}
+
+impl ForeachItem {
+    /// この要素のループ変数・インデックス変数・インスタンスキーを束縛して式を評価
+    pub fn eval<F>(&self, node: &ViewNode, item_index: usize, expr: &Expr, eval: &F) -> String
+    where
+        F: Fn(&Expr) -> String,
+    {
+        let ViewNode::ForEach { var, index_var, .. } = node else {
+            return eval(expr);
+        };
+        let scope = ForeachScope {
+            var,
+            index_var: index_var.as_deref(),
+            value: &self.value,
+            index: &self.index,
+            position: item_index,
+        };
+        match expr {
+            Expr::Path(path) if path.contains('{') => eval(&bind_foreach_vars(
+                &Expr::Path(bind_foreach_key(path, var, &self.key)),
+                &scope,
+            )),
+            _ => eval(&bind_foreach_vars(expr, &scope)),
+        }
+    }
+}
+
+/// foreachの要素を列挙し、要素ごとにkeyを評価する
+/// 並べ替えてもkeyが同じ要素は同じインスタンス（ローカル状態・レイアウトキャッシュ）を使う
+pub fn foreach_items<F>(node: &ViewNode, eval: &F) -> Vec<ForeachItem>
+where
+    F: Fn(&Expr) -> String,
+{
+    let ViewNode::ForEach {
+        var,
+        index_var,
+        iterable,
+        key,
+        ..
+    } = node
+    else {
+        return Vec::new();
+    };
+
+    foreach_entries(eval(iterable))
+        .into_iter()
+        .enumerate()
+        .map(|(position, (index, value))| {
+            let key = match key {
+                Some(key) => {
+                    let scope = ForeachScope {
+                        var,
+                        index_var: index_var.as_deref(),
+                        value: &value,
+                        index: &index,
+                        position,
+                    };
+                    eval(&bind_foreach_vars(key, &scope))
+                }
+                None => position.to_string(),
+            };
+            ForeachItem { key, index, value }
+        })
+        .collect()
+}
+
+/// 式中のループ変数・インデックス変数（`item`, `item.title`, `i`, `item_index`）を値に置き換える
+/// パスの添字に現れるループ変数・インデックス変数を値に置き換える（置き換えがなければNone）
+fn bind_foreach_indices(path: &str, scope: &ForeachScope) -> Option<String> {
+    if !path.contains('[') {
+        return None;
+    }
+    let segments: Vec<PathSegment> = state_path::parse_path(path)
+        .into_iter()
+        .map(|segment| match segment {
+            PathSegment::Index(index) if index == scope.var => {
+                PathSegment::Index(foreach_index_literal(scope.value))
+            }
+            PathSegment::Index(index) if Some(index.as_str()) == scope.index_var => {
+                PathSegment::Index(foreach_index_literal(scope.index))
+            }
+            other => other,
+        })
+        .collect();
+    let bound = state_path::join_path(&segments);
+    (bound != path).then_some(bound)
+}
+
+fn foreach_index_literal(value: &str) -> String {
+    if state_path::is_literal_index(value) {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+fn bind_foreach_vars(expr: &Expr, scope: &ForeachScope) -> Expr {
+    let bind = |e: &Expr| bind_foreach_vars(e, scope);
+    match expr {
+        // ★ オプショナルチェーン（item?.profile?.name）: 要素に無ければnull
+        Expr::Path(name) if name.contains("?.") => {
+            match bind(&Expr::Path(name.replace("?.", "."))) {
+                Expr::Path(path) if state_path::split_root(&path).0 == scope.var => Expr::Null,
+                Expr::Path(_) => expr.clone(),
+                bound => bound,
+            }
+        }
+        Expr::Path(name) | Expr::Ident(name) => {
+            if name == scope.var {
+                foreach_literal(scope.value.to_string())
+            } else if Some(name.as_str()) == scope.index_var {
+                foreach_literal(scope.index.to_string())
+            } else if name == "item_index" {
+                Expr::Number(scope.position as f32)
+            } else if let Some(bound) = bind_foreach_indices(name, scope) {
+                // 添字にループ変数を使うパス（例: item.tags[i], state.users[i].name）
+                bind(&Expr::Path(bound))
+            } else if let Some(property_path) = name
+                .strip_prefix(scope.var)
+                .filter(|rest| rest.starts_with('.') || rest.starts_with('['))
+            {
+                // オブジェクトの要素のプロパティ（例: item.title, item.tags[0]）
+                let Ok(item) = serde_json::from_str::<serde_json::Value>(scope.value) else {
+                    return expr.clone();
+                };
+                state_path::lookup(&item, &state_path::parse_path(property_path))
+                    .map(|v| match v {
+                        serde_json::Value::Null => Expr::Null,
+                        v => foreach_literal(state_path::value_to_string(v)),
+                    })
+                    .unwrap_or_else(|| expr.clone())
+            } else {
+                expr.clone()
+            }
+        }
+        Expr::Array(items) => Expr::Array(items.iter().map(bind).collect()),
+        Expr::Object(entries) => Expr::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (k.clone(), bind(v)))
+                .collect(),
+        ),
+        Expr::CalcExpr(inner) => Expr::CalcExpr(Box::new(bind(inner))),
+        Expr::FunctionCall { name, args } => Expr::FunctionCall {
+            name: name.clone(),
+            args: args.iter().map(bind).collect(),
+        },
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+            left: Box::new(bind(left)),
+            op: op.clone(),
+            right: Box::new(bind(right)),
+        },
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+        } => Expr::Conditional {
+            condition: Box::new(bind(condition)),
+            then_expr: Box::new(bind(then_expr)),
+            else_expr: Box::new(bind(else_expr)),
+        },
+        Expr::Match {
+            expr,
+            arms,
+            default,
+        } => Expr::Match {
+            expr: Box::new(bind(expr)),
+            arms: arms
+                .iter()
+                .map(|arm| {
+                    let mut case = arm.case.clone();
+                    for e in case.exprs_mut() {
+                        *e = bind(e);
+                    }
+                    MatchArm {
+                        case,
+                        value: bind(&arm.value),
+                    }
+                })
+                .collect(),
+            default: default.as_ref().map(|d| Box::new(bind(d))),
+        },
+        _ => expr.clone(),
+    }
+}
+
+/// 束縛した値を式にする（数値として読めるものは数値、それ以外は文字列）
+fn foreach_literal(value: String) -> Expr {
+    match value.parse::<f32>() {
+        Ok(n) if n.to_string() == value => Expr::Number(n),
+        _ => Expr::String(value),
+    }
+}
+
 /// レイアウト結果（ノード＋座標・サイズ）
 #[derive(Debug, Clone)]
 pub struct LayoutedNode<'a> {
@@ -81,6 +595,8 @@ pub struct LayoutParams {
     pub font_size: f32,
     /// デフォルトフォント名
     pub default_font: String,
+    /// ★ 表示中の範囲。仮想化したforeachはこの付近の行だけをレイアウトする
+    pub viewport: Option<LayoutViewport>,
 }
 
 impl Default for LayoutParams {
@@ -95,6 +611,7 @@ impl Default for LayoutParams {
             root_font_size: 16.0,
             font_size: 16.0,
             default_font: "system-ui".to_string(), // システムフォントを優先
+            viewport: None,
         }
     }
 }
@@ -110,6 +627,7 @@ impl LayoutParams {
             root_font_size: 16.0,
             font_size: 16.0,
             default_font: "system-ui".to_string(),
+            viewport: None,
         }
     }
 
@@ -165,12 +683,27 @@ pub struct LayoutEngine {
     /// コンポーネントのキャッシュ
     #[allow(dead_code)]
     component_cache: HashMap<String, ComputedSize>,
+    /// ★ 表示中の範囲（ScrollContainer内ではコンテナの表示範囲）
+    viewport: Option<[f32; 2]>,
+    /// ★ ScrollContainerごとのスクロール量（LayoutParams::viewport）
+    container_offsets: HashMap<String, [f32; 2]>,
+    /// ★ foreachの要素ごとのレイアウト結果（LayoutParams::viewport）
+    foreach_cache: Option<ForeachLayoutCache>,
+}
+
+impl Default for LayoutEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LayoutEngine {
     pub fn new() -> Self {
         Self {
             component_cache: HashMap::new(),
+            viewport: None,
+            container_offsets: HashMap::new(),
+            foreach_cache: None,
         }
     }
 
@@ -204,6 +737,18 @@ impl LayoutEngine {
             );
         }
 
+        // ★ Dialogはオーバーレイ層に表示するため通常のフローでは場所を取らない
+        if let ViewNode::Dialog { .. } = &node.node {
+            return ComputedSize {
+                width: 0.0,
+                height: 0.0,
+                intrinsic_width: 0.0,
+                intrinsic_height: 0.0,
+                has_explicit_width: true,
+                has_explicit_height: true,
+            };
+        }
+
         self.compute_node_size_internal(node, context, eval, get_image_size, app)
     }
 
@@ -317,6 +862,12 @@ impl LayoutEngine {
                 self.compute_button_size(label, node.style.as_ref(), context)
             }
             ViewNode::Image { path } => self.compute_image_size(path, get_image_size),
+            ViewNode::Checkbox { .. }
+            | ViewNode::Switch { .. }
+            | ViewNode::RadioGroup { .. }
+            | ViewNode::Select { .. }
+            | ViewNode::Slider { .. }
+            | ViewNode::Stepper { .. } => self.compute_form_control_size(node, context, eval),
             // Ensure TextInput has a sensible intrinsic size so it renders visibly
             ViewNode::TextInput { .. } => {
                 // デフォルトの見やすいサイズ（Button同等）
@@ -412,26 +963,37 @@ impl LayoutEngine {
                     has_explicit_height: false,
                 }
             }
-            ViewNode::ForEach {
-                var,
-                iterable,
-                body,
-            } => self.compute_foreach_size(var, iterable, body, context, eval, get_image_size, app),
+            ViewNode::ForEach { iterable, .. } => {
+                // ★ 仮想化したforeachは行を評価せず「行数×行の高さ」で確定
+                if let Some(item_height) = virtual_item_height(node.style.as_ref()) {
+                    let height = foreach_entries(eval(iterable)).len() as f32 * item_height;
+                    return ComputedSize {
+                        width: context.parent_size[0],
+                        height,
+                        intrinsic_width: context.parent_size[0],
+                        intrinsic_height: height,
+                        has_explicit_width: false,
+                        has_explicit_height: true,
+                    };
+                }
+                self.compute_foreach_size(node, context, eval, get_image_size, app)
+            }
             ViewNode::DynamicSection { name: _, body } => {
                 // DynamicSectionの内容のサイズを計算
                 self.compute_vstack_size(body, None, context, eval, get_image_size, app)
             }
             // 状態操作ノード（Set, RustCallなど）はUIに干渉しない
-            ViewNode::Set { .. } | ViewNode::RustCall { .. } | ViewNode::LetDecl { .. } => {
-                ComputedSize {
-                    width: 0.0,
-                    height: 0.0,
-                    intrinsic_width: 0.0,
-                    intrinsic_height: 0.0,
-                    has_explicit_width: true,
-                    has_explicit_height: true,
-                }
-            }
+            ViewNode::Set { .. }
+            | ViewNode::RustCall { .. }
+            | ViewNode::LetDecl { .. }
+            | ViewNode::Toast { .. } => ComputedSize {
+                width: 0.0,
+                height: 0.0,
+                intrinsic_width: 0.0,
+                intrinsic_height: 0.0,
+                has_explicit_width: true,
+                has_explicit_height: true,
+            },
             // ★ Phase 2: スロットはプレースホルダーとして最小サイズ
             ViewNode::Slot { .. } | ViewNode::SlotCheck { .. } => ComputedSize {
                 width: 0.0,
@@ -560,6 +1122,82 @@ impl LayoutEngine {
         }
     }
 
+    /// フォーム部品のサイズ計算（インジケータ + ラベル、RadioGroupは選択肢を縦に並べる）
+    /// Selectは最も長い選択肢が収まる幅の1行（右端のインジケータは開閉の矢印）
+    /// Sliderは既定幅のトラック、Stepperは −/+ ボタンの間に現在値を表示する
+    fn compute_form_control_size<F>(
+        &self,
+        node: &WithSpan<ViewNode>,
+        context: &LayoutContext,
+        eval: &F,
+    ) -> ComputedSize
+    where
+        F: Fn(&Expr) -> String,
+    {
+        let style = node.style.as_ref();
+        let font_size = style.and_then(|s| s.font_size).unwrap_or(context.font_size);
+        let font_family = style
+            .and_then(|s| s.font_family.as_deref())
+            .unwrap_or(&context.default_font);
+
+        let indicator = form_indicator_size(font_size);
+        let (indicator_width, labels) = match &node.node {
+            ViewNode::Checkbox { label, .. } => (indicator, label.iter().cloned().collect()),
+            ViewNode::Switch { label, .. } => (indicator * 1.8, label.iter().cloned().collect()),
+            ViewNode::RadioGroup { options, .. } => (indicator, parse_option_list(&eval(options))),
+            ViewNode::Select {
+                options,
+                placeholder,
+                ..
+            } => {
+                let mut labels = parse_option_list(&eval(options));
+                labels.extend(placeholder.iter().cloned());
+                (indicator, labels)
+            }
+            ViewNode::Slider { .. } => (indicator * 10.0, Vec::new()),
+            ViewNode::Stepper { bind, .. } => {
+                // 値の桁数が変わっても幅が揺れにくいよう最低3桁分を確保
+                (indicator, vec![eval(bind), "000".to_string()])
+            }
+            _ => (indicator, Vec::new()),
+        };
+
+        let mut label_width: f32 = 0.0;
+        let mut label_height: f32 = 0.0;
+        for label in &labels {
+            let m = self.measure_text(label, font_size, font_family, None);
+            label_width = label_width.max(m.width);
+            label_height = label_height.max(m.height);
+        }
+
+        let row_height = indicator.max(label_height) + 8.0;
+        let width = if label_width > 0.0 {
+            indicator_width + FORM_CONTROL_GAP + label_width
+        } else {
+            indicator_width
+        };
+        // Selectは枠の内側に左右の余白を取る、Stepperは左右に −/+ ボタンを置く
+        let width = match &node.node {
+            ViewNode::Select { .. } => width + FORM_CONTROL_GAP * 2.0,
+            ViewNode::Stepper { .. } => label_width + FORM_CONTROL_GAP * 2.0 + row_height * 2.0,
+            _ => width,
+        };
+        let rows = match &node.node {
+            ViewNode::RadioGroup { .. } => labels.len().max(1),
+            _ => 1,
+        };
+        let height = row_height * rows as f32 + FORM_CONTROL_GAP * (rows - 1) as f32;
+
+        ComputedSize {
+            width,
+            height,
+            intrinsic_width: width,
+            intrinsic_height: height,
+            has_explicit_width: false,
+            has_explicit_height: false,
+        }
+    }
+
     /// ボタンサイズを計算
     fn compute_button_size(
         &self,
@@ -897,9 +1535,7 @@ impl LayoutEngine {
     /// ForEach文のサイズを計算（各アイテムの展開を事前計算）- 完全に正確な実装
     fn compute_foreach_size<F, G>(
         &mut self,
-        _var: &str,
-        iterable: &Expr,
-        body: &[WithSpan<ViewNode>],
+        node: &WithSpan<ViewNode>,
         context: &LayoutContext,
         eval: &F,
         get_image_size: &G,
@@ -909,40 +1545,43 @@ impl LayoutEngine {
         F: Fn(&Expr) -> String,
         G: Fn(&str) -> (u32, u32),
     {
-        // 反復対象の評価
-        let iterable_value = eval(iterable);
-        let items: Vec<String> = if iterable_value.starts_with('[') && iterable_value.ends_with(']')
-        {
-            // JSON配列として解析を試行
-            serde_json::from_str::<Vec<serde_json::Value>>(&iterable_value)
-                .map(|vs| {
-                    vs.into_iter()
-                        .map(|v| match v {
-                            serde_json::Value::String(s) => s,
-                            serde_json::Value::Number(n) => n.to_string(),
-                            serde_json::Value::Bool(b) => b.to_string(),
-                            _ => v.to_string().trim_matches('"').to_string(),
-                        })
-                        .collect()
-                })
-                .unwrap_or_else(|_| vec![iterable_value])
-        } else {
-            vec![iterable_value]
+        let ViewNode::ForEach {
+            var,
+            index_var,
+            iterable,
+            body,
+            ..
+        } = &node.node
+        else {
+            return ComputedSize::default();
         };
 
+        // 反復対象の評価
+        let items = foreach_entries(eval(iterable));
+
         log::info!("🔍 compute_foreach_size: items.len()={}, parent_size={:?}", items.len(), context.parent_size);
 
         let mut total_height: f32 = 0.0;
         let mut max_width: f32 = 0.0;
 
         // 各アイテムに対してボディの各ノードのサイズを計算
-        for (item_index, _item) in items.iter().enumerate() {
+        for (item_index, (index, value)) in items.iter().enumerate() {
             // ★ 各アイテムのボディ全体の高さを正確に計算
             let mut item_height = 0.0;
+            let scope = ForeachScope {
+                var,
+                index_var: index_var.as_deref(),
+                value,
+                index,
+                position: item_index,
+            };
+            let item_eval = |e: &Expr| eval(&bind_foreach_vars(e, &scope));
+            let item_eval: &dyn Fn(&Expr) -> String = &item_eval;
             
             for (body_index, child) in body.iter().enumerate() {
                 // 通常のcompute_node_sizeを使用してスタイルを正しく反映
-                let child_size = self.compute_node_size(child, context, eval, get_image_size, app);
+                let child_size =
+                    self.compute_node_size(child, context, &item_eval, get_image_size, app);
 
                 log::info!("  📦 Item[{}] Body[{}]: size={}x{}", item_index, body_index, child_size.width, child_size.height);
                 
@@ -1024,6 +1663,12 @@ impl LayoutEngine {
                 self.compute_button_size(label, node.style.as_ref(), context)
             }
             ViewNode::Image { path } => self.compute_image_size(path, get_image_size),
+            ViewNode::Checkbox { .. }
+            | ViewNode::Switch { .. }
+            | ViewNode::RadioGroup { .. }
+            | ViewNode::Select { .. }
+            | ViewNode::Slider { .. }
+            | ViewNode::Stepper { .. } => self.compute_form_control_size(node, context, eval),
             ViewNode::ComponentCall { name, .. } => self.compute_component_size_with_style(
                 node,
                 name,
@@ -1449,8 +2094,20 @@ impl LayoutEngine {
         
         // ★ 一旦ScrollContainer機能を無効化して通常のレイアウトとして処理
         // TODO: ScrollContainerのレンダリングを修正後に再有効化
-        let _has_overflow_scroll = !matches!(overflow_mode, crate::parser::ast::OverflowMode::Visible);
-        
+        let has_overflow_scroll =
+            !matches!(overflow_mode, crate::parser::ast::OverflowMode::Visible);
+
+        // ★ ScrollContainer内の仮想化したforeachは、ページではなくコンテナの表示範囲で行を選ぶ
+        let outer_viewport = self.viewport;
+        if has_overflow_scroll {
+            let offset = self
+                .container_offsets
+                .get(&scroll_container_id(node))
+                .map_or(0.0, |offset| offset[1]);
+            let top = position[1] - offset;
+            self.viewport = Some([top, top + computed_size.height]);
+        }
+
         // ★ VStack/HStackの場合
         match &node.node {
             ViewNode::VStack(children) => {
@@ -1511,16 +2168,10 @@ impl LayoutEngine {
                     results.append(&mut child_results);
                 }
             }
-            ViewNode::ForEach {
-                var,
-                iterable,
-                body,
-            } => {
+            ViewNode::ForEach { .. } => {
                 // ★ ForEachもここで展開してレイアウト
                 self.layout_foreach_recursive(
-                    var,
-                    iterable,
-                    body,
+                    node,
                     context,
                     position,
                     eval,
@@ -1547,6 +2198,24 @@ impl LayoutEngine {
                     results,
                 );
             }
+            ViewNode::Match {
+                expr,
+                arms,
+                default,
+            } => {
+                // Match文を展開してレイアウト
+                self.layout_match_recursive(
+                    expr,
+                    arms,
+                    default.as_ref(),
+                    context,
+                    position,
+                    eval,
+                    get_image_size,
+                    app,
+                    results,
+                );
+            }
             ViewNode::Slot { .. } | ViewNode::SlotCheck { .. } => {
                 // スロットは何もしない
             }
@@ -1559,6 +2228,7 @@ impl LayoutEngine {
                 });
             }
         }
+        self.viewport = outer_viewport;
     }
 
     /// VStackレイアウト（再帰的処理版）
@@ -1648,16 +2318,10 @@ impl LayoutEngine {
 
             // 特別な処理が必要なノードタイプをチェック
             match &child.node {
-                ViewNode::ForEach {
-                    var,
-                    iterable,
-                    body,
-                } => {
+                ViewNode::ForEach { .. } => {
                     // Foreach文を展開してレイアウト
                     self.layout_foreach_recursive(
-                        var,
-                        iterable,
-                        body,
+                        child,
                         &child_context,
                         child_position,
                         eval,
@@ -1684,6 +2348,24 @@ impl LayoutEngine {
                         &mut results,
                     );
                 }
+                ViewNode::Match {
+                    expr,
+                    arms,
+                    default,
+                } => {
+                    // Match文を直接処理
+                    self.layout_match_recursive(
+                        expr,
+                        arms,
+                        default.as_ref(),
+                        &child_context,
+                        child_position,
+                        eval,
+                        get_image_size,
+                        app,
+                        &mut results,
+                    );
+                }
                 ViewNode::DynamicSection { name: _, body } => {
                     // DynamicSectionを展開してレイアウト
                     let child_results = self.layout_with_positioning(
@@ -1855,11 +2537,10 @@ impl LayoutEngine {
     }
 
     /// Foreach文のレイアウト処理（再帰的）
+    #[allow(clippy::too_many_arguments)]
     fn layout_foreach_recursive<'a, F, G>(
         &mut self,
-        var: &str,
-        iterable: &Expr,
-        body: &'a [WithSpan<ViewNode>],
+        node: &'a WithSpan<ViewNode>,
         context: &LayoutContext,
         position: [f32; 2],
         eval: &F,
@@ -1870,63 +2551,264 @@ impl LayoutEngine {
         F: Fn(&Expr) -> String,
         G: Fn(&str) -> (u32, u32),
     {
-        // 配列の値を取得
-        let iterable_value = eval(iterable);
-
-        // 簡単な配列パース：[1, 2, 3] -> ["1", "2", "3"]
-        let items: Vec<String> = if iterable_value.starts_with('[') && iterable_value.ends_with(']')
-        {
-            let inner = &iterable_value[1..iterable_value.len() - 1];
-            if inner.trim().is_empty() {
-                vec![]
-            } else {
-                inner.split(',').map(|s| s.trim().to_string()).collect()
-            }
-        } else {
-            vec![iterable_value]
+        let ViewNode::ForEach { var, .. } = &node.node else {
+            return;
         };
 
-        // パフォーマンス最適化：デバッグ出力を削除
-
-        let mut current_y = position[1];
+        // 配列の値を取得（要素ごとのkeyもここで評価）
+        let items = foreach_items(&node.node, eval);
+        if let Some(cache) = &self.foreach_cache {
+            cache.mark_rendered(var, &items);
+        }
 
-        // 各itemを処理
-        for (item_index, item) in items.iter().enumerate() {
-            for child in body {
-                self.process_foreach_node_recursive(
-                    child,
-                    var,
+        // ★ 仮想化：表示範囲付近の行だけを展開し、リスト全体の高さはスペーサーで確保
+        if let Some(item_height) = virtual_item_height(node.style.as_ref()) {
+            let viewport = self.viewport.unwrap_or([0.0, context.window_size[1]]);
+            let [top, bottom] = virtual_render_range(viewport);
+            let first = ((top - position[1]) / item_height).floor().max(0.0) as usize;
+            let last =
+                (((bottom - position[1]) / item_height).ceil().max(0.0) as usize).min(items.len());
+
+            for (item_index, item) in items.iter().enumerate().take(last).skip(first) {
+                let mut current_y = position[1] + item_index as f32 * item_height;
+                self.layout_foreach_item_cached(
+                    node,
                     item,
-                    &item_index.to_string(),
+                    item_index,
                     context,
-                    [position[0], current_y],
+                    position[0],
+                    &mut current_y,
                     eval,
                     get_image_size,
                     app,
                     results,
-                    &mut current_y,
                 );
             }
 
+            let total_height = items.len() as f32 * item_height;
+            results.push(LayoutedNode {
+                node: &VIRTUAL_LIST_END,
+                position: [position[0], position[1] + total_height],
+                size: [context.parent_size[0], 0.0],
+            });
+            return;
+        }
+
+        let mut current_y = position[1];
+
+        // 各itemを処理
+        for (item_index, item) in items.iter().enumerate() {
+            self.layout_foreach_item_cached(
+                node,
+                item,
+                item_index,
+                context,
+                position[0],
+                &mut current_y,
+                eval,
+                get_image_size,
+                app,
+                results,
+            );
+
             // アイテム間のスペーシングを追加（最後のアイテム以外）
             if item_index < items.len() - 1 {
-                let inter_item_spacing = if let Some(first_child) = body.first() {
-                    self.get_spacing_from_style(first_child.style.as_ref(), context)
-                } else {
-                    0.0
-                };
-                current_y += inter_item_spacing;
+                current_y += self.foreach_item_spacing(node, context);
             }
         }
     }
 
+    /// foreachのアイテム間のスペーシング（bodyの最初の要素のスタイルから取得）
+    pub fn foreach_item_spacing(&self, node: &WithSpan<ViewNode>, context: &LayoutContext) -> f32 {
+        match &node.node {
+            ViewNode::ForEach { body, .. } => body
+                .first()
+                .map(|first_child| self.get_spacing_from_style(first_child.style.as_ref(), context))
+                .unwrap_or(0.0),
+            _ => 0.0,
+        }
+    }
+
+    /// ★ foreachの1要素だけをレイアウトし、(結果, 要素の高さ) を返す
+    /// 差分計算でkeyごとにレイアウト結果を再利用するために使う
+    #[allow(clippy::too_many_arguments)]
+    pub fn layout_foreach_entry<'a, F, G>(
+        &mut self,
+        node: &'a WithSpan<ViewNode>,
+        item: &ForeachItem,
+        item_index: usize,
+        context: &LayoutContext,
+        position: [f32; 2],
+        eval: &F,
+        get_image_size: &G,
+        app: &'a App,
+    ) -> (Vec<LayoutedNode<'a>>, f32)
+    where
+        F: Fn(&Expr) -> String,
+        G: Fn(&str) -> (u32, u32),
+    {
+        let mut results = Vec::new();
+        let mut current_y = position[1];
+        self.layout_foreach_item(
+            node,
+            item,
+            item_index,
+            context,
+            position[0],
+            &mut current_y,
+            eval,
+            get_image_size,
+            app,
+            &mut results,
+        );
+        (results, current_y - position[1])
+    }
+
+    /// ★ foreachの1要素をレイアウト（キャッシュがあればkeyが同じ要素の結果を再利用）
+    #[allow(clippy::too_many_arguments)]
+    fn layout_foreach_item_cached<'a, F, G>(
+        &mut self,
+        node: &'a WithSpan<ViewNode>,
+        item: &ForeachItem,
+        item_index: usize,
+        context: &LayoutContext,
+        x: f32,
+        current_y: &mut f32,
+        eval: &F,
+        get_image_size: &G,
+        app: &'a App,
+        results: &mut Vec<LayoutedNode<'a>>,
+    ) where
+        F: Fn(&Expr) -> String,
+        G: Fn(&str) -> (u32, u32),
+    {
+        let (Some(cache), ViewNode::ForEach { body, .. }) =
+            (self.foreach_cache.clone(), &node.node)
+        else {
+            self.layout_foreach_item(
+                node,
+                item,
+                item_index,
+                context,
+                x,
+                current_y,
+                eval,
+                get_image_size,
+                app,
+                results,
+            );
+            return;
+        };
+
+        let key = format!("{}:{}#{}", node.line, node.column, item.key);
+        let item_eval = |e: &Expr| item.eval(&node.node, item_index, e, eval);
+        let hash = NodeHash::from_foreach_item(item, body, &item_eval);
+        let width = context.parent_size[0];
+        let origin = [x, *current_y];
+
+        if let Some((layouted, height)) = cache.reuse(&key, &hash, width, origin) {
+            results.extend(layouted);
+            *current_y += height;
+            return;
+        }
+
+        let start = results.len();
+        self.layout_foreach_item(
+            node,
+            item,
+            item_index,
+            context,
+            x,
+            current_y,
+            eval,
+            get_image_size,
+            app,
+            results,
+        );
+        // 展開したノードはフレームをまたいで使うため、キャッシュ用に複製して保持する
+        let layouted = results[start..]
+            .iter()
+            .map(|n| LayoutedNode {
+                node: &*Box::leak(Box::new(n.node.clone())),
+                position: n.position,
+                size: n.size,
+            })
+            .collect();
+        cache.store(
+            key,
+            ForeachCacheEntry {
+                hash,
+                width,
+                origin,
+                height: *current_y - origin[1],
+                layouted,
+            },
+        );
+    }
+
+    /// foreachの1要素分のボディをレイアウト（ループ変数・インデックス変数を束縛して評価）
+    #[allow(clippy::too_many_arguments)]
+    fn layout_foreach_item<'a, F, G>(
+        &mut self,
+        node: &'a WithSpan<ViewNode>,
+        item: &ForeachItem,
+        item_index: usize,
+        context: &LayoutContext,
+        x: f32,
+        current_y: &mut f32,
+        eval: &F,
+        get_image_size: &G,
+        app: &'a App,
+        results: &mut Vec<LayoutedNode<'a>>,
+    ) where
+        F: Fn(&Expr) -> String,
+        G: Fn(&str) -> (u32, u32),
+    {
+        let ViewNode::ForEach {
+            var,
+            index_var,
+            body,
+            ..
+        } = &node.node
+        else {
+            return;
+        };
+
+        let scope = ForeachScope {
+            var,
+            index_var: index_var.as_deref(),
+            value: &item.value,
+            index: &item.index,
+            position: item_index,
+        };
+        // ★ ネストしたforeachでも型が膨らまないよう、束縛付きの評価関数はトレイトオブジェクトで渡す
+        let item_eval = |e: &Expr| eval(&bind_foreach_vars(e, &scope));
+        let item_eval: &dyn Fn(&Expr) -> String = &item_eval;
+
+        // ★ 部品のインスタンスはkeyの値（無ければ番号）で区別する
+        // 並べ替えてもローカル状態や入力中の値が同じ要素に付いていく
+        for child in body {
+            self.process_foreach_node_recursive(
+                child,
+                var,
+                &item.key,
+                context,
+                [x, *current_y],
+                &item_eval,
+                get_image_size,
+                app,
+                results,
+                current_y,
+            );
+        }
+    }
+
     /// foreach内のノードを再帰的に処理（HStack/VStackも展開）- 変数展開対応版
     fn process_foreach_node_recursive<'a, F, G>(
         &mut self,
         node: &'a WithSpan<ViewNode>,
         var: &str,
-        item_value: &str,
-        item_index_value: &str,
+        item_key: &str,
         context: &LayoutContext,
         position: [f32; 2],
         eval: &F,
@@ -1945,8 +2827,10 @@ impl LayoutEngine {
                 // 各引数を処理
                 for arg in args {
                     let value = match arg {
-                        Expr::Path(path) if path == var => item_value.to_string(),
-                        Expr::Path(path) if path == "item_index" => item_index_value.to_string(),
+                        // ★ foreach内コンポーネントのローカル状態は要素のキーでインスタンスを特定
+                        Expr::Path(path) if path.contains('{') => eval(&Expr::Path(
+                            bind_foreach_key(path, var, item_key),
+                        )),
                         _ => eval(arg),
                     };
                     final_format = final_format.replacen("{}", &value, 1);
@@ -1980,7 +2864,7 @@ impl LayoutEngine {
                 // ★ HStack/VStackの子要素を変数展開してから処理
                 let expanded_children: Vec<WithSpan<ViewNode>> = children
                     .iter()
-                    .map(|child| self.expand_foreach_variables(child, var, item_value, item_index_value, eval))
+                    .map(|child| self.expand_foreach_variables(child, var, item_key, eval))
                     .collect();
                 
                 // 展開された子要素でHStack/VStackノードを作成
@@ -2019,13 +2903,10 @@ impl LayoutEngine {
                 *current_y += size.height;
             }
             _ => {
-                // その他のノードタイプも正しく処理
-                let new_node = Box::leak(Box::new(WithSpan {
-                    node: node.node.clone(),
-                    line: node.line,
-                    column: node.column,
-                    style: node.style.clone(),
-                }));
+                // その他のノードタイプも正しく処理（ボタンIDのforeachキーもここで確定）
+                let new_node = Box::leak(Box::new(
+                    self.expand_foreach_variables(node, var, item_key, eval),
+                ));
 
                 let size = self.compute_node_size(new_node, context, eval, get_image_size, app);
 
@@ -2046,8 +2927,7 @@ impl LayoutEngine {
         &self,
         node: &WithSpan<ViewNode>,
         var: &str,
-        item_value: &str,
-        item_index_value: &str,
+        item_key: &str,
         eval: &F,
     ) -> WithSpan<ViewNode>
     where
@@ -2060,8 +2940,10 @@ impl LayoutEngine {
                 // 各引数を処理
                 for arg in args {
                     let value = match arg {
-                        Expr::Path(path) if path == var => item_value.to_string(),
-                        Expr::Path(path) if path == "item_index" => item_index_value.to_string(),
+                        // ★ foreach内コンポーネントのローカル状態は要素のキーでインスタンスを特定
+                        Expr::Path(path) if path.contains('{') => eval(&Expr::Path(
+                            bind_foreach_key(path, var, item_key),
+                        )),
                         _ => eval(arg),
                     };
                     final_format = final_format.replacen("{}", &value, 1);
@@ -2075,17 +2957,99 @@ impl LayoutEngine {
             ViewNode::VStack(children) => {
                 let expanded_children: Vec<WithSpan<ViewNode>> = children
                     .iter()
-                    .map(|child| self.expand_foreach_variables(child, var, item_value, item_index_value, eval))
+                    .map(|child| self.expand_foreach_variables(child, var, item_key, eval))
                     .collect();
                 ViewNode::VStack(expanded_children)
             }
             ViewNode::HStack(children) => {
                 let expanded_children: Vec<WithSpan<ViewNode>> = children
                     .iter()
-                    .map(|child| self.expand_foreach_variables(child, var, item_value, item_index_value, eval))
+                    .map(|child| self.expand_foreach_variables(child, var, item_key, eval))
                     .collect();
                 ViewNode::HStack(expanded_children)
             }
+            ViewNode::Button { id, label, onclick } => ViewNode::Button {
+                id: bind_foreach_key(id, var, item_key),
+                label: label.clone(),
+                onclick: onclick.clone(),
+            },
+            // ★ foreach内コンポーネントのフォーム部品もIDとbind先を要素のキーで特定
+            ViewNode::Checkbox { id, bind, label } => ViewNode::Checkbox {
+                id: bind_foreach_key(id, var, item_key),
+                bind: bind_foreach_path(bind, var, item_key),
+                label: label.clone(),
+            },
+            ViewNode::Switch { id, bind, label } => ViewNode::Switch {
+                id: bind_foreach_key(id, var, item_key),
+                bind: bind_foreach_path(bind, var, item_key),
+                label: label.clone(),
+            },
+            ViewNode::RadioGroup { id, bind, options } => ViewNode::RadioGroup {
+                id: bind_foreach_key(id, var, item_key),
+                bind: bind_foreach_path(bind, var, item_key),
+                options: options.clone(),
+            },
+            ViewNode::Select {
+                id,
+                bind,
+                options,
+                placeholder,
+            } => ViewNode::Select {
+                id: bind_foreach_key(id, var, item_key),
+                bind: bind_foreach_path(bind, var, item_key),
+                options: options.clone(),
+                placeholder: placeholder.clone(),
+            },
+            ViewNode::Slider {
+                id,
+                bind,
+                min,
+                max,
+                step,
+            } => ViewNode::Slider {
+                id: bind_foreach_key(id, var, item_key),
+                bind: bind_foreach_path(bind, var, item_key),
+                min: min.clone(),
+                max: max.clone(),
+                step: step.clone(),
+            },
+            ViewNode::Stepper {
+                id,
+                bind,
+                min,
+                max,
+                step,
+            } => ViewNode::Stepper {
+                id: bind_foreach_key(id, var, item_key),
+                bind: bind_foreach_path(bind, var, item_key),
+                min: min.clone(),
+                max: max.clone(),
+                step: step.clone(),
+            },
+            // ★ foreach内コンポーネントのテキスト入力はIDとbind先を要素のキーで特定
+            ViewNode::TextInput {
+                id,
+                placeholder,
+                value,
+                bind,
+                input_type,
+                on_change,
+                multiline,
+                max_length,
+                ime_enabled,
+            } => ViewNode::TextInput {
+                id: bind_foreach_key(id, var, item_key),
+                placeholder: placeholder.clone(),
+                value: value.clone(),
+                bind: bind
+                    .as_ref()
+                    .map(|expr| bind_foreach_path(expr, var, item_key)),
+                input_type: *input_type,
+                on_change: on_change.clone(),
+                multiline: *multiline,
+                max_length: *max_length,
+                ime_enabled: *ime_enabled,
+            },
             _ => node.node.clone(),
         };
 
@@ -2098,7 +3062,6 @@ impl LayoutEngine {
     }
 
     /// ノード内の変数を展開したノードを作成
-    #[allow(dead_code)]
     fn expand_node_variables<F>(&self, node: &WithSpan<ViewNode>, eval: &F) -> WithSpan<ViewNode>
     where
         F: Fn(&Expr) -> String,
@@ -2225,6 +3188,65 @@ impl LayoutEngine {
         }
     }
 
+    /// Match文のレイアウト処理（一致したcaseの本体だけをレイアウト）
+    #[allow(clippy::too_many_arguments)]
+    fn layout_match_recursive<'a, F, G>(
+        &mut self,
+        expr: &Expr,
+        arms: &'a [(MatchCase, Vec<WithSpan<ViewNode>>)],
+        default: Option<&'a Vec<WithSpan<ViewNode>>>,
+        context: &LayoutContext,
+        position: [f32; 2],
+        eval: &F,
+        get_image_size: &G,
+        app: &'a App,
+        results: &mut Vec<LayoutedNode<'a>>,
+    ) where
+        F: Fn(&Expr) -> String,
+        G: Fn(&str) -> (u32, u32),
+    {
+        let value = eval(expr);
+        let (binding, selected_body): (Option<&str>, &'a [WithSpan<ViewNode>]) = match arms
+            .iter()
+            .find(|(case, _)| match_pattern::case_matches(case, &value, eval))
+        {
+            Some((case, body)) => (case.binding.as_deref(), body),
+            None => (None, default.map(|v| v.as_slice()).unwrap_or(&[])),
+        };
+
+        let Some(name) = binding else {
+            for child in selected_body {
+                self.layout_single_node_recursive(
+                    child,
+                    context,
+                    position,
+                    eval,
+                    get_image_size,
+                    app,
+                    results,
+                );
+            }
+            return;
+        };
+
+        // ★ 束縛した名前（`case n`）は値に置き換えて評価し、foreachと同様に展開済みのノードを作る
+        let bound_eval = |e: &Expr| eval(&match_pattern::bind_value(e, name, &value));
+        let bound_eval: &dyn Fn(&Expr) -> String = &bound_eval;
+        for child in selected_body {
+            let expanded: &'a WithSpan<ViewNode> =
+                Box::leak(Box::new(self.expand_node_variables(child, &bound_eval)));
+            self.layout_single_node_recursive(
+                expanded,
+                context,
+                position,
+                &bound_eval,
+                get_image_size,
+                app,
+                results,
+            );
+        }
+    }
+
     /// VStackレイアウト（互換性のため残存）
     #[allow(dead_code)]
     fn layout_vstack<'a, F, G>(
@@ -2365,6 +3387,11 @@ where
     G: Fn(&str) -> (u32, u32),
 {
     let mut engine = LayoutEngine::new();
+    if let Some(viewport) = &params.viewport {
+        engine.viewport = Some(viewport.range);
+        engine.container_offsets = viewport.container_offsets.clone();
+        engine.foreach_cache = Some(viewport.foreach_cache.clone());
+    }
     let context = LayoutContext::from(&params);
 
     engine.layout_with_positioning(
@@ -2393,6 +3420,7 @@ pub fn layout_node<'a>(
         root_font_size: 16.0,
         font_size: 16.0,
         default_font: "Arial".to_string(),
+        viewport: None,
     };
 
     let eval = |expr: &Expr| -> String {
@@ -2423,10 +3451,13 @@ pub fn layout_node<'a>(
             flow: crate::parser::ast::Flow {
                 start: "start".to_string(),
                 start_url: None,
+                not_found: None,
                 transitions: vec![],
+                sub_flows: vec![],
             },
             timelines: vec![],
             components: vec![],
+            functions: vec![],
         },
     );
 
@@ -2439,3 +3470,88 @@ pub fn layout_node<'a>(
     let total_size = [available_size[0], computed_size.height];
     Some((result, total_size))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse::parse_nilo;
+
+    /// 1000行の仮想化したリストをレイアウトし、(テキスト, y座標) と全ノードを返す
+    fn layout_logs(source: &str, viewport: LayoutViewport) -> (Vec<(String, f32)>, f32) {
+        let app = parse_nilo(source).unwrap();
+        let logs = serde_json::to_string(&(0..1000).collect::<Vec<_>>()).unwrap();
+        let eval = |e: &Expr| match e {
+            Expr::Path(p) if p == "state.logs" => logs.clone(),
+            Expr::String(s) => s.clone(),
+            Expr::Number(n) => n.to_string(),
+            _ => String::new(),
+        };
+        let params = LayoutParams {
+            viewport: Some(viewport),
+            ..LayoutParams::default()
+        };
+        let results = layout_vstack(&app.timelines[0].body, params, &app, &eval, &|_| (0, 0));
+        let texts = results
+            .iter()
+            .filter_map(|n| match &n.node.node {
+                ViewNode::Text { format, .. } => Some((format.clone(), n.position[1])),
+                _ => None,
+            })
+            .collect();
+        let bottom = results
+            .iter()
+            .map(|n| n.position[1] + n.size[1])
+            .fold(0.0, f32::max);
+        (texts, bottom)
+    }
+
+    #[test]
+    fn test_virtual_foreach_lays_out_visible_rows() {
+        let source = r#"
+            flow { start: Main }
+            timeline Main {
+                foreach n in state.logs (virtual: true, item_height: 20px) {
+                    Text("row {}", n)
+                }
+            }
+        "#;
+
+        let viewport = LayoutViewport {
+            range: [2000.0, 2600.0],
+            ..LayoutViewport::default()
+        };
+        let (texts, bottom) = layout_logs(source, viewport);
+        // 表示範囲と上下1画面分（y: 1400〜3200）の行だけを展開する
+        assert_eq!(texts.len(), 90);
+        assert_eq!(texts[0], ("row 70".to_string(), 1400.0));
+        // 展開しない行の分もリストの高さに含める
+        assert_eq!(bottom, 20000.0);
+    }
+
+    #[test]
+    fn test_virtual_foreach_uses_scroll_container_range() {
+        let source = r#"
+            flow { start: Main }
+            timeline Main {
+                VStack(style: { height: 100px, overflow: "scroll" }) {
+                    foreach n in state.logs (virtual: true, item_height: 20px) {
+                        Text("row {}", n)
+                    }
+                }
+            }
+        "#;
+        let app = parse_nilo(source).unwrap();
+        let container = &app.timelines[0].body[0];
+
+        // コンテナを400pxスクロールすると、ページの位置によらずコンテナ内の表示範囲の行を展開する
+        let viewport = LayoutViewport {
+            range: [0.0, 600.0],
+            container_offsets: HashMap::from([(scroll_container_id(container), [0.0, -400.0])]),
+            ..LayoutViewport::default()
+        };
+        let (texts, _) = layout_logs(source, viewport);
+        let rows: Vec<_> = texts.iter().map(|(text, _)| text.as_str()).collect();
+        assert_eq!(rows.first(), Some(&"row 15"));
+        assert_eq!(rows.last(), Some(&"row 29"));
+    }
+}
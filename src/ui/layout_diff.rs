@@ -2,6 +2,7 @@
 // ノードツリーの変更を検出し、変更があった部分のみを再計算する
 
 use crate::parser::ast::{App, Expr, Style, ViewNode, WithSpan};
+use crate::ui::layout::{ForeachItem, LayoutContext, LayoutEngine, foreach_items};
 use crate::ui::{LayoutParams, LayoutedNode};
 use std::collections::HashMap;
 
@@ -22,6 +23,13 @@ impl NodeId {
         Self { path: new_path }
     }
 
+    /// ★ foreachの要素をkeyの値で識別する子ID（並べ替えても同じ要素は同じIDになる）
+    pub fn keyed(&self, item_key: &str) -> Self {
+        let mut new_path = self.path.clone();
+        new_path.push(format!("#{}", item_key));
+        Self { path: new_path }
+    }
+
     pub fn key(&self) -> String {
         self.path.join("/")
     }
@@ -40,6 +48,57 @@ impl NodeHash {
         Self { hash }
     }
 
+    /// ★ foreachの1要素のハッシュ（キー・要素の値と、ボディの子孫ノードすべてを含む）
+    /// ボディはループ変数を束縛した評価関数で評価するため、ローカル状態の変化も検出できる
+    /// 並び順はボディがインデックス変数を参照している場合だけハッシュに影響する
+    pub fn from_foreach_item(
+        item: &ForeachItem,
+        body: &[WithSpan<ViewNode>],
+        eval: &dyn Fn(&Expr) -> String,
+    ) -> Self {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        item.key.hash(&mut hasher);
+        item.value.hash(&mut hasher);
+        Self::hash_subtree(body, eval, &mut hasher);
+        Self {
+            hash: format!("{:x}", hasher.finish()),
+        }
+    }
+
+    fn hash_subtree(
+        nodes: &[WithSpan<ViewNode>],
+        eval: &dyn Fn(&Expr) -> String,
+        hasher: &mut impl std::hash::Hasher,
+    ) {
+        use std::hash::Hash;
+
+        for node in nodes {
+            Self::compute_hash(node, eval).hash(hasher);
+            match &node.node {
+                ViewNode::VStack(children)
+                | ViewNode::HStack(children)
+                | ViewNode::Dialog { body: children, .. }
+                | ViewNode::DynamicSection { body: children, .. } => {
+                    Self::hash_subtree(children, eval, hasher);
+                }
+                ViewNode::If {
+                    then_body,
+                    else_body,
+                    ..
+                } => {
+                    Self::hash_subtree(then_body, eval, hasher);
+                    if let Some(else_body) = else_body {
+                        Self::hash_subtree(else_body, eval, hasher);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
     fn compute_hash(node: &WithSpan<ViewNode>, eval: &dyn Fn(&Expr) -> String) -> String {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
@@ -73,6 +132,61 @@ impl NodeHash {
                     s.push_str(ph);
                 }
             }
+            ViewNode::Checkbox { id, bind, label } | ViewNode::Switch { id, bind, label } => {
+                s.push_str("Toggle:");
+                s.push_str(id);
+                s.push_str(&eval(bind));
+                if let Some(label) = label {
+                    s.push_str(label);
+                }
+            }
+            ViewNode::RadioGroup { id, bind, options } => {
+                s.push_str("RadioGroup:");
+                s.push_str(id);
+                s.push_str(&eval(bind));
+                s.push_str(&eval(options));
+            }
+            ViewNode::Select {
+                id,
+                bind,
+                options,
+                placeholder,
+            } => {
+                s.push_str("Select:");
+                s.push_str(id);
+                s.push_str(&eval(bind));
+                s.push_str(&eval(options));
+                if let Some(ph) = placeholder {
+                    s.push_str(ph);
+                }
+            }
+            ViewNode::Slider {
+                id,
+                bind,
+                min,
+                max,
+                step,
+            }
+            | ViewNode::Stepper {
+                id,
+                bind,
+                min,
+                max,
+                step,
+            } => {
+                s.push_str("Numeric:");
+                s.push_str(id);
+                s.push_str(&eval(bind));
+                for expr in [min, max, step].into_iter().flatten() {
+                    s.push_str(&eval(expr));
+                }
+            }
+            ViewNode::Dialog { id, open, body } => {
+                s.push_str("Dialog:");
+                s.push_str(id);
+                s.push_str(&eval(open));
+                s.push_str(&format!("{}", body.len()));
+            }
             ViewNode::VStack(children) => {
                 s.push_str(&format!("VStack:{}", children.len()));
             }
@@ -87,12 +201,20 @@ impl NodeHash {
             }
             ViewNode::ForEach {
                 var,
+                index_var,
                 iterable,
+                key,
                 body,
             } => {
                 s.push_str("ForEach:");
                 s.push_str(var);
+                if let Some(index_var) = index_var {
+                    s.push_str(index_var);
+                }
                 s.push_str(&eval(iterable));
+                if let Some(key) = key {
+                    s.push_str(&format!("key:{:?}", key));
+                }
                 s.push_str(&format!("{}", body.len()));
             }
             ViewNode::If {
@@ -122,6 +244,10 @@ impl NodeHash {
                             s.push_str(":");
                             s.push_str(&eval(expr));
                         }
+                        crate::parser::ast::ComponentArg::Handler { name, actions, .. } => {
+                            s.push_str(name);
+                            s.push_str(&format!(":handler:{}", actions.len()));
+                        }
                     }
                 }
             }
@@ -135,6 +261,17 @@ impl NodeHash {
                     s.push_str(&eval(arg));
                 }
             }
+            ViewNode::Emit { name, args } => {
+                s.push_str("Emit:");
+                s.push_str(name);
+                for arg in args {
+                    s.push_str(&eval(arg));
+                }
+            }
+            ViewNode::Toast { message, .. } => {
+                s.push_str("Toast:");
+                s.push_str(&eval(message));
+            }
             ViewNode::DynamicSection { name, body } => {
                 s.push_str("Dynamic:");
                 s.push_str(name);
@@ -153,10 +290,38 @@ impl NodeHash {
                     default.is_some()
                 ));
             }
-            ViewNode::NavigateTo { target } => {
+            ViewNode::NavigateTo { target, params } => {
                 s.push_str("NavigateTo:");
                 s.push_str(target);
+                for (name, value) in params {
+                    s.push_str(name);
+                    s.push_str(&eval(value));
+                }
             }
+            ViewNode::NavigateBack => s.push_str("NavigateBack"),
+            ViewNode::CallFlow { flow, into, params } => {
+                s.push_str("CallFlow:");
+                s.push_str(flow);
+                s.push_str(into.as_deref().unwrap_or(""));
+                for (name, value) in params {
+                    s.push_str(name);
+                    s.push_str(&eval(value));
+                }
+            }
+            ViewNode::Finish { value } => {
+                s.push_str("Finish:");
+                if let Some(value) = value {
+                    s.push_str(&eval(value));
+                }
+            }
+            ViewNode::Http {
+                method, url, into, ..
+            } => {
+                s.push_str(&format!("Http:{:?}:", method));
+                s.push_str(&eval(url));
+                s.push_str(into.as_deref().unwrap_or(""));
+            }
+            ViewNode::NavigateForward => s.push_str("NavigateForward"),
             ViewNode::Set { path, value, .. } => {
                 s.push_str("Set:");
                 s.push_str(path);
@@ -259,6 +424,15 @@ struct LayoutCacheEntry<'a> {
     children_hashes: Vec<NodeHash>,
 }
 
+/// ★ foreachの1要素分のレイアウト結果（keyごとに保持し、並べ替え後は座標をずらして再利用）
+#[derive(Debug, Clone)]
+struct ForeachItemCacheEntry<'a> {
+    hash: NodeHash,
+    layouted: Vec<LayoutedNode<'a>>,
+    origin: [f32; 2],
+    height: f32,
+}
+
 /// レイアウト差分計算エンジン
 #[derive(Debug)]
 pub struct LayoutDiffEngine<'a> {
@@ -266,6 +440,9 @@ pub struct LayoutDiffEngine<'a> {
     prev_cache: HashMap<String, LayoutCacheEntry<'a>>,
     // 現在のレイアウト結果
     current_cache: HashMap<String, LayoutCacheEntry<'a>>,
+    // ★ foreachの要素ごとのレイアウト結果（キーは NodeId::keyed）
+    prev_item_cache: HashMap<String, ForeachItemCacheEntry<'a>>,
+    current_item_cache: HashMap<String, ForeachItemCacheEntry<'a>>,
     // 変更があったノードのID
     dirty_nodes: Vec<String>,
 }
@@ -275,6 +452,8 @@ impl<'a> LayoutDiffEngine<'a> {
         Self {
             prev_cache: HashMap::new(),
             current_cache: HashMap::new(),
+            prev_item_cache: HashMap::new(),
+            current_item_cache: HashMap::new(),
             dirty_nodes: Vec::new(),
         }
     }
@@ -300,6 +479,8 @@ impl<'a> LayoutDiffEngine<'a> {
 
         // 現在のキャッシュを前回のキャッシュとして保存
         self.prev_cache = self.current_cache.clone();
+        // 今回表示されなかった要素のキャッシュは破棄する
+        self.prev_item_cache = std::mem::take(&mut self.current_item_cache);
 
         results
     }
@@ -322,6 +503,20 @@ impl<'a> LayoutDiffEngine<'a> {
 
         for (idx, node) in nodes.iter().enumerate() {
             let node_id = parent_id.child(idx, &Self::node_type_name(&node.node));
+
+            // ★ foreachは要素ごとにkeyで差分を取る
+            if matches!(node.node, ViewNode::ForEach { .. }) {
+                results.extend(self.compute_foreach_diff(
+                    node,
+                    &node_id,
+                    params,
+                    app,
+                    eval,
+                    get_image_size,
+                ));
+                continue;
+            }
+
             let node_key = node_id.key();
 
             // ハッシュを計算
@@ -366,6 +561,87 @@ impl<'a> LayoutDiffEngine<'a> {
         results
     }
 
+    /// ★ foreachの差分計算
+    /// 要素は NodeId::keyed（keyの値、key指定が無ければ番号）で識別し、
+    /// 値とボディが変わっていない要素は並べ替えで位置が変わっても再レイアウトしない
+    fn compute_foreach_diff<F, G>(
+        &mut self,
+        node: &'a WithSpan<ViewNode>,
+        node_id: &NodeId,
+        params: &LayoutParams,
+        app: &'a App,
+        eval: &F,
+        get_image_size: &G,
+    ) -> Vec<LayoutedNode<'a>>
+    where
+        F: Fn(&Expr) -> String,
+        G: Fn(&str) -> (u32, u32),
+    {
+        let ViewNode::ForEach { body, .. } = &node.node else {
+            return Vec::new();
+        };
+
+        let items = foreach_items(&node.node, eval);
+        let context = LayoutContext::from(params);
+        let mut engine = LayoutEngine::new();
+        let spacing = engine.foreach_item_spacing(node, &context);
+
+        let mut results = Vec::new();
+        let mut current_y = params.start[1];
+        for (item_index, item) in items.iter().enumerate() {
+            let item_key = node_id.keyed(&item.key).key();
+            let item_eval = |e: &Expr| item.eval(&node.node, item_index, e, eval);
+            let hash = NodeHash::from_foreach_item(item, body, &item_eval);
+            let origin = [params.start[0], current_y];
+
+            let entry = match self.prev_item_cache.remove(&item_key) {
+                Some(prev) if prev.hash == hash => {
+                    let offset = [origin[0] - prev.origin[0], origin[1] - prev.origin[1]];
+                    ForeachItemCacheEntry {
+                        layouted: prev
+                            .layouted
+                            .into_iter()
+                            .map(|n| LayoutedNode {
+                                position: [n.position[0] + offset[0], n.position[1] + offset[1]],
+                                ..n
+                            })
+                            .collect(),
+                        origin,
+                        ..prev
+                    }
+                }
+                _ => {
+                    self.dirty_nodes.push(item_key.clone());
+                    let (layouted, height) = engine.layout_foreach_entry(
+                        node,
+                        item,
+                        item_index,
+                        &context,
+                        origin,
+                        eval,
+                        get_image_size,
+                        app,
+                    );
+                    ForeachItemCacheEntry {
+                        hash,
+                        layouted,
+                        origin,
+                        height,
+                    }
+                }
+            };
+
+            current_y += entry.height;
+            if item_index + 1 < items.len() {
+                current_y += spacing;
+            }
+            results.extend(entry.layouted.iter().cloned());
+            self.current_item_cache.insert(item_key, entry);
+        }
+
+        results
+    }
+
     /// 実際のレイアウト計算（既存のlayout_vstackを呼び出す）
     fn compute_layout_for_node<F, G>(
         &self,
@@ -403,6 +679,13 @@ impl<'a> LayoutDiffEngine<'a> {
             ViewNode::Button { .. } => "Button",
             ViewNode::Image { .. } => "Image",
             ViewNode::TextInput { .. } => "TextInput",
+            ViewNode::Checkbox { .. } => "Checkbox",
+            ViewNode::Switch { .. } => "Switch",
+            ViewNode::RadioGroup { .. } => "RadioGroup",
+            ViewNode::Select { .. } => "Select",
+            ViewNode::Slider { .. } => "Slider",
+            ViewNode::Stepper { .. } => "Stepper",
+            ViewNode::Dialog { .. } => "Dialog",
             ViewNode::VStack(_) => "VStack",
             ViewNode::HStack(_) => "HStack",
             ViewNode::Spacing(_) => "Spacing",
@@ -415,8 +698,15 @@ impl<'a> LayoutDiffEngine<'a> {
             ViewNode::SlotCheck { .. } => "SlotCheck", // ★ Phase 2
             ViewNode::Stencil(_) => "Stencil",
             ViewNode::RustCall { .. } => "RustCall",
+            ViewNode::Emit { .. } => "Emit",
+            ViewNode::Toast { .. } => "Toast",
             ViewNode::DynamicSection { .. } => "Dynamic",
             ViewNode::NavigateTo { .. } => "NavigateTo",
+            ViewNode::NavigateBack => "NavigateBack",
+            ViewNode::CallFlow { .. } => "CallFlow",
+            ViewNode::Finish { .. } => "Finish",
+            ViewNode::Http { .. } => "Http",
+            ViewNode::NavigateForward => "NavigateForward",
             ViewNode::Set { .. } => "Set",
             ViewNode::Toggle { .. } => "Toggle",
             ViewNode::ListAppend { .. } => "ListAppend",
@@ -443,6 +733,8 @@ impl<'a> LayoutDiffEngine<'a> {
     pub fn clear_cache(&mut self) {
         self.prev_cache.clear();
         self.current_cache.clear();
+        self.prev_item_cache.clear();
+        self.current_item_cache.clear();
         self.dirty_nodes.clear();
     }
 }
@@ -489,6 +781,65 @@ mod tests {
 
         let grandchild = child1.child(0, "Text");
         assert_eq!(grandchild.key(), "VStack_0/Text_0");
+
+        let item = child1.child(0, "ForEach").keyed("42");
+        assert_eq!(item.key(), "VStack_0/ForEach_0/#42");
+    }
+
+    #[test]
+    fn test_keyed_foreach_reorder() {
+        let app = crate::parser::parse::parse_nilo(
+            r#"
+            flow { start: Main }
+            timeline Main {
+                foreach item in state.items key item.id {
+                    Text("{}", item.name)
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        let nodes = &app.timelines[0].body;
+        let items = std::cell::RefCell::new(
+            r#"[{"id":"a","name":"Alpha"},{"id":"b","name":"Beta"}]"#.to_string(),
+        );
+        let eval = |e: &Expr| match e {
+            Expr::Path(p) if p == "state.items" => items.borrow().clone(),
+            Expr::String(s) => s.clone(),
+            Expr::Number(n) => n.to_string(),
+            _ => String::new(),
+        };
+        let texts = |results: &[LayoutedNode]| -> Vec<(String, f32)> {
+            results
+                .iter()
+                .filter_map(|n| match &n.node.node {
+                    ViewNode::Text { format, .. } => Some((format.clone(), n.position[1])),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let mut engine = LayoutDiffEngine::new();
+        let params = LayoutParams::default();
+        let first = texts(&engine.compute_diff(nodes, &params, &app, &eval, &|_| (0, 0)));
+        assert_eq!(engine.dirty_nodes, vec!["ForEach_0/#a", "ForEach_0/#b"]);
+        assert_eq!(first[0].0, "Alpha");
+        assert_eq!(first[1].0, "Beta");
+
+        // 並べ替えただけなら再レイアウトせず、要素ごとのIDとレイアウト結果が付いていく
+        *items.borrow_mut() = r#"[{"id":"b","name":"Beta"},{"id":"a","name":"Alpha"}]"#.into();
+        let reordered = texts(&engine.compute_diff(nodes, &params, &app, &eval, &|_| (0, 0)));
+        assert_eq!(engine.dirty_count(), 0);
+        assert_eq!(
+            reordered,
+            vec![("Beta".into(), first[0].1), ("Alpha".into(), first[1].1)]
+        );
+
+        // 値が変わった要素だけが再レイアウトされる
+        *items.borrow_mut() = r#"[{"id":"b","name":"Bravo"},{"id":"a","name":"Alpha"}]"#.into();
+        let changed = texts(&engine.compute_diff(nodes, &params, &app, &eval, &|_| (0, 0)));
+        assert_eq!(engine.dirty_nodes, vec!["ForEach_0/#b"]);
+        assert_eq!(changed[0].0, "Bravo");
     }
 
     #[test]
@@ -81,7 +81,10 @@ pub fn filter_visible_stencils(
                 };
                 (y, *height)
             }
-            Stencil::Group(_) | Stencil::ScrollBar { .. } | Stencil::ScrollContainer { .. } => {
+            Stencil::Group(_)
+            | Stencil::ScrollBar { .. }
+            | Stencil::ScrollContainer { .. }
+            | Stencil::Overlay { .. } => {
                 result.push(s.clone());
                 continue;
             }
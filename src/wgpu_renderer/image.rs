@@ -123,6 +123,11 @@ impl ImageRenderer {
         }
     }
 
+    /// ★ 読み込み済みのテクスチャを破棄する（ホットリロードで画像が変更されたとき。次の描画で読み込み直す）
+    pub fn invalidate_texture(&mut self, path: &str) {
+        self.textures.remove(path);
+    }
+
     pub fn load_texture(&mut self, device: &Device, queue: &Queue, path: &str) {
         if self.textures.contains_key(path) {
             return;
@@ -161,8 +161,15 @@ impl TextRenderer {
         None
     }
 
+    /// ★ フォントファイルを読み込み直し、DSLで指定したパスの名前で登録する（ホットリロード用）
+    pub fn reload_font_file(&mut self, font_path: &str) {
+        if let Some(family_name) = Self::load_and_register_font(&mut self.font_system, font_path) {
+            self.font_name_map
+                .insert(font_path.to_string(), family_name);
+        }
+    }
+
     /// フォントファイルを読み込んで登録（外部ファイル用、オプション）
-    #[allow(dead_code)]
     fn load_and_register_font(font_system: &mut FontSystem, font_path: &str) -> Option<String> {
         match std::fs::read(font_path) {
             Ok(font_data) => {
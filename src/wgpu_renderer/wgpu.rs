@@ -167,6 +167,31 @@ impl WgpuRenderer {
         self.depth_view = depth_view;
     }
 
+    /// ★ ホットリロードで変更された画像・フォントを読み込み直す
+    ///
+    /// `asset_paths` はDSLに書かれたパス（テクスチャ・フォントの登録名）。
+    /// そのうち `changed` と同じファイルを指すものだけを対象にする
+    pub fn reload_assets(&mut self, changed: &[std::path::PathBuf], asset_paths: &[String]) {
+        let changed: Vec<_> = changed
+            .iter()
+            .filter_map(|p| p.canonicalize().ok())
+            .collect();
+        for path in asset_paths {
+            let Ok(canonical) = std::path::Path::new(path).canonicalize() else {
+                continue;
+            };
+            if !changed.contains(&canonical) {
+                continue;
+            }
+            if path.ends_with(".ttf") || path.ends_with(".otf") {
+                self.text_renderer.reload_font_file(path);
+            } else {
+                self.image_renderer.invalidate_texture(path);
+            }
+            log::info!("🔄 Reloaded asset: {}", path);
+        }
+    }
+
     pub fn render(&mut self, draw_list: &DrawList, scroll_offset: [f32; 2], scale_factor: f32) {
         let surface_texture = self
             .surface
@@ -176,17 +201,56 @@ impl WgpuRenderer {
             .texture
             .create_view(&TextureViewDescriptor::default());
 
-        let mut encoder = self.device.create_command_encoder(&Default::default());
-
         // 画像の事前ロード（必要な場合のみ）
         self.preload_images(&draw_list.0);
 
-        // 単一のRenderPassで全て描画
+        // ★ オーバーレイ層は下層とは別のRenderPassで描画する
+        // 深度をクリアしてから描くため、下層のテキスト等に隠されず常に最前面になる
+        let overlays: Vec<&Vec<DrawCommand>> = draw_list
+            .0
+            .iter()
+            .filter_map(|cmd| match cmd {
+                DrawCommand::Overlay { children } => Some(children),
+                _ => None,
+            })
+            .collect();
+
+        if overlays.is_empty() {
+            self.encode_pass(&texture_view, &draw_list.0, scroll_offset, scale_factor);
+        } else {
+            let base: Vec<DrawCommand> = draw_list
+                .0
+                .iter()
+                .filter(|cmd| !matches!(cmd, DrawCommand::Overlay { .. }))
+                .cloned()
+                .collect();
+            self.encode_pass(&texture_view, &base, scroll_offset, scale_factor);
+            for children in overlays {
+                self.encode_pass(&texture_view, children, scroll_offset, scale_factor);
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.window.pre_present_notify();
+
+        surface_texture.present();
+    }
+
+    /// 1つのRenderPassで描画して送信
+    /// （テキストの頂点バッファはパスごとに書き換わるため、パス単位でsubmitする）
+    fn encode_pass(
+        &mut self,
+        texture_view: &TextureView,
+        commands: &[DrawCommand],
+        scroll_offset: [f32; 2],
+        scale_factor: f32,
+    ) {
+        let mut encoder = self.device.create_command_encoder(&Default::default());
         {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Unified Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &texture_view,
+                    view: texture_view,
                     depth_slice: None,
                     resolve_target: None,
                     ops: wgpu::Operations {
@@ -208,15 +272,10 @@ impl WgpuRenderer {
             });
 
             // stencil_to_wgpu_draw_list側で深度ソート済み。追加のソートやクローンを避けてオーバーヘッド削減。
-            self.render_batched_commands(&mut rpass, &draw_list.0, scroll_offset, scale_factor);
+            self.render_batched_commands(&mut rpass, commands, scroll_offset, scale_factor);
         }
 
         self.queue.submit(Some(encoder.finish()));
-
-        #[cfg(not(target_arch = "wasm32"))]
-        self.window.pre_present_notify();
-
-        surface_texture.present();
     }
 
     #[inline]
@@ -228,14 +287,19 @@ impl WgpuRenderer {
             | DrawCommand::Image { depth, .. }
             | DrawCommand::Text { depth, .. }
             | DrawCommand::ScrollContainer { depth, .. } => *depth,
+            DrawCommand::Overlay { .. } => 0.0,
         }
     }
 
     fn preload_images(&mut self, commands: &[DrawCommand]) {
         for cmd in commands {
-            if let DrawCommand::Image { path, .. } = cmd {
-                self.image_renderer
-                    .load_texture(&self.device, &self.queue, path);
+            match cmd {
+                DrawCommand::Image { path, .. } => {
+                    self.image_renderer
+                        .load_texture(&self.device, &self.queue, path);
+                }
+                DrawCommand::Overlay { children } => self.preload_images(children),
+                _ => {}
             }
         }
     }
@@ -323,6 +387,7 @@ impl WgpuRenderer {
                         DrawCommand::Text { scroll, .. } => *scroll,
                         DrawCommand::Image { scroll, .. } => *scroll,
                         DrawCommand::ScrollContainer { .. } => false, // 既に処理済み
+                        DrawCommand::Overlay { .. } => false,         // 別パスで描画
                     };
                     
                     let mut cmd_with_scroll = cmd.clone();
@@ -365,8 +430,8 @@ impl WgpuRenderer {
                 position[0] += offset[0];
                 position[1] += offset[1];
             }
-            DrawCommand::ScrollContainer { .. } => {
-                // ScrollContainerは既に展開済み
+            DrawCommand::ScrollContainer { .. } | DrawCommand::Overlay { .. } => {
+                // ScrollContainerは既に展開済み、Overlayは別パスで描画
             }
         }
     }
@@ -476,8 +541,8 @@ impl WgpuRenderer {
                         *max_width,
                     ));
                 }
-                DrawCommand::ScrollContainer { .. } => {
-                    // 既に展開済み
+                DrawCommand::ScrollContainer { .. } | DrawCommand::Overlay { .. } => {
+                    // 既に展開済み（Overlayは別パスで描画）
                 }
             }
         }